@@ -1,6 +1,11 @@
 use std::io::Write;
 
-use anchor_lang::{__private::CLOSED_ACCOUNT_DISCRIMINATOR, prelude::*};
+use anchor_lang::{
+    __private::CLOSED_ACCOUNT_DISCRIMINATOR,
+    prelude::*,
+    solana_program::{program::invoke, system_instruction},
+    Discriminator,
+};
 
 use crate::{errors::ErrorCode, try_math::*};
 
@@ -23,3 +28,36 @@ pub fn close_account(
         .map_err(|_| error!(ErrorCode::AnchorSerializationIssue))?;
     Ok(())
 }
+
+/// fix missing discriminator check on init_if_needed accounts, returns true if the account is new
+pub fn assert_discriminator_or_new<T: Discriminator>(acct: &AccountInfo) -> Result<bool> {
+    let data = acct.try_borrow_data()?;
+    let disc_bytes = data
+        .get(..8)
+        .ok_or_else(|| error!(ErrorCode::AccountDiscriminatorMismatch))?;
+    let is_new = disc_bytes.iter().all(|a| a == &0);
+    if !is_new && disc_bytes != T::discriminator() {
+        return Err(error!(ErrorCode::AccountDiscriminatorMismatch));
+    }
+    Ok(is_new)
+}
+
+/// grows an account to new_len (zero-filled), with the payer topping up the rent
+pub fn realloc_account<'info>(
+    acct: &AccountInfo<'info>,
+    new_len: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let rent_due = Rent::get()?
+        .minimum_balance(new_len)
+        .saturating_sub(acct.lamports());
+    if rent_due > 0 {
+        invoke(
+            &system_instruction::transfer(payer.key, acct.key, rent_due),
+            &[payer.clone(), acct.clone(), system_program.clone()],
+        )?;
+    }
+
+    acct.realloc(new_len, true).map_err(Into::into)
+}
@@ -36,8 +36,12 @@ pub enum ErrorCode {
     #[msg("bad metadata passed")]
     BadMetadata,
 
-    Reserved8,
-    Reserved9,
+    #[msg("token program doesn't match the mint, or isn't a token program at all")]
+    InvalidTokenProgram,
+
+    #[msg("signer isn't the pending manager / owner this handover was proposed to")]
+    NotPendingAuthority,
+
     Reserved10,
     Reserved11,
     Reserved12,
@@ -64,10 +68,19 @@ pub enum ErrorCode {
 
     #[msg("attempted transfer not allowed")]
     TransferNotAllowed,
-    Reserved25,
-    Reserved26,
-    Reserved27,
-    Reserved28,
+
+    #[msg("vault still contains gems")]
+    VaultNotEmpty,
+
+    #[msg("bank still has vaults or whitelist entries registered with it")]
+    BankNotEmpty,
+
+    #[msg("account is not a valid metaplex core asset")]
+    InvalidCoreAsset,
+
+    #[msg("vault has gems frozen in the owner's wallet, thaw them first")]
+    VaultHasFrozenGems,
+
     Reserved29,
     Reserved30,
     Reserved31,
@@ -93,7 +106,8 @@ pub enum ErrorCode {
     #[msg("can't unstake, cooldown period has not passed yet")]
     CooldownNotPassed,
 
-    Reserved44,
+    #[msg("all reward slots on this farm are taken")]
+    TooManyRewards,
 
     #[msg("reward has insufficient funding, please top up")]
     RewardUnderfunded, //0x179D
@@ -116,12 +130,84 @@ pub enum ErrorCode {
     #[msg("unstaking fee lamports must be either 0 or greater than 890880")]
     InvalidUnstakingFee, //0x17a3
 
-    Reserved52,
-    Reserved53,
-    Reserved54,
-    Reserved55,
-    Reserved56,
-    Reserved57,
-    Reserved58,
-    Reserved59,
+    #[msg("this reward mint is already used by the farm")]
+    RewardMintAlreadyUsed,
+
+    #[msg("reward still has pending funds or balances farmers haven't picked up")]
+    RewardNotRetirable,
+
+    #[msg("farmer still has gems staked or rewards to claim")]
+    FarmerNotSettled,
+
+    #[msg("farm still has farmers, funders or rewards to settle")]
+    FarmNotSettled,
+
+    #[msg("lockup tier isn't offered by this farm, or is misconfigured")]
+    InvalidLockupTier,
+
+    #[msg("reward can't be scheduled to start in the past")]
+    RewardStartInPast,
+
+    #[msg("the reward's epoch queue is full")]
+    TooManyRewardEpochs,
+
+    #[msg("fixed rate schedule has too many or unsorted tiers, or a zero denominator")]
+    InvalidFixedRateSchedule,
+
+    #[msg("too many gems are already cooling down, withdraw some first")]
+    TooManyPendingWithdrawals,
+
+    #[msg("farm is timelocked, this action has to be queued first")]
+    TimelockRequired,
+
+    #[msg("queued action's timelock hasn't passed yet")]
+    TimelockNotPassed,
+
+    #[msg("queued action doesn't match the one being executed")]
+    PendingActionMismatch,
+
+    #[msg("signer is neither the farm manager nor holds the role needed")]
+    MissingRole,
+
+    #[msg("every funder of the reward has to be passed in, exactly once")]
+    IncompleteFunderList,
+
+    #[msg("funder's authorization has expired")]
+    FunderAuthorizationExpired,
+
+    #[msg("funder is only authorized to fund a different reward")]
+    FunderNotAuthorizedForMint,
+
+    #[msg("funding would take the funder over their allowance")]
+    FunderAllowanceExceeded,
+
+    #[msg("the farm's fixed rate tiers have to be passed in to enroll the farmer")]
+    FixedRateTiersRequired,
+
+    #[msg("pending action has already been executed")]
+    PendingActionExecuted,
+
+    #[msg("account doesn't need migrating")]
+    AccountAlreadyMigrated,
+
+    #[msg("mint has a token-2022 extension that isn't supported")]
+    UnsupportedMintExtension,
+
+    #[msg("core asset (or its collection) has a permanent transfer / burn delegate")]
+    UnsupportedCorePlugin,
+
+    #[msg("farmers from before the upgrade have to be migrated first")]
+    LegacyFarmersPending,
+
+    #[msg("can't partially unstake the last of the staked gems, unstake instead")]
+    LastGemsUnstaked,
+
+    #[msg("none of the funders have anything left in the reward to split a refund by")]
+    NoFunderBalance,
+
+    #[msg("farmers are already enrolled in too many different fixed rate schedules")]
+    TooManyFixedRateSchedules,
+
+    #[msg("funding config doesn't match the reward's type")]
+    RewardConfigMismatch,
 }
@@ -1,5 +1,6 @@
 pub mod account;
 pub mod errors;
+pub mod token_interface;
 pub mod try_math;
 pub mod util;
 
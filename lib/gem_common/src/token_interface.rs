@@ -0,0 +1,293 @@
+//! helpers for moving tokens that may belong to either the original token program or token-2022
+//!
+//! anchor-spl (as of 0.26) only knows about the former, so accounts that may belong to either
+//! are taken in as plain AccountInfos and are validated / unpacked here instead
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        program::{invoke, invoke_signed},
+        system_instruction,
+    },
+};
+use anchor_spl::{
+    associated_token::{self, Create},
+    token,
+};
+use spl_token_2022::{
+    extension::{
+        default_account_state::DefaultAccountState,
+        transfer_fee::{instruction::harvest_withheld_tokens_to_mint, TransferFeeAmount},
+        BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+    },
+    state::{Account as TokenAccountState, AccountState, Mint as MintState},
+};
+
+use crate::{errors::ErrorCode, try_math::*};
+
+pub use spl_token_2022::ID as TOKEN_2022_PROGRAM_ID;
+
+pub fn is_token_program(key: &Pubkey) -> bool {
+    *key == token::ID || *key == TOKEN_2022_PROGRAM_ID
+}
+
+/// for ixs that touch several mints at once - picks whichever of the 2 programs owns the mint
+pub fn token_program_for_mint<'info>(
+    mint: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    token_2022_program: Option<&AccountInfo<'info>>,
+) -> Result<AccountInfo<'info>> {
+    if mint.owner == token_program.key {
+        return Ok(token_program.clone());
+    }
+    match token_2022_program {
+        Some(program) if mint.owner == program.key => Ok(program.clone()),
+        _ => Err(error!(ErrorCode::InvalidTokenProgram)),
+    }
+}
+
+pub fn unpack_mint(mint: &AccountInfo) -> Result<MintState> {
+    if !is_token_program(mint.owner) {
+        return Err(error!(ErrorCode::InvalidTokenProgram));
+    }
+    let data = mint.try_borrow_data()?;
+    Ok(StateWithExtensions::<MintState>::unpack(&data)?.base)
+}
+
+pub fn unpack_token_account(account: &AccountInfo) -> Result<TokenAccountState> {
+    if !is_token_program(account.owner) {
+        return Err(error!(ErrorCode::InvalidTokenProgram));
+    }
+    let data = account.try_borrow_data()?;
+    Ok(StateWithExtensions::<TokenAccountState>::unpack(&data)?.base)
+}
+
+/// rejects token-2022 mints whose extensions would let someone other than the program move,
+/// burn or freeze what it holds, or keep it from ever being moved back out
+pub fn assert_supported_mint(mint: &AccountInfo) -> Result<()> {
+    if *mint.owner != TOKEN_2022_PROGRAM_ID {
+        return Ok(());
+    }
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<MintState>::unpack(&data)?;
+
+    // extensions this version doesn't know about (eg TransferHook) fail to parse, so are rejected too
+    let extensions = mint_state
+        .get_extension_types()
+        .map_err(|_| error!(ErrorCode::UnsupportedMintExtension))?;
+
+    for extension in extensions {
+        let supported = match extension {
+            ExtensionType::PermanentDelegate
+            | ExtensionType::NonTransferable
+            | ExtensionType::MintCloseAuthority => false,
+            ExtensionType::DefaultAccountState => {
+                mint_state.get_extension::<DefaultAccountState>()?.state
+                    != AccountState::Frozen as u8
+            }
+            _ => true,
+        };
+        if !supported {
+            return Err(error!(ErrorCode::UnsupportedMintExtension));
+        }
+    }
+    Ok(())
+}
+
+/// token-2022 mints can require extra space on token accounts (eg to track withheld fees)
+fn token_account_len(mint: &AccountInfo) -> Result<usize> {
+    if *mint.owner == token::ID {
+        return Ok(token::TokenAccount::LEN);
+    }
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<MintState>::unpack(&data)?;
+    let required =
+        ExtensionType::get_required_init_account_extensions(&mint_state.get_extension_types()?);
+    Ok(ExtensionType::get_account_len::<TokenAccountState>(
+        &required,
+    ))
+}
+
+/// unlike in the original token program, token-2022 accounts with withheld fees can't be closed
+fn has_withheld_fees(account: &AccountInfo) -> Result<bool> {
+    let data = account.try_borrow_data()?;
+    let state = StateWithExtensions::<TokenAccountState>::unpack(&data)?;
+    Ok(state
+        .get_extension::<TransferFeeAmount>()
+        .map(|ext| u64::from(ext.withheld_amount) > 0)
+        .unwrap_or(false))
+}
+
+pub fn associated_token_address(wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &associated_token::ID,
+    )
+    .0
+}
+
+/// creates a token account at a PDA owned by the calling program,
+/// (!) only for mints that pass assert_supported_mint
+pub fn create_token_account_pda<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    account_seeds: &[&[u8]],
+) -> Result<()> {
+    assert_supported_mint(mint)?;
+
+    let space = token_account_len(mint)?;
+    let rent = Rent::get()?.minimum_balance(space);
+
+    if account.lamports() == 0 {
+        invoke_signed(
+            &system_instruction::create_account(
+                payer.key,
+                account.key,
+                rent,
+                space as u64,
+                token_program.key,
+            ),
+            &[payer.clone(), account.clone(), system_program.clone()],
+            &[account_seeds],
+        )?;
+    } else {
+        // anyone can send lamports to the address up front, which would make create_account fail
+        let rent_due = rent.saturating_sub(account.lamports());
+        if rent_due > 0 {
+            invoke(
+                &system_instruction::transfer(payer.key, account.key, rent_due),
+                &[payer.clone(), account.clone(), system_program.clone()],
+            )?;
+        }
+        invoke_signed(
+            &system_instruction::allocate(account.key, space as u64),
+            &[account.clone(), system_program.clone()],
+            &[account_seeds],
+        )?;
+        invoke_signed(
+            &system_instruction::assign(account.key, token_program.key),
+            &[account.clone(), system_program.clone()],
+            &[account_seeds],
+        )?;
+    }
+
+    invoke(
+        &spl_token_2022::instruction::initialize_account3(
+            token_program.key,
+            account.key,
+            mint.key,
+            authority.key,
+        )?,
+        &[account.clone(), mint.clone(), token_program.clone()],
+    )
+    .map_err(Into::into)
+}
+
+/// verifies the account is the wallet's ATA and creates it if it doesn't exist yet
+#[allow(clippy::too_many_arguments)]
+pub fn init_associated_token_account_if_needed<'info>(
+    ata: &AccountInfo<'info>,
+    wallet: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    associated_token_program: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    if ata.key() != associated_token_address(wallet.key, mint.key, token_program.key) {
+        return Err(error!(ErrorCode::InvalidParameter));
+    }
+    if !ata.data_is_empty() {
+        return Ok(());
+    }
+
+    associated_token::create(CpiContext::new(
+        associated_token_program.clone(),
+        Create {
+            payer: payer.clone(),
+            associated_token: ata.clone(),
+            authority: wallet.clone(),
+            mint: mint.clone(),
+            system_program: system_program.clone(),
+            token_program: token_program.clone(),
+        },
+    ))
+}
+
+/// works the same for both token programs, returns the amount that actually arrived
+/// (for token-2022 mints with a transfer fee that's less than the amount sent)
+pub fn transfer_checked<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    let decimals = unpack_mint(mint)?.decimals;
+    let balance_before = unpack_token_account(to)?.amount;
+
+    invoke_signed(
+        &spl_token_2022::instruction::transfer_checked(
+            token_program.key,
+            from.key,
+            mint.key,
+            to.key,
+            authority.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[
+            from.clone(),
+            mint.clone(),
+            to.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+
+    unpack_token_account(to)?.amount.try_sub(balance_before)
+}
+
+/// (!) the mint needs to be writable if the account might hold withheld transfer fees
+pub fn close_token_account<'info>(
+    token_program: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    // harvesting is permissionless, no need to sign
+    if *token_program.key == TOKEN_2022_PROGRAM_ID && has_withheld_fees(account)? {
+        invoke(
+            &harvest_withheld_tokens_to_mint(token_program.key, mint.key, &[account.key])?,
+            &[mint.clone(), account.clone(), token_program.clone()],
+        )?;
+    }
+
+    invoke_signed(
+        &spl_token_2022::instruction::close_account(
+            token_program.key,
+            account.key,
+            destination.key,
+            authority.key,
+            &[],
+        )?,
+        &[
+            account.clone(),
+            destination.clone(),
+            authority.clone(),
+            token_program.clone(),
+        ],
+        signer_seeds,
+    )
+    .map_err(Into::into)
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+use gem_common::errors::ErrorCode;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct AcceptBankManager<'info> {
+    // bank
+    #[account(mut, constraint = bank.pending_bank_manager == new_manager.key()
+        @ ErrorCode::NotPendingAuthority)]
+    pub bank: Box<Account<'info, Bank>>,
+    pub new_manager: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<AcceptBankManager>) -> Result<()> {
+    let bank = &mut ctx.accounts.bank;
+
+    bank.bank_manager = bank.pending_bank_manager;
+    bank.pending_bank_manager = Pubkey::default();
+
+    //msg!("bank manager updated to: {}", bank.bank_manager);
+    Ok(())
+}
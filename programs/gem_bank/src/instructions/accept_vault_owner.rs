@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+use gem_common::errors::ErrorCode;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct AcceptVaultOwner<'info> {
+    // bank
+    pub bank: Box<Account<'info, Bank>>,
+
+    // vault
+    // same rationale for not verifying the PDA as in deposit
+    #[account(mut, has_one = bank, constraint = vault.pending_owner == new_owner.key()
+        @ ErrorCode::NotPendingAuthority)]
+    pub vault: Box<Account<'info, Vault>>,
+    pub new_owner: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<AcceptVaultOwner>) -> Result<()> {
+    let bank = &ctx.accounts.bank;
+    let vault = &mut ctx.accounts.vault;
+
+    // same checks as when proposing, things might have changed since
+    if Bank::read_flags(bank.flags)?.contains(BankFlags::FREEZE_VAULTS) {
+        return Err(error!(ErrorCode::VaultAccessSuspended));
+    }
+
+    if vault.frozen_gem_count > 0 {
+        return Err(error!(ErrorCode::VaultHasFrozenGems));
+    }
+
+    vault.owner = vault.pending_owner;
+    vault.pending_owner = Pubkey::default();
+
+    //msg!("owner updated to: {}", vault.owner);
+    Ok(())
+}
@@ -58,6 +58,9 @@ pub fn handler(ctx: Context<AddToWhitelist>, whitelist_type: u8) -> Result<()> {
         if existing_whitelist.contains(WhitelistType::MINT) {
             bank.whitelisted_mints.try_sub_assign(1)?;
         }
+        if existing_whitelist.contains(WhitelistType::COLLECTION) {
+            bank.whitelisted_collections.try_sub_assign(1)?;
+        }
     }
 
     // record new whitelist and increment counts
@@ -75,6 +78,9 @@ pub fn handler(ctx: Context<AddToWhitelist>, whitelist_type: u8) -> Result<()> {
     if new_whitelist.contains(WhitelistType::MINT) {
         bank.whitelisted_mints.try_add_assign(1)?;
     }
+    if new_whitelist.contains(WhitelistType::COLLECTION) {
+        bank.whitelisted_collections.try_add_assign(1)?;
+    }
 
     // msg!(
     //     "{} added to whitelist",
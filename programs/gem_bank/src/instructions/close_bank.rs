@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+use gem_common::{errors::ErrorCode, *};
+
+use crate::state::*;
+
+/// (!) rarity PDAs aren't tracked on the bank, so close them with close_rarity_points BEFORE
+/// closing the bank - once the bank is gone, so is the manager's ability to do so
+#[derive(Accounts)]
+pub struct CloseBank<'info> {
+    // bank
+    #[account(mut, has_one = bank_manager)]
+    pub bank: Box<Account<'info, Bank>>,
+    pub bank_manager: Signer<'info>,
+
+    // misc
+    /// CHECK:
+    #[account(mut)]
+    pub funds_receiver: AccountInfo<'info>,
+}
+
+pub fn handler(ctx: Context<CloseBank>) -> Result<()> {
+    let bank = &ctx.accounts.bank;
+
+    // vaults and whitelist proofs all hold rent of their own, so they have to go first
+    if bank.vault_count > 0
+        || bank.whitelisted_creators > 0
+        || bank.whitelisted_mints > 0
+        || bank.whitelisted_collections > 0
+    {
+        return Err(error!(ErrorCode::BankNotEmpty));
+    }
+
+    close_account(
+        &mut ctx.accounts.bank.to_account_info(),
+        &mut ctx.accounts.funds_receiver,
+    )?;
+
+    //msg!("bank {} closed", &ctx.accounts.bank.key());
+    Ok(())
+}
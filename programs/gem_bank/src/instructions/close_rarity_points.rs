@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+use gem_common::{errors::ErrorCode, *};
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct CloseRarityPoints<'info> {
+    // bank
+    #[account(has_one = bank_manager)]
+    pub bank: Box<Account<'info, Bank>>,
+    pub bank_manager: Signer<'info>,
+
+    // misc
+    /// CHECK:
+    #[account(mut)]
+    pub funds_receiver: AccountInfo<'info>,
+    //
+    // remaining accounts can be any number of:
+    //   pub gem_mint: Box<Account<'info, Mint>>,
+    //   #[account(mut)]
+    //   pub gem_rarity: Box<Account<'info, Rarity>>,
+}
+
+pub fn handler<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, CloseRarityPoints<'info>>,
+) -> Result<()> {
+    let remaining_accs = &mut ctx.remaining_accounts.iter();
+
+    while remaining_accs.len() > 0 {
+        let gem_mint = next_account_info(remaining_accs)?;
+        let gem_rarity = next_account_info(remaining_accs)?;
+
+        // make sure it's this bank's rarity PDA we're closing
+        let (pk, _bump) = Pubkey::find_program_address(
+            &[
+                b"gem_rarity".as_ref(),
+                ctx.accounts.bank.key().as_ref(),
+                gem_mint.key().as_ref(),
+            ],
+            ctx.program_id,
+        );
+        if gem_rarity.key() != pk || gem_rarity.owner != ctx.program_id {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+
+        close_account(
+            &mut gem_rarity.clone(),
+            &mut ctx.accounts.funds_receiver.clone(),
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+use gem_common::{errors::ErrorCode, *};
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct CloseVault<'info> {
+    // bank
+    #[account(mut, has_one = bank_manager)]
+    pub bank: Box<Account<'info, Bank>>,
+    // has to sign off too, so a farm (whose authority manages the bank) gets to settle
+    // the farmer before their vault goes away
+    pub bank_manager: Signer<'info>,
+
+    // vault
+    #[account(mut, has_one = bank, has_one = owner)]
+    pub vault: Box<Account<'info, Vault>>,
+    pub owner: Signer<'info>,
+
+    // misc
+    /// CHECK:
+    #[account(mut)]
+    pub funds_receiver: AccountInfo<'info>,
+}
+
+pub fn handler(ctx: Context<CloseVault>) -> Result<()> {
+    let vault = &ctx.accounts.vault;
+
+    if vault.locked {
+        return Err(error!(ErrorCode::VaultAccessSuspended));
+    }
+
+    // gem boxes are closed as gems get withdrawn, so this means none are left hanging around
+    if vault.gem_box_count > 0 || vault.gem_count > 0 {
+        return Err(error!(ErrorCode::VaultNotEmpty));
+    }
+
+    // decrement vault counter on bank
+    let bank = &mut ctx.accounts.bank;
+
+    bank.vault_count.try_sub_assign(1)?;
+
+    // delete vault
+    close_account(
+        &mut ctx.accounts.vault.to_account_info(),
+        &mut ctx.accounts.funds_receiver,
+    )?;
+
+    //msg!("vault {} closed", &ctx.accounts.vault.key());
+    Ok(())
+}
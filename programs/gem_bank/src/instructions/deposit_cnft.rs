@@ -0,0 +1,176 @@
+use anchor_lang::prelude::*;
+use gem_common::{errors::ErrorCode, *};
+
+use crate::{instructions::calc_rarity_points, state::*, *};
+
+#[derive(Accounts)]
+#[instruction(bump_auth: u8, bump_rarity: u8, root: [u8; 32], leaf: CnftLeaf)]
+pub struct DepositCnft<'info> {
+    // bank
+    pub bank: Box<Account<'info, Bank>>,
+
+    // vault
+    // same rationale for not verifying the PDA as in deposit
+    #[account(mut, has_one = bank, has_one = owner, has_one = authority)]
+    pub vault: Box<Account<'info, Vault>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK:
+    #[account(seeds = [vault.key().as_ref()], bump = bump_auth)]
+    pub authority: AccountInfo<'info>,
+
+    // cnft
+    /// CHECK: part of the leaf, bubblegum verifies it (pass the owner if no delegate is set)
+    pub leaf_delegate: AccountInfo<'info>,
+    /// CHECK: bubblegum checks it's the tree's config
+    pub tree_authority: AccountInfo<'info>,
+    /// CHECK: bubblegum / account compression verify the leaf against it
+    #[account(mut)]
+    pub merkle_tree: AccountInfo<'info>,
+    #[account(init, seeds = [
+            b"cnft_deposit_receipt".as_ref(),
+            vault.key().as_ref(),
+            get_cnft_asset_id(&merkle_tree.key(), leaf.nonce).as_ref(),
+        ],
+        bump,
+        payer = owner,
+        space = 8 + std::mem::size_of::<CnftDepositReceipt>())]
+    pub cnft_deposit_receipt: Box<Account<'info, CnftDepositReceipt>>,
+    // we MUST ask for this PDA both during deposit and withdrawal for sec reasons, even if it's zero'ed
+    /// CHECK:
+    #[account(seeds = [
+            b"gem_rarity".as_ref(),
+            bank.key().as_ref(),
+            get_cnft_asset_id(&merkle_tree.key(), leaf.nonce).as_ref(),
+        ],
+        bump = bump_rarity)]
+    pub gem_rarity: AccountInfo<'info>,
+
+    // whitelists (the proof path takes up the remaining accounts, so these can't go there)
+    /// CHECK: only needed if the bank has mint whitelists, the asset id acts as the mint
+    pub mint_whitelist_proof: Option<AccountInfo<'info>>,
+    /// CHECK: only needed if the bank has creator whitelists
+    pub creator_whitelist_proof: Option<AccountInfo<'info>>,
+
+    // misc
+    pub cnft_shared: CnftShared<'info>,
+    pub system_program: Program<'info, System>,
+    //
+    // remaining accounts are the proof path (minus whatever is in the tree's canopy)
+}
+
+fn assert_whitelisted(
+    ctx: &Context<DepositCnft>,
+    asset_id: &Pubkey,
+    leaf: &CnftLeaf,
+    creators: &[CnftCreator],
+) -> Result<()> {
+    let bank = &*ctx.accounts.bank;
+
+    // attempt to verify based on asset id
+    if bank.whitelisted_mints > 0 {
+        if let Some(mint_whitelist_proof_info) = &ctx.accounts.mint_whitelist_proof {
+            if let Ok(()) = assert_valid_whitelist_proof(
+                mint_whitelist_proof_info,
+                &bank.key(),
+                asset_id,
+                ctx.program_id,
+                WhitelistType::MINT,
+            ) {
+                return Ok(());
+            }
+        }
+    }
+
+    // if asset verification above failed, attempt to verify based on creator
+    if bank.whitelisted_creators > 0 {
+        if let Some(creator_whitelist_proof_info) = &ctx.accounts.creator_whitelist_proof {
+            // creators aren't stored in the leaf, only their hash - so make sure we got the real ones
+            if hash_cnft_creators(creators) != leaf.creator_hash {
+                return Err(error!(ErrorCode::BadMetadata));
+            }
+
+            for creator in creators {
+                // verify creator actually signed off on this nft
+                if !creator.verified {
+                    continue;
+                }
+
+                if let Ok(()) = assert_valid_whitelist_proof(
+                    creator_whitelist_proof_info,
+                    &bank.key(),
+                    &creator.address,
+                    ctx.program_id,
+                    WhitelistType::CREATOR,
+                ) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    Err(error!(ErrorCode::NotWhitelisted))
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, DepositCnft<'info>>,
+    root: [u8; 32],
+    leaf: CnftLeaf,
+    creators: Vec<CnftCreator>,
+) -> Result<()> {
+    let asset_id = get_cnft_asset_id(&ctx.accounts.merkle_tree.key(), leaf.nonce);
+
+    // if even a single whitelist exists, verify the cnft against it
+    let bank = &*ctx.accounts.bank;
+
+    if bank.whitelisted_mints > 0
+        || bank.whitelisted_creators > 0
+        || bank.whitelisted_collections > 0
+    {
+        assert_whitelisted(&ctx, &asset_id, &leaf, &creators)?;
+    }
+
+    // verify vault not suspended
+    let vault = &ctx.accounts.vault;
+
+    if vault.access_suspended(bank.flags)? {
+        return Err(error!(ErrorCode::VaultAccessSuspended));
+    }
+
+    // do the transfer
+    send_cnft(
+        &ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.leaf_delegate,
+        &ctx.accounts.authority,
+        &ctx.accounts.tree_authority,
+        &ctx.accounts.merkle_tree,
+        &ctx.accounts.cnft_shared,
+        &ctx.accounts.system_program,
+        ctx.remaining_accounts,
+        root,
+        &leaf,
+        None,
+    )?;
+
+    // each cnft counts as its own gem box
+    let vault = &mut ctx.accounts.vault;
+    vault.gem_box_count.try_add_assign(1)?;
+    vault.gem_count.try_add_assign(1)?;
+    vault
+        .rarity_points
+        .try_add_assign(calc_rarity_points(&ctx.accounts.gem_rarity, 1)?)?;
+
+    // record a receipt
+    let receipt = &mut *ctx.accounts.cnft_deposit_receipt;
+
+    receipt.vault = vault.key();
+    receipt.asset_id = asset_id;
+    receipt.merkle_tree = ctx.accounts.merkle_tree.key();
+    receipt.data_hash = leaf.data_hash;
+    receipt.creator_hash = leaf.creator_hash;
+    receipt.nonce = leaf.nonce;
+    receipt.index = leaf.index;
+
+    // msg!("cnft {} deposited", asset_id);
+    Ok(())
+}
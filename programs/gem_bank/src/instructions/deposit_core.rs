@@ -0,0 +1,152 @@
+use anchor_lang::prelude::*;
+use gem_common::{errors::ErrorCode, *};
+
+use crate::{instructions::calc_rarity_points, state::*, *};
+
+#[derive(Accounts)]
+#[instruction(bump_auth: u8, bump_rarity: u8)]
+pub struct DepositCore<'info> {
+    // bank
+    pub bank: Box<Account<'info, Bank>>,
+
+    // vault
+    // same rationale for not verifying the PDA as in deposit
+    #[account(mut, has_one = bank, has_one = owner, has_one = authority)]
+    pub vault: Box<Account<'info, Vault>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK:
+    #[account(seeds = [vault.key().as_ref()], bump = bump_auth)]
+    pub authority: AccountInfo<'info>,
+
+    // asset
+    /// CHECK: assert_decode_core_asset
+    #[account(mut)]
+    pub asset: AccountInfo<'info>,
+    /// CHECK: has to match the asset's collection, if it has one
+    pub collection: Option<AccountInfo<'info>>,
+    #[account(init, seeds = [
+            b"core_deposit_receipt".as_ref(),
+            vault.key().as_ref(),
+            asset.key().as_ref(),
+        ],
+        bump,
+        payer = owner,
+        space = 8 + std::mem::size_of::<CoreDepositReceipt>())]
+    pub core_deposit_receipt: Box<Account<'info, CoreDepositReceipt>>,
+    // we MUST ask for this PDA both during deposit and withdrawal for sec reasons, even if it's zero'ed
+    /// CHECK:
+    #[account(seeds = [
+            b"gem_rarity".as_ref(),
+            bank.key().as_ref(),
+            asset.key().as_ref(),
+        ],
+        bump = bump_rarity)]
+    pub gem_rarity: AccountInfo<'info>,
+
+    // misc
+    /// CHECK: address below
+    #[account(address = mpl_core_program::ID)]
+    pub mpl_core_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    //
+    // remaining accounts could be passed, in this order:
+    // - mint_whitelist_proof <- the asset address acts as the mint
+    // - collection_whitelist_proof
+}
+
+fn assert_whitelisted<'info>(
+    ctx: &Context<'_, '_, '_, 'info, DepositCore<'info>>,
+    core_asset: &CoreAsset,
+) -> Result<()> {
+    let bank = &*ctx.accounts.bank;
+    let remaining_accs = &mut ctx.remaining_accounts.iter();
+
+    // whitelisted mint (asset) is always the 1st optional account
+    let mint_whitelist_proof_info = next_account_info(remaining_accs)?;
+
+    // attempt to verify based on asset
+    if bank.whitelisted_mints > 0 {
+        if let Ok(()) = assert_valid_whitelist_proof(
+            mint_whitelist_proof_info,
+            &bank.key(),
+            &ctx.accounts.asset.key(),
+            ctx.program_id,
+            WhitelistType::MINT,
+        ) {
+            return Ok(());
+        }
+    }
+
+    // if asset verification above failed, attempt to verify based on collection
+    // (creators live in an optional royalties plugin on core, so aren't supported)
+    if bank.whitelisted_collections > 0 {
+        if let Some(collection) = core_asset.collection {
+            let collection_whitelist_proof_info = next_account_info(remaining_accs)?;
+
+            if let Ok(()) = assert_valid_whitelist_proof(
+                collection_whitelist_proof_info,
+                &bank.key(),
+                &collection,
+                ctx.program_id,
+                WhitelistType::COLLECTION,
+            ) {
+                return Ok(());
+            }
+        }
+    }
+
+    Err(error!(ErrorCode::NotWhitelisted))
+}
+
+pub fn handler<'info>(ctx: Context<'_, '_, '_, 'info, DepositCore<'info>>) -> Result<()> {
+    let core_asset = assert_decode_core_asset(&ctx.accounts.asset)?;
+    assert_core_collection_matches(&core_asset, ctx.accounts.collection.as_ref())?;
+    assert_no_core_permanent_delegates(&core_asset, ctx.accounts.collection.as_ref())?;
+
+    // if even a single whitelist exists, verify the asset against it
+    let bank = &*ctx.accounts.bank;
+
+    if bank.whitelisted_mints > 0
+        || bank.whitelisted_creators > 0
+        || bank.whitelisted_collections > 0
+    {
+        assert_whitelisted(&ctx, &core_asset)?;
+    }
+
+    // verify vault not suspended
+    let vault = &ctx.accounts.vault;
+
+    if vault.access_suspended(bank.flags)? {
+        return Err(error!(ErrorCode::VaultAccessSuspended));
+    }
+
+    // do the transfer
+    send_core_asset(
+        &ctx.accounts.asset,
+        ctx.accounts.collection.as_ref(),
+        &ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.authority,
+        &ctx.accounts.mpl_core_program,
+        None,
+    )?;
+
+    // each asset counts as its own gem box
+    let vault = &mut ctx.accounts.vault;
+    vault.gem_box_count.try_add_assign(1)?;
+    vault.gem_count.try_add_assign(1)?;
+    vault
+        .rarity_points
+        .try_add_assign(calc_rarity_points(&ctx.accounts.gem_rarity, 1)?)?;
+
+    // record a receipt
+    let receipt = &mut *ctx.accounts.core_deposit_receipt;
+
+    receipt.vault = vault.key();
+    receipt.asset = ctx.accounts.asset.key();
+    receipt.collection = core_asset.collection.unwrap_or_default();
+
+    // msg!("core asset {} deposited", receipt.asset);
+    Ok(())
+}
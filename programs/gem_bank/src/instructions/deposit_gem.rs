@@ -1,9 +1,12 @@
 use anchor_lang::{prelude::*, Discriminator};
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 use arrayref::array_ref;
-use gem_common::{errors::ErrorCode, *};
+use gem_common::{
+    errors::ErrorCode,
+    token_interface::{self, is_token_program},
+    *,
+};
 
-use crate::{assert_decode_metadata, state::*};
+use crate::{assert_decode_metadata, assert_valid_whitelist_proof, state::*};
 
 #[derive(Accounts)]
 #[instruction(bump_auth: u8, bump_rarity: u8)]
@@ -25,16 +28,15 @@ pub struct DepositGem<'info> {
     pub authority: AccountInfo<'info>,
 
     // gem
-    #[account(init_if_needed, seeds = [
+    // token accounts & the mint are taken in raw, as they can belong to either token program
+    /// CHECK: created on first deposit
+    #[account(mut, seeds = [
             b"gem_box".as_ref(),
             vault.key().as_ref(),
             gem_mint.key().as_ref(),
         ],
-        bump,
-        token::mint = gem_mint,
-        token::authority = authority,
-        payer = owner)]
-    pub gem_box: Box<Account<'info, TokenAccount>>,
+        bump)]
+    pub gem_box: AccountInfo<'info>,
     #[account(init_if_needed, seeds = [
             b"gem_deposit_receipt".as_ref(),
             vault.key().as_ref(),
@@ -44,9 +46,11 @@ pub struct DepositGem<'info> {
         payer = owner,
         space = 8 + std::mem::size_of::<GemDepositReceipt>())]
     pub gem_deposit_receipt: Box<Account<'info, GemDepositReceipt>>,
+    /// CHECK: verified by the token program during the transfer
     #[account(mut)]
-    pub gem_source: Box<Account<'info, TokenAccount>>,
-    pub gem_mint: Box<Account<'info, Mint>>,
+    pub gem_source: AccountInfo<'info>,
+    /// CHECK: unpacked during the transfer
+    pub gem_mint: AccountInfo<'info>,
     // we MUST ask for this PDA both during deposit and withdrawal for sec reasons, even if it's zero'ed
     /// CHECK:
     #[account(seeds = [
@@ -58,60 +62,58 @@ pub struct DepositGem<'info> {
     pub gem_rarity: AccountInfo<'info>,
 
     // misc
-    pub token_program: Program<'info, Token>,
+    /// CHECK: either of the 2 token programs, as long as it's the one owning the mint
+    #[account(constraint = is_token_program(token_program.key)
+        && gem_mint.owner == token_program.key @ ErrorCode::InvalidTokenProgram)]
+    pub token_program: AccountInfo<'info>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
     //
     // remaining accounts could be passed, in this order:
     // - mint_whitelist_proof
     // - gem_metadata <- if we got to this point we can assume gem = NFT, not a fungible token
-    // - creator_whitelist_proof
+    // - creator_whitelist_proof <- only if the bank has creator whitelists
+    // - collection_whitelist_proof <- only if the bank has collection whitelists
 }
 
 impl<'info> DepositGem<'info> {
-    fn transfer_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        CpiContext::new(
-            self.token_program.to_account_info(),
-            Transfer {
-                from: self.gem_source.to_account_info(),
-                to: self.gem_box.to_account_info(),
-                authority: self.owner.to_account_info(),
-            },
+    fn init_gem_box(&self, bump: u8) -> Result<()> {
+        let vault = self.vault.key();
+        let gem_mint = self.gem_mint.key();
+
+        token_interface::create_token_account_pda(
+            &self.owner.to_account_info(),
+            &self.gem_box,
+            &self.gem_mint,
+            &self.authority,
+            &self.token_program,
+            &self.system_program.to_account_info(),
+            &[
+                b"gem_box".as_ref(),
+                vault.as_ref(),
+                gem_mint.as_ref(),
+                &[bump],
+            ],
         )
     }
-}
 
-fn assert_valid_whitelist_proof<'info>(
-    whitelist_proof: &AccountInfo<'info>,
-    bank: &Pubkey,
-    address_to_whitelist: &Pubkey,
-    program_id: &Pubkey,
-    expected_whitelist_type: WhitelistType,
-) -> Result<()> {
-    // 1 verify the PDA seeds match
-    let seed = &[
-        b"whitelist".as_ref(),
-        bank.as_ref(),
-        address_to_whitelist.as_ref(),
-    ];
-    let (whitelist_addr, _bump) = Pubkey::find_program_address(seed, program_id);
-
-    // we can't use an assert_eq statement, we want to catch this error and continue along to creator testing
-    if whitelist_addr != whitelist_proof.key() {
-        return Err(error!(ErrorCode::NotWhitelisted));
+    /// returns the amount that actually arrived in the gem box
+    fn transfer(&self, amount: u64) -> Result<u64> {
+        token_interface::transfer_checked(
+            &self.token_program,
+            &self.gem_source,
+            &self.gem_mint,
+            &self.gem_box,
+            &self.owner.to_account_info(),
+            amount,
+            &[&self.vault.vault_seeds()],
+        )
     }
-
-    // 2 no need to verify ownership, deserialization does that for us
-    // https://github.com/project-serum/anchor/blob/fcb07eb8c3c9355f3cabc00afa4faa6247ccc960/lang/src/account.rs#L36
-    let proof = Account::<'info, WhitelistProof>::try_from(whitelist_proof)?;
-
-    // 3 verify whitelist type matches
-    proof.contains_type(expected_whitelist_type)
 }
 
 fn assert_whitelisted<'info>(ctx: &Context<'_, '_, '_, 'info, DepositGem<'info>>) -> Result<()> {
     let bank = &*ctx.accounts.bank;
-    let mint = &*ctx.accounts.gem_mint;
+    let mint = &ctx.accounts.gem_mint;
     let remaining_accs = &mut ctx.remaining_accounts.iter();
 
     // whitelisted mint is always the 1st optional account
@@ -132,18 +134,23 @@ fn assert_whitelisted<'info>(ctx: &Context<'_, '_, '_, 'info, DepositGem<'info>>
         }
     }
 
-    // if mint verification above failed, attempt to verify based on creator
+    // if mint verification above failed, attempt to verify based on creator / collection
+    if bank.whitelisted_creators == 0 && bank.whitelisted_collections == 0 {
+        return Err(error!(ErrorCode::NotWhitelisted));
+    }
+
+    // both need the metadata, which is expected next
+    let metadata_info = next_account_info(remaining_accs)?;
+
+    // verify metadata is legit
+    let metadata = assert_decode_metadata(&mint.key(), metadata_info)?;
+
     if bank.whitelisted_creators > 0 {
-        // 2 additional accounts are expected - metadata and creator whitelist proof
-        let metadata_info = next_account_info(remaining_accs)?;
         let creator_whitelist_proof_info = next_account_info(remaining_accs)?;
 
-        // verify metadata is legit
-        let metadata = assert_decode_metadata(&mint, &metadata_info)?;
-
         // metaplex constraints this to max 5, so won't go crazy on compute
         // (empirical testing showed there's practically 0 diff between stopping at 0th and 5th creator)
-        for creator in &metadata.data.creators.unwrap() {
+        for creator in metadata.data.creators.iter().flatten() {
             // verify creator actually signed off on this nft
             if !creator.verified {
                 continue;
@@ -167,7 +174,25 @@ fn assert_whitelisted<'info>(ctx: &Context<'_, '_, '_, 'info, DepositGem<'info>>
         }
     }
 
-    // if both conditions above failed tok return Ok(()), then verification failed
+    // if creator verification above failed, attempt to verify based on collection
+    if bank.whitelisted_collections > 0 {
+        let collection_whitelist_proof_info = next_account_info(remaining_accs)?;
+
+        // verify collection actually signed off on this nft
+        if let Some(collection) = metadata.collection.filter(|c| c.verified) {
+            if let Ok(()) = assert_valid_whitelist_proof(
+                collection_whitelist_proof_info,
+                &bank.key(),
+                &collection.key,
+                ctx.program_id,
+                WhitelistType::COLLECTION,
+            ) {
+                return Ok(());
+            }
+        }
+    }
+
+    // if all conditions above failed to return Ok(()), then verification failed
     Err(error!(ErrorCode::NotWhitelisted))
 }
 
@@ -198,7 +223,10 @@ pub fn handler<'info>(
     // if even a single whitelist exists, verify the token against it
     let bank = &*ctx.accounts.bank;
 
-    if bank.whitelisted_mints > 0 || bank.whitelisted_creators > 0 {
+    if bank.whitelisted_mints > 0
+        || bank.whitelisted_creators > 0
+        || bank.whitelisted_collections > 0
+    {
         assert_whitelisted(&ctx)?;
     }
 
@@ -210,13 +238,14 @@ pub fn handler<'info>(
         return Err(error!(ErrorCode::VaultAccessSuspended));
     }
 
-    // do the transfer
-    token::transfer(
+    // create the gem box on first deposit
+    if ctx.accounts.gem_box.data_is_empty() {
         ctx.accounts
-            .transfer_ctx()
-            .with_signer(&[&vault.vault_seeds()]),
-        amount,
-    )?;
+            .init_gem_box(*ctx.bumps.get("gem_box").unwrap())?;
+    }
+
+    // do the transfer - for mints with a transfer fee only the net amount is credited
+    let amount = ctx.accounts.transfer(amount)?;
 
     // record total number of gem boxes in vault's state
     let vault = &mut ctx.accounts.vault;
@@ -228,15 +257,16 @@ pub fn handler<'info>(
 
     // record a gdr
     let gdr = &mut *ctx.accounts.gem_deposit_receipt;
-    let gem_box = &*ctx.accounts.gem_box;
+    let gem_box = &ctx.accounts.gem_box;
 
     gdr.vault = vault.key();
     gdr.gem_box_address = gem_box.key();
-    gdr.gem_mint = gem_box.mint;
+    gdr.gem_mint = ctx.accounts.gem_mint.key();
     gdr.gem_count.try_add_assign(amount)?;
+    gdr.deposited_at_ts = now_ts()?;
 
     // this check is semi-useless but won't hurt
-    if gdr.gem_count != gem_box.amount.try_add(amount)? {
+    if gdr.gem_count != token_interface::unpack_token_account(gem_box)?.amount {
         // msg!("{} {}", gdr.gem_count, gem_box.amount);
         return Err(error!(ErrorCode::AmountMismatch));
     }
@@ -129,35 +129,8 @@ pub struct DepositGemPnft<'info> {
     // remaining accounts could be passed, in this order:
     // - rules account
     // - mint_whitelist_proof
-    // - creator_whitelist_proof
-}
-
-fn assert_valid_whitelist_proof<'info>(
-    whitelist_proof: &AccountInfo<'info>,
-    bank: &Pubkey,
-    address_to_whitelist: &Pubkey,
-    program_id: &Pubkey,
-    expected_whitelist_type: WhitelistType,
-) -> Result<()> {
-    // 1 verify the PDA seeds match
-    let seed = &[
-        b"whitelist".as_ref(),
-        bank.as_ref(),
-        address_to_whitelist.as_ref(),
-    ];
-    let (whitelist_addr, _bump) = Pubkey::find_program_address(seed, program_id);
-
-    // we can't use an assert_eq statement, we want to catch this error and continue along to creator testing
-    if whitelist_addr != whitelist_proof.key() {
-        return Err(error!(ErrorCode::NotWhitelisted));
-    }
-
-    // 2 no need to verify ownership, deserialization does that for us
-    // https://github.com/project-serum/anchor/blob/fcb07eb8c3c9355f3cabc00afa4faa6247ccc960/lang/src/account.rs#L36
-    let proof = Account::<'info, WhitelistProof>::try_from(whitelist_proof)?;
-
-    // 3 verify whitelist type matches
-    proof.contains_type(expected_whitelist_type)
+    // - creator_whitelist_proof <- only if the bank has creator whitelists
+    // - collection_whitelist_proof <- only if the bank has collection whitelists
 }
 
 fn assert_whitelisted<'info>(
@@ -185,16 +158,20 @@ fn assert_whitelisted<'info>(
         }
     }
 
-    // if mint verification above failed, attempt to verify based on creator
+    // if mint verification above failed, attempt to verify based on creator / collection
+    if bank.whitelisted_creators == 0 && bank.whitelisted_collections == 0 {
+        return Err(error!(ErrorCode::NotWhitelisted));
+    }
+
+    //here metadata passed in as a fixed account
+    let metadata = assert_decode_metadata(&mint.key(), &ctx.accounts.gem_metadata)?;
+
     if bank.whitelisted_creators > 0 {
         let creator_whitelist_proof_info = next_account_info(remaining_accs)?;
 
-        //here metadata passed in as a fixed account
-        let metadata = assert_decode_metadata(&mint, &ctx.accounts.gem_metadata)?;
-
         // metaplex constraints this to max 5, so won't go crazy on compute
         // (empirical testing showed there's practically 0 diff between stopping at 0th and 5th creator)
-        for creator in &metadata.data.creators.unwrap() {
+        for creator in metadata.data.creators.iter().flatten() {
             // verify creator actually signed off on this nft
             if !creator.verified {
                 continue;
@@ -218,7 +195,25 @@ fn assert_whitelisted<'info>(
         }
     }
 
-    // if both conditions above failed tok return Ok(()), then verification failed
+    // if creator verification above failed, attempt to verify based on collection
+    if bank.whitelisted_collections > 0 {
+        let collection_whitelist_proof_info = next_account_info(remaining_accs)?;
+
+        // verify collection actually signed off on this nft
+        if let Some(collection) = metadata.collection.filter(|c| c.verified) {
+            if let Ok(()) = assert_valid_whitelist_proof(
+                collection_whitelist_proof_info,
+                &bank.key(),
+                &collection.key,
+                ctx.program_id,
+                WhitelistType::COLLECTION,
+            ) {
+                return Ok(());
+            }
+        }
+    }
+
+    // if all conditions above failed to return Ok(()), then verification failed
     Err(error!(ErrorCode::NotWhitelisted))
 }
 
@@ -269,7 +264,10 @@ pub fn handler<'info>(
     // if even a single whitelist exists, verify the token against it
     let bank = &*ctx.accounts.bank;
 
-    if bank.whitelisted_mints > 0 || bank.whitelisted_creators > 0 {
+    if bank.whitelisted_mints > 0
+        || bank.whitelisted_creators > 0
+        || bank.whitelisted_collections > 0
+    {
         assert_whitelisted(&ctx, rem_acc)?;
     }
 
@@ -297,6 +295,7 @@ pub fn handler<'info>(
     gdr.gem_box_address = gem_box.key();
     gdr.gem_mint = gem_box.mint;
     gdr.gem_count.try_add_assign(amount)?;
+    gdr.deposited_at_ts = now_ts()?;
 
     // this check is semi-useless but won't hurt
     if gdr.gem_count != gem_box.amount.try_add(amount)? {
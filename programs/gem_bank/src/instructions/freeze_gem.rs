@@ -0,0 +1,255 @@
+use std::slice::Iter;
+
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use gem_common::{errors::ErrorCode, *};
+
+use crate::*;
+
+#[derive(Accounts)]
+#[instruction(bump_auth: u8, bump_rarity: u8)]
+pub struct FreezeGem<'info> {
+    // bank
+    pub bank: Box<Account<'info, Bank>>,
+
+    // vault
+    // same rationale for not verifying the PDA as in deposit
+    #[account(mut, has_one = bank, has_one = owner, has_one = authority)]
+    pub vault: Box<Account<'info, Vault>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK:
+    #[account(seeds = [vault.key().as_ref()], bump = bump_auth)]
+    pub authority: AccountInfo<'info>,
+
+    // gem
+    // stays in the owner's wallet, so only a single NFT per token account
+    #[account(mut,
+        constraint = gem_account.owner == owner.key() @ ErrorCode::InvalidParameter,
+        constraint = gem_account.amount == 1 @ ErrorCode::AmountMismatch,
+        token::mint = gem_mint)]
+    pub gem_account: Box<Account<'info, TokenAccount>>,
+    #[account(init, seeds = [
+            b"frozen_gem_receipt".as_ref(),
+            vault.key().as_ref(),
+            gem_mint.key().as_ref(),
+        ],
+        bump,
+        payer = owner,
+        space = 8 + std::mem::size_of::<FrozenGemReceipt>())]
+    pub frozen_gem_receipt: Box<Account<'info, FrozenGemReceipt>>,
+    pub gem_mint: Box<Account<'info, Mint>>,
+    // we MUST ask for this PDA both during deposit and withdrawal for sec reasons, even if it's zero'ed
+    /// CHECK:
+    #[account(seeds = [
+            b"gem_rarity".as_ref(),
+            bank.key().as_ref(),
+            gem_mint.key().as_ref()
+        ],
+        bump = bump_rarity)]
+    pub gem_rarity: AccountInfo<'info>,
+
+    // misc
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    // nft / pnft
+    /// CHECK: assert_decode_metadata + seeds below
+    #[account(
+        mut,
+        seeds=[
+            mpl_token_metadata::state::PREFIX.as_bytes(),
+            mpl_token_metadata::id().as_ref(),
+            gem_mint.key().as_ref(),
+        ],
+        seeds::program = mpl_token_metadata::id(),
+        bump
+    )]
+    pub gem_metadata: UncheckedAccount<'info>,
+
+    //note that MASTER EDITION and EDITION share the same seeds, and so it's valid to check them here
+    /// CHECK: seeds below
+    #[account(
+        seeds=[
+            mpl_token_metadata::state::PREFIX.as_bytes(),
+            mpl_token_metadata::id().as_ref(),
+            gem_mint.key().as_ref(),
+            mpl_token_metadata::state::EDITION.as_bytes(),
+        ],
+        seeds::program = mpl_token_metadata::id(),
+        bump
+    )]
+    pub gem_edition: UncheckedAccount<'info>,
+
+    // only exists for pNFTs
+    /// CHECK: seeds below
+    #[account(mut,
+        seeds=[
+            mpl_token_metadata::state::PREFIX.as_bytes(),
+            mpl_token_metadata::id().as_ref(),
+            gem_mint.key().as_ref(),
+            mpl_token_metadata::state::TOKEN_RECORD_SEED.as_bytes(),
+            gem_account.key().as_ref()
+        ],
+        seeds::program = mpl_token_metadata::id(),
+        bump
+    )]
+    pub gem_token_record: UncheckedAccount<'info>,
+    pub pnft_shared: ProgNftShared<'info>,
+    //
+    // remaining accounts could be passed, in this order:
+    // - rules account
+    // - mint_whitelist_proof
+    // - creator_whitelist_proof <- only if the bank has creator whitelists
+    // - collection_whitelist_proof <- only if the bank has collection whitelists
+}
+
+fn assert_whitelisted<'info>(
+    ctx: &Context<FreezeGem<'info>>,
+    remaining_accs: &mut Iter<AccountInfo<'info>>,
+) -> Result<()> {
+    let bank = &*ctx.accounts.bank;
+    let mint = &*ctx.accounts.gem_mint;
+
+    // whitelisted mint is always the 1st optional account
+    // this is because it's applicable to both NFTs and standard fungible tokens
+    let mint_whitelist_proof_info = next_account_info(remaining_accs)?;
+
+    // attempt to verify based on mint
+    if bank.whitelisted_mints > 0 {
+        if let Ok(()) = assert_valid_whitelist_proof(
+            mint_whitelist_proof_info,
+            &bank.key(),
+            &mint.key(),
+            ctx.program_id,
+            WhitelistType::MINT,
+        ) {
+            // msg!("mint whitelisted: {}, going ahead", &mint.key());
+            return Ok(());
+        }
+    }
+
+    // if mint verification above failed, attempt to verify based on creator / collection
+    if bank.whitelisted_creators == 0 && bank.whitelisted_collections == 0 {
+        return Err(error!(ErrorCode::NotWhitelisted));
+    }
+
+    //here metadata passed in as a fixed account
+    let metadata = assert_decode_metadata(&mint.key(), &ctx.accounts.gem_metadata)?;
+
+    if bank.whitelisted_creators > 0 {
+        let creator_whitelist_proof_info = next_account_info(remaining_accs)?;
+
+        // metaplex constraints this to max 5, so won't go crazy on compute
+        // (empirical testing showed there's practically 0 diff between stopping at 0th and 5th creator)
+        for creator in metadata.data.creators.iter().flatten() {
+            // verify creator actually signed off on this nft
+            if !creator.verified {
+                continue;
+            }
+
+            // check if creator is whitelisted, returns an error if not
+            let attempted_proof = assert_valid_whitelist_proof(
+                creator_whitelist_proof_info,
+                &bank.key(),
+                &creator.address,
+                ctx.program_id,
+                WhitelistType::CREATOR,
+            );
+
+            match attempted_proof {
+                //proof succeeded, return out of the function, no need to continue looping
+                Ok(()) => return Ok(()),
+                //proof failed, continue to check next creator
+                Err(_e) => continue,
+            }
+        }
+    }
+
+    // if creator verification above failed, attempt to verify based on collection
+    if bank.whitelisted_collections > 0 {
+        let collection_whitelist_proof_info = next_account_info(remaining_accs)?;
+
+        // verify collection actually signed off on this nft
+        if let Some(collection) = metadata.collection.filter(|c| c.verified) {
+            if let Ok(()) = assert_valid_whitelist_proof(
+                collection_whitelist_proof_info,
+                &bank.key(),
+                &collection.key,
+                ctx.program_id,
+                WhitelistType::COLLECTION,
+            ) {
+                return Ok(());
+            }
+        }
+    }
+
+    // if all conditions above failed to return Ok(()), then verification failed
+    Err(error!(ErrorCode::NotWhitelisted))
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, FreezeGem<'info>>,
+    authorization_data: Option<AuthorizationDataLocal>,
+    rules_acc_present: bool,
+) -> Result<()> {
+    let rem_acc = &mut ctx.remaining_accounts.iter();
+    let auth_rules = if rules_acc_present {
+        Some(next_account_info(rem_acc)?)
+    } else {
+        None
+    };
+
+    // if even a single whitelist exists, verify the token against it
+    let bank = &*ctx.accounts.bank;
+
+    if bank.whitelisted_mints > 0
+        || bank.whitelisted_creators > 0
+        || bank.whitelisted_collections > 0
+    {
+        assert_whitelisted(&ctx, rem_acc)?;
+    }
+
+    // verify vault not suspended
+    let vault = &ctx.accounts.vault;
+
+    if vault.access_suspended(bank.flags)? {
+        return Err(error!(ErrorCode::VaultAccessSuspended));
+    }
+
+    // freeze the gem where it is
+    freeze_gem_in_place(
+        &ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.authority,
+        &ctx.accounts.gem_account,
+        &ctx.accounts.gem_mint,
+        &ctx.accounts.gem_metadata,
+        &ctx.accounts.gem_edition,
+        &ctx.accounts.gem_token_record,
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+        &ctx.accounts.pnft_shared,
+        auth_rules,
+        authorization_data,
+        vault,
+    )?;
+
+    // counted exactly like a custodial deposit, so farms can't tell the difference
+    let vault = &mut ctx.accounts.vault;
+    vault.gem_box_count.try_add_assign(1)?;
+    vault.gem_count.try_add_assign(1)?;
+    vault
+        .rarity_points
+        .try_add_assign(calc_rarity_points(&ctx.accounts.gem_rarity, 1)?)?;
+    vault.frozen_gem_count.try_add_assign(1)?;
+
+    // record a receipt
+    let receipt = &mut *ctx.accounts.frozen_gem_receipt;
+
+    receipt.vault = vault.key();
+    receipt.gem_account = ctx.accounts.gem_account.key();
+    receipt.gem_mint = ctx.accounts.gem_mint.key();
+
+    // msg!("gem {} frozen in place", receipt.gem_mint);
+    Ok(())
+}
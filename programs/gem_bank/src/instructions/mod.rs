@@ -1,6 +1,14 @@
+pub mod accept_bank_manager;
+pub mod accept_vault_owner;
 pub mod add_to_whitelist;
+pub mod close_bank;
+pub mod close_rarity_points;
+pub mod close_vault;
+pub mod deposit_cnft;
+pub mod deposit_core;
 pub mod deposit_gem;
 pub mod deposit_gem_pnft;
+pub mod freeze_gem;
 pub mod init_bank;
 pub mod init_vault;
 pub mod record_rarity_points;
@@ -8,15 +16,26 @@ pub mod remove_from_whitelist;
 pub mod set_bank_flags;
 pub mod set_vault_lock;
 pub mod shared;
+pub mod thaw_gem;
 pub mod update_bank_manager;
 pub mod update_vault_owner;
+pub mod withdraw_cnft;
+pub mod withdraw_core;
 pub mod withdraw_gem;
 pub mod withdraw_gem_pnft;
 pub mod withdraw_tokens_auth;
 
+pub use accept_bank_manager::*;
+pub use accept_vault_owner::*;
 pub use add_to_whitelist::*;
+pub use close_bank::*;
+pub use close_rarity_points::*;
+pub use close_vault::*;
+pub use deposit_cnft::*;
+pub use deposit_core::*;
 pub use deposit_gem::*;
 pub use deposit_gem_pnft::*;
+pub use freeze_gem::*;
 pub use init_bank::*;
 pub use init_vault::*;
 pub use record_rarity_points::*;
@@ -24,8 +43,11 @@ pub use remove_from_whitelist::*;
 pub use set_bank_flags::*;
 pub use set_vault_lock::*;
 pub use shared::*;
+pub use thaw_gem::*;
 pub use update_bank_manager::*;
 pub use update_vault_owner::*;
+pub use withdraw_cnft::*;
+pub use withdraw_core::*;
 pub use withdraw_gem::*;
 pub use withdraw_gem_pnft::*;
 pub use withdraw_tokens_auth::*;
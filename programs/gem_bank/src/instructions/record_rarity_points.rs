@@ -33,7 +33,7 @@ pub struct RecordRarityPoints<'info> {
     pub system_program: Program<'info, System>,
     //
     // remaining accounts can be any number of:
-    //   pub gem_mint: Box<Account<'info, Mint>>,
+    //   pub gem_mint: Box<Account<'info, Mint>>, (or asset id, for cNFTs / Core assets)
     //   #[account(mut)]
     //   pub gem_rarity: Box<Account<'info, Rarity>>,
 }
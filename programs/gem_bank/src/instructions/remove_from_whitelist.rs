@@ -37,6 +37,9 @@ pub fn handler(ctx: Context<RemoveFromWhitelist>) -> Result<()> {
     if let Ok(()) = proof.contains_type(WhitelistType::CREATOR) {
         bank.whitelisted_creators.try_sub_assign(1)?;
     }
+    if let Ok(()) = proof.contains_type(WhitelistType::COLLECTION) {
+        bank.whitelisted_collections.try_sub_assign(1)?;
+    }
 
     // delete whitelist proof
     close_account(
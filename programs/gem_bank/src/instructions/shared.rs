@@ -1,18 +1,29 @@
-use anchor_lang::solana_program::program::{invoke, invoke_signed};
+use anchor_lang::solana_program::{
+    instruction::{AccountMeta, Instruction},
+    keccak,
+    program::{invoke, invoke_signed},
+};
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{Mint, Token, TokenAccount},
+    token::{self, Approve, Mint, Revoke, Token, TokenAccount},
 };
 use gem_common::errors::ErrorCode;
 use mpl_token_auth_rules::payload::{Payload, PayloadType, ProofInfo, SeedsVec};
 use mpl_token_metadata::{
     self,
-    instruction::{builders::TransferBuilder, InstructionBuilder, TransferArgs},
+    instruction::{
+        builders::{DelegateBuilder, LockBuilder, RevokeBuilder, TransferBuilder, UnlockBuilder},
+        freeze_delegated_account, thaw_delegated_account, DelegateArgs, InstructionBuilder,
+        LockArgs, RevokeArgs, TransferArgs, UnlockArgs,
+    },
     processor::AuthorizationData,
     state::{Metadata, ProgrammableConfig::V1, TokenMetadataAccount, TokenStandard},
 };
 
-use crate::{state::Vault, *};
+use crate::{
+    state::{Vault, WhitelistProof, WhitelistType},
+    *,
+};
 
 #[derive(Accounts)]
 pub struct ProgNftShared<'info> {
@@ -33,14 +44,14 @@ pub struct ProgNftShared<'info> {
 
 #[inline(never)]
 pub fn assert_decode_metadata<'info>(
-    nft_mint: &Account<'info, Mint>,
+    nft_mint: &Pubkey,
     metadata_account: &AccountInfo<'info>,
 ) -> Result<Metadata> {
     let (key, _) = Pubkey::find_program_address(
         &[
             mpl_token_metadata::state::PREFIX.as_bytes(),
             mpl_token_metadata::id().as_ref(),
-            nft_mint.key().as_ref(),
+            nft_mint.as_ref(),
         ],
         &mpl_token_metadata::id(),
     );
@@ -130,7 +141,7 @@ pub fn send_pnft<'info>(
         //passed in below, if needed
     ];
 
-    let metadata = assert_decode_metadata(nft_mint, &nft_metadata.to_account_info())?;
+    let metadata = assert_decode_metadata(&nft_mint.key(), &nft_metadata.to_account_info())?;
     if let Some(standard) = metadata.token_standard {
         if standard == TokenStandard::ProgrammableNonFungible {
             msg!("programmable standard triggered");
@@ -189,6 +200,677 @@ pub fn send_pnft<'info>(
     Ok(())
 }
 
+// --------------------------------------- frozen (non-custodial) gems
+//the gem never leaves the owner's wallet - vault's authority becomes its delegate and freezes it in place
+//pNFTs go through token metadata's staking delegate + Lock, regular NFTs through spl approve + the edition freeze
+
+fn is_programmable(metadata: &Metadata) -> bool {
+    metadata.token_standard == Some(TokenStandard::ProgrammableNonFungible)
+}
+
+//pNFTs with a ruleset need it passed in for every action, not just transfers
+fn assert_rule_set<'a, 'info>(
+    metadata: &Metadata,
+    rules_acc: Option<&'a AccountInfo<'info>>,
+) -> Result<Option<&'a AccountInfo<'info>>> {
+    match &metadata.programmable_config {
+        Some(V1 {
+            rule_set: Some(rule_set),
+        }) => match rules_acc {
+            Some(rules_acc) if rules_acc.key == rule_set => Ok(Some(rules_acc)),
+            _ => Err(error!(ErrorCode::BadRuleset)),
+        },
+        _ => Ok(None),
+    }
+}
+
+#[inline(never)]
+#[allow(clippy::too_many_arguments)]
+pub fn freeze_gem_in_place<'info>(
+    //(!) also pays for the token record updates, so can't carry data, see send_pnft
+    owner: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    gem_account: &Account<'info, TokenAccount>,
+    gem_mint: &Account<'info, Mint>,
+    gem_metadata: &UncheckedAccount<'info>,
+    gem_edition: &UncheckedAccount<'info>,
+    gem_token_record: &UncheckedAccount<'info>,
+    system_program: &Program<'info, System>,
+    token_program: &Program<'info, Token>,
+    pnft_shared: &ProgNftShared<'info>,
+    rules_acc: Option<&AccountInfo<'info>>,
+    authorization_data: Option<AuthorizationDataLocal>,
+    vault: &Account<'info, Vault>,
+) -> Result<()> {
+    let metadata = assert_decode_metadata(&gem_mint.key(), gem_metadata)?;
+
+    if !is_programmable(&metadata) {
+        // 1 owner makes vault's authority the delegate
+        token::approve(
+            CpiContext::new(
+                token_program.to_account_info(),
+                Approve {
+                    to: gem_account.to_account_info(),
+                    delegate: authority.clone(),
+                    authority: owner.clone(),
+                },
+            ),
+            1,
+        )?;
+
+        // 2 which then freezes the account through the edition (the mint's freeze authority)
+        invoke_signed(
+            &freeze_delegated_account(
+                mpl_token_metadata::id(),
+                authority.key(),
+                gem_account.key(),
+                gem_edition.key(),
+                gem_mint.key(),
+            ),
+            &[
+                authority.clone(),
+                gem_account.to_account_info(),
+                gem_edition.to_account_info(),
+                gem_mint.to_account_info(),
+                token_program.to_account_info(),
+            ],
+            &[&vault.vault_seeds()],
+        )?;
+
+        return Ok(());
+    }
+
+    let rules_acc = assert_rule_set(&metadata, rules_acc)?;
+    let authorization_data = authorization_data.map(AuthorizationData::from);
+
+    let mut account_infos = vec![
+        pnft_shared.token_metadata_program.to_account_info(),
+        owner.clone(),
+        authority.clone(),
+        gem_account.to_account_info(),
+        gem_mint.to_account_info(),
+        gem_metadata.to_account_info(),
+        gem_edition.to_account_info(),
+        gem_token_record.to_account_info(),
+        system_program.to_account_info(),
+        pnft_shared.instructions.to_account_info(),
+        token_program.to_account_info(),
+        pnft_shared.authorization_rules_program.to_account_info(),
+    ];
+    if let Some(rules_acc) = rules_acc {
+        account_infos.push(rules_acc.clone());
+    }
+
+    // 1 owner makes vault's authority the staking delegate
+    let mut delegate = DelegateBuilder::new();
+    delegate
+        .delegate(authority.key())
+        .metadata(gem_metadata.key())
+        .master_edition(gem_edition.key())
+        .token_record(gem_token_record.key())
+        .mint(gem_mint.key())
+        .token(gem_account.key())
+        .authority(owner.key())
+        .payer(owner.key())
+        .spl_token_program(token_program.key());
+    if let Some(rules_acc) = rules_acc {
+        delegate.authorization_rules(rules_acc.key());
+    }
+    let delegate_ix = delegate
+        .build(DelegateArgs::StakingV1 {
+            amount: 1,
+            authorization_data: authorization_data.clone(),
+        })
+        .unwrap()
+        .instruction();
+
+    invoke(&delegate_ix, &account_infos)?;
+
+    // 2 which then locks it
+    let mut lock = LockBuilder::new();
+    lock.authority(authority.key())
+        .token_owner(owner.key())
+        .token(gem_account.key())
+        .mint(gem_mint.key())
+        .metadata(gem_metadata.key())
+        .edition(gem_edition.key())
+        .token_record(gem_token_record.key())
+        .payer(owner.key())
+        .spl_token_program(token_program.key());
+    if let Some(rules_acc) = rules_acc {
+        lock.authorization_rules(rules_acc.key());
+    }
+    let lock_ix = lock
+        .build(LockArgs::V1 { authorization_data })
+        .unwrap()
+        .instruction();
+
+    invoke_signed(&lock_ix, &account_infos, &[&vault.vault_seeds()])?;
+
+    Ok(())
+}
+
+#[inline(never)]
+#[allow(clippy::too_many_arguments)]
+pub fn thaw_gem_in_place<'info>(
+    //has to still hold the gem - only the holder can revoke the delegate
+    owner: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    gem_account: &Account<'info, TokenAccount>,
+    gem_mint: &Account<'info, Mint>,
+    gem_metadata: &UncheckedAccount<'info>,
+    gem_edition: &UncheckedAccount<'info>,
+    gem_token_record: &UncheckedAccount<'info>,
+    system_program: &Program<'info, System>,
+    token_program: &Program<'info, Token>,
+    pnft_shared: &ProgNftShared<'info>,
+    rules_acc: Option<&AccountInfo<'info>>,
+    authorization_data: Option<AuthorizationDataLocal>,
+    vault: &Account<'info, Vault>,
+) -> Result<()> {
+    let metadata = assert_decode_metadata(&gem_mint.key(), gem_metadata)?;
+
+    if !is_programmable(&metadata) {
+        // 1 vault's authority thaws the account
+        invoke_signed(
+            &thaw_delegated_account(
+                mpl_token_metadata::id(),
+                authority.key(),
+                gem_account.key(),
+                gem_edition.key(),
+                gem_mint.key(),
+            ),
+            &[
+                authority.clone(),
+                gem_account.to_account_info(),
+                gem_edition.to_account_info(),
+                gem_mint.to_account_info(),
+                token_program.to_account_info(),
+            ],
+            &[&vault.vault_seeds()],
+        )?;
+
+        // 2 and the owner takes back the delegation
+        token::revoke(CpiContext::new(
+            token_program.to_account_info(),
+            Revoke {
+                source: gem_account.to_account_info(),
+                authority: owner.clone(),
+            },
+        ))?;
+
+        return Ok(());
+    }
+
+    let rules_acc = assert_rule_set(&metadata, rules_acc)?;
+
+    let mut account_infos = vec![
+        pnft_shared.token_metadata_program.to_account_info(),
+        owner.clone(),
+        authority.clone(),
+        gem_account.to_account_info(),
+        gem_mint.to_account_info(),
+        gem_metadata.to_account_info(),
+        gem_edition.to_account_info(),
+        gem_token_record.to_account_info(),
+        system_program.to_account_info(),
+        pnft_shared.instructions.to_account_info(),
+        token_program.to_account_info(),
+        pnft_shared.authorization_rules_program.to_account_info(),
+    ];
+    if let Some(rules_acc) = rules_acc {
+        account_infos.push(rules_acc.clone());
+    }
+
+    // 1 vault's authority unlocks the pNFT
+    let mut unlock = UnlockBuilder::new();
+    unlock
+        .authority(authority.key())
+        .token_owner(owner.key())
+        .token(gem_account.key())
+        .mint(gem_mint.key())
+        .metadata(gem_metadata.key())
+        .edition(gem_edition.key())
+        .token_record(gem_token_record.key())
+        .payer(owner.key())
+        .spl_token_program(token_program.key());
+    if let Some(rules_acc) = rules_acc {
+        unlock.authorization_rules(rules_acc.key());
+    }
+    let unlock_ix = unlock
+        .build(UnlockArgs::V1 {
+            authorization_data: authorization_data.map(AuthorizationData::from),
+        })
+        .unwrap()
+        .instruction();
+
+    invoke_signed(&unlock_ix, &account_infos, &[&vault.vault_seeds()])?;
+
+    // 2 and the owner takes back the delegation
+    let mut revoke = RevokeBuilder::new();
+    revoke
+        .delegate(authority.key())
+        .metadata(gem_metadata.key())
+        .master_edition(gem_edition.key())
+        .token_record(gem_token_record.key())
+        .mint(gem_mint.key())
+        .token(gem_account.key())
+        .authority(owner.key())
+        .payer(owner.key())
+        .spl_token_program(token_program.key());
+    if let Some(rules_acc) = rules_acc {
+        revoke.authorization_rules(rules_acc.key());
+    }
+    let revoke_ix = revoke.build(RevokeArgs::StakingV1).unwrap().instruction();
+
+    invoke(&revoke_ix, &account_infos)?;
+
+    Ok(())
+}
+
+// --------------------------------------- compressed nfts (bubblegum)
+//no bubblegum crate that plays nice with our anchor version, so the transfer CPI is built by hand
+
+pub mod bubblegum_program {
+    anchor_lang::declare_id!("BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY");
+}
+pub mod account_compression_program {
+    anchor_lang::declare_id!("cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK");
+}
+pub mod noop_program {
+    anchor_lang::declare_id!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+}
+
+//sha256("global:transfer")[..8]
+const BUBBLEGUM_TRANSFER_DISCRIMINATOR: [u8; 8] = [163, 52, 200, 231, 140, 3, 69, 186];
+
+#[derive(Accounts)]
+pub struct CnftShared<'info> {
+    /// CHECK: address below
+    #[account(address = bubblegum_program::ID)]
+    pub bubblegum_program: UncheckedAccount<'info>,
+
+    /// CHECK: address below
+    #[account(address = account_compression_program::ID)]
+    pub compression_program: UncheckedAccount<'info>,
+
+    /// CHECK: address below
+    #[account(address = noop_program::ID)]
+    pub log_wrapper: UncheckedAccount<'info>,
+}
+
+/// everything needed to prove a leaf, apart from the root + proof path (which change all the time)
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone, Copy)]
+pub struct CnftLeaf {
+    pub data_hash: [u8; 32],
+    pub creator_hash: [u8; 32],
+    pub nonce: u64,
+    pub index: u32,
+}
+
+/// same as the creator stored in bubblegum's metadata args
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Clone)]
+pub struct CnftCreator {
+    pub address: Pubkey,
+    pub verified: bool,
+    pub share: u8,
+}
+
+pub fn get_cnft_asset_id(merkle_tree: &Pubkey, nonce: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"asset", merkle_tree.as_ref(), &nonce.to_le_bytes()],
+        &bubblegum_program::ID,
+    )
+    .0
+}
+
+/// mirrors how bubblegum computes the creator hash stored in the leaf
+pub fn hash_cnft_creators(creators: &[CnftCreator]) -> [u8; 32] {
+    let creator_data = creators
+        .iter()
+        .map(|c| [c.address.as_ref(), &[c.verified as u8], &[c.share]].concat())
+        .collect::<Vec<_>>();
+
+    keccak::hashv(
+        &creator_data
+            .iter()
+            .map(|c| c.as_slice())
+            .collect::<Vec<&[u8]>>(),
+    )
+    .to_bytes()
+}
+
+//bubblegum's TransferArgs, in order
+fn bubblegum_transfer_data(root: [u8; 32], leaf: &CnftLeaf) -> Vec<u8> {
+    let mut data = BUBBLEGUM_TRANSFER_DISCRIMINATOR.to_vec();
+    data.extend_from_slice(&root);
+    data.extend_from_slice(&leaf.data_hash);
+    data.extend_from_slice(&leaf.creator_hash);
+    data.extend_from_slice(&leaf.nonce.to_le_bytes());
+    data.extend_from_slice(&leaf.index.to_le_bytes());
+    data
+}
+
+#[inline(never)]
+#[allow(clippy::too_many_arguments)]
+pub fn send_cnft<'info>(
+    //always the owner signing, never the delegate
+    leaf_owner: &AccountInfo<'info>,
+    //part of the leaf hash, so has to be passed even if not signing (= owner if not set)
+    leaf_delegate: &AccountInfo<'info>,
+    new_leaf_owner: &AccountInfo<'info>,
+    tree_authority: &AccountInfo<'info>,
+    merkle_tree: &AccountInfo<'info>,
+    shared: &CnftShared<'info>,
+    system_program: &Program<'info, System>,
+    proof: &[AccountInfo<'info>],
+    root: [u8; 32],
+    leaf: &CnftLeaf,
+    //if passed, use signed_invoke() instead of invoke()
+    vault: Option<&Account<'info, Vault>>,
+) -> Result<()> {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*tree_authority.key, false),
+        AccountMeta::new_readonly(*leaf_owner.key, true),
+        AccountMeta::new_readonly(*leaf_delegate.key, false),
+        AccountMeta::new_readonly(*new_leaf_owner.key, false),
+        AccountMeta::new(*merkle_tree.key, false),
+        AccountMeta::new_readonly(*shared.log_wrapper.key, false),
+        AccountMeta::new_readonly(*shared.compression_program.key, false),
+        AccountMeta::new_readonly(*system_program.key, false),
+    ];
+    accounts.extend(
+        proof
+            .iter()
+            .map(|node| AccountMeta::new_readonly(*node.key, false)),
+    );
+
+    let transfer_ix = Instruction {
+        program_id: bubblegum_program::ID,
+        accounts,
+        data: bubblegum_transfer_data(root, leaf),
+    };
+
+    let mut account_infos = vec![
+        tree_authority.clone(),
+        leaf_owner.clone(),
+        leaf_delegate.clone(),
+        new_leaf_owner.clone(),
+        merkle_tree.clone(),
+        shared.log_wrapper.to_account_info(),
+        shared.compression_program.to_account_info(),
+        system_program.to_account_info(),
+        shared.bubblegum_program.to_account_info(),
+    ];
+    account_infos.extend_from_slice(proof);
+
+    if let Some(vault) = vault {
+        invoke_signed(&transfer_ix, &account_infos, &[&vault.vault_seeds()])?;
+    } else {
+        invoke(&transfer_ix, &account_infos)?;
+    }
+
+    Ok(())
+}
+
+// --------------------------------------- whitelists
+
+pub fn assert_valid_whitelist_proof<'info>(
+    whitelist_proof: &AccountInfo<'info>,
+    bank: &Pubkey,
+    address_to_whitelist: &Pubkey,
+    program_id: &Pubkey,
+    expected_whitelist_type: WhitelistType,
+) -> Result<()> {
+    // 1 verify the PDA seeds match
+    let seed = &[
+        b"whitelist".as_ref(),
+        bank.as_ref(),
+        address_to_whitelist.as_ref(),
+    ];
+    let (whitelist_addr, _bump) = Pubkey::find_program_address(seed, program_id);
+
+    // we can't use an assert_eq statement, we want to catch this error and continue along to creator testing
+    if whitelist_addr != whitelist_proof.key() {
+        return Err(error!(ErrorCode::NotWhitelisted));
+    }
+
+    // 2 no need to verify ownership, deserialization does that for us
+    // https://github.com/project-serum/anchor/blob/fcb07eb8c3c9355f3cabc00afa4faa6247ccc960/lang/src/account.rs#L36
+    let proof = Account::<'info, WhitelistProof>::try_from(whitelist_proof)?;
+
+    // 3 verify whitelist type matches
+    proof.contains_type(expected_whitelist_type)
+}
+
+// --------------------------------------- metaplex core
+//same story as bubblegum - the transfer CPI and the (few) asset fields we need are done by hand
+
+pub mod mpl_core_program {
+    anchor_lang::declare_id!("CoREENxT6tW1HoK8ypY1SxRMZTcVPm7R94rH4PZNhX7d");
+}
+
+//index of TransferV1 in core's instruction enum
+const CORE_TRANSFER_V1_DISCRIMINATOR: u8 = 14;
+const CORE_ASSET_V1_KEY: u8 = 1;
+const CORE_PLUGIN_HEADER_V1_KEY: u8 = 3;
+const CORE_PLUGIN_REGISTRY_V1_KEY: u8 = 4;
+const CORE_COLLECTION_V1_KEY: u8 = 5;
+//plugins that let their authority move / burn the asset out of the vault at any time
+const CORE_PERMANENT_TRANSFER_DELEGATE: u8 = 7;
+const CORE_PERMANENT_BURN_DELEGATE: u8 = 8;
+
+#[derive(AnchorDeserialize)]
+enum CoreUpdateAuthority {
+    None,
+    Address(Pubkey),
+    Collection(Pubkey),
+}
+
+#[derive(AnchorDeserialize)]
+struct CoreAssetBaseV1 {
+    key: u8,
+    owner: Pubkey,
+    update_authority: CoreUpdateAuthority,
+    _name: String,
+    _uri: String,
+    _seq: Option<u64>,
+}
+
+#[derive(AnchorDeserialize)]
+struct CoreCollectionBaseV1 {
+    key: u8,
+    _update_authority: Pubkey,
+    _name: String,
+    _uri: String,
+    _num_minted: u32,
+    _current_size: u32,
+}
+
+#[derive(AnchorDeserialize)]
+struct CorePluginHeaderV1 {
+    key: u8,
+    plugin_registry_offset: u64,
+}
+
+#[derive(AnchorDeserialize)]
+enum CoreAuthority {
+    None,
+    Owner,
+    UpdateAuthority,
+    Address { _address: Pubkey },
+}
+
+#[derive(AnchorDeserialize)]
+struct CoreRegistryRecord {
+    plugin_type: u8,
+    _authority: CoreAuthority,
+    _offset: u64,
+}
+
+pub struct CoreAsset {
+    pub owner: Pubkey,
+    /// core assets are part of a collection iff their update authority is that collection
+    pub collection: Option<Pubkey>,
+    pub plugin_types: Vec<u8>,
+}
+
+/// plugins live after the base account data, behind a header pointing to their registry
+fn decode_core_plugin_types(data: &[u8], base_len: usize) -> Result<Vec<u8>> {
+    let invalid = || error!(ErrorCode::InvalidCoreAsset);
+
+    //no header = no plugins
+    if data.len() == base_len {
+        return Ok(vec![]);
+    }
+
+    let header = CorePluginHeaderV1::deserialize(&mut &data[base_len..]).map_err(|_| invalid())?;
+    if header.key != CORE_PLUGIN_HEADER_V1_KEY {
+        return Err(invalid());
+    }
+
+    let mut registry = usize::try_from(header.plugin_registry_offset)
+        .ok()
+        .and_then(|offset| data.get(offset..))
+        .ok_or_else(invalid)?;
+    if u8::deserialize(&mut registry).map_err(|_| invalid())? != CORE_PLUGIN_REGISTRY_V1_KEY {
+        return Err(invalid());
+    }
+    let records = Vec::<CoreRegistryRecord>::deserialize(&mut registry).map_err(|_| invalid())?;
+
+    Ok(records.iter().map(|r| r.plugin_type).collect())
+}
+
+fn decode_core_asset(data: &[u8]) -> Result<CoreAsset> {
+    let mut rest = data;
+    let base =
+        CoreAssetBaseV1::deserialize(&mut rest).map_err(|_| error!(ErrorCode::InvalidCoreAsset))?;
+    if base.key != CORE_ASSET_V1_KEY {
+        return Err(error!(ErrorCode::InvalidCoreAsset));
+    }
+
+    let collection = match base.update_authority {
+        CoreUpdateAuthority::Collection(collection) => Some(collection),
+        _ => None,
+    };
+    let plugin_types = decode_core_plugin_types(data, data.len() - rest.len())?;
+
+    Ok(CoreAsset {
+        owner: base.owner,
+        collection,
+        plugin_types,
+    })
+}
+
+fn decode_core_collection_plugin_types(data: &[u8]) -> Result<Vec<u8>> {
+    let mut rest = data;
+    let base = CoreCollectionBaseV1::deserialize(&mut rest)
+        .map_err(|_| error!(ErrorCode::InvalidCoreAsset))?;
+    if base.key != CORE_COLLECTION_V1_KEY {
+        return Err(error!(ErrorCode::InvalidCoreAsset));
+    }
+
+    decode_core_plugin_types(data, data.len() - rest.len())
+}
+
+pub fn assert_decode_core_asset(asset: &AccountInfo) -> Result<CoreAsset> {
+    if *asset.owner != mpl_core_program::ID {
+        return Err(error!(ErrorCode::InvalidCoreAsset));
+    }
+    decode_core_asset(&asset.try_borrow_data()?)
+}
+
+fn has_permanent_delegate(plugin_types: &[u8]) -> bool {
+    plugin_types
+        .iter()
+        .any(|t| *t == CORE_PERMANENT_TRANSFER_DELEGATE || *t == CORE_PERMANENT_BURN_DELEGATE)
+}
+
+/// permanent delegates on the collection apply to every asset in it, so both get checked
+/// (!) call assert_core_collection_matches first
+pub fn assert_no_core_permanent_delegates(
+    core_asset: &CoreAsset,
+    collection: Option<&AccountInfo>,
+) -> Result<()> {
+    if has_permanent_delegate(&core_asset.plugin_types) {
+        return Err(error!(ErrorCode::UnsupportedCorePlugin));
+    }
+
+    if let Some(collection) = collection {
+        if *collection.owner != mpl_core_program::ID {
+            return Err(error!(ErrorCode::InvalidCoreAsset));
+        }
+        let plugin_types = decode_core_collection_plugin_types(&collection.try_borrow_data()?)?;
+        if has_permanent_delegate(&plugin_types) {
+            return Err(error!(ErrorCode::UnsupportedCorePlugin));
+        }
+    }
+    Ok(())
+}
+
+/// core wants the collection passed in whenever the asset is part of one
+pub fn assert_core_collection_matches(
+    core_asset: &CoreAsset,
+    collection: Option<&AccountInfo>,
+) -> Result<()> {
+    if core_asset.collection != collection.map(|c| c.key()) {
+        return Err(error!(ErrorCode::InvalidCoreAsset));
+    }
+    Ok(())
+}
+
+#[inline(never)]
+#[allow(clippy::too_many_arguments)]
+pub fn send_core_asset<'info>(
+    asset: &AccountInfo<'info>,
+    collection: Option<&AccountInfo<'info>>,
+    //(!) payer can't carry data, see send_pnft
+    payer: &AccountInfo<'info>,
+    authority_and_owner: &AccountInfo<'info>,
+    new_owner: &AccountInfo<'info>,
+    core_program: &AccountInfo<'info>,
+    //if passed, use signed_invoke() instead of invoke()
+    vault: Option<&Account<'info, Vault>>,
+) -> Result<()> {
+    //core expects its own program id in place of any optional accounts that are missing
+    let accounts = vec![
+        AccountMeta::new(*asset.key, false),
+        AccountMeta::new_readonly(collection.map_or(mpl_core_program::ID, |c| *c.key), false),
+        AccountMeta::new(*payer.key, true),
+        AccountMeta::new_readonly(*authority_and_owner.key, true),
+        AccountMeta::new_readonly(*new_owner.key, false),
+        //system program & log wrapper
+        AccountMeta::new_readonly(mpl_core_program::ID, false),
+        AccountMeta::new_readonly(mpl_core_program::ID, false),
+    ];
+
+    //TransferV1Args { compression_proof: None }
+    let transfer_ix = Instruction {
+        program_id: mpl_core_program::ID,
+        accounts,
+        data: vec![CORE_TRANSFER_V1_DISCRIMINATOR, 0],
+    };
+
+    let mut account_infos = vec![
+        asset.clone(),
+        payer.clone(),
+        authority_and_owner.clone(),
+        new_owner.clone(),
+        core_program.clone(),
+    ];
+    if let Some(collection) = collection {
+        account_infos.push(collection.clone());
+    }
+
+    if let Some(vault) = vault {
+        invoke_signed(&transfer_ix, &account_infos, &[&vault.vault_seeds()])?;
+    } else {
+        invoke(&transfer_ix, &account_infos)?;
+    }
+
+    Ok(())
+}
+
 // --------------------------------------- replicating mplex type for anchor IDL export
 //have to do this because anchor won't include foreign structs in the IDL
 
@@ -263,3 +945,136 @@ impl From<ProofInfoLocal> for ProofInfo {
         ProofInfo { proof: val.proof }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anchor_lang::solana_program::hash;
+
+    use super::*;
+
+    #[test]
+    fn test_bubblegum_transfer_data() {
+        assert_eq!(
+            BUBBLEGUM_TRANSFER_DISCRIMINATOR,
+            hash::hash(b"global:transfer").to_bytes()[..8]
+        );
+
+        let leaf = CnftLeaf {
+            data_hash: [1; 32],
+            creator_hash: [2; 32],
+            nonce: 3,
+            index: 4,
+        };
+        let data = bubblegum_transfer_data([9; 32], &leaf);
+
+        assert_eq!(data.len(), 8 + 32 + 32 + 32 + 8 + 4);
+        assert_eq!(data[8..40], [9; 32]);
+        assert_eq!(data[104..112], 3u64.to_le_bytes());
+        assert_eq!(data[112..], 4u32.to_le_bytes());
+    }
+
+    #[test]
+    fn test_hash_cnft_creators() {
+        let address = Pubkey::new_unique();
+        let creators = vec![CnftCreator {
+            address,
+            verified: true,
+            share: 100,
+        }];
+
+        assert_eq!(
+            hash_cnft_creators(&creators),
+            keccak::hashv(&[&[address.as_ref(), &[1], &[100]].concat()]).to_bytes()
+        );
+        assert_eq!(hash_cnft_creators(&[]), keccak::hashv(&[]).to_bytes());
+    }
+
+    fn with_plugins(mut data: Vec<u8>, plugin_types: &[u8]) -> Vec<u8> {
+        if plugin_types.is_empty() {
+            return data;
+        }
+
+        //registry goes right after the header
+        let registry_offset = data.len() + 9;
+        data.push(CORE_PLUGIN_HEADER_V1_KEY);
+        data.extend((registry_offset as u64).to_le_bytes());
+
+        data.push(CORE_PLUGIN_REGISTRY_V1_KEY);
+        data.extend((plugin_types.len() as u32).to_le_bytes());
+        for plugin_type in plugin_types {
+            data.push(*plugin_type);
+            data.push(1); //owner authority
+            data.extend(0u64.to_le_bytes());
+        }
+        data.extend(0u32.to_le_bytes()); //external registry
+        data
+    }
+
+    fn with_name_and_uri(mut data: Vec<u8>) -> Vec<u8> {
+        for s in ["name", "uri"] {
+            data.extend((s.len() as u32).to_le_bytes());
+            data.extend(s.as_bytes());
+        }
+        data
+    }
+
+    fn asset_data(collection: Option<Pubkey>, plugin_types: &[u8]) -> Vec<u8> {
+        let mut data = vec![CORE_ASSET_V1_KEY];
+        data.extend(Pubkey::new_unique().to_bytes());
+        match collection {
+            Some(collection) => {
+                data.push(2);
+                data.extend(collection.to_bytes());
+            }
+            None => data.push(0),
+        }
+        let mut data = with_name_and_uri(data);
+        data.push(0); //no seq
+        with_plugins(data, plugin_types)
+    }
+
+    fn collection_data(plugin_types: &[u8]) -> Vec<u8> {
+        let mut data = vec![CORE_COLLECTION_V1_KEY];
+        data.extend(Pubkey::new_unique().to_bytes());
+        let mut data = with_name_and_uri(data);
+        data.extend([0; 8]); //num minted & current size
+        with_plugins(data, plugin_types)
+    }
+
+    #[test]
+    fn test_decode_core_asset() {
+        let collection = Pubkey::new_unique();
+
+        let asset = decode_core_asset(&asset_data(None, &[])).unwrap();
+        assert_eq!(asset.collection, None);
+        assert!(asset.plugin_types.is_empty());
+
+        let asset = decode_core_asset(&asset_data(Some(collection), &[0, 6])).unwrap();
+        assert_eq!(asset.collection, Some(collection));
+        assert_eq!(asset.plugin_types, vec![0, 6]);
+        assert!(!has_permanent_delegate(&asset.plugin_types));
+
+        // a truncated registry
+        let mut data = asset_data(None, &[CORE_PERMANENT_TRANSFER_DELEGATE]);
+        data.truncate(data.len() - 20);
+        assert!(decode_core_asset(&data).is_err());
+    }
+
+    #[test]
+    fn test_core_permanent_delegates() {
+        for plugin_type in [
+            CORE_PERMANENT_TRANSFER_DELEGATE,
+            CORE_PERMANENT_BURN_DELEGATE,
+        ] {
+            let asset = decode_core_asset(&asset_data(None, &[0, plugin_type])).unwrap();
+            assert!(has_permanent_delegate(&asset.plugin_types));
+
+            let plugin_types =
+                decode_core_collection_plugin_types(&collection_data(&[plugin_type]));
+            assert!(has_permanent_delegate(&plugin_types.unwrap()));
+        }
+        assert!(decode_core_collection_plugin_types(&collection_data(&[]))
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Mint, Token, TokenAccount};
+use gem_common::{errors::ErrorCode, *};
+
+use crate::*;
+
+#[derive(Accounts)]
+#[instruction(bump_auth: u8, bump_receipt: u8, bump_rarity: u8)]
+pub struct ThawGem<'info> {
+    // bank
+    pub bank: Box<Account<'info, Bank>>,
+
+    // vault
+    // same rationale for not verifying the PDA as in deposit
+    #[account(mut, has_one = bank, has_one = owner, has_one = authority)]
+    pub vault: Box<Account<'info, Vault>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK:
+    #[account(seeds = [vault.key().as_ref()], bump = bump_auth)]
+    pub authority: AccountInfo<'info>,
+
+    // gem
+    // unlike with withdrawals, the gem can't go anywhere else - it never left the owner's wallet
+    #[account(mut)]
+    pub gem_account: Box<Account<'info, TokenAccount>>,
+    #[account(mut, has_one = vault, has_one = gem_account, has_one = gem_mint, seeds = [
+            b"frozen_gem_receipt".as_ref(),
+            vault.key().as_ref(),
+            gem_mint.key().as_ref(),
+        ],
+        bump = bump_receipt)]
+    pub frozen_gem_receipt: Box<Account<'info, FrozenGemReceipt>>,
+    pub gem_mint: Box<Account<'info, Mint>>,
+    // we MUST ask for this PDA both during deposit and withdrawal for sec reasons, even if it's zero'ed
+    /// CHECK:
+    #[account(seeds = [
+            b"gem_rarity".as_ref(),
+            bank.key().as_ref(),
+            gem_mint.key().as_ref()
+        ],
+        bump = bump_rarity)]
+    pub gem_rarity: AccountInfo<'info>,
+
+    // misc
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+
+    // nft / pnft
+    /// CHECK: assert_decode_metadata + seeds below
+    #[account(
+        mut,
+        seeds=[
+            mpl_token_metadata::state::PREFIX.as_bytes(),
+            mpl_token_metadata::id().as_ref(),
+            gem_mint.key().as_ref(),
+        ],
+        seeds::program = mpl_token_metadata::id(),
+        bump
+    )]
+    pub gem_metadata: UncheckedAccount<'info>,
+
+    //note that MASTER EDITION and EDITION share the same seeds, and so it's valid to check them here
+    /// CHECK: seeds below
+    #[account(
+        seeds=[
+            mpl_token_metadata::state::PREFIX.as_bytes(),
+            mpl_token_metadata::id().as_ref(),
+            gem_mint.key().as_ref(),
+            mpl_token_metadata::state::EDITION.as_bytes(),
+        ],
+        seeds::program = mpl_token_metadata::id(),
+        bump
+    )]
+    pub gem_edition: UncheckedAccount<'info>,
+
+    // only exists for pNFTs
+    /// CHECK: seeds below
+    #[account(mut,
+        seeds=[
+            mpl_token_metadata::state::PREFIX.as_bytes(),
+            mpl_token_metadata::id().as_ref(),
+            gem_mint.key().as_ref(),
+            mpl_token_metadata::state::TOKEN_RECORD_SEED.as_bytes(),
+            gem_account.key().as_ref()
+        ],
+        seeds::program = mpl_token_metadata::id(),
+        bump
+    )]
+    pub gem_token_record: UncheckedAccount<'info>,
+    pub pnft_shared: ProgNftShared<'info>,
+    //
+    // remaining accounts could be passed, in this order:
+    // - rules account
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, ThawGem<'info>>,
+    authorization_data: Option<AuthorizationDataLocal>,
+    rules_acc_present: bool,
+) -> Result<()> {
+    // verify vault not suspended
+    let bank = &*ctx.accounts.bank;
+    let vault = &ctx.accounts.vault;
+
+    if vault.access_suspended(bank.flags)? {
+        return Err(error!(ErrorCode::VaultAccessSuspended));
+    }
+
+    // thaw the gem
+    let rem_acc = &mut ctx.remaining_accounts.iter();
+    let auth_rules = if rules_acc_present {
+        Some(next_account_info(rem_acc)?)
+    } else {
+        None
+    };
+
+    thaw_gem_in_place(
+        &ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.authority,
+        &ctx.accounts.gem_account,
+        &ctx.accounts.gem_mint,
+        &ctx.accounts.gem_metadata,
+        &ctx.accounts.gem_edition,
+        &ctx.accounts.gem_token_record,
+        &ctx.accounts.system_program,
+        &ctx.accounts.token_program,
+        &ctx.accounts.pnft_shared,
+        auth_rules,
+        authorization_data,
+        vault,
+    )?;
+
+    // close the receipt, and return funds to user
+    let owner = &mut ctx.accounts.owner.to_account_info();
+    let receipt = &mut (*ctx.accounts.frozen_gem_receipt).to_account_info();
+
+    close_account(receipt, owner)?;
+
+    // decrement counts stored in vault's state
+    let vault = &mut ctx.accounts.vault;
+    vault.gem_box_count.try_sub_assign(1)?;
+    vault.gem_count.try_sub_assign(1)?;
+    vault
+        .rarity_points
+        .try_sub_assign(calc_rarity_points(&ctx.accounts.gem_rarity, 1)?)?;
+    vault.frozen_gem_count.try_sub_assign(1)?;
+
+    //msg!("gem {} thawed", &ctx.accounts.gem_mint.key());
+    Ok(())
+}
@@ -10,11 +10,13 @@ pub struct UpdateBankManager<'info> {
     pub bank_manager: Signer<'info>,
 }
 
+/// only proposes the handover, the new manager has to accept it (see accept_bank_manager)
+/// proposing Pubkey::default() calls off a pending one
 pub fn handler(ctx: Context<UpdateBankManager>, new_manager: Pubkey) -> Result<()> {
     let bank = &mut ctx.accounts.bank;
 
-    bank.bank_manager = new_manager;
+    bank.pending_bank_manager = new_manager;
 
-    //msg!("bank manager updated to: {}", new_manager);
+    //msg!("bank manager handover proposed to: {}", new_manager);
     Ok(())
 }
@@ -15,6 +15,8 @@ pub struct UpdateVaultOwner<'info> {
     pub owner: Signer<'info>,
 }
 
+/// only proposes the handover, the new owner has to accept it (see accept_vault_owner)
+/// proposing Pubkey::default() calls off a pending one
 pub fn handler(ctx: Context<UpdateVaultOwner>, new_owner: Pubkey) -> Result<()> {
     let bank = &ctx.accounts.bank;
     let vault = &mut ctx.accounts.vault;
@@ -23,8 +25,13 @@ pub fn handler(ctx: Context<UpdateVaultOwner>, new_owner: Pubkey) -> Result<()>
         return Err(error!(ErrorCode::VaultAccessSuspended));
     }
 
-    vault.owner = new_owner;
+    // frozen gems can only be thawed with the help of whoever holds them
+    if vault.frozen_gem_count > 0 {
+        return Err(error!(ErrorCode::VaultHasFrozenGems));
+    }
+
+    vault.pending_owner = new_owner;
 
-    //msg!("owner updated to: {}", new_owner);
+    //msg!("owner handover proposed to: {}", new_owner);
     Ok(())
 }
@@ -0,0 +1,107 @@
+use anchor_lang::prelude::*;
+use gem_common::{errors::ErrorCode, *};
+
+use crate::{instructions::calc_rarity_points, state::*, *};
+
+#[derive(Accounts)]
+#[instruction(bump_auth: u8, bump_receipt: u8, bump_rarity: u8)]
+pub struct WithdrawCnft<'info> {
+    // bank
+    pub bank: Box<Account<'info, Bank>>,
+
+    // vault
+    // same rationale for not verifying the PDA as in deposit
+    #[account(mut, has_one = bank, has_one = owner, has_one = authority)]
+    pub vault: Box<Account<'info, Vault>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK:
+    #[account(seeds = [vault.key().as_ref()], bump = bump_auth)]
+    pub authority: AccountInfo<'info>,
+
+    // cnft
+    /// CHECK: bubblegum checks it's the tree's config
+    pub tree_authority: AccountInfo<'info>,
+    /// CHECK: bubblegum / account compression verify the leaf against it
+    #[account(mut)]
+    pub merkle_tree: AccountInfo<'info>,
+    #[account(mut, has_one = vault, has_one = merkle_tree, seeds = [
+            b"cnft_deposit_receipt".as_ref(),
+            vault.key().as_ref(),
+            cnft_deposit_receipt.asset_id.as_ref(),
+        ],
+        bump = bump_receipt)]
+    pub cnft_deposit_receipt: Box<Account<'info, CnftDepositReceipt>>,
+    // we MUST ask for this PDA both during deposit and withdrawal for sec reasons, even if it's zero'ed
+    /// CHECK:
+    #[account(seeds = [
+            b"gem_rarity".as_ref(),
+            bank.key().as_ref(),
+            cnft_deposit_receipt.asset_id.as_ref(),
+        ],
+        bump = bump_rarity)]
+    pub gem_rarity: AccountInfo<'info>,
+    // unlike with deposits, the cnft can be sent out to anyone, not just the owner
+    /// CHECK:
+    #[account(mut)]
+    pub receiver: AccountInfo<'info>,
+
+    // misc
+    pub cnft_shared: CnftShared<'info>,
+    pub system_program: Program<'info, System>,
+    //
+    // remaining accounts are the proof path (minus whatever is in the tree's canopy)
+}
+
+pub fn handler<'info>(
+    ctx: Context<'_, '_, '_, 'info, WithdrawCnft<'info>>,
+    root: [u8; 32],
+) -> Result<()> {
+    // verify vault not suspended
+    let bank = &*ctx.accounts.bank;
+    let vault = &ctx.accounts.vault;
+
+    if vault.access_suspended(bank.flags)? {
+        return Err(error!(ErrorCode::VaultAccessSuspended));
+    }
+
+    // do the transfer
+    let receipt = &ctx.accounts.cnft_deposit_receipt;
+
+    // bubblegum resets the delegate on transfer, so the vault's authority is both
+    send_cnft(
+        &ctx.accounts.authority,
+        &ctx.accounts.authority,
+        &ctx.accounts.receiver,
+        &ctx.accounts.tree_authority,
+        &ctx.accounts.merkle_tree,
+        &ctx.accounts.cnft_shared,
+        &ctx.accounts.system_program,
+        ctx.remaining_accounts,
+        root,
+        &CnftLeaf {
+            data_hash: receipt.data_hash,
+            creator_hash: receipt.creator_hash,
+            nonce: receipt.nonce,
+            index: receipt.index,
+        },
+        Some(vault),
+    )?;
+
+    // close the receipt, and return funds to user
+    let receiver = &mut ctx.accounts.receiver;
+    let receipt = &mut (*ctx.accounts.cnft_deposit_receipt).to_account_info();
+
+    close_account(receipt, receiver)?;
+
+    // decrement counts stored in vault's state
+    let vault = &mut ctx.accounts.vault;
+    vault.gem_box_count.try_sub_assign(1)?;
+    vault.gem_count.try_sub_assign(1)?;
+    vault
+        .rarity_points
+        .try_sub_assign(calc_rarity_points(&ctx.accounts.gem_rarity, 1)?)?;
+
+    //msg!("cnft {} withdrawn", receipt.asset_id);
+    Ok(())
+}
@@ -0,0 +1,95 @@
+use anchor_lang::prelude::*;
+use gem_common::{errors::ErrorCode, *};
+
+use crate::{instructions::calc_rarity_points, state::*, *};
+
+#[derive(Accounts)]
+#[instruction(bump_auth: u8, bump_receipt: u8, bump_rarity: u8)]
+pub struct WithdrawCore<'info> {
+    // bank
+    pub bank: Box<Account<'info, Bank>>,
+
+    // vault
+    // same rationale for not verifying the PDA as in deposit
+    #[account(mut, has_one = bank, has_one = owner, has_one = authority)]
+    pub vault: Box<Account<'info, Vault>>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// CHECK:
+    #[account(seeds = [vault.key().as_ref()], bump = bump_auth)]
+    pub authority: AccountInfo<'info>,
+
+    // asset
+    /// CHECK: assert_decode_core_asset
+    #[account(mut)]
+    pub asset: AccountInfo<'info>,
+    /// CHECK: has to match the asset's collection, if it has one
+    pub collection: Option<AccountInfo<'info>>,
+    #[account(mut, has_one = vault, has_one = asset, seeds = [
+            b"core_deposit_receipt".as_ref(),
+            vault.key().as_ref(),
+            asset.key().as_ref(),
+        ],
+        bump = bump_receipt)]
+    pub core_deposit_receipt: Box<Account<'info, CoreDepositReceipt>>,
+    // we MUST ask for this PDA both during deposit and withdrawal for sec reasons, even if it's zero'ed
+    /// CHECK:
+    #[account(seeds = [
+            b"gem_rarity".as_ref(),
+            bank.key().as_ref(),
+            asset.key().as_ref(),
+        ],
+        bump = bump_rarity)]
+    pub gem_rarity: AccountInfo<'info>,
+    // unlike with deposits, the asset can be sent out to anyone, not just the owner
+    /// CHECK:
+    #[account(mut)]
+    pub receiver: AccountInfo<'info>,
+
+    // misc
+    /// CHECK: address below
+    #[account(address = mpl_core_program::ID)]
+    pub mpl_core_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<WithdrawCore>) -> Result<()> {
+    // verify vault not suspended
+    let bank = &*ctx.accounts.bank;
+    let vault = &ctx.accounts.vault;
+
+    if vault.access_suspended(bank.flags)? {
+        return Err(error!(ErrorCode::VaultAccessSuspended));
+    }
+
+    // do the transfer
+    let core_asset = assert_decode_core_asset(&ctx.accounts.asset)?;
+    assert_core_collection_matches(&core_asset, ctx.accounts.collection.as_ref())?;
+
+    send_core_asset(
+        &ctx.accounts.asset,
+        ctx.accounts.collection.as_ref(),
+        &ctx.accounts.owner.to_account_info(),
+        &ctx.accounts.authority,
+        &ctx.accounts.receiver,
+        &ctx.accounts.mpl_core_program,
+        Some(vault),
+    )?;
+
+    // close the receipt, and return funds to user
+    let receiver = &mut ctx.accounts.receiver;
+    let receipt = &mut (*ctx.accounts.core_deposit_receipt).to_account_info();
+
+    close_account(receipt, receiver)?;
+
+    // decrement counts stored in vault's state
+    let vault = &mut ctx.accounts.vault;
+    vault.gem_box_count.try_sub_assign(1)?;
+    vault.gem_count.try_sub_assign(1)?;
+    vault
+        .rarity_points
+        .try_sub_assign(calc_rarity_points(&ctx.accounts.gem_rarity, 1)?)?;
+
+    //msg!("core asset {} withdrawn", &ctx.accounts.asset.key());
+    Ok(())
+}
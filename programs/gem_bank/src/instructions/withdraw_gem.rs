@@ -1,10 +1,11 @@
 use crate::instructions::calc_rarity_points;
 use anchor_lang::prelude::*;
-use anchor_spl::{
-    associated_token::AssociatedToken,
-    token::{self, CloseAccount, Mint, Token, TokenAccount, Transfer},
+use anchor_spl::associated_token::AssociatedToken;
+use gem_common::{
+    errors::ErrorCode,
+    token_interface::{self, is_token_program},
+    *,
 };
-use gem_common::{errors::ErrorCode, *};
 
 use crate::state::*;
 
@@ -25,13 +26,15 @@ pub struct WithdrawGem<'info> {
     pub authority: AccountInfo<'info>,
 
     // gem
+    // token accounts & the mint are taken in raw, as they can belong to either token program
+    /// CHECK:
     #[account(mut, seeds = [
             b"gem_box".as_ref(),
             vault.key().as_ref(),
             gem_mint.key().as_ref(),
         ],
         bump = bump_gem_box)]
-    pub gem_box: Box<Account<'info, TokenAccount>>,
+    pub gem_box: AccountInfo<'info>,
     #[account(mut, has_one = vault, has_one = gem_mint, seeds = [
             b"gem_deposit_receipt".as_ref(),
             vault.key().as_ref(),
@@ -39,12 +42,13 @@ pub struct WithdrawGem<'info> {
         ],
         bump = bump_gdr)]
     pub gem_deposit_receipt: Box<Account<'info, GemDepositReceipt>>,
-    #[account(init_if_needed,
-        associated_token::mint = gem_mint,
-        associated_token::authority = receiver,
-        payer = owner)]
-    pub gem_destination: Box<Account<'info, TokenAccount>>,
-    pub gem_mint: Box<Account<'info, Mint>>,
+    /// CHECK: has to be the receiver's ATA, created if missing
+    #[account(mut)]
+    pub gem_destination: AccountInfo<'info>,
+    // writable in case the gem box has withheld transfer fees that need harvesting before closing
+    /// CHECK: unpacked during the transfer
+    #[account(mut)]
+    pub gem_mint: AccountInfo<'info>,
     // we MUST ask for this PDA both during deposit and withdrawal for sec reasons, even if it's zero'ed
     /// CHECK:
     #[account(seeds = [
@@ -59,32 +63,48 @@ pub struct WithdrawGem<'info> {
     pub receiver: AccountInfo<'info>,
 
     // misc
-    pub token_program: Program<'info, Token>,
+    /// CHECK: either of the 2 token programs, as long as it's the one owning the mint
+    #[account(constraint = is_token_program(token_program.key)
+        && gem_mint.owner == token_program.key @ ErrorCode::InvalidTokenProgram)]
+    pub token_program: AccountInfo<'info>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
 impl<'info> WithdrawGem<'info> {
-    fn transfer_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        CpiContext::new(
-            self.token_program.to_account_info(),
-            Transfer {
-                from: self.gem_box.to_account_info(),
-                to: self.gem_destination.to_account_info(),
-                authority: self.authority.to_account_info(),
-            },
+    fn init_destination_if_needed(&self) -> Result<()> {
+        token_interface::init_associated_token_account_if_needed(
+            &self.gem_destination,
+            &self.receiver,
+            &self.gem_mint,
+            &self.owner.to_account_info(),
+            &self.token_program,
+            &self.associated_token_program.to_account_info(),
+            &self.system_program.to_account_info(),
+        )
+    }
+
+    fn transfer(&self, amount: u64) -> Result<u64> {
+        token_interface::transfer_checked(
+            &self.token_program,
+            &self.gem_box,
+            &self.gem_mint,
+            &self.gem_destination,
+            &self.authority,
+            amount,
+            &[&self.vault.vault_seeds()],
         )
     }
 
-    fn close_context(&self) -> CpiContext<'_, '_, '_, 'info, CloseAccount<'info>> {
-        CpiContext::new(
-            self.token_program.to_account_info(),
-            CloseAccount {
-                account: self.gem_box.to_account_info(),
-                destination: self.receiver.to_account_info(),
-                authority: self.authority.clone(),
-            },
+    fn close_gem_box(&self) -> Result<()> {
+        token_interface::close_token_account(
+            &self.token_program,
+            &self.gem_box,
+            &self.gem_mint,
+            &self.receiver,
+            &self.authority,
+            &[&self.vault.vault_seeds()],
         )
     }
 }
@@ -98,13 +118,9 @@ pub fn handler(ctx: Context<WithdrawGem>, amount: u64) -> Result<()> {
         return Err(error!(ErrorCode::VaultAccessSuspended));
     }
 
-    // do the transfer
-    token::transfer(
-        ctx.accounts
-            .transfer_ctx()
-            .with_signer(&[&vault.vault_seeds()]),
-        amount,
-    )?;
+    // do the transfer (any transfer fee is on the receiving end)
+    ctx.accounts.init_destination_if_needed()?;
+    ctx.accounts.transfer(amount)?;
 
     // update the gdr
     let gdr = &mut *ctx.accounts.gem_deposit_receipt;
@@ -113,18 +129,14 @@ pub fn handler(ctx: Context<WithdrawGem>, amount: u64) -> Result<()> {
     gdr.gem_count.try_sub_assign(amount)?;
 
     // this check is semi-useless but won't hurt
-    if gdr.gem_count != gem_box.amount.try_sub(amount)? {
+    if gdr.gem_count != token_interface::unpack_token_account(gem_box)?.amount {
         return Err(error!(ErrorCode::AmountMismatch));
     }
 
     // if gembox empty, close both the box and the GDR, and return funds to user
     if gdr.gem_count == 0 {
         // close gem box
-        token::close_account(
-            ctx.accounts
-                .close_context()
-                .with_signer(&[&vault.vault_seeds()]),
-        )?;
+        ctx.accounts.close_gem_box()?;
 
         // close GDR
         let receiver = &mut ctx.accounts.receiver;
@@ -33,6 +33,10 @@ pub mod gem_bank {
         instructions::update_vault_owner::handler(ctx, new_owner)
     }
 
+    pub fn accept_vault_owner(ctx: Context<AcceptVaultOwner>) -> Result<()> {
+        instructions::accept_vault_owner::handler(ctx)
+    }
+
     pub fn deposit_gem<'info>(
         ctx: Context<'_, '_, '_, 'info, DepositGem<'info>>,
         _bump_auth: u8,
@@ -65,6 +69,10 @@ pub mod gem_bank {
         instructions::update_bank_manager::handler(ctx, new_manager)
     }
 
+    pub fn accept_bank_manager(ctx: Context<AcceptBankManager>) -> Result<()> {
+        instructions::accept_bank_manager::handler(ctx)
+    }
+
     pub fn record_rarity_points<'a, 'b, 'c, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, RecordRarityPoints<'info>>,
         rarity_configs: Vec<RarityConfig>,
@@ -77,6 +85,20 @@ pub mod gem_bank {
         instructions::withdraw_tokens_auth::handler(ctx)
     }
 
+    pub fn close_vault(ctx: Context<CloseVault>) -> Result<()> {
+        instructions::close_vault::handler(ctx)
+    }
+
+    pub fn close_bank(ctx: Context<CloseBank>) -> Result<()> {
+        instructions::close_bank::handler(ctx)
+    }
+
+    pub fn close_rarity_points<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CloseRarityPoints<'info>>,
+    ) -> Result<()> {
+        instructions::close_rarity_points::handler(ctx)
+    }
+
     pub fn deposit_gem_pnft<'info>(
         ctx: Context<'_, '_, '_, 'info, DepositGemPnft<'info>>,
         _bump_auth: u8,
@@ -100,4 +122,63 @@ pub mod gem_bank {
     ) -> Result<()> {
         instructions::withdraw_gem_pnft::handler(ctx, amount, authorization_data, rules_acc_present)
     }
+
+    pub fn deposit_cnft<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositCnft<'info>>,
+        _bump_auth: u8,
+        _bump_rarity: u8,
+        root: [u8; 32],
+        leaf: CnftLeaf,
+        creators: Vec<CnftCreator>,
+    ) -> Result<()> {
+        instructions::deposit_cnft::handler(ctx, root, leaf, creators)
+    }
+
+    pub fn withdraw_cnft<'info>(
+        ctx: Context<'_, '_, '_, 'info, WithdrawCnft<'info>>,
+        _bump_auth: u8,
+        _bump_receipt: u8,
+        _bump_rarity: u8,
+        root: [u8; 32],
+    ) -> Result<()> {
+        instructions::withdraw_cnft::handler(ctx, root)
+    }
+
+    pub fn deposit_core<'info>(
+        ctx: Context<'_, '_, '_, 'info, DepositCore<'info>>,
+        _bump_auth: u8,
+        _bump_rarity: u8,
+    ) -> Result<()> {
+        instructions::deposit_core::handler(ctx)
+    }
+
+    pub fn withdraw_core(
+        ctx: Context<WithdrawCore>,
+        _bump_auth: u8,
+        _bump_receipt: u8,
+        _bump_rarity: u8,
+    ) -> Result<()> {
+        instructions::withdraw_core::handler(ctx)
+    }
+
+    pub fn freeze_gem<'info>(
+        ctx: Context<'_, '_, '_, 'info, FreezeGem<'info>>,
+        _bump_auth: u8,
+        _bump_rarity: u8,
+        authorization_data: Option<AuthorizationDataLocal>,
+        rules_acc_present: bool,
+    ) -> Result<()> {
+        instructions::freeze_gem::handler(ctx, authorization_data, rules_acc_present)
+    }
+
+    pub fn thaw_gem<'info>(
+        ctx: Context<'_, '_, '_, 'info, ThawGem<'info>>,
+        _bump_auth: u8,
+        _bump_receipt: u8,
+        _bump_rarity: u8,
+        authorization_data: Option<AuthorizationDataLocal>,
+        rules_acc_present: bool,
+    ) -> Result<()> {
+        instructions::thaw_gem::handler(ctx, authorization_data, rules_acc_present)
+    }
 }
@@ -10,7 +10,7 @@ pub struct Bank {
     pub version: u16,
 
     /// sole control over gem whitelist, un/locking the vaults, and bank flags
-    /// can update itself to another Pubkey
+    /// can hand itself over to another Pubkey (see pending_bank_manager)
     pub bank_manager: Pubkey,
 
     pub flags: u32,
@@ -25,8 +25,15 @@ pub struct Bank {
     /// total vault count registered with this bank
     pub vault_count: u64,
 
-    /// reserved for future updates, has to be /8
-    _reserved: [u8; 64],
+    /// OR
+    /// 3) collection from this list (stored here, rather than next to the others, to keep the layout)
+    pub whitelisted_collections: u32,
+
+    _reserved1: [u8; 28],
+
+    /// proposed by the current bank manager, only takes over once it accepts
+    /// (default Pubkey = no handover in progress)
+    pub pending_bank_manager: Pubkey,
 }
 
 impl Bank {
@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+
+/// cNFT equivalent of a GDR - there's no gem box, the leaf itself is owned by vault's authority
+/// one receipt per cNFT, so gem count is always 1
+#[proc_macros::assert_size(208)]
+#[repr(C)]
+#[account]
+pub struct CnftDepositReceipt {
+    /// each cNFT sits inside a single vault
+    pub vault: Pubkey,
+
+    /// bubblegum's asset id (derived from tree + nonce), used in place of a mint for rarities / whitelists
+    pub asset_id: Pubkey,
+
+    pub merkle_tree: Pubkey,
+
+    /// the following don't change when the leaf changes hands, so storing them
+    /// means only the root + proof are needed to transfer the cNFT back out
+    pub data_hash: [u8; 32],
+
+    pub creator_hash: [u8; 32],
+
+    pub nonce: u64,
+
+    pub index: u32,
+
+    _padding: [u8; 4],
+
+    /// reserved for future updates, has to be /8
+    _reserved: [u8; 32],
+}
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+/// Core equivalent of a GDR - there's no gem box, the asset itself is owned by vault's authority
+/// one receipt per asset, so gem count is always 1
+#[proc_macros::assert_size(128)]
+#[repr(C)]
+#[account]
+pub struct CoreDepositReceipt {
+    /// each asset sits inside a single vault
+    pub vault: Pubkey,
+
+    /// used in place of a mint for rarities / whitelists
+    pub asset: Pubkey,
+
+    /// default if the asset isn't part of a collection
+    pub collection: Pubkey,
+
+    /// reserved for future updates, has to be /8
+    _reserved: [u8; 32],
+}
@@ -0,0 +1,20 @@
+use anchor_lang::prelude::*;
+
+/// non-custodial equivalent of a GDR - there's no gem box, the gem stays in the owner's wallet,
+/// delegated to and frozen by vault's authority
+/// one receipt per NFT, so gem count is always 1
+#[proc_macros::assert_size(128)]
+#[repr(C)]
+#[account]
+pub struct FrozenGemReceipt {
+    /// each frozen gem counts towards a single vault
+    pub vault: Pubkey,
+
+    /// the owner's token account that's been frozen in place
+    pub gem_account: Pubkey,
+
+    pub gem_mint: Pubkey,
+
+    /// reserved for future updates, has to be /8
+    _reserved: [u8; 32],
+}
@@ -20,6 +20,9 @@ pub struct GemDepositReceipt {
     /// but the vault is generic enough to support fungible tokens as well, so this can be >1
     pub gem_count: u64,
 
+    /// latest deposit into this GDR, lets the farm tell how long the gem(s) have been staked for
+    pub deposited_at_ts: u64,
+
     /// reserved for future updates, has to be /8
-    _reserved: [u8; 32],
+    _reserved: [u8; 24],
 }
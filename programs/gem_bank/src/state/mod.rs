@@ -1,10 +1,16 @@
 pub mod bank;
+pub mod cnft_deposit_receipt;
+pub mod core_deposit_receipt;
+pub mod frozen_gem_receipt;
 pub mod gem_deposit_receipt;
 pub mod rarity;
 pub mod vault;
 pub mod whitelist_proof;
 
 pub use bank::*;
+pub use cnft_deposit_receipt::*;
+pub use core_deposit_receipt::*;
+pub use frozen_gem_receipt::*;
 pub use gem_deposit_receipt::*;
 pub use rarity::*;
 pub use vault::*;
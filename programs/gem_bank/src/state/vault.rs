@@ -11,7 +11,7 @@ pub struct Vault {
 
     /// responsible for signing deposits / withdrawals into the vault
     /// (!) NOTE: does NOT un/lock the vault - the bank manager does that
-    /// can update itself to another Pubkey
+    /// can hand itself over to another Pubkey (see pending_owner)
     pub owner: Pubkey,
 
     /// pubkey used to create the vault, baked into vault's PDA - NOT CHANGEABLE
@@ -39,8 +39,15 @@ pub struct Vault {
     /// thus worst case, when rarities aren't enabled, this is == gem_count
     pub rarity_points: u64,
 
-    /// reserved for future updates, has to be /8
-    _reserved: [u8; 64],
+    /// gems that stay in the owner's wallet, frozen in place (already included in the counts above)
+    /// while > 0, the owner can't be changed, since only they can revoke the delegation
+    pub frozen_gem_count: u64,
+
+    _reserved1: [u8; 24],
+
+    /// proposed by the current owner, only takes over once it accepts
+    /// (default Pubkey = no handover in progress)
+    pub pending_owner: Pubkey,
 }
 
 impl Vault {
@@ -2,8 +2,8 @@ use anchor_lang::prelude::*;
 use gem_common::errors::ErrorCode;
 
 /// whitelists are used to control what gems can/can't go into the vault
-/// currently 2 types of vault lists are supported: by mint and by creator
-/// if the whitelist PDA exists, then the mint/creator is considered accepted
+/// currently 3 types of vault lists are supported: by mint, by creator and by collection
+/// if the whitelist PDA exists, then the mint/creator/collection is considered accepted
 /// if at least 1 whitelist PDA exists total, then all deposit attempts will start getting checked
 #[repr(C)]
 #[account]
@@ -43,5 +43,6 @@ bitflags::bitflags! {
     pub struct WhitelistType: u8 {
         const CREATOR = 1 << 0;
         const MINT = 1 << 1;
+        const COLLECTION = 1 << 2;
     }
 }
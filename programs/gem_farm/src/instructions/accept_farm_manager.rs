@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+use gem_common::errors::ErrorCode;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct AcceptFarmManager<'info> {
+    // farm
+    #[account(mut, constraint = farm.pending_manager == new_manager.key()
+        @ ErrorCode::NotPendingAuthority)]
+    pub farm: Box<Account<'info, Farm>>,
+    pub new_manager: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<AcceptFarmManager>) -> Result<()> {
+    let farm = &mut ctx.accounts.farm;
+
+    farm.farm_manager = farm.pending_manager;
+    farm.pending_manager = Pubkey::default();
+
+    msg!("farm manager updated to: {}", farm.farm_manager);
+    Ok(())
+}
@@ -0,0 +1,24 @@
+use anchor_lang::prelude::*;
+use gem_common::errors::ErrorCode;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct AcceptProtocolAdmin<'info> {
+    // protocol
+    #[account(mut, seeds = [b"protocol_config".as_ref()], bump = protocol_config.bump,
+        constraint = protocol_config.pending_admin == new_admin.key()
+        @ ErrorCode::NotPendingAuthority)]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+    pub new_admin: Signer<'info>,
+}
+
+pub fn handler(ctx: Context<AcceptProtocolAdmin>) -> Result<()> {
+    let config = &mut ctx.accounts.protocol_config;
+
+    config.admin = config.pending_admin;
+    config.pending_admin = Pubkey::default();
+
+    msg!("protocol admin updated to: {}", config.admin);
+    Ok(())
+}
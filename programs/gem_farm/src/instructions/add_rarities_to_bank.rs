@@ -2,6 +2,7 @@ use anchor_lang::prelude::*;
 use gem_bank::{
     self, cpi::accounts::RecordRarityPoints, instructions::RarityConfig, program::GemBank,
 };
+use gem_common::errors::ErrorCode;
 
 use crate::state::*;
 
@@ -9,10 +10,15 @@ use crate::state::*;
 #[instruction(bump_auth: u8)]
 pub struct AddRaritiesToBank<'info> {
     // farm
-    #[account(has_one = farm_manager, has_one = farm_authority, has_one = bank)]
+    #[account(has_one = farm_authority, has_one = bank,
+        constraint = has_farm_role(&farm, farm_manager.key(), role_proof.as_deref(), FarmRoles::RARITY_ADMIN)
+            @ ErrorCode::MissingRole)]
     pub farm: Box<Account<'info, Farm>>,
+    // the farm manager, or anyone holding the role above
     #[account(mut)]
     pub farm_manager: Signer<'info>,
+    // only needed if the signer isn't the farm manager
+    pub role_proof: Option<Box<Account<'info, RoleProof>>>,
     /// CHECK:
     #[account(seeds = [farm.key().as_ref()], bump = bump_auth)]
     pub farm_authority: AccountInfo<'info>,
@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+use gem_common::{
+    errors::ErrorCode,
+    token_interface::{self, is_token_program},
+};
+
+use crate::state::*;
+
+/// (!) a new fixed-rate reward only applies to farmers who stake (or stake extra gems)
+/// after it's been added - those already staked aren't enrolled retroactively
+#[derive(Accounts)]
+#[instruction(bump_auth: u8)]
+pub struct AddRewardSlot<'info> {
+    // farm
+    #[account(mut, has_one = farm_manager, has_one = farm_authority)]
+    pub farm: Box<Account<'info, Farm>>,
+    #[account(mut)]
+    pub farm_manager: Signer<'info>,
+    /// CHECK:
+    #[account(seeds = [farm.key().as_ref()], bump = bump_auth)]
+    pub farm_authority: AccountInfo<'info>,
+
+    // reward
+    // if the mint has been used by the farm before, the pot will already exist
+    /// CHECK: created in the handler if missing
+    #[account(mut, seeds = [
+            b"reward_pot".as_ref(),
+            farm.key().as_ref(),
+            reward_mint.key().as_ref(),
+        ],
+        bump)]
+    pub reward_pot: AccountInfo<'info>,
+    /// CHECK: unpacked in the handler
+    pub reward_mint: AccountInfo<'info>,
+
+    // only needed if the farm is timelocked
+    #[account(mut, has_one = farm)]
+    pub pending_action: Option<Box<Account<'info, PendingAction>>>,
+
+    // misc
+    pub rent: Sysvar<'info, Rent>,
+    /// CHECK: either of the 2 token programs, as long as it's the one owning the mint
+    #[account(constraint = is_token_program(token_program.key)
+        && reward_mint.owner == token_program.key @ ErrorCode::InvalidTokenProgram)]
+    pub token_program: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> AddRewardSlot<'info> {
+    fn init_reward_pot(&self, bump: u8) -> Result<()> {
+        let farm = self.farm.key();
+        let reward_mint = self.reward_mint.key();
+
+        token_interface::create_token_account_pda(
+            &self.farm_manager.to_account_info(),
+            &self.reward_pot,
+            &self.reward_mint,
+            &self.farm_authority,
+            &self.token_program,
+            &self.system_program.to_account_info(),
+            &[
+                b"reward_pot".as_ref(),
+                farm.as_ref(),
+                reward_mint.as_ref(),
+                &[bump],
+            ],
+        )
+    }
+}
+
+pub fn handler(
+    ctx: Context<AddRewardSlot>,
+    reward_type: RewardType,
+    vesting: Option<VestingConfig>,
+) -> Result<()> {
+    consume_pending_action(
+        &ctx.accounts.farm,
+        ctx.accounts.pending_action.as_deref_mut(),
+        &FarmAction::AddRewardSlot {
+            reward_mint: ctx.accounts.reward_mint.key(),
+            reward_type,
+            vesting,
+        },
+    )?;
+
+    // make sure we're actually dealing with a mint
+    token_interface::unpack_mint(&ctx.accounts.reward_mint)?;
+
+    if ctx.accounts.reward_pot.data_is_empty() {
+        ctx.accounts
+            .init_reward_pot(*ctx.bumps.get("reward_pot").unwrap())?;
+    }
+
+    let farm = &mut ctx.accounts.farm;
+
+    let index = farm.add_reward(
+        ctx.accounts.reward_mint.key(),
+        ctx.accounts.reward_pot.key(),
+        reward_type,
+        vesting.unwrap_or_default(),
+    )?;
+
+    msg!(
+        "reward {} added to slot {}",
+        ctx.accounts.reward_mint.key(),
+        index
+    );
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 use gem_bank::{self, cpi::accounts::AddToWhitelist, program::GemBank, state::Bank};
+use gem_common::errors::ErrorCode;
 
 use crate::state::*;
 
@@ -7,10 +8,15 @@ use crate::state::*;
 #[instruction(bump_auth: u8)]
 pub struct AddToBankWhitelist<'info> {
     // farm
-    #[account(has_one = farm_manager, has_one = farm_authority, has_one = bank)]
+    #[account(has_one = farm_authority, has_one = bank,
+        constraint = has_farm_role(&farm, farm_manager.key(), role_proof.as_deref(), FarmRoles::WHITELIST_ADMIN)
+            @ ErrorCode::MissingRole)]
     pub farm: Box<Account<'info, Farm>>,
+    // the farm manager, or anyone holding the role above
     #[account(mut)]
     pub farm_manager: Signer<'info>,
+    // only needed if the signer isn't the farm manager
+    pub role_proof: Option<Box<Account<'info, RoleProof>>>,
     /// CHECK:
     #[account(seeds = [farm.key().as_ref()], bump = bump_auth)]
     pub farm_authority: AccountInfo<'info>,
@@ -44,6 +50,7 @@ impl<'info> AddToBankWhitelist<'info> {
     }
 }
 
+/// whitelist_type is passed through as is - any combination of CREATOR, MINT and COLLECTION
 pub fn handler(ctx: Context<AddToBankWhitelist>, whitelist_type: u8) -> Result<()> {
     gem_bank::cpi::add_to_whitelist(
         ctx.accounts
@@ -1,17 +1,20 @@
 use anchor_lang::prelude::*;
-use anchor_lang::Discriminator;
-use arrayref::array_ref;
-use gem_common::*;
+use gem_common::{errors::ErrorCode, *};
 
 use crate::state::*;
 
 #[derive(Accounts)]
 pub struct AuthorizeFunder<'info> {
     // farm
-    #[account(mut, has_one = farm_manager)]
+    #[account(mut,
+        constraint = has_farm_role(&farm, farm_manager.key(), role_proof.as_deref(), FarmRoles::FUNDER_ADMIN)
+            @ ErrorCode::MissingRole)]
     pub farm: Box<Account<'info, Farm>>,
+    // the farm manager, or anyone holding the role above
     #[account(mut)]
     pub farm_manager: Signer<'info>,
+    // only needed if the signer isn't the farm manager
+    pub role_proof: Option<Box<Account<'info, RoleProof>>>,
 
     // funder
     /// CHECK:
@@ -26,32 +29,54 @@ pub struct AuthorizeFunder<'info> {
         space = 8 + std::mem::size_of::<AuthorizationProof>())]
     authorization_proof: Box<Account<'info, AuthorizationProof>>,
 
+    // only needed if the farm is timelocked
+    #[account(mut, has_one = farm)]
+    pub pending_action: Option<Box<Account<'info, PendingAction>>>,
+
     // misc
     system_program: Program<'info, System>,
 }
 
-pub fn handler(ctx: Context<AuthorizeFunder>) -> Result<()> {
+/// can be called again on an already authorized funder to change their limits,
+/// what they've funded so far keeps counting against the new cap
+pub fn handler(
+    ctx: Context<AuthorizeFunder>,
+    reward_mint: Option<Pubkey>,
+    max_amount: Option<u64>,
+    expires_ts: Option<u64>,
+) -> Result<()> {
+    consume_pending_action(
+        &ctx.accounts.farm,
+        ctx.accounts.pending_action.as_deref_mut(),
+        &FarmAction::AuthorizeFunder {
+            funder: ctx.accounts.funder_to_authorize.key(),
+            reward_mint,
+            max_amount,
+            expires_ts,
+        },
+    )?;
+
     // fix missing discriminator check
-    {
-        let acct = ctx.accounts.authorization_proof.to_account_info();
-        let data: &[u8] = &acct.try_borrow_data()?;
-        let disc_bytes = array_ref![data, 0, 8];
-        if disc_bytes != &AuthorizationProof::discriminator() && disc_bytes.iter().any(|a| a != &0)
-        {
-            return Err(error!(ErrorCode::AccountDiscriminatorMismatch));
-        }
-    }
+    let is_new = assert_discriminator_or_new::<AuthorizationProof>(
+        &ctx.accounts.authorization_proof.to_account_info(),
+    )?;
 
     // create/update authorization proof
     let proof = &mut ctx.accounts.authorization_proof;
 
     proof.authorized_funder = ctx.accounts.funder_to_authorize.key();
     proof.farm = ctx.accounts.farm.key();
+    proof.reward_mint = reward_mint.unwrap_or_default();
+    proof.max_amount = max_amount.unwrap_or_default();
+    proof.has_max_amount = max_amount.is_some();
+    proof.expires_ts = expires_ts.unwrap_or_default();
 
-    // update farm
-    let farm = &mut ctx.accounts.farm;
+    // update farm (only the first time round, or the count never gets back down to 0)
+    if is_new {
+        let farm = &mut ctx.accounts.farm;
 
-    farm.authorized_funder_count.try_add_assign(1)?;
+        farm.authorized_funder_count.try_add_assign(1)?;
+    }
 
     msg!(
         "funder authorized: {}",
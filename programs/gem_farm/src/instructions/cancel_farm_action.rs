@@ -0,0 +1,31 @@
+use anchor_lang::prelude::*;
+use gem_common::*;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct CancelFarmAction<'info> {
+    // farm
+    #[account(has_one = farm_manager)]
+    pub farm: Box<Account<'info, Farm>>,
+    #[account(mut)]
+    pub farm_manager: Signer<'info>,
+
+    // action
+    #[account(mut, has_one = farm)]
+    pub pending_action: Box<Account<'info, PendingAction>>,
+}
+
+/// also used to close actions that have already been executed, to get the rent back
+pub fn handler(ctx: Context<CancelFarmAction>) -> Result<()> {
+    close_account(
+        &mut ctx.accounts.pending_action.to_account_info(),
+        &mut ctx.accounts.farm_manager.to_account_info(),
+    )?;
+
+    match ctx.accounts.pending_action.executed_ts {
+        0 => msg!("action {} cancelled", ctx.accounts.pending_action.action_id),
+        _ => msg!("action {} closed", ctx.accounts.pending_action.action_id),
+    }
+    Ok(())
+}
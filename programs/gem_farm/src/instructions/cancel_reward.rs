@@ -1,9 +1,10 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{
-    associated_token::AssociatedToken,
-    token::{self, Mint, Token, TokenAccount, Transfer},
+use anchor_spl::associated_token::AssociatedToken;
+use gem_common::{
+    errors::ErrorCode,
+    token_interface::{self, is_token_program},
+    *,
 };
-use gem_common::*;
 
 use crate::state::*;
 
@@ -20,61 +21,90 @@ pub struct CancelReward<'info> {
     pub farm_authority: AccountInfo<'info>,
 
     // reward
+    // token accounts & the mint are taken in raw, as they can belong to either token program
+    /// CHECK:
     #[account(mut, seeds = [
             b"reward_pot".as_ref(),
             farm.key().as_ref(),
             reward_mint.key().as_ref(),
         ],
         bump = bump_pot)]
-    pub reward_pot: Box<Account<'info, TokenAccount>>,
-    #[account(init_if_needed,
-        associated_token::mint = reward_mint,
-        associated_token::authority = receiver,
-        payer = farm_manager)]
-    pub reward_destination: Box<Account<'info, TokenAccount>>,
-    pub reward_mint: Box<Account<'info, Mint>>,
+    pub reward_pot: AccountInfo<'info>,
+    /// CHECK: has to be the receiver's ATA, created if missing
+    #[account(mut)]
+    pub reward_destination: AccountInfo<'info>,
+    /// CHECK: unpacked during the transfer
+    pub reward_mint: AccountInfo<'info>,
     // unlike with funding, cancelled proceeds can be sent anywhere
     /// CHECK:
     #[account(mut)]
     pub receiver: AccountInfo<'info>,
 
+    // only needed if the farm is timelocked
+    #[account(mut, has_one = farm)]
+    pub pending_action: Option<Box<Account<'info, PendingAction>>>,
+
     // misc
-    pub token_program: Program<'info, Token>,
+    /// CHECK: either of the 2 token programs, as long as it's the one owning the mint
+    #[account(constraint = is_token_program(token_program.key)
+        && reward_mint.owner == token_program.key @ ErrorCode::InvalidTokenProgram)]
+    pub token_program: AccountInfo<'info>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
 }
 
 impl<'info> CancelReward<'info> {
-    fn transfer_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        CpiContext::new(
-            self.token_program.to_account_info(),
-            Transfer {
-                from: self.reward_pot.to_account_info(),
-                to: self.reward_destination.to_account_info(),
-                authority: self.farm_authority.to_account_info(),
-            },
+    fn init_destination_if_needed(&self) -> Result<()> {
+        token_interface::init_associated_token_account_if_needed(
+            &self.reward_destination,
+            &self.receiver,
+            &self.reward_mint,
+            &self.farm_manager.to_account_info(),
+            &self.token_program,
+            &self.associated_token_program.to_account_info(),
+            &self.system_program.to_account_info(),
+        )
+    }
+
+    fn transfer(&self, amount: u64) -> Result<u64> {
+        token_interface::transfer_checked(
+            &self.token_program,
+            &self.reward_pot,
+            &self.reward_mint,
+            &self.reward_destination,
+            &self.farm_authority,
+            amount,
+            &[&self.farm.farm_seeds()],
         )
     }
 }
 
 pub fn handler(ctx: Context<CancelReward>) -> Result<()> {
+    consume_pending_action(
+        &ctx.accounts.farm,
+        ctx.accounts.pending_action.as_deref_mut(),
+        &FarmAction::CancelReward {
+            reward_mint: ctx.accounts.reward_mint.key(),
+            receiver: ctx.accounts.receiver.key(),
+        },
+    )?;
+
     // update existing rewards
     let farm = &mut ctx.accounts.farm;
     let now_ts = now_ts()?;
 
-    farm.update_rewards(now_ts, None, true)?;
+    farm.update_rewards(now_ts, None, true, None)?;
 
     // calculate cancellation amount while recording cancellation
     let cancel_amount = farm.cancel_reward_by_mint(now_ts, ctx.accounts.reward_mint.key())?;
 
+    // the refund goes to the receiver rather than back to the funders, so their balances start over
+    farm.start_funding_round_by_mint(ctx.accounts.reward_mint.key())?;
+
     // do the transfer
-    token::transfer(
-        ctx.accounts
-            .transfer_ctx()
-            .with_signer(&[&ctx.accounts.farm.farm_seeds()]),
-        cancel_amount,
-    )?;
+    ctx.accounts.init_destination_if_needed()?;
+    ctx.accounts.transfer(cancel_amount)?;
 
     msg!(
         "{} reward cancelled, {} tokens refunded",
@@ -0,0 +1,200 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use gem_common::{
+    errors::ErrorCode,
+    token_interface::{self, is_token_program},
+    *,
+};
+
+use crate::state::*;
+
+#[derive(Accounts)]
+#[instruction(bump_auth: u8, bump_pot: u8)]
+pub struct CancelRewardProRata<'info> {
+    // farm
+    #[account(mut, has_one = farm_manager, has_one = farm_authority)]
+    pub farm: Box<Account<'info, Farm>>,
+    #[account(mut)]
+    pub farm_manager: Signer<'info>,
+    /// CHECK:
+    #[account(seeds = [farm.key().as_ref()], bump = bump_auth)]
+    pub farm_authority: AccountInfo<'info>,
+
+    // reward
+    // token accounts & the mint are taken in raw, as they can belong to either token program
+    /// CHECK:
+    #[account(mut, seeds = [
+            b"reward_pot".as_ref(),
+            farm.key().as_ref(),
+            reward_mint.key().as_ref(),
+        ],
+        bump = bump_pot)]
+    pub reward_pot: AccountInfo<'info>,
+    /// CHECK: unpacked during the transfer
+    pub reward_mint: AccountInfo<'info>,
+
+    // only needed if the farm is timelocked
+    #[account(mut, has_one = farm)]
+    pub pending_action: Option<Box<Account<'info, PendingAction>>>,
+
+    // misc
+    /// CHECK: either of the 2 token programs, as long as it's the one owning the mint
+    #[account(constraint = is_token_program(token_program.key)
+        && reward_mint.owner == token_program.key @ ErrorCode::InvalidTokenProgram)]
+    pub token_program: AccountInfo<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    //
+    // remaining accounts, one triple per funder of the reward (all of them have to be passed):
+    //   #[account(mut)]
+    //   pub contribution: Box<Account<'info, FunderContribution>>,
+    //   pub funder: AccountInfo<'info>,
+    //   #[account(mut)]
+    //   pub funder_destination: AccountInfo<'info>, (funder's ATA, created if missing)
+}
+
+impl<'info> CancelRewardProRata<'info> {
+    fn init_destination_if_needed(
+        &self,
+        funder: &AccountInfo<'info>,
+        funder_destination: &AccountInfo<'info>,
+    ) -> Result<()> {
+        token_interface::init_associated_token_account_if_needed(
+            funder_destination,
+            funder,
+            &self.reward_mint,
+            &self.farm_manager.to_account_info(),
+            &self.token_program,
+            &self.associated_token_program.to_account_info(),
+            &self.system_program.to_account_info(),
+        )
+    }
+
+    fn transfer(&self, funder_destination: &AccountInfo<'info>, amount: u64) -> Result<u64> {
+        token_interface::transfer_checked(
+            &self.token_program,
+            &self.reward_pot,
+            &self.reward_mint,
+            funder_destination,
+            &self.farm_authority,
+            amount,
+            &[&self.farm.farm_seeds()],
+        )
+    }
+}
+
+/// same as cancel_reward, except the refund is split between the reward's funders,
+/// in proportion to how much each of them still has in it
+///
+/// (!) only works once every token funded this round is backed by a contribution - rewards
+/// funded before contributions were tracked have to be cancelled by the manager first,
+/// which starts a new round
+pub fn handler<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, CancelRewardProRata<'info>>,
+) -> Result<()> {
+    let reward_mint = ctx.accounts.reward_mint.key();
+
+    consume_pending_action(
+        &ctx.accounts.farm,
+        ctx.accounts.pending_action.as_deref_mut(),
+        &FarmAction::CancelRewardProRata { reward_mint },
+    )?;
+
+    // update existing rewards
+    let farm = &mut ctx.accounts.farm;
+    let now_ts = now_ts()?;
+
+    farm.update_rewards(now_ts, None, true, None)?;
+
+    // calculate cancellation amount while recording cancellation
+    let cancel_amount = farm.cancel_reward_by_mint(now_ts, reward_mint)?;
+
+    // (!) only read after cancelling, which adds any queued epochs to the total funded
+    let reward = &farm.rewards[farm.reward_index_by_mint(reward_mint)?];
+    let (reward_id, funding_round, round_funded) = (
+        reward.reward_id,
+        reward.funding_round,
+        reward.round_funded()?,
+    );
+
+    // load up the contributions
+    if ctx.remaining_accounts.len() % 3 != 0 {
+        return Err(error!(ErrorCode::InvalidParameter));
+    }
+
+    let farm_key = ctx.accounts.farm.key();
+    let mut contributions: Vec<(
+        Account<'info, FunderContribution>,
+        &AccountInfo<'info>,
+        &AccountInfo<'info>,
+    )> = Vec::with_capacity(ctx.remaining_accounts.len() / 3);
+
+    for accs in ctx.remaining_accounts.chunks(3) {
+        let contribution = Account::<'info, FunderContribution>::try_from(&accs[0])?;
+
+        if contribution.farm != farm_key
+            || contribution.reward_mint != reward_mint
+            || contribution.funder != accs[1].key()
+        {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+
+        // no double counting
+        if contributions
+            .iter()
+            .any(|(c, _, _)| c.key() == contribution.key())
+        {
+            return Err(error!(ErrorCode::IncompleteFunderList));
+        }
+
+        // left over from a retired reward that used the same mint, or from before the manager
+        // cancelled the reward, doesn't get a share
+        if !contribution.is_current(reward_id, funding_round) {
+            continue;
+        }
+
+        contributions.push((contribution, &accs[1], &accs[2]));
+    }
+
+    // shares only add up if everyone who funded the reward this round is here
+    let funded = contributions
+        .iter()
+        .try_fold(0u64, |total, (c, _, _)| total.try_add(c.total_funded))?;
+    if funded != round_funded {
+        return Err(error!(ErrorCode::IncompleteFunderList));
+    }
+
+    // split by what each funder still has in the reward, after any earlier pro-rata refunds
+    let balances = contributions
+        .iter()
+        .map(|(c, _, _)| c.balance())
+        .collect::<Result<Vec<_>>>()?;
+
+    let shares = pro_rata_shares(cancel_amount, &balances)?;
+
+    // do the transfers
+    for ((contribution, funder, funder_destination), share) in
+        contributions.iter_mut().zip(shares.into_iter())
+    {
+        if share == 0 {
+            continue;
+        }
+
+        ctx.accounts
+            .init_destination_if_needed(funder, funder_destination)?;
+        ctx.accounts.transfer(funder_destination, share)?;
+
+        contribution.total_refunded.try_add_assign(share)?;
+        contribution.exit(ctx.program_id)?;
+
+        msg!("{} tokens refunded to {}", share, funder.key());
+    }
+
+    msg!(
+        "{} reward cancelled, {} tokens refunded pro-rata",
+        reward_mint,
+        cancel_amount,
+    );
+    Ok(())
+}
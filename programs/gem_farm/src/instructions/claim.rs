@@ -1,14 +1,15 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{
-    associated_token::AssociatedToken,
-    token::{self, Mint, Token, TokenAccount, Transfer},
+use anchor_spl::{associated_token::AssociatedToken, token::Token};
+use gem_common::{
+    errors::ErrorCode,
+    token_interface::{self, TOKEN_2022_PROGRAM_ID},
+    *,
 };
-use gem_common::*;
 
 use crate::state::*;
 
 #[derive(Accounts)]
-#[instruction(bump_auth: u8, bump_farmer: u8, bump_pot_a: u8, bump_pot_b: u8)]
+#[instruction(bump_auth: u8, bump_farmer: u8)]
 pub struct Claim<'info> {
     // farm
     #[account(mut, has_one = farm_authority)]
@@ -17,6 +18,10 @@ pub struct Claim<'info> {
     #[account(seeds = [farm.key().as_ref()], bump = bump_auth)]
     pub farm_authority: AccountInfo<'info>,
 
+    // only needed if the farmer is (or is being) enrolled in a fixed rate reward
+    #[account(mut, has_one = farm)]
+    pub fixed_rate_tiers: Option<Box<Account<'info, FixedRateTiers>>>,
+
     // farmer
     #[account(mut, has_one = farm, has_one = identity, seeds = [
             b"farmer".as_ref(),
@@ -28,100 +33,111 @@ pub struct Claim<'info> {
     #[account(mut)] //payer
     pub identity: Signer<'info>,
 
-    // reward a
-    #[account(mut, seeds = [
-            b"reward_pot".as_ref(),
-            farm.key().as_ref(),
-            reward_a_mint.key().as_ref(),
-        ],
-        bump = bump_pot_a)]
-    pub reward_a_pot: Box<Account<'info, TokenAccount>>,
-    pub reward_a_mint: Box<Account<'info, Mint>>,
-    #[account(init_if_needed,
-        associated_token::mint = reward_a_mint,
-        associated_token::authority = identity,
-        payer = identity)]
-    pub reward_a_destination: Box<Account<'info, TokenAccount>>,
-
-    // reward b
-    #[account(mut, seeds = [
-            b"reward_pot".as_ref(),
-            farm.key().as_ref(),
-            reward_b_mint.key().as_ref(),
-        ],
-        bump = bump_pot_b)]
-    pub reward_b_pot: Box<Account<'info, TokenAccount>>,
-    pub reward_b_mint: Box<Account<'info, Mint>>,
-    #[account(init_if_needed,
-        associated_token::mint = reward_b_mint,
-        associated_token::authority = identity,
-        payer = identity)]
-    pub reward_b_destination: Box<Account<'info, TokenAccount>>,
-
     // misc
     pub token_program: Program<'info, Token>,
+    // only needed if claiming any token-2022 rewards
+    /// CHECK:
+    #[account(address = TOKEN_2022_PROGRAM_ID)]
+    pub token_2022_program: Option<AccountInfo<'info>>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
+    //
+    // remaining accounts, one triple per reward to be claimed (any subset of the farm's rewards):
+    //   pub reward_mint: AccountInfo<'info>, (either token program)
+    //   #[account(mut)]
+    //   pub reward_pot: AccountInfo<'info>,
+    //   #[account(mut)]
+    //   pub reward_destination: AccountInfo<'info>, (identity's ATA, created if missing)
 }
 
 impl<'info> Claim<'info> {
-    fn transfer_a_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        CpiContext::new(
-            self.token_program.to_account_info(),
-            Transfer {
-                from: self.reward_a_pot.to_account_info(),
-                to: self.reward_a_destination.to_account_info(),
-                authority: self.farm_authority.to_account_info(),
-            },
+    fn token_program_for(&self, reward_mint: &AccountInfo<'info>) -> Result<AccountInfo<'info>> {
+        token_interface::token_program_for_mint(
+            reward_mint,
+            &self.token_program.to_account_info(),
+            self.token_2022_program.as_ref(),
         )
     }
 
-    fn transfer_b_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        CpiContext::new(
-            self.token_program.to_account_info(),
-            Transfer {
-                from: self.reward_b_pot.to_account_info(),
-                to: self.reward_b_destination.to_account_info(),
-                authority: self.farm_authority.to_account_info(),
-            },
+    fn init_destination_if_needed(
+        &self,
+        reward_mint: &AccountInfo<'info>,
+        reward_destination: &AccountInfo<'info>,
+        token_program: &AccountInfo<'info>,
+    ) -> Result<()> {
+        token_interface::init_associated_token_account_if_needed(
+            reward_destination,
+            &self.identity.to_account_info(),
+            reward_mint,
+            &self.identity.to_account_info(),
+            token_program,
+            &self.associated_token_program.to_account_info(),
+            &self.system_program.to_account_info(),
         )
     }
 }
 
-pub fn handler(ctx: Context<Claim>) -> Result<()> {
+pub fn handler<'a, 'b, 'c, 'info>(ctx: Context<'a, 'b, 'c, 'info, Claim<'info>>) -> Result<()> {
     // update accrued rewards before claiming
     let farm = &mut ctx.accounts.farm;
+    let fixed_rate_tiers = ctx
+        .accounts
+        .fixed_rate_tiers
+        .as_deref_mut()
+        .map(|t| &mut **t);
     let farmer = &mut ctx.accounts.farmer;
+    let now_ts = now_ts()?;
 
-    farm.update_rewards(now_ts()?, Some(farmer), true)?;
-
-    // calculate claimed amounts (capped at what's available in the pot)
-    let to_claim_a = farmer
-        .reward_a
-        .claim_reward(ctx.accounts.reward_a_pot.amount)?;
-    let to_claim_b = farmer
-        .reward_b
-        .claim_reward(ctx.accounts.reward_b_pot.amount)?;
-
-    // do the transfers
-    if to_claim_a > 0 {
-        token::transfer(
-            ctx.accounts
-                .transfer_a_ctx()
-                .with_signer(&[&ctx.accounts.farm.farm_seeds()]),
-            to_claim_a,
-        )?;
+    farm.update_rewards(now_ts, Some(farmer), true, fixed_rate_tiers)?;
+
+    if ctx.remaining_accounts.len() % 3 != 0 {
+        return Err(error!(ErrorCode::InvalidParameter));
     }
-    if to_claim_b > 0 {
-        token::transfer(
-            ctx.accounts
-                .transfer_b_ctx()
-                .with_signer(&[&ctx.accounts.farm.farm_seeds()]),
-            to_claim_b,
+
+    for accs in ctx.remaining_accounts.chunks(3) {
+        let (reward_mint, reward_pot, reward_destination) = (&accs[0], &accs[1], &accs[2]);
+
+        // make sure the pot actually belongs to the reward slot
+        let index = ctx.accounts.farm.reward_index_by_mint(reward_mint.key())?;
+        if ctx.accounts.farm.rewards[index].reward_pot != reward_pot.key() {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+        let pot_balance = token_interface::unpack_token_account(reward_pot)?.amount;
+        let token_program = ctx.accounts.token_program_for(reward_mint)?;
+
+        // rewards can only ever go to the farmer's ATA
+        ctx.accounts
+            .init_destination_if_needed(reward_mint, reward_destination, &token_program)?;
+
+        // claiming early costs the same cut as unstaking early would
+        ctx.accounts
+            .farm
+            .apply_early_claim_penalty(now_ts, index, &mut ctx.accounts.farmer)?;
+
+        // calculate claimed amount (capped at what's available in the pot)
+        // for vesting rewards this is 0, the claimed amount starts vesting instead
+        let to_claim = ctx.accounts.farm.rewards[index].claim_farmer_reward(
+            now_ts,
+            &mut ctx.accounts.farmer.rewards[index],
+            pot_balance,
         )?;
+
+        // do the transfer
+        if to_claim > 0 {
+            token_interface::transfer_checked(
+                &token_program,
+                reward_pot,
+                reward_mint,
+                reward_destination,
+                &ctx.accounts.farm_authority,
+                to_claim,
+                &[&ctx.accounts.farm.farm_seeds()],
+            )?;
+        }
+
+        msg!("reward claimed ({} {})", to_claim, reward_mint.key());
     }
 
-    msg!("rewards claimed ({} A) and ({} B)", to_claim_a, to_claim_b);
     Ok(())
 }
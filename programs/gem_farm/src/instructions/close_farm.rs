@@ -0,0 +1,165 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{program::invoke_signed, system_instruction},
+};
+use anchor_spl::token::Token;
+use gem_bank::{self, cpi::accounts::CloseBank, program::GemBank};
+use gem_common::{
+    errors::ErrorCode,
+    token_interface::{self, TOKEN_2022_PROGRAM_ID},
+    *,
+};
+
+use crate::state::*;
+
+/// (!) before the farm can be closed:
+/// - all farmers need to have closed their accounts (which requires them to unstake & claim)
+/// - all funders need to be deauthorized
+/// - all rewards need to be fully paid out (see FarmReward::is_retirable)
+/// - all bank whitelist entries need to be removed (rarities too, if you want the rent back)
+#[derive(Accounts)]
+#[instruction(bump_auth: u8, bump_treasury: u8)]
+pub struct CloseFarm<'info> {
+    // farm
+    #[account(mut, has_one = farm_manager, has_one = farm_authority,
+        has_one = farm_treasury, has_one = bank)]
+    pub farm: Box<Account<'info, Farm>>,
+    pub farm_manager: Signer<'info>,
+    /// CHECK:
+    #[account(seeds = [farm.key().as_ref()], bump = bump_auth)]
+    pub farm_authority: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut, seeds = [b"treasury".as_ref(), farm.key().as_ref()], bump = bump_treasury)]
+    pub farm_treasury: AccountInfo<'info>,
+
+    // cpi
+    /// CHECK:
+    #[account(mut)]
+    pub bank: AccountInfo<'info>,
+    pub gem_bank: Program<'info, GemBank>,
+
+    // misc
+    /// CHECK:
+    #[account(mut)]
+    pub funds_receiver: AccountInfo<'info>,
+    pub token_program: Program<'info, Token>,
+    // only needed if any of the pots are token-2022
+    /// CHECK:
+    #[account(address = TOKEN_2022_PROGRAM_ID)]
+    pub token_2022_program: Option<AccountInfo<'info>>,
+    pub system_program: Program<'info, System>,
+    //
+    // remaining accounts can be any number of:
+    //   #[account(mut)]
+    //   pub reward_mint: AccountInfo<'info>, (mut in case withheld transfer fees need harvesting)
+    //   #[account(mut)]
+    //   pub reward_pot: AccountInfo<'info>,
+    //   #[account(mut)]
+    //   pub reward_destination: AccountInfo<'info>, (gets any leftover tokens)
+}
+
+impl<'info> CloseFarm<'info> {
+    fn close_bank_ctx(&self) -> CpiContext<'_, '_, '_, 'info, CloseBank<'info>> {
+        CpiContext::new(
+            self.gem_bank.to_account_info(),
+            CloseBank {
+                bank: self.bank.clone(),
+                bank_manager: self.farm_authority.clone(),
+                funds_receiver: self.funds_receiver.clone(),
+            },
+        )
+    }
+
+    fn empty_treasury(&self, bump_treasury: u8) -> Result<()> {
+        invoke_signed(
+            &system_instruction::transfer(
+                self.farm_treasury.key,
+                self.funds_receiver.key,
+                self.farm_treasury.lamports(),
+            ),
+            &[
+                self.farm_treasury.clone(),
+                self.funds_receiver.clone(),
+                self.system_program.to_account_info(),
+            ],
+            &[&[
+                b"treasury".as_ref(),
+                self.farm.key().as_ref(),
+                &[bump_treasury],
+            ]],
+        )
+        .map_err(Into::into)
+    }
+}
+
+pub fn handler<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, CloseFarm<'info>>,
+    bump_treasury: u8,
+) -> Result<()> {
+    // make sure no one is owed anything
+    let farm = &mut ctx.accounts.farm;
+
+    farm.assert_settled(now_ts()?)?;
+
+    // drain & close the pots
+    let remaining_accs = &mut ctx.remaining_accounts.iter();
+
+    while remaining_accs.len() > 0 {
+        let reward_mint = next_account_info(remaining_accs)?;
+        let reward_pot = next_account_info(remaining_accs)?;
+        let reward_destination = next_account_info(remaining_accs)?;
+
+        // only ever touch pots controlled by the farm
+        let pot = token_interface::unpack_token_account(reward_pot)?;
+        if pot.owner != ctx.accounts.farm_authority.key() || pot.mint != reward_mint.key() {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+        let token_program = token_interface::token_program_for_mint(
+            reward_mint,
+            &ctx.accounts.token_program.to_account_info(),
+            ctx.accounts.token_2022_program.as_ref(),
+        )?;
+
+        // whatever is left are rounding leftovers no one has a claim on
+        if pot.amount > 0 {
+            token_interface::transfer_checked(
+                &token_program,
+                reward_pot,
+                reward_mint,
+                reward_destination,
+                &ctx.accounts.farm_authority,
+                pot.amount,
+                &[&ctx.accounts.farm.farm_seeds()],
+            )?;
+        }
+
+        token_interface::close_token_account(
+            &token_program,
+            reward_pot,
+            reward_mint,
+            &ctx.accounts.funds_receiver,
+            &ctx.accounts.farm_authority,
+            &[&ctx.accounts.farm.farm_seeds()],
+        )?;
+    }
+
+    // empty the treasury, no one will be able to pay out from it after this
+    if ctx.accounts.farm_treasury.lamports() > 0 {
+        ctx.accounts.empty_treasury(bump_treasury)?;
+    }
+
+    // close the bank, then the farm
+    gem_bank::cpi::close_bank(
+        ctx.accounts
+            .close_bank_ctx()
+            .with_signer(&[&ctx.accounts.farm.farm_seeds()]),
+    )?;
+
+    close_account(
+        &mut ctx.accounts.farm.to_account_info(),
+        &mut ctx.accounts.funds_receiver,
+    )?;
+
+    msg!("farm {} closed", ctx.accounts.farm.key());
+    Ok(())
+}
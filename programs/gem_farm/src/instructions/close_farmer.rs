@@ -0,0 +1,76 @@
+use anchor_lang::prelude::*;
+use gem_bank::{self, cpi::accounts::CloseVault, program::GemBank};
+use gem_common::*;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+#[instruction(bump_auth: u8, bump_farmer: u8)]
+pub struct CloseFarmer<'info> {
+    // farm
+    #[account(mut, has_one = farm_authority, has_one = bank)]
+    pub farm: Box<Account<'info, Farm>>,
+    /// CHECK:
+    #[account(seeds = [farm.key().as_ref()], bump = bump_auth)]
+    pub farm_authority: AccountInfo<'info>,
+
+    // farmer
+    #[account(mut, has_one = farm, has_one = identity, has_one = vault,
+        seeds = [
+            b"farmer".as_ref(),
+            farm.key().as_ref(),
+            identity.key().as_ref(),
+        ],
+        bump = bump_farmer)]
+    pub farmer: Box<Account<'info, Farmer>>,
+    #[account(mut)] //receives the rent
+    pub identity: Signer<'info>,
+
+    // cpi
+    /// CHECK:
+    #[account(mut)]
+    pub bank: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut)]
+    pub vault: AccountInfo<'info>,
+    pub gem_bank: Program<'info, GemBank>,
+}
+
+impl<'info> CloseFarmer<'info> {
+    fn close_vault_ctx(&self) -> CpiContext<'_, '_, '_, 'info, CloseVault<'info>> {
+        CpiContext::new(
+            self.gem_bank.to_account_info(),
+            CloseVault {
+                bank: self.bank.clone(),
+                bank_manager: self.farm_authority.clone(),
+                vault: self.vault.clone(),
+                owner: self.identity.to_account_info(),
+                funds_receiver: self.identity.to_account_info(),
+            },
+        )
+    }
+}
+
+/// (!) gems need to be withdrawn from the vault first, as it gets closed too
+pub fn handler(ctx: Context<CloseFarmer>) -> Result<()> {
+    // make sure the farmer isn't leaving anything behind
+    let farm = &mut ctx.accounts.farm;
+
+    farm.assert_farmer_settled(&ctx.accounts.farmer)?;
+    farm.farmer_count.try_sub_assign(1)?;
+
+    // close the vault, then the farmer
+    gem_bank::cpi::close_vault(
+        ctx.accounts
+            .close_vault_ctx()
+            .with_signer(&[&ctx.accounts.farm.farm_seeds()]),
+    )?;
+
+    close_account(
+        &mut ctx.accounts.farmer.to_account_info(),
+        &mut ctx.accounts.identity.to_account_info(),
+    )?;
+
+    msg!("farmer {} closed", ctx.accounts.identity.key());
+    Ok(())
+}
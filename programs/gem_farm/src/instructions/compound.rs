@@ -0,0 +1,221 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use gem_bank::{
+    self,
+    cpi::accounts::{DepositGem, SetVaultLock},
+    program::GemBank,
+    state::{Bank, Vault},
+};
+use gem_common::{
+    errors::ErrorCode,
+    token_interface::{self, is_token_program},
+    *,
+};
+
+use crate::state::*;
+
+#[derive(Accounts)]
+#[instruction(bump_auth: u8, bump_farmer: u8)]
+pub struct Compound<'info> {
+    // farm
+    #[account(mut, has_one = farm_authority, has_one = bank)]
+    pub farm: Box<Account<'info, Farm>>,
+    /// CHECK:
+    #[account(seeds = [farm.key().as_ref()], bump = bump_auth)]
+    pub farm_authority: AccountInfo<'info>,
+
+    // only needed if the farmer is (or is being) enrolled in a fixed rate reward
+    #[account(mut, has_one = farm)]
+    pub fixed_rate_tiers: Option<Box<Account<'info, FixedRateTiers>>>,
+
+    // farmer
+    #[account(mut, has_one = farm, has_one = identity, has_one = vault,
+        seeds = [
+            b"farmer".as_ref(),
+            farm.key().as_ref(),
+            identity.key().as_ref(),
+        ],
+        bump = bump_farmer)]
+    pub farmer: Box<Account<'info, Farmer>>,
+    #[account(mut)]
+    pub identity: Signer<'info>,
+
+    // reward (= gem)
+    /// CHECK: has to be the reward slot's pot, checked in the handler
+    #[account(mut)]
+    pub reward_pot: AccountInfo<'info>,
+    // the reward passes through the farmer's ATA on its way into the vault
+    /// CHECK: has to be the identity's ATA, created if missing
+    #[account(mut)]
+    pub gem_source: AccountInfo<'info>,
+    /// CHECK: has to be one of the farm's reward mints, checked in the handler
+    pub gem_mint: AccountInfo<'info>,
+
+    // cpi
+    pub bank: Box<Account<'info, Bank>>,
+    #[account(mut)]
+    pub vault: Box<Account<'info, Vault>>,
+    /// CHECK:
+    pub vault_authority: AccountInfo<'info>,
+    // trying to deserialize here leads to errors (doesn't exist yet)
+    /// CHECK:
+    #[account(mut)]
+    pub gem_box: AccountInfo<'info>,
+    // trying to deserialize here leads to errors (doesn't exist yet)
+    /// CHECK:
+    #[account(mut)]
+    pub gem_deposit_receipt: AccountInfo<'info>,
+    /// CHECK:
+    pub gem_rarity: AccountInfo<'info>,
+    /// CHECK: either of the 2 token programs, as long as it's the one owning the mint
+    #[account(constraint = is_token_program(token_program.key)
+        && gem_mint.owner == token_program.key @ ErrorCode::InvalidTokenProgram)]
+    pub token_program: AccountInfo<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    pub gem_bank: Program<'info, GemBank>,
+    //
+    // remaining accounts could be passed:
+    // - mint_whitelist_proof <- fungible gems can only be whitelisted by mint
+}
+
+impl<'info> Compound<'info> {
+    fn set_lock_vault_ctx(&self) -> CpiContext<'_, '_, '_, 'info, SetVaultLock<'info>> {
+        CpiContext::new(
+            self.gem_bank.to_account_info(),
+            SetVaultLock {
+                bank: self.bank.to_account_info(),
+                vault: self.vault.to_account_info(),
+                bank_manager: self.farm_authority.clone(),
+            },
+        )
+    }
+
+    fn deposit_gem_ctx(&self) -> CpiContext<'_, '_, '_, 'info, DepositGem<'info>> {
+        CpiContext::new(
+            self.gem_bank.to_account_info(),
+            DepositGem {
+                bank: self.bank.to_account_info(),
+                vault: self.vault.to_account_info(),
+                owner: self.identity.to_account_info(),
+                authority: self.vault_authority.clone(),
+                gem_box: self.gem_box.clone(),
+                gem_deposit_receipt: self.gem_deposit_receipt.clone(),
+                gem_source: self.gem_source.clone(),
+                gem_mint: self.gem_mint.clone(),
+                gem_rarity: self.gem_rarity.clone(),
+                token_program: self.token_program.clone(),
+                system_program: self.system_program.to_account_info(),
+                rent: self.rent.to_account_info(),
+            },
+        )
+    }
+
+    fn init_gem_source_if_needed(&self) -> Result<()> {
+        token_interface::init_associated_token_account_if_needed(
+            &self.gem_source,
+            &self.identity.to_account_info(),
+            &self.gem_mint,
+            &self.identity.to_account_info(),
+            &self.token_program,
+            &self.associated_token_program.to_account_info(),
+            &self.system_program.to_account_info(),
+        )
+    }
+}
+
+pub fn handler<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, Compound<'info>>,
+    bump_vault_auth: u8,
+    bump_rarity: u8,
+) -> Result<()> {
+    // only makes sense for farmers that are staked, into their locked vault
+    if ctx.accounts.farmer.state != FarmerState::Staked {
+        return Err(error!(ErrorCode::InvalidParameter));
+    }
+
+    // update accrued rewards BEFORE we take them out
+    let farm = &mut ctx.accounts.farm;
+    let fixed_rate_tiers = ctx
+        .accounts
+        .fixed_rate_tiers
+        .as_deref_mut()
+        .map(|t| &mut **t);
+    let farmer = &mut ctx.accounts.farmer;
+    let now_ts = now_ts()?;
+
+    farm.update_rewards(now_ts, Some(farmer), true, fixed_rate_tiers)?;
+
+    // the reward has to be the same token as the gem
+    let index = farm.reward_index_by_mint(ctx.accounts.gem_mint.key())?;
+    if farm.rewards[index].reward_pot != ctx.accounts.reward_pot.key() {
+        return Err(error!(ErrorCode::InvalidParameter));
+    }
+    let pot_balance = token_interface::unpack_token_account(&ctx.accounts.reward_pot)?.amount;
+
+    // compounding early costs the same cut as claiming early would
+    farm.apply_early_claim_penalty(now_ts, index, farmer)?;
+
+    let to_compound =
+        farm.rewards[index].compound_farmer_reward(&mut farmer.rewards[index], pot_balance)?;
+    if to_compound == 0 {
+        return Ok(());
+    }
+
+    // pot -> farmer's ATA (any transfer fee is taken off what gets staked)
+    ctx.accounts.init_gem_source_if_needed()?;
+    let received = token_interface::transfer_checked(
+        &ctx.accounts.token_program,
+        &ctx.accounts.reward_pot,
+        &ctx.accounts.gem_mint,
+        &ctx.accounts.gem_source,
+        &ctx.accounts.farm_authority,
+        to_compound,
+        &[&ctx.accounts.farm.farm_seeds()],
+    )?;
+
+    // farmer's ATA -> vault, same as a flash deposit
+    gem_bank::cpi::set_vault_lock(
+        ctx.accounts
+            .set_lock_vault_ctx()
+            .with_signer(&[&ctx.accounts.farm.farm_seeds()]),
+        false,
+    )?;
+
+    gem_bank::cpi::deposit_gem(
+        ctx.accounts
+            .deposit_gem_ctx()
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec()),
+        bump_vault_auth,
+        bump_rarity,
+        received,
+    )?;
+
+    gem_bank::cpi::set_vault_lock(
+        ctx.accounts
+            .set_lock_vault_ctx()
+            .with_signer(&[&ctx.accounts.farm.farm_seeds()]),
+        true,
+    )?;
+
+    // stake whatever made it into the vault (w/o locking the farmer in for any longer)
+    let gems_before = ctx.accounts.vault.gem_count;
+    ctx.accounts.vault.reload()?;
+    let extra_gems = ctx.accounts.vault.gem_count.try_sub(gems_before)?;
+
+    ctx.accounts.farm.compound_gems(
+        now_ts,
+        ctx.accounts.vault.gem_count,
+        ctx.accounts.vault.rarity_points,
+        extra_gems,
+        &mut ctx.accounts.farmer,
+        ctx.accounts
+            .fixed_rate_tiers
+            .as_deref_mut()
+            .map(|t| &mut **t),
+    )?;
+
+    msg!("{} reward compounded ({})", to_compound, extra_gems);
+    Ok(())
+}
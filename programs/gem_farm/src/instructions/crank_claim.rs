@@ -0,0 +1,143 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token::Token};
+use gem_common::{
+    errors::ErrorCode,
+    token_interface::{self, TOKEN_2022_PROGRAM_ID},
+    *,
+};
+
+use crate::state::*;
+
+#[derive(Accounts)]
+#[instruction(bump_auth: u8)]
+pub struct CrankClaim<'info> {
+    // farm
+    #[account(mut, has_one = farm_authority)]
+    pub farm: Box<Account<'info, Farm>>,
+    /// CHECK:
+    #[account(seeds = [farm.key().as_ref()], bump = bump_auth)]
+    pub farm_authority: AccountInfo<'info>,
+
+    // only needed if the farmer is (or is being) enrolled in a fixed rate reward
+    #[account(mut, has_one = farm)]
+    pub fixed_rate_tiers: Option<Box<Account<'info, FixedRateTiers>>>,
+
+    // farmer
+    #[account(mut, has_one = farm)]
+    pub farmer: Box<Account<'info, Farmer>>,
+    /// CHECK: can be any account the farmer chose, rewards go to its ATAs
+    #[account(address = farmer.reward_recipient())]
+    pub reward_recipient: AccountInfo<'info>,
+
+    // anyone can crank, they only pay for any ATAs that need creating
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    // misc
+    pub token_program: Program<'info, Token>,
+    // only needed if claiming any token-2022 rewards
+    /// CHECK:
+    #[account(address = TOKEN_2022_PROGRAM_ID)]
+    pub token_2022_program: Option<AccountInfo<'info>>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    //
+    // remaining accounts, one triple per reward to be claimed (any subset of the farm's rewards):
+    //   pub reward_mint: AccountInfo<'info>, (either token program)
+    //   #[account(mut)]
+    //   pub reward_pot: AccountInfo<'info>,
+    //   #[account(mut)]
+    //   pub reward_destination: AccountInfo<'info>, (reward_recipient's ATA, created if missing)
+}
+
+impl<'info> CrankClaim<'info> {
+    fn token_program_for(&self, reward_mint: &AccountInfo<'info>) -> Result<AccountInfo<'info>> {
+        token_interface::token_program_for_mint(
+            reward_mint,
+            &self.token_program.to_account_info(),
+            self.token_2022_program.as_ref(),
+        )
+    }
+
+    fn init_destination_if_needed(
+        &self,
+        reward_mint: &AccountInfo<'info>,
+        reward_destination: &AccountInfo<'info>,
+        token_program: &AccountInfo<'info>,
+    ) -> Result<()> {
+        token_interface::init_associated_token_account_if_needed(
+            reward_destination,
+            &self.reward_recipient,
+            reward_mint,
+            &self.payer.to_account_info(),
+            token_program,
+            &self.associated_token_program.to_account_info(),
+            &self.system_program.to_account_info(),
+        )
+    }
+}
+
+pub fn handler<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, CrankClaim<'info>>,
+) -> Result<()> {
+    // update accrued rewards before claiming
+    let farm = &mut ctx.accounts.farm;
+    let fixed_rate_tiers = ctx
+        .accounts
+        .fixed_rate_tiers
+        .as_deref_mut()
+        .map(|t| &mut **t);
+    let farmer = &mut ctx.accounts.farmer;
+    let now_ts = now_ts()?;
+
+    farm.update_rewards(now_ts, Some(farmer), true, fixed_rate_tiers)?;
+
+    if ctx.remaining_accounts.len() % 3 != 0 {
+        return Err(error!(ErrorCode::InvalidParameter));
+    }
+
+    for accs in ctx.remaining_accounts.chunks(3) {
+        let (reward_mint, reward_pot, reward_destination) = (&accs[0], &accs[1], &accs[2]);
+
+        // make sure the pot actually belongs to the reward slot
+        let index = ctx.accounts.farm.reward_index_by_mint(reward_mint.key())?;
+        if ctx.accounts.farm.rewards[index].reward_pot != reward_pot.key() {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+        let pot_balance = token_interface::unpack_token_account(reward_pot)?.amount;
+        let token_program = ctx.accounts.token_program_for(reward_mint)?;
+
+        // rewards can only ever go to the recipient's ATA
+        ctx.accounts
+            .init_destination_if_needed(reward_mint, reward_destination, &token_program)?;
+
+        // calculate claimed amount (capped at what's available in the pot)
+        // for vesting rewards only what's already unlocked, nothing new starts vesting
+        // same during the early unstake period, claiming then is up to the farmer
+        let in_early_unstake_period = ctx.accounts.farmer.in_early_unstake_period(now_ts)?;
+        let to_claim = ctx.accounts.farm.rewards[index].crank_farmer_reward(
+            now_ts,
+            &mut ctx.accounts.farmer.rewards[index],
+            pot_balance,
+            in_early_unstake_period,
+        )?;
+
+        // do the transfer
+        if to_claim > 0 {
+            token_interface::transfer_checked(
+                &token_program,
+                reward_pot,
+                reward_mint,
+                reward_destination,
+                &ctx.accounts.farm_authority,
+                to_claim,
+                &[&ctx.accounts.farm.farm_seeds()],
+            )?;
+        }
+
+        msg!("reward claimed ({} {})", to_claim, reward_mint.key());
+    }
+
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use gem_common::*;
+use gem_common::{errors::ErrorCode, *};
 
 use crate::state::*;
 
@@ -7,10 +7,15 @@ use crate::state::*;
 #[instruction(bump: u8)]
 pub struct DeauthorizeFunder<'info> {
     // farm
-    #[account(mut, has_one = farm_manager)]
+    #[account(mut,
+        constraint = has_farm_role(&farm, farm_manager.key(), role_proof.as_deref(), FarmRoles::FUNDER_ADMIN)
+            @ ErrorCode::MissingRole)]
     pub farm: Box<Account<'info, Farm>>,
+    // the farm manager, or anyone holding the role above
     #[account(mut)]
     pub farm_manager: Signer<'info>,
+    // only needed if the signer isn't the farm manager
+    pub role_proof: Option<Box<Account<'info, RoleProof>>>,
 
     // funder
     /// CHECK:
@@ -25,11 +30,23 @@ pub struct DeauthorizeFunder<'info> {
         bump = bump)]
     authorization_proof: Box<Account<'info, AuthorizationProof>>,
 
+    // only needed if the farm is timelocked
+    #[account(mut, has_one = farm)]
+    pub pending_action: Option<Box<Account<'info, PendingAction>>>,
+
     // misc
     system_program: Program<'info, System>,
 }
 
 pub fn handler(ctx: Context<DeauthorizeFunder>) -> Result<()> {
+    consume_pending_action(
+        &ctx.accounts.farm,
+        ctx.accounts.pending_action.as_deref_mut(),
+        &FarmAction::DeauthorizeFunder {
+            funder: ctx.accounts.funder_to_deauthorize.key(),
+        },
+    )?;
+
     // close authorization proof
     close_account(
         &mut ctx.accounts.authorization_proof.to_account_info(),
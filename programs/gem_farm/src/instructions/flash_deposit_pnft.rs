@@ -1,5 +1,3 @@
-use std::str::FromStr;
-
 use anchor_lang::{
     prelude::*,
     solana_program::{program::invoke, system_instruction},
@@ -11,16 +9,12 @@ use anchor_spl::{
 use gem_bank::{
     self,
     cpi::accounts::{DepositGemPnft, ProgNftShared, SetVaultLock},
-    instructions::calc_rarity_points,
     program::GemBank,
     state::{Bank, Vault},
 };
 use gem_common::*;
 
-use crate::{instructions::FEE_WALLET, state::*};
-
-const FEE_LAMPORTS: u64 = 2_000_000; // 0.002 SOL per stake/unstake
-const FD_FEE_LAMPORTS: u64 = 1_000_000; // half of that for FDs
+use crate::state::*;
 
 #[derive(Accounts)]
 #[instruction(bump_farmer: u8)]
@@ -32,6 +26,10 @@ pub struct FlashDepositPnft<'info> {
     /// CHECK:
     pub farm_authority: AccountInfo<'info>,
 
+    // only needed if the farmer is (or is being) enrolled in a fixed rate reward
+    #[account(mut, has_one = farm)]
+    pub fixed_rate_tiers: Option<Box<Account<'info, FixedRateTiers>>>,
+
     // farmer
     #[account(mut, has_one = farm, has_one = identity, has_one = vault,
         seeds = [
@@ -67,8 +65,10 @@ pub struct FlashDepositPnft<'info> {
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
     pub gem_bank: Program<'info, GemBank>,
+    #[account(seeds = [b"protocol_config".as_ref()], bump = protocol_config.bump)]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
     /// CHECK:
-    #[account(mut, address = Pubkey::from_str(FEE_WALLET).unwrap())]
+    #[account(mut, address = protocol_config.fee_recipient)]
     pub fee_acc: AccountInfo<'info>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     ///CHECK: downstream
@@ -138,6 +138,10 @@ impl<'info> FlashDepositPnft<'info> {
     }
 
     fn transfer_fee(&self, fee: u64) -> Result<()> {
+        if fee == 0 {
+            return Ok(());
+        }
+
         invoke(
             &system_instruction::transfer(self.identity.key, self.fee_acc.key, fee),
             &[
@@ -185,35 +189,43 @@ pub fn handler<'a, 'b, 'c, 'info>(
 
     // update accrued rewards BEFORE we increment the stake
     let farm = &mut ctx.accounts.farm;
+    let mut fixed_rate_tiers = ctx
+        .accounts
+        .fixed_rate_tiers
+        .as_deref_mut()
+        .map(|t| &mut **t);
     let farmer = &mut ctx.accounts.farmer;
     let now_ts = now_ts()?;
 
-    farm.update_rewards(now_ts, Some(farmer), true)?;
+    farm.update_rewards(now_ts, Some(farmer), true, fixed_rate_tiers.as_deref_mut())?;
 
     ctx.accounts.vault.reload()?;
 
     // in case the command is used BEFORE farmer staked
-    if farmer.gems_staked == 0 {
+    if farmer.state != FarmerState::Staked {
         farm.begin_staking(
             now_ts,
             ctx.accounts.vault.gem_count,
             ctx.accounts.vault.rarity_points,
+            0, //no lockup - use stake to opt into one
             farmer,
+            fixed_rate_tiers,
         )?;
         //collect a fee for staking
-        ctx.accounts.transfer_fee(FEE_LAMPORTS)?;
+        let fee = ctx.accounts.protocol_config.fees.stake_fee_lamp;
+        ctx.accounts.transfer_fee(fee)?;
     } else {
-        let extra_rarity = calc_rarity_points(&ctx.accounts.gem_rarity, amount)?;
         farm.stake_extra_gems(
             now_ts,
             ctx.accounts.vault.gem_count,
             ctx.accounts.vault.rarity_points,
             amount,
-            extra_rarity,
             farmer,
+            fixed_rate_tiers,
         )?;
         //collect a fee for staking
-        ctx.accounts.transfer_fee(FD_FEE_LAMPORTS)?;
+        let fee = ctx.accounts.protocol_config.fees.flash_deposit_fee_lamp;
+        ctx.accounts.transfer_fee(fee)?;
     }
 
     // msg!("{} extra gems staked for {}", amount, farmer.key());
@@ -1,6 +1,12 @@
-use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
-use gem_common::*;
+use anchor_lang::{
+    prelude::*,
+    solana_program::{program::invoke_signed, system_instruction},
+};
+use gem_common::{
+    errors::ErrorCode,
+    token_interface::{self, is_token_program},
+    *,
+};
 
 use crate::state::*;
 
@@ -12,7 +18,7 @@ pub struct FundReward<'info> {
     pub farm: Box<Account<'info, Farm>>,
 
     // funder
-    #[account(has_one = farm, has_one = authorized_funder, seeds = [
+    #[account(mut, has_one = farm, has_one = authorized_funder, seeds = [
             b"authorization".as_ref(),
             farm.key().as_ref(),
             authorized_funder.key().as_ref(),
@@ -21,66 +27,165 @@ pub struct FundReward<'info> {
     pub authorization_proof: Box<Account<'info, AuthorizationProof>>,
     #[account(mut)]
     pub authorized_funder: Signer<'info>,
+    #[account(init_if_needed, seeds = [
+            b"contribution".as_ref(),
+            farm.key().as_ref(),
+            reward_mint.key().as_ref(),
+            authorized_funder.key().as_ref(),
+        ],
+        bump,
+        payer = authorized_funder,
+        space = 8 + std::mem::size_of::<FunderContribution>())]
+    pub contribution: Box<Account<'info, FunderContribution>>,
 
     // reward
+    // only ever created (and paid for) when a fixed rate reward is funded
+    /// CHECK: created in the handler if missing
+    #[account(mut, seeds = [
+            b"fixed_rate_tiers".as_ref(),
+            farm.key().as_ref(),
+        ],
+        bump)]
+    pub fixed_rate_tiers: AccountInfo<'info>,
+    // token accounts & the mint are taken in raw, as they can belong to either token program
+    /// CHECK:
     #[account(mut, seeds = [
             b"reward_pot".as_ref(),
             farm.key().as_ref(),
             reward_mint.key().as_ref(),
         ],
         bump = bump_pot)]
-    pub reward_pot: Box<Account<'info, TokenAccount>>,
+    pub reward_pot: AccountInfo<'info>,
+    /// CHECK: verified by the token program during the transfer
     #[account(mut)]
-    pub reward_source: Box<Account<'info, TokenAccount>>,
-    pub reward_mint: Box<Account<'info, Mint>>,
+    pub reward_source: AccountInfo<'info>,
+    /// CHECK: unpacked during the transfer
+    pub reward_mint: AccountInfo<'info>,
 
     // misc
-    pub token_program: Program<'info, Token>,
+    /// CHECK: either of the 2 token programs, as long as it's the one owning the mint
+    #[account(constraint = is_token_program(token_program.key)
+        && reward_mint.owner == token_program.key @ ErrorCode::InvalidTokenProgram)]
+    pub token_program: AccountInfo<'info>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> FundReward<'info> {
-    fn transfer_ctx(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        CpiContext::new(
-            self.token_program.to_account_info(),
-            Transfer {
-                from: self.reward_source.to_account_info(),
-                to: self.reward_pot.to_account_info(),
-                authority: self.authorized_funder.to_account_info(),
-            },
+    /// returns the amount that actually arrived in the pot
+    fn transfer(&self, amount: u64) -> Result<u64> {
+        token_interface::transfer_checked(
+            &self.token_program,
+            &self.reward_source,
+            &self.reward_mint,
+            &self.reward_pot,
+            &self.authorized_funder.to_account_info(),
+            amount,
+            &[&self.farm.farm_seeds()],
         )
     }
+
+    fn init_fixed_rate_tiers(&self, bump: u8) -> Result<()> {
+        let space = 8 + std::mem::size_of::<FixedRateTiers>();
+
+        invoke_signed(
+            &system_instruction::create_account(
+                self.authorized_funder.key,
+                self.fixed_rate_tiers.key,
+                Rent::get()?.minimum_balance(space),
+                space as u64,
+                &crate::ID,
+            ),
+            &[
+                self.authorized_funder.to_account_info(),
+                self.fixed_rate_tiers.clone(),
+                self.system_program.to_account_info(),
+            ],
+            &[&[
+                b"fixed_rate_tiers".as_ref(),
+                self.farm.key().as_ref(),
+                &[bump],
+            ]],
+        )?;
+
+        let mut data = self.fixed_rate_tiers.try_borrow_mut_data()?;
+        FixedRateTiers::default().try_serialize(&mut &mut data[..])
+    }
 }
 
 pub fn handler(
     ctx: Context<FundReward>,
-    variable_rate_config: Option<VariableRateConfig>,
-    fixed_rate_config: Option<FixedRateConfig>,
+    mut variable_rate_config: Option<VariableRateConfig>,
+    mut fixed_rate_config: Option<FixedRateConfig>,
 ) -> Result<()> {
-    let amount = if let Some(config) = variable_rate_config {
-        config.amount
-    } else {
-        fixed_rate_config.unwrap().amount
+    // fix missing discriminator check
+    assert_discriminator_or_new::<FunderContribution>(
+        &ctx.accounts.contribution.to_account_info(),
+    )?;
+
+    // exactly one of the two, the farm checks it's the one matching the reward's type
+    let amount = match (variable_rate_config, fixed_rate_config) {
+        (Some(config), None) => config.amount,
+        (None, Some(config)) => config.amount,
+        _ => return Err(error!(ErrorCode::RewardConfigMismatch)),
     };
 
-    // update existing rewards + record new ones
-    let farm = &mut ctx.accounts.farm;
+    // make sure the funder is still within their allowance
     let now_ts = now_ts()?;
+    let reward_mint = ctx.accounts.reward_mint.key();
+
+    ctx.accounts
+        .authorization_proof
+        .record_funding(now_ts, reward_mint, amount)?;
+
+    // update existing rewards
 
-    farm.update_rewards(now_ts, None, true)?;
+    ctx.accounts.farm.update_rewards(now_ts, None, true, None)?;
+
+    // do the transfer - for mints with a transfer fee, only the net amount is funded
+    let amount = ctx.accounts.transfer(amount)?;
+
+    if let Some(config) = variable_rate_config.as_mut() {
+        config.amount = amount;
+    }
+    if let Some(config) = fixed_rate_config.as_mut() {
+        config.amount = amount;
+    }
 
-    farm.fund_reward_by_mint(
+    // record the new reward
+    ctx.accounts.farm.fund_reward_by_mint(
         now_ts,
-        ctx.accounts.reward_mint.key(),
+        reward_mint,
         variable_rate_config,
         fixed_rate_config,
     )?;
 
-    // do the transfer
-    token::transfer(
-        ctx.accounts
-            .transfer_ctx()
-            .with_signer(&[&ctx.accounts.farm.farm_seeds()]),
+    let index = ctx.accounts.farm.reward_index_by_mint(reward_mint)?;
+    let reward_id = ctx.accounts.farm.rewards[index].reward_id;
+    let funding_round = ctx.accounts.farm.rewards[index].funding_round;
+    let farm = ctx.accounts.farm.key();
+
+    // the schedule is kept off the farm, farmers only keep track of which one they were promised
+    if let Some(config) = fixed_rate_config {
+        if ctx.accounts.fixed_rate_tiers.data_is_empty() {
+            ctx.accounts
+                .init_fixed_rate_tiers(*ctx.bumps.get("fixed_rate_tiers").unwrap())?;
+        }
+
+        let mut fixed_rate_tiers =
+            Account::<FixedRateTiers>::try_from(&ctx.accounts.fixed_rate_tiers)?;
+        fixed_rate_tiers.set_schedule(farm, index, reward_id, &config.schedule)?;
+        fixed_rate_tiers.exit(ctx.program_id)?;
+    }
+
+    // keep track of who put in what, for pro-rata refunds
+    let funder = ctx.accounts.authorized_funder.key();
+
+    ctx.accounts.contribution.record_funding(
+        farm,
+        funder,
+        reward_mint,
+        reward_id,
+        funding_round,
         amount,
     )?;
 
@@ -0,0 +1,52 @@
+use anchor_lang::prelude::*;
+use gem_common::*;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct GrantRoles<'info> {
+    // farm
+    #[account(has_one = farm_manager)]
+    pub farm: Box<Account<'info, Farm>>,
+    #[account(mut)]
+    pub farm_manager: Signer<'info>,
+
+    // holder
+    /// CHECK:
+    pub holder: AccountInfo<'info>,
+    #[account(init_if_needed, seeds = [
+            b"role".as_ref(),
+            farm.key().as_ref(),
+            holder.key().as_ref(),
+        ],
+        bump,
+        payer = farm_manager,
+        space = 8 + std::mem::size_of::<RoleProof>())]
+    role_proof: Box<Account<'info, RoleProof>>,
+
+    // misc
+    system_program: Program<'info, System>,
+}
+
+/// replaces whatever roles the holder had before
+pub fn handler(ctx: Context<GrantRoles>, roles: u32) -> Result<()> {
+    // fix missing discriminator check
+    assert_discriminator_or_new::<RoleProof>(&ctx.accounts.role_proof.to_account_info())?;
+
+    RoleProof::read_roles(roles)?;
+
+    // create/update role proof
+    let proof = &mut ctx.accounts.role_proof;
+
+    proof.holder = ctx.accounts.holder.key();
+    proof.farm = ctx.accounts.farm.key();
+    proof.granted_by = ctx.accounts.farm_manager.key();
+    proof.roles = roles;
+
+    msg!(
+        "roles {:#b} granted to: {}",
+        roles,
+        ctx.accounts.holder.key()
+    );
+    Ok(())
+}
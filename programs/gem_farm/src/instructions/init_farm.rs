@@ -1,15 +1,14 @@
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::{program::invoke, system_instruction};
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token::Token;
 use gem_bank::{self, cpi::accounts::InitBank, program::GemBank};
-use gem_common::errors::ErrorCode;
-use std::str::FromStr;
+use gem_common::{
+    errors::ErrorCode,
+    token_interface::{self, TOKEN_2022_PROGRAM_ID},
+};
 
 use crate::state::*;
 
-pub const FEE_WALLET: &str = "2xhBxVVuXkdq2MRKerE9mr2s1szfHSedy21MVqf8gPoM"; //5th
-const FEE_LAMPORTS: u64 = 2_500_000_000; // 2.5 SOL per farm
-
 #[derive(Accounts)]
 #[instruction(bump_auth: u8, bump_treasury: u8)]
 pub struct InitFarm<'info> {
@@ -21,32 +20,6 @@ pub struct InitFarm<'info> {
     #[account(mut, seeds = [farm.key().as_ref()], bump = bump_auth)]
     pub farm_authority: AccountInfo<'info>,
 
-    // reward a
-    #[account(init, seeds = [
-            b"reward_pot".as_ref(),
-            farm.key().as_ref(),
-            reward_a_mint.key().as_ref(),
-        ],
-        bump,
-        token::mint = reward_a_mint,
-        token::authority = farm_authority,
-        payer = payer)]
-    pub reward_a_pot: Box<Account<'info, TokenAccount>>,
-    pub reward_a_mint: Box<Account<'info, Mint>>,
-
-    // reward b
-    #[account(init, seeds = [
-            b"reward_pot".as_ref(),
-            farm.key().as_ref(),
-            reward_b_mint.key().as_ref(),
-        ],
-        bump,
-        token::mint = reward_b_mint,
-        token::authority = farm_authority,
-        payer = payer)]
-    pub reward_b_pot: Box<Account<'info, TokenAccount>>,
-    pub reward_b_mint: Box<Account<'info, Mint>>,
-
     // cpi
     #[account(mut)]
     pub bank: Signer<'info>,
@@ -55,12 +28,23 @@ pub struct InitFarm<'info> {
     // misc
     #[account(mut)]
     pub payer: Signer<'info>,
+    #[account(seeds = [b"protocol_config".as_ref()], bump = protocol_config.bump)]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
     /// CHECK:
-    #[account(mut, address = Pubkey::from_str(FEE_WALLET).unwrap())]
+    #[account(mut, address = protocol_config.fee_recipient)]
     pub fee_acc: AccountInfo<'info>,
     pub rent: Sysvar<'info, Rent>,
     pub token_program: Program<'info, Token>,
+    // only needed if any of the reward mints are token-2022
+    /// CHECK:
+    #[account(address = TOKEN_2022_PROGRAM_ID)]
+    pub token_2022_program: Option<AccountInfo<'info>>,
     pub system_program: Program<'info, System>,
+    //
+    // remaining accounts, one pair per reward type passed in:
+    //   pub reward_mint: AccountInfo<'info>, (either token program)
+    //   #[account(mut)]
+    //   pub reward_pot: AccountInfo<'info>, (uninitialized)
 }
 
 impl<'info> InitFarm<'info> {
@@ -79,9 +63,58 @@ impl<'info> InitFarm<'info> {
         )
     }
 
+    /// creates the reward pot PDA and initializes it as a token account owned by farm authority
+    fn init_reward_pot(
+        &self,
+        reward_mint: &AccountInfo<'info>,
+        reward_pot: &AccountInfo<'info>,
+        program_id: &Pubkey,
+    ) -> Result<()> {
+        // make sure we're actually dealing with a mint
+        token_interface::unpack_mint(reward_mint)?;
+        let token_program = token_interface::token_program_for_mint(
+            reward_mint,
+            &self.token_program.to_account_info(),
+            self.token_2022_program.as_ref(),
+        )?;
+
+        let farm = self.farm.key();
+        let (pk, bump) = Pubkey::find_program_address(
+            &[
+                b"reward_pot".as_ref(),
+                farm.as_ref(),
+                reward_mint.key().as_ref(),
+            ],
+            program_id,
+        );
+        if reward_pot.key() != pk {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+
+        token_interface::create_token_account_pda(
+            &self.payer.to_account_info(),
+            reward_pot,
+            reward_mint,
+            &self.farm_authority,
+            &token_program,
+            &self.system_program.to_account_info(),
+            &[
+                b"reward_pot".as_ref(),
+                farm.as_ref(),
+                reward_mint.key().as_ref(),
+                &[bump],
+            ],
+        )
+    }
+
     fn transfer_fee(&self) -> Result<()> {
+        let fee = self.protocol_config.fees.init_farm_fee_lamp;
+        if fee == 0 {
+            return Ok(());
+        }
+
         invoke(
-            &system_instruction::transfer(self.payer.key, self.fee_acc.key, FEE_LAMPORTS),
+            &system_instruction::transfer(self.payer.key, self.fee_acc.key, fee),
             &[
                 self.payer.to_account_info(),
                 self.fee_acc.clone(),
@@ -92,11 +125,11 @@ impl<'info> InitFarm<'info> {
     }
 }
 
-pub fn handler(
-    ctx: Context<InitFarm>,
+pub fn handler<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, InitFarm<'info>>,
     bump_auth: u8,
-    reward_type_a: RewardType,
-    reward_type_b: RewardType,
+    reward_types: Vec<RewardType>,
+    vesting: Vec<VestingConfig>,
     farm_config: FarmConfig,
     max_counts: Option<MaxCounts>,
     farm_treasury: Pubkey,
@@ -110,6 +143,17 @@ pub fn handler(
         return Err(error!(ErrorCode::InvalidParameter));
     }
 
+    if reward_types.is_empty() {
+        return Err(error!(ErrorCode::InvalidParameter));
+    }
+    if reward_types.len() > MAX_REWARDS {
+        return Err(error!(ErrorCode::TooManyRewards));
+    }
+    // one per reward type, any left out don't vest
+    if vesting.len() > reward_types.len() {
+        return Err(error!(ErrorCode::InvalidParameter));
+    }
+
     //ensure unstaking fee does not violate solana v1.9.5 rent requirements
     if farm_config.unstaking_fee_lamp > 0 && farm_config.unstaking_fee_lamp < 890880 {
         return Err(error!(ErrorCode::InvalidUnstakingFee));
@@ -127,20 +171,27 @@ pub fn handler(
     farm.bank = ctx.accounts.bank.key();
     farm.config = farm_config;
 
-    farm.reward_a.reward_mint = ctx.accounts.reward_a_mint.key();
-    farm.reward_a.reward_pot = ctx.accounts.reward_a_pot.key();
-    farm.reward_a.reward_type = reward_type_a;
-    farm.reward_a.fixed_rate.schedule = FixedRateSchedule::default(); //denom to 1
-
-    farm.reward_b.reward_mint = ctx.accounts.reward_b_mint.key();
-    farm.reward_b.reward_pot = ctx.accounts.reward_b_pot.key();
-    farm.reward_b.reward_type = reward_type_b;
-    farm.reward_b.fixed_rate.schedule = FixedRateSchedule::default(); //denom to 1
-
     if let Some(max_counts) = max_counts {
         farm.max_counts = max_counts;
     }
 
+    //create a pot for each of the rewards and record them on the farm
+    let remaining_accs = &mut ctx.remaining_accounts.iter();
+
+    for (i, reward_type) in reward_types.into_iter().enumerate() {
+        let reward_mint = next_account_info(remaining_accs)?;
+        let reward_pot = next_account_info(remaining_accs)?;
+
+        ctx.accounts
+            .init_reward_pot(reward_mint, reward_pot, ctx.program_id)?;
+        ctx.accounts.farm.add_reward(
+            reward_mint.key(),
+            reward_pot.key(),
+            reward_type,
+            vesting.get(i).copied().unwrap_or_default(),
+        )?;
+    }
+
     //do a cpi call to start a new bank
     gem_bank::cpi::init_bank(
         ctx.accounts
@@ -1,14 +1,10 @@
-use crate::instructions::FEE_WALLET;
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::{program::invoke, system_instruction};
 use gem_bank::{self, cpi::accounts::InitVault, program::GemBank, state::Bank};
 use gem_common::*;
-use std::str::FromStr;
 
 use crate::state::*;
 
-const FEE_LAMPORTS: u64 = 10_000_000; // 0.01 SOL per farmer
-
 #[derive(Accounts)]
 pub struct InitFarmer<'info> {
     // farm
@@ -39,8 +35,10 @@ pub struct InitFarmer<'info> {
     // misc
     #[account(mut)]
     pub payer: Signer<'info>,
+    #[account(seeds = [b"protocol_config".as_ref()], bump = protocol_config.bump)]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
     /// CHECK:
-    #[account(mut, address = Pubkey::from_str(FEE_WALLET).unwrap())]
+    #[account(mut, address = protocol_config.fee_recipient)]
     pub fee_acc: AccountInfo<'info>,
     pub system_program: Program<'info, System>,
 }
@@ -61,8 +59,13 @@ impl<'info> InitFarmer<'info> {
     }
 
     fn transfer_fee(&self) -> Result<()> {
+        let fee = self.protocol_config.fees.init_farmer_fee_lamp;
+        if fee == 0 {
+            return Ok(());
+        }
+
         invoke(
-            &system_instruction::transfer(self.payer.key, self.fee_acc.key, FEE_LAMPORTS),
+            &system_instruction::transfer(self.payer.key, self.fee_acc.key, fee),
             &[
                 self.payer.to_account_info(),
                 self.fee_acc.clone(),
@@ -80,8 +83,6 @@ pub fn handler(ctx: Context<InitFarmer>) -> Result<()> {
     farmer.farm = ctx.accounts.farm.key();
     farmer.identity = ctx.accounts.identity.key();
     farmer.vault = ctx.accounts.vault.key();
-    farmer.reward_a.fixed_rate.promised_schedule = FixedRateSchedule::default(); //denom to 1
-    farmer.reward_b.fixed_rate.promised_schedule = FixedRateSchedule::default(); //denom to 1
 
     // update farm
     let farm = &mut ctx.accounts.farm;
@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+
+use crate::{program::GemFarm, state::*};
+
+/// can only be called once, by the program's upgrade authority
+#[derive(Accounts)]
+pub struct InitProtocolConfig<'info> {
+    // protocol
+    #[account(init, seeds = [b"protocol_config".as_ref()], bump,
+        payer = admin,
+        space = 8 + std::mem::size_of::<ProtocolConfig>())]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+    #[account(mut)]
+    pub admin: Signer<'info>,
+
+    // program
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, GemFarm>,
+    #[account(constraint = program_data.upgrade_authority_address == Some(admin.key()))]
+    pub program_data: Account<'info, ProgramData>,
+
+    // misc
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(
+    ctx: Context<InitProtocolConfig>,
+    fee_recipient: Pubkey,
+    fees: FeeSchedule,
+) -> Result<()> {
+    let config = &mut ctx.accounts.protocol_config;
+
+    config.admin = ctx.accounts.admin.key();
+    config.fee_recipient = fee_recipient;
+    config.fees = fees;
+    config.bump = *ctx.bumps.get("protocol_config").unwrap();
+
+    msg!("protocol config initialized");
+    Ok(())
+}
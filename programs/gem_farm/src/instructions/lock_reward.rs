@@ -1,7 +1,7 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::Mint;
 
-use crate::state::Farm;
+use crate::state::*;
 
 #[derive(Accounts)]
 pub struct LockReward<'info> {
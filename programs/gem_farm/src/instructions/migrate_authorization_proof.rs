@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use gem_common::{errors::ErrorCode, *};
+
+use crate::state::*;
+
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct MigrateAuthorizationProof<'info> {
+    // farm
+    pub farm: Box<Account<'info, Farm>>,
+
+    // funder
+    /// CHECK:
+    pub authorized_funder: AccountInfo<'info>,
+    /// CHECK: can't be deserialized until it's been migrated, checked in the handler
+    #[account(mut, owner = crate::ID,
+        seeds = [
+            b"authorization".as_ref(),
+            farm.key().as_ref(),
+            authorized_funder.key().as_ref(),
+        ],
+        bump = bump)]
+    pub authorization_proof: AccountInfo<'info>,
+
+    // misc
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// permissionless - the appended limits are zeroed, which keeps the proof as open-ended
+/// as it was before (any reward, no cap, never expires)
+pub fn handler(ctx: Context<MigrateAuthorizationProof>) -> Result<()> {
+    let proof = &ctx.accounts.authorization_proof;
+
+    {
+        let data = proof.try_borrow_data()?;
+        if data.len() != AuthorizationProof::LEGACY_LEN {
+            return Err(error!(ErrorCode::AccountAlreadyMigrated));
+        }
+        if data[..8] != AuthorizationProof::discriminator() {
+            return Err(error!(ErrorCode::AccountDiscriminatorMismatch));
+        }
+    }
+
+    realloc_account(
+        proof,
+        8 + std::mem::size_of::<AuthorizationProof>(),
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    msg!("authorization proof migrated: {}", proof.key());
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use gem_common::{errors::ErrorCode, *};
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct MigrateFarm<'info> {
+    // farm
+    /// CHECK: can't be deserialized until it's been migrated, checked in the handler
+    #[account(mut, owner = crate::ID)]
+    pub farm: AccountInfo<'info>,
+
+    // reward
+    #[account(init_if_needed, seeds = [
+            b"fixed_rate_tiers".as_ref(),
+            farm.key().as_ref(),
+        ],
+        bump,
+        payer = payer,
+        space = 8 + std::mem::size_of::<FixedRateTiers>())]
+    pub fixed_rate_tiers: Box<Account<'info, FixedRateTiers>>,
+
+    // misc
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// permissionless - the legacy rewards are carried over as they were, into the first 2 slots
+/// (!) the farm's farmers have to be migrated too before any reward can be retired
+pub fn handler(ctx: Context<MigrateFarm>) -> Result<()> {
+    let farm = &ctx.accounts.farm;
+
+    let legacy = {
+        let data = farm.try_borrow_data()?;
+        if data[..8] != Farm::discriminator() {
+            return Err(error!(ErrorCode::AccountDiscriminatorMismatch));
+        }
+        let legacy = LegacyFarm::deserialize(&mut &data[8..])?;
+        if legacy.version != 0 {
+            return Err(error!(ErrorCode::AccountAlreadyMigrated));
+        }
+        legacy
+    };
+
+    realloc_account(
+        farm,
+        8 + std::mem::size_of::<Farm>(),
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    // start from scratch, then fill in what the legacy layout had
+    let mut data = farm.try_borrow_mut_data()?;
+    data[8..].fill(0);
+    let mut migrated = Farm::try_deserialize(&mut &data[..])?;
+
+    migrated.migrate_legacy(farm.key(), &legacy, &mut ctx.accounts.fixed_rate_tiers)?;
+    migrated.try_serialize(&mut &mut data[..])?;
+
+    msg!("farm migrated: {}", farm.key());
+    Ok(())
+}
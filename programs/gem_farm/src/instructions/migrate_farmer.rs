@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+use anchor_lang::Discriminator;
+use gem_common::{errors::ErrorCode, *};
+
+use crate::state::*;
+
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct MigrateFarmer<'info> {
+    // farm
+    // has to have been migrated already
+    #[account(mut)]
+    pub farm: Box<Account<'info, Farm>>,
+
+    // farmer
+    /// CHECK:
+    pub identity: AccountInfo<'info>,
+    /// CHECK: can't be deserialized until it's been migrated, checked in the handler
+    #[account(mut, owner = crate::ID,
+        seeds = [
+            b"farmer".as_ref(),
+            farm.key().as_ref(),
+            identity.key().as_ref(),
+        ],
+        bump = bump)]
+    pub farmer: AccountInfo<'info>,
+
+    // only needed if the farmer was promised a fixed rate schedule
+    #[account(mut, has_one = farm)]
+    pub fixed_rate_tiers: Option<Box<Account<'info, FixedRateTiers>>>,
+
+    // misc
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+/// permissionless - the farmer's rewards are carried over as they were, into the slots
+/// the farm's legacy rewards were migrated into
+pub fn handler(ctx: Context<MigrateFarmer>) -> Result<()> {
+    let farmer = &ctx.accounts.farmer;
+    let new_len = 8 + std::mem::size_of::<Farmer>();
+
+    let legacy = {
+        let data = farmer.try_borrow_data()?;
+        if data.len() >= new_len {
+            return Err(error!(ErrorCode::AccountAlreadyMigrated));
+        }
+        if data[..8] != Farmer::discriminator() {
+            return Err(error!(ErrorCode::AccountDiscriminatorMismatch));
+        }
+        LegacyFarmer::deserialize(&mut &data[8..])?
+    };
+
+    realloc_account(
+        farmer,
+        new_len,
+        &ctx.accounts.payer.to_account_info(),
+        &ctx.accounts.system_program.to_account_info(),
+    )?;
+
+    // start from scratch, then fill in what the legacy layout had
+    let mut data = farmer.try_borrow_mut_data()?;
+    data[8..].fill(0);
+    let mut migrated = Farmer::try_deserialize(&mut &data[..])?;
+
+    ctx.accounts.farm.migrate_legacy_farmer(
+        now_ts()?,
+        &legacy,
+        &mut migrated,
+        ctx.accounts
+            .fixed_rate_tiers
+            .as_deref_mut()
+            .map(|t| &mut **t),
+    )?;
+    migrated.try_serialize(&mut &mut data[..])?;
+
+    msg!("farmer migrated: {}", farmer.key());
+    Ok(())
+}
@@ -1,44 +1,90 @@
+pub mod accept_farm_manager;
+pub mod accept_protocol_admin;
 pub mod add_rarities_to_bank;
+pub mod add_reward_slot;
 pub mod add_to_bank_whitelist;
 pub mod authorize_funder;
+pub mod cancel_farm_action;
 pub mod cancel_reward;
+pub mod cancel_reward_pro_rata;
 pub mod claim;
+pub mod close_farm;
+pub mod close_farmer;
+pub mod compound;
+pub mod crank_claim;
 pub mod deauthorize_funder;
 pub mod flash_deposit;
 pub mod flash_deposit_pnft;
 pub mod fund_reward;
+pub mod grant_roles;
 pub mod init_farm;
 pub mod init_farmer;
+pub mod init_protocol_config;
 pub mod lock_reward;
+pub mod migrate_authorization_proof;
+pub mod migrate_farm;
+pub mod migrate_farmer;
+pub mod partial_unstake;
+pub mod queue_farm_action;
+pub mod queue_reward_epochs;
 pub mod refresh_farmer;
 pub mod refresh_farmer_signed;
 pub mod remove_from_bank_whitelist;
+pub mod remove_rarities_from_bank;
+pub mod retire_reward_slot;
+pub mod revoke_roles;
+pub mod set_reward_recipient;
 pub mod stake;
 pub mod treasury_payout;
 pub mod unstake;
 pub mod update_farm;
+pub mod update_protocol_config;
+pub mod withdraw_vested;
 
+pub use accept_farm_manager::*;
+pub use accept_protocol_admin::*;
 pub use add_rarities_to_bank::*;
+pub use add_reward_slot::*;
 pub use add_to_bank_whitelist::*;
 // have to duplicate or this won't show up in IDL
 use anchor_lang::prelude::*;
 pub use authorize_funder::*;
+pub use cancel_farm_action::*;
 pub use cancel_reward::*;
+pub use cancel_reward_pro_rata::*;
 pub use claim::*;
+pub use close_farm::*;
+pub use close_farmer::*;
+pub use compound::*;
+pub use crank_claim::*;
 pub use deauthorize_funder::*;
 pub use flash_deposit::*;
 pub use flash_deposit_pnft::*;
 pub use fund_reward::*;
+pub use grant_roles::*;
 pub use init_farm::*;
 pub use init_farmer::*;
+pub use init_protocol_config::*;
 pub use lock_reward::*;
+pub use migrate_authorization_proof::*;
+pub use migrate_farm::*;
+pub use migrate_farmer::*;
+pub use partial_unstake::*;
+pub use queue_farm_action::*;
+pub use queue_reward_epochs::*;
 pub use refresh_farmer::*;
 pub use refresh_farmer_signed::*;
 pub use remove_from_bank_whitelist::*;
+pub use remove_rarities_from_bank::*;
+pub use retire_reward_slot::*;
+pub use revoke_roles::*;
+pub use set_reward_recipient::*;
 pub use stake::*;
 pub use treasury_payout::*;
 pub use unstake::*;
 pub use update_farm::*;
+pub use update_protocol_config::*;
+pub use withdraw_vested::*;
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq)]
 pub struct RarityConfig {
     pub mint: Pubkey,
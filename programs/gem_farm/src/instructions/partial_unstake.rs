@@ -0,0 +1,236 @@
+use anchor_lang::{
+    prelude::*,
+    solana_program::{program::invoke, system_instruction},
+};
+use anchor_spl::associated_token::AssociatedToken;
+use gem_bank::{
+    self,
+    cpi::accounts::{SetVaultLock, WithdrawGem},
+    instructions::calc_rarity_points,
+    program::GemBank,
+    state::{Bank, GemDepositReceipt, Vault},
+};
+use gem_common::*;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+#[instruction(bump_farmer: u8, bump_vault_auth: u8, bump_gem_box: u8, bump_gdr: u8, bump_rarity: u8)]
+pub struct PartialUnstake<'info> {
+    // farm
+    #[account(mut, has_one = farm_authority, has_one = farm_treasury, has_one = bank)]
+    pub farm: Box<Account<'info, Farm>>,
+    //skipping seeds verification to save compute budget, has_one check above should be enough
+    /// CHECK:
+    pub farm_authority: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut)]
+    pub farm_treasury: AccountInfo<'info>,
+
+    // farmer
+    #[account(mut, has_one = farm, has_one = identity, has_one = vault,
+        seeds = [
+            b"farmer".as_ref(),
+            farm.key().as_ref(),
+            identity.key().as_ref(),
+        ],
+        bump = bump_farmer)]
+    pub farmer: Box<Account<'info, Farmer>>,
+    #[account(mut)]
+    pub identity: Signer<'info>,
+
+    // only needed if the farmer is enrolled in a fixed rate reward
+    #[account(mut, has_one = farm)]
+    pub fixed_rate_tiers: Option<Box<Account<'info, FixedRateTiers>>>,
+
+    // cpi
+    pub bank: Box<Account<'info, Bank>>,
+    #[account(mut)]
+    pub vault: Box<Account<'info, Vault>>,
+    /// CHECK:
+    pub vault_authority: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut)]
+    pub gem_box: AccountInfo<'info>,
+    #[account(mut, has_one = vault, has_one = gem_mint)]
+    pub gem_deposit_receipt: Box<Account<'info, GemDepositReceipt>>,
+    /// CHECK:
+    #[account(mut)]
+    pub gem_destination: AccountInfo<'info>,
+    /// CHECK:
+    #[account(mut)]
+    pub gem_mint: AccountInfo<'info>,
+    // has to be verified here, since it decides how many rarity points come off the stake
+    /// CHECK:
+    #[account(seeds = [
+            b"gem_rarity".as_ref(),
+            bank.key().as_ref(),
+            gem_mint.key().as_ref()
+        ],
+        seeds::program = gem_bank.key(),
+        bump = bump_rarity)]
+    pub gem_rarity: AccountInfo<'info>,
+    /// CHECK:
+    pub token_program: AccountInfo<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    pub gem_bank: Program<'info, GemBank>,
+
+    //misc
+    #[account(seeds = [b"protocol_config".as_ref()], bump = protocol_config.bump)]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+    /// CHECK:
+    #[account(mut, address = protocol_config.fee_recipient)]
+    pub fee_acc: AccountInfo<'info>,
+}
+
+impl<'info> PartialUnstake<'info> {
+    fn set_lock_vault_ctx(&self) -> CpiContext<'_, '_, '_, 'info, SetVaultLock<'info>> {
+        CpiContext::new(
+            self.gem_bank.to_account_info(),
+            SetVaultLock {
+                bank: self.bank.to_account_info(),
+                vault: self.vault.to_account_info(),
+                bank_manager: self.farm_authority.clone(),
+            },
+        )
+    }
+
+    fn withdraw_gem_ctx(&self) -> CpiContext<'_, '_, '_, 'info, WithdrawGem<'info>> {
+        CpiContext::new(
+            self.gem_bank.to_account_info(),
+            WithdrawGem {
+                bank: self.bank.to_account_info(),
+                vault: self.vault.to_account_info(),
+                owner: self.identity.to_account_info(),
+                authority: self.vault_authority.clone(),
+                gem_box: self.gem_box.clone(),
+                gem_deposit_receipt: self.gem_deposit_receipt.to_account_info(),
+                gem_destination: self.gem_destination.clone(),
+                gem_mint: self.gem_mint.clone(),
+                gem_rarity: self.gem_rarity.clone(),
+                receiver: self.identity.to_account_info(),
+                token_program: self.token_program.clone(),
+                associated_token_program: self.associated_token_program.to_account_info(),
+                system_program: self.system_program.to_account_info(),
+                rent: self.rent.to_account_info(),
+            },
+        )
+    }
+
+    fn pay_treasury(&self, lamports: u64) -> Result<()> {
+        invoke(
+            &system_instruction::transfer(self.identity.key, self.farm_treasury.key, lamports),
+            &[
+                self.identity.to_account_info(),
+                self.farm_treasury.clone(),
+                self.system_program.to_account_info(),
+            ],
+        )
+        .map_err(Into::into)
+    }
+
+    fn transfer_fee(&self) -> Result<()> {
+        let fee = self.protocol_config.fees.unstake_fee_lamp;
+        if fee == 0 {
+            return Ok(());
+        }
+
+        invoke(
+            &system_instruction::transfer(self.identity.key, self.fee_acc.key, fee),
+            &[
+                self.identity.to_account_info(),
+                self.fee_acc.clone(),
+                self.system_program.to_account_info(),
+            ],
+        )
+        .map_err(Into::into)
+    }
+}
+
+/// cycles through 2 states on repeated calls, same as unstake:
+/// 1) takes the gem out of the stake and starts its cooldown
+/// 2) once that's passed, withdraws it from the vault, which stays locked for the rest
+/// fees are charged the same as unstake - the unstaking fee on 1), the protocol fee on both
+/// (!) can't take out the last of the staked gems, use unstake for that
+pub fn handler(
+    ctx: Context<PartialUnstake>,
+    bump_vault_auth: u8,
+    bump_gem_box: u8,
+    bump_gdr: u8,
+    bump_rarity: u8,
+) -> Result<()> {
+    let now_ts = now_ts()?;
+    let gem_mint = ctx.accounts.gem_mint.key();
+
+    if ctx.accounts.farmer.has_pending_withdrawal(gem_mint) {
+        let gem_count = ctx.accounts.farmer.end_gem_cooldown(now_ts, gem_mint)?;
+
+        gem_bank::cpi::set_vault_lock(
+            ctx.accounts
+                .set_lock_vault_ctx()
+                .with_signer(&[&ctx.accounts.farm.farm_seeds()]),
+            false,
+        )?;
+
+        gem_bank::cpi::withdraw_gem(
+            ctx.accounts.withdraw_gem_ctx(),
+            bump_vault_auth,
+            bump_gem_box,
+            bump_gdr,
+            bump_rarity,
+            gem_count,
+        )?;
+
+        gem_bank::cpi::set_vault_lock(
+            ctx.accounts
+                .set_lock_vault_ctx()
+                .with_signer(&[&ctx.accounts.farm.farm_seeds()]),
+            true,
+        )?;
+
+        //collect a fee for unstaking
+        ctx.accounts.transfer_fee()?;
+
+        msg!("{} gems withdrawn", gem_count);
+        return Ok(());
+    }
+
+    // collect any unstaking fee
+    let unstaking_fee_lamp = ctx.accounts.farm.config.unstaking_fee_lamp;
+    if unstaking_fee_lamp > 0 {
+        ctx.accounts.pay_treasury(unstaking_fee_lamp)?;
+    }
+
+    // update accrued rewards BEFORE we decrement the stake
+    // (!) no re-enrolling on the way out, a fixed reward might not have enough left for it
+    let farm = &mut ctx.accounts.farm;
+    let mut fixed_rate_tiers = ctx
+        .accounts
+        .fixed_rate_tiers
+        .as_deref_mut()
+        .map(|t| &mut **t);
+    let farmer = &mut ctx.accounts.farmer;
+
+    farm.update_rewards(now_ts, Some(farmer), false, fixed_rate_tiers.as_deref_mut())?;
+
+    let gdr = &ctx.accounts.gem_deposit_receipt;
+    let rarity_points = calc_rarity_points(&ctx.accounts.gem_rarity, gdr.gem_count)?;
+
+    farm.unstake_gem(
+        now_ts,
+        gem_mint,
+        gdr.gem_count,
+        rarity_points,
+        gdr.deposited_at_ts,
+        farmer,
+        fixed_rate_tiers,
+    )?;
+
+    //collect a fee for unstaking
+    ctx.accounts.transfer_fee()?;
+
+    msg!("{} gems now cooling down", gdr.gem_count);
+    Ok(())
+}
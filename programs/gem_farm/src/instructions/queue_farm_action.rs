@@ -0,0 +1,55 @@
+use anchor_lang::prelude::*;
+use gem_common::{errors::ErrorCode, *};
+
+use crate::state::*;
+
+#[derive(Accounts)]
+#[instruction(action_id: u64)]
+pub struct QueueFarmAction<'info> {
+    // farm
+    #[account(mut, has_one = farm_manager)]
+    pub farm: Box<Account<'info, Farm>>,
+    #[account(mut)]
+    pub farm_manager: Signer<'info>,
+
+    // action
+    #[account(init, seeds = [
+            b"pending_action".as_ref(),
+            farm.key().as_ref(),
+            action_id.to_le_bytes().as_ref(),
+        ],
+        bump,
+        payer = farm_manager,
+        space = PendingAction::SPACE)]
+    pub pending_action: Box<Account<'info, PendingAction>>,
+
+    // misc
+    pub system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<QueueFarmAction>, action_id: u64, action: FarmAction) -> Result<()> {
+    action.assert_valid()?;
+
+    // ids are handed out in order, so pending actions can be enumerated off the farm
+    let farm = &mut ctx.accounts.farm;
+    farm.last_action_id.try_add_assign(1)?;
+    if action_id != farm.last_action_id {
+        return Err(error!(ErrorCode::InvalidParameter));
+    }
+
+    let now_ts = now_ts()?;
+    let pending_action = &mut ctx.accounts.pending_action;
+
+    pending_action.farm = farm.key();
+    pending_action.action_id = action_id;
+    pending_action.queued_ts = now_ts;
+    pending_action.executable_ts = now_ts.try_add(farm.timelock_sec)?;
+    pending_action.action = action;
+
+    msg!(
+        "action {} queued, executable from {}",
+        action_id,
+        pending_action.executable_ts
+    );
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use anchor_lang::prelude::*;
+use gem_common::{
+    errors::ErrorCode,
+    token_interface::{self, is_token_program},
+    *,
+};
+
+use crate::state::*;
+
+#[derive(Accounts)]
+#[instruction(bump_proof: u8, bump_pot: u8)]
+pub struct QueueRewardEpochs<'info> {
+    // farm
+    #[account(mut)]
+    pub farm: Box<Account<'info, Farm>>,
+
+    // funder
+    #[account(mut, has_one = farm, has_one = authorized_funder, seeds = [
+            b"authorization".as_ref(),
+            farm.key().as_ref(),
+            authorized_funder.key().as_ref(),
+        ],
+        bump = bump_proof)]
+    pub authorization_proof: Box<Account<'info, AuthorizationProof>>,
+    #[account(mut)]
+    pub authorized_funder: Signer<'info>,
+    #[account(init_if_needed, seeds = [
+            b"contribution".as_ref(),
+            farm.key().as_ref(),
+            reward_mint.key().as_ref(),
+            authorized_funder.key().as_ref(),
+        ],
+        bump,
+        payer = authorized_funder,
+        space = 8 + std::mem::size_of::<FunderContribution>())]
+    pub contribution: Box<Account<'info, FunderContribution>>,
+
+    // reward
+    // token accounts & the mint are taken in raw, as they can belong to either token program
+    /// CHECK:
+    #[account(mut, seeds = [
+            b"reward_pot".as_ref(),
+            farm.key().as_ref(),
+            reward_mint.key().as_ref(),
+        ],
+        bump = bump_pot)]
+    pub reward_pot: AccountInfo<'info>,
+    /// CHECK: verified by the token program during the transfer
+    #[account(mut)]
+    pub reward_source: AccountInfo<'info>,
+    /// CHECK: unpacked during the transfer
+    pub reward_mint: AccountInfo<'info>,
+
+    // misc
+    /// CHECK: either of the 2 token programs, as long as it's the one owning the mint
+    #[account(constraint = is_token_program(token_program.key)
+        && reward_mint.owner == token_program.key @ ErrorCode::InvalidTokenProgram)]
+    pub token_program: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> QueueRewardEpochs<'info> {
+    /// returns the amount that actually arrived in the pot
+    fn transfer(&self, amount: u64) -> Result<u64> {
+        token_interface::transfer_checked(
+            &self.token_program,
+            &self.reward_source,
+            &self.reward_mint,
+            &self.reward_pot,
+            &self.authorized_funder.to_account_info(),
+            amount,
+            &[&self.farm.farm_seeds()],
+        )
+    }
+}
+
+pub fn handler(ctx: Context<QueueRewardEpochs>, mut epochs: Vec<RewardEpoch>) -> Result<()> {
+    // fix missing discriminator check
+    assert_discriminator_or_new::<FunderContribution>(
+        &ctx.accounts.contribution.to_account_info(),
+    )?;
+
+    let amount = epochs
+        .iter()
+        .try_fold(0u64, |total, e| total.try_add(e.amount))?;
+
+    // make sure the funder is still within their allowance
+    let now_ts = now_ts()?;
+    let reward_mint = ctx.accounts.reward_mint.key();
+
+    ctx.accounts
+        .authorization_proof
+        .record_funding(now_ts, reward_mint, amount)?;
+
+    // update existing rewards
+
+    ctx.accounts.farm.update_rewards(now_ts, None, true, None)?;
+
+    // do the transfer - for mints with a transfer fee, the fee comes out of each epoch pro-rata
+    let received = ctx.accounts.transfer(amount)?;
+
+    let amounts: Vec<u64> = epochs.iter().map(|e| e.amount).collect();
+    for (epoch, share) in epochs.iter_mut().zip(pro_rata_shares(received, &amounts)?) {
+        epoch.amount = share;
+    }
+
+    // queue the epochs up
+    let index = ctx.accounts.farm.reward_index_by_mint(reward_mint)?;
+    let reward_id = ctx.accounts.farm.rewards[index].reward_id;
+    let funding_round = ctx.accounts.farm.rewards[index].funding_round;
+    let farm = ctx.accounts.farm.key();
+
+    ctx.accounts
+        .farm
+        .queue_reward_epochs_by_mint(now_ts, reward_mint, epochs)?;
+
+    // keep track of who put in what, for pro-rata refunds
+    let funder = ctx.accounts.authorized_funder.key();
+
+    ctx.accounts.contribution.record_funding(
+        farm,
+        funder,
+        reward_mint,
+        reward_id,
+        funding_round,
+        received,
+    )?;
+
+    msg!(
+        "{} reward tokens queued up in {} pot",
+        received,
+        ctx.accounts.reward_pot.key()
+    );
+    Ok(())
+}
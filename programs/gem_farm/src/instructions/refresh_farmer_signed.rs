@@ -10,6 +10,10 @@ pub struct RefreshFarmerSigned<'info> {
     #[account(mut)]
     pub farm: Box<Account<'info, Farm>>,
 
+    // only needed if the farmer is (or is being) enrolled in a fixed rate reward
+    #[account(mut, has_one = farm)]
+    pub fixed_rate_tiers: Option<Box<Account<'info, FixedRateTiers>>>,
+
     // farmer
     #[account(mut, has_one = farm, has_one = identity, seeds = [
             b"farmer".as_ref(),
@@ -23,10 +27,15 @@ pub struct RefreshFarmerSigned<'info> {
 
 pub fn handler(ctx: Context<RefreshFarmerSigned>, reenroll: bool) -> Result<()> {
     let farm = &mut ctx.accounts.farm;
+    let fixed_rate_tiers = ctx
+        .accounts
+        .fixed_rate_tiers
+        .as_deref_mut()
+        .map(|t| &mut **t);
     let farmer = &mut ctx.accounts.farmer;
     let now_ts = now_ts()?;
 
-    farm.update_rewards(now_ts, Some(farmer), reenroll)?;
+    farm.update_rewards(now_ts, Some(farmer), reenroll, fixed_rate_tiers)?;
 
     msg!("{} farmer refreshed (SIGNED)", farmer.key());
     Ok(())
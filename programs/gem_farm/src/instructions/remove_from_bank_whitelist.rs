@@ -5,16 +5,22 @@ use gem_bank::{
     program::GemBank,
     state::{Bank, WhitelistProof},
 };
+use gem_common::errors::ErrorCode;
 
 use crate::state::*;
 
 #[derive(Accounts)]
 #[instruction(bump_auth: u8)]
 pub struct RemoveFromBankWhitelist<'info> {
-    #[account(has_one = farm_manager, has_one = farm_authority, has_one = bank)]
+    #[account(has_one = farm_authority, has_one = bank,
+        constraint = has_farm_role(&farm, farm_manager.key(), role_proof.as_deref(), FarmRoles::WHITELIST_ADMIN)
+            @ ErrorCode::MissingRole)]
     pub farm: Box<Account<'info, Farm>>,
+    // the farm manager, or anyone holding the role above
     #[account(mut)]
     pub farm_manager: Signer<'info>,
+    // only needed if the signer isn't the farm manager
+    pub role_proof: Option<Box<Account<'info, RoleProof>>>,
     /// CHECK:
     #[account(mut, seeds = [farm.key().as_ref()], bump = bump_auth)]
     pub farm_authority: AccountInfo<'info>,
@@ -27,6 +33,10 @@ pub struct RemoveFromBankWhitelist<'info> {
     #[account(mut)]
     pub whitelist_proof: Box<Account<'info, WhitelistProof>>,
     pub gem_bank: Program<'info, GemBank>,
+
+    // only needed if the farm is timelocked
+    #[account(mut, has_one = farm)]
+    pub pending_action: Option<Box<Account<'info, PendingAction>>>,
 }
 
 impl<'info> RemoveFromBankWhitelist<'info> {
@@ -47,6 +57,14 @@ impl<'info> RemoveFromBankWhitelist<'info> {
 }
 
 pub fn handler(ctx: Context<RemoveFromBankWhitelist>, bump_wl: u8) -> Result<()> {
+    consume_pending_action(
+        &ctx.accounts.farm,
+        ctx.accounts.pending_action.as_deref_mut(),
+        &FarmAction::RemoveFromBankWhitelist {
+            address_to_remove: ctx.accounts.address_to_remove.key(),
+        },
+    )?;
+
     gem_bank::cpi::remove_from_whitelist(
         ctx.accounts
             .remove_from_whitelist_ctx()
@@ -0,0 +1,73 @@
+use anchor_lang::prelude::*;
+use gem_bank::{self, cpi::accounts::CloseRarityPoints, program::GemBank};
+use gem_common::errors::ErrorCode;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+#[instruction(bump_auth: u8)]
+pub struct RemoveRaritiesFromBank<'info> {
+    // farm
+    #[account(has_one = farm_authority, has_one = bank,
+        constraint = has_farm_role(&farm, farm_manager.key(), role_proof.as_deref(), FarmRoles::RARITY_ADMIN)
+            @ ErrorCode::MissingRole)]
+    pub farm: Box<Account<'info, Farm>>,
+    // the farm manager, or anyone holding the role above
+    #[account(mut)]
+    pub farm_manager: Signer<'info>,
+    // only needed if the signer isn't the farm manager
+    pub role_proof: Option<Box<Account<'info, RoleProof>>>,
+    /// CHECK:
+    #[account(seeds = [farm.key().as_ref()], bump = bump_auth)]
+    pub farm_authority: AccountInfo<'info>,
+
+    // cpi
+    /// CHECK:
+    pub bank: AccountInfo<'info>,
+    pub gem_bank: Program<'info, GemBank>,
+
+    // only needed if the farm is timelocked
+    #[account(mut, has_one = farm)]
+    pub pending_action: Option<Box<Account<'info, PendingAction>>>,
+    //
+    // remaining accounts can be any number of:
+    //   pub gem_mint: Box<Account<'info, Mint>>,
+    //   #[account(mut)]
+    //   pub gem_rarity: Box<Account<'info, Rarity>>,
+}
+
+impl<'info> RemoveRaritiesFromBank<'info> {
+    fn remove_rarities(&self) -> CpiContext<'_, '_, '_, 'info, CloseRarityPoints<'info>> {
+        CpiContext::new(
+            self.gem_bank.to_account_info(),
+            CloseRarityPoints {
+                bank: self.bank.clone(),
+                bank_manager: self.farm_authority.clone(),
+                funds_receiver: self.farm_manager.to_account_info(),
+            },
+        )
+    }
+}
+
+pub fn handler<'a, 'b, 'c, 'info>(
+    ctx: Context<'a, 'b, 'c, 'info, RemoveRaritiesFromBank<'info>>,
+) -> Result<()> {
+    let gem_mints = ctx
+        .remaining_accounts
+        .chunks(2)
+        .map(|accs| accs[0].key())
+        .collect();
+
+    consume_pending_action(
+        &ctx.accounts.farm,
+        ctx.accounts.pending_action.as_deref_mut(),
+        &FarmAction::RemoveRaritiesFromBank { gem_mints },
+    )?;
+
+    gem_bank::cpi::close_rarity_points(
+        ctx.accounts
+            .remove_rarities()
+            .with_remaining_accounts(ctx.remaining_accounts.to_vec())
+            .with_signer(&[&ctx.accounts.farm.farm_seeds()]),
+    )
+}
@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::Mint;
+use gem_common::*;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct RetireRewardSlot<'info> {
+    // farm
+    #[account(mut, has_one = farm_manager)]
+    pub farm: Box<Account<'info, Farm>>,
+    #[account(mut)]
+    pub farm_manager: Signer<'info>,
+
+    // reward
+    pub reward_mint: Box<Account<'info, Mint>>,
+
+    // only needed if the farm is timelocked
+    #[account(mut, has_one = farm)]
+    pub pending_action: Option<Box<Account<'info, PendingAction>>>,
+}
+
+pub fn handler(ctx: Context<RetireRewardSlot>) -> Result<()> {
+    consume_pending_action(
+        &ctx.accounts.farm,
+        ctx.accounts.pending_action.as_deref_mut(),
+        &FarmAction::RetireRewardSlot {
+            reward_mint: ctx.accounts.reward_mint.key(),
+        },
+    )?;
+
+    let farm = &mut ctx.accounts.farm;
+
+    farm.retire_reward_by_mint(now_ts()?, ctx.accounts.reward_mint.key())?;
+
+    msg!("reward {} retired", ctx.accounts.reward_mint.key());
+    Ok(())
+}
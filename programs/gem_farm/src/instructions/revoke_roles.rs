@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+use gem_common::*;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+#[instruction(bump: u8)]
+pub struct RevokeRoles<'info> {
+    // farm
+    #[account(has_one = farm_manager)]
+    pub farm: Box<Account<'info, Farm>>,
+    #[account(mut)]
+    pub farm_manager: Signer<'info>,
+
+    // holder
+    /// CHECK:
+    pub holder: AccountInfo<'info>,
+    #[account(mut, has_one = farm, has_one = holder,
+        seeds = [
+            b"role".as_ref(),
+            farm.key().as_ref(),
+            holder.key().as_ref(),
+        ],
+        bump = bump)]
+    role_proof: Box<Account<'info, RoleProof>>,
+
+    // misc
+    system_program: Program<'info, System>,
+}
+
+pub fn handler(ctx: Context<RevokeRoles>) -> Result<()> {
+    // close role proof
+    close_account(
+        &mut ctx.accounts.role_proof.to_account_info(),
+        &mut ctx.accounts.farm_manager.to_account_info(),
+    )?;
+
+    msg!("roles revoked from: {}", ctx.accounts.holder.key());
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+#[instruction(bump_farmer: u8)]
+pub struct SetRewardRecipient<'info> {
+    // farm
+    pub farm: Box<Account<'info, Farm>>,
+
+    // farmer
+    #[account(mut, has_one = farm, has_one = identity, seeds = [
+            b"farmer".as_ref(),
+            farm.key().as_ref(),
+            identity.key().as_ref(),
+        ],
+        bump = bump_farmer)]
+    pub farmer: Box<Account<'info, Farmer>>,
+    pub identity: Signer<'info>,
+}
+
+/// passing None sends rewards back to the identity
+pub fn handler(ctx: Context<SetRewardRecipient>, reward_recipient: Option<Pubkey>) -> Result<()> {
+    let farmer = &mut ctx.accounts.farmer;
+    farmer.reward_recipient = reward_recipient.unwrap_or_default();
+
+    msg!("reward recipient set to {}", farmer.reward_recipient());
+    Ok(())
+}
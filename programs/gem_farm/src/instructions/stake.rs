@@ -1,4 +1,3 @@
-use crate::instructions::FEE_WALLET;
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::program::invoke;
 use anchor_lang::solana_program::system_instruction;
@@ -9,12 +8,9 @@ use gem_bank::{
     state::{Bank, Vault},
 };
 use gem_common::{errors::ErrorCode, *};
-use std::str::FromStr;
 
 use crate::state::*;
 
-const FEE_LAMPORTS: u64 = 2_000_000; // 0.002 SOL per stake/unstake
-
 #[derive(Accounts)]
 #[instruction(bump_auth: u8, bump_farmer: u8)]
 pub struct Stake<'info> {
@@ -25,6 +21,10 @@ pub struct Stake<'info> {
     #[account(seeds = [farm.key().as_ref()], bump = bump_auth)]
     pub farm_authority: AccountInfo<'info>,
 
+    // only needed if the farmer is (or is being) enrolled in a fixed rate reward
+    #[account(mut, has_one = farm)]
+    pub fixed_rate_tiers: Option<Box<Account<'info, FixedRateTiers>>>,
+
     // farmer
     #[account(mut, has_one = farm, has_one = identity, has_one = vault,
         seeds = [
@@ -44,8 +44,10 @@ pub struct Stake<'info> {
     pub vault: Box<Account<'info, Vault>>,
     pub gem_bank: Program<'info, GemBank>,
 
+    #[account(seeds = [b"protocol_config".as_ref()], bump = protocol_config.bump)]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
     /// CHECK:
-    #[account(mut, address = Pubkey::from_str(FEE_WALLET).unwrap())]
+    #[account(mut, address = protocol_config.fee_recipient)]
     pub fee_acc: AccountInfo<'info>,
     pub system_program: Program<'info, System>,
 }
@@ -63,8 +65,13 @@ impl<'info> Stake<'info> {
     }
 
     fn transfer_fee(&self) -> Result<()> {
+        let fee = self.protocol_config.fees.stake_fee_lamp;
+        if fee == 0 {
+            return Ok(());
+        }
+
         invoke(
-            &system_instruction::transfer(self.identity.key, self.fee_acc.key, FEE_LAMPORTS),
+            &system_instruction::transfer(self.identity.key, self.fee_acc.key, fee),
             &[
                 self.identity.to_account_info(),
                 self.fee_acc.clone(),
@@ -75,7 +82,7 @@ impl<'info> Stake<'info> {
     }
 }
 
-pub fn handler(ctx: Context<Stake>) -> Result<()> {
+pub fn handler(ctx: Context<Stake>, lockup_tier: u8) -> Result<()> {
     if ctx.accounts.vault.gem_count == 0 {
         return Err(error!(ErrorCode::VaultIsEmpty));
     }
@@ -90,14 +97,26 @@ pub fn handler(ctx: Context<Stake>) -> Result<()> {
 
     // update accrued rewards BEFORE we increment the stake
     let farm = &mut ctx.accounts.farm;
+    let mut fixed_rate_tiers = ctx
+        .accounts
+        .fixed_rate_tiers
+        .as_deref_mut()
+        .map(|t| &mut **t);
     let farmer = &mut ctx.accounts.farmer;
     let vault = &ctx.accounts.vault;
     let now_ts = now_ts()?;
 
-    farm.update_rewards(now_ts, Some(farmer), true)?;
+    farm.update_rewards(now_ts, Some(farmer), true, fixed_rate_tiers.as_deref_mut())?;
 
-    // begin staking
-    farm.begin_staking(now_ts, vault.gem_count, vault.rarity_points, farmer)?;
+    // begin staking, optionally committing to one of the farm's lockup tiers
+    farm.begin_staking(
+        now_ts,
+        vault.gem_count,
+        vault.rarity_points,
+        lockup_tier,
+        farmer,
+        fixed_rate_tiers,
+    )?;
 
     //collect a fee for staking
     ctx.accounts.transfer_fee()?;
@@ -2,6 +2,7 @@ use anchor_lang::{
     prelude::*,
     solana_program::{program::invoke_signed, system_instruction},
 };
+use gem_common::errors::ErrorCode;
 
 use crate::state::*;
 
@@ -9,9 +10,14 @@ use crate::state::*;
 #[instruction(bump_auth: u8, bump_treasury: u8)]
 pub struct TreasuryPayout<'info> {
     // farm
-    #[account(mut, has_one = farm_authority, has_one = farm_manager, has_one = farm_treasury)]
+    #[account(mut, has_one = farm_authority, has_one = farm_treasury,
+        constraint = has_farm_role(&farm, farm_manager.key(), role_proof.as_deref(), FarmRoles::TREASURY)
+            @ ErrorCode::MissingRole)]
     pub farm: Box<Account<'info, Farm>>,
+    // the farm manager, or anyone holding the role above
     pub farm_manager: Signer<'info>,
+    // only needed if the signer isn't the farm manager
+    pub role_proof: Option<Box<Account<'info, RoleProof>>>,
     /// CHECK:
     #[account(seeds = [farm.key().as_ref()], bump = bump_auth)]
     pub farm_authority: AccountInfo<'info>,
@@ -24,6 +30,10 @@ pub struct TreasuryPayout<'info> {
     #[account(mut)]
     pub destination: AccountInfo<'info>,
 
+    // only needed if the farm is timelocked
+    #[account(mut, has_one = farm)]
+    pub pending_action: Option<Box<Account<'info, PendingAction>>>,
+
     // misc
     pub system_program: Program<'info, System>,
 }
@@ -48,6 +58,15 @@ impl<'info> TreasuryPayout<'info> {
 }
 
 pub fn handler(ctx: Context<TreasuryPayout>, bump: u8, lamports: u64) -> Result<()> {
+    consume_pending_action(
+        &ctx.accounts.farm,
+        ctx.accounts.pending_action.as_deref_mut(),
+        &FarmAction::PayoutFromTreasury {
+            destination: ctx.accounts.destination.key(),
+            lamports,
+        },
+    )?;
+
     ctx.accounts.payout_from_treasury(bump, lamports)?;
 
     msg!("{} lamports paid out from treasury", lamports);
@@ -1,4 +1,3 @@
-use crate::instructions::FEE_WALLET;
 use anchor_lang::{
     prelude::*,
     solana_program::{program::invoke, system_instruction},
@@ -10,12 +9,9 @@ use gem_bank::{
     state::{Bank, Vault},
 };
 use gem_common::*;
-use std::str::FromStr;
 
 use crate::state::*;
 
-const FEE_LAMPORTS: u64 = 1_000_000; // 0.002 SOL per entire unstake op (charged twice, so 0.001 2x)
-
 #[derive(Accounts)]
 #[instruction(bump_auth: u8, bump_treasury: u8, bump_farmer: u8)]
 pub struct Unstake<'info> {
@@ -41,6 +37,10 @@ pub struct Unstake<'info> {
     #[account(mut)]
     pub identity: Signer<'info>,
 
+    // only needed if the farmer is enrolled in a fixed rate reward
+    #[account(mut, has_one = farm)]
+    pub fixed_rate_tiers: Option<Box<Account<'info, FixedRateTiers>>>,
+
     // cpi
     #[account(constraint = bank.bank_manager == farm_authority.key())]
     pub bank: Box<Account<'info, Bank>>,
@@ -49,8 +49,10 @@ pub struct Unstake<'info> {
     pub gem_bank: Program<'info, GemBank>,
 
     //misc
+    #[account(seeds = [b"protocol_config".as_ref()], bump = protocol_config.bump)]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
     /// CHECK:
-    #[account(mut, address = Pubkey::from_str(FEE_WALLET).unwrap())]
+    #[account(mut, address = protocol_config.fee_recipient)]
     pub fee_acc: AccountInfo<'info>,
     pub system_program: Program<'info, System>,
 }
@@ -80,8 +82,13 @@ impl<'info> Unstake<'info> {
     }
 
     fn transfer_fee(&self) -> Result<()> {
+        let fee = self.protocol_config.fees.unstake_fee_lamp;
+        if fee == 0 {
+            return Ok(());
+        }
+
         invoke(
-            &system_instruction::transfer(self.identity.key, self.fee_acc.key, FEE_LAMPORTS),
+            &system_instruction::transfer(self.identity.key, self.fee_acc.key, fee),
             &[
                 self.identity.to_account_info(),
                 self.fee_acc.clone(),
@@ -102,17 +109,22 @@ pub fn handler(ctx: Context<Unstake>, skip_rewards: bool) -> Result<()> {
 
     // update accrued rewards BEFORE we decrement the stake
     let farm = &mut ctx.accounts.farm;
+    let mut fixed_rate_tiers = ctx
+        .accounts
+        .fixed_rate_tiers
+        .as_deref_mut()
+        .map(|t| &mut **t);
     let farmer = &mut ctx.accounts.farmer;
     let now_ts = now_ts()?;
 
     // skipping rewards is an EMERGENCY measure in case farmer's rewards are overflowing
     // at least this lets them get their assets out
     if !skip_rewards {
-        farm.update_rewards(now_ts, Some(farmer), false)?;
+        farm.update_rewards(now_ts, Some(farmer), false, fixed_rate_tiers.as_deref_mut())?;
     }
 
     // end staking (will cycle through state on repeated calls)
-    farm.end_staking(now_ts, farmer)?;
+    farm.end_staking(now_ts, farmer, fixed_rate_tiers)?;
 
     if farmer.state == FarmerState::Unstaked {
         // unlock the vault so the user can withdraw their gems
@@ -1,35 +1,94 @@
 use anchor_lang::prelude::*;
+use gem_common::errors::ErrorCode;
 
 use crate::state::*;
 
 #[derive(Accounts)]
 pub struct UpdateFarm<'info> {
     // farm
-    #[account(mut, has_one = farm_manager)]
+    #[account(mut,
+        constraint = has_farm_role(&farm, farm_manager.key(), role_proof.as_deref(), FarmRoles::CONFIG)
+            @ ErrorCode::MissingRole)]
     pub farm: Box<Account<'info, Farm>>,
+    // the farm manager, or anyone holding the role above
     pub farm_manager: Signer<'info>,
+    // only needed if the signer isn't the farm manager
+    pub role_proof: Option<Box<Account<'info, RoleProof>>>,
+
+    // only needed if the farm is timelocked
+    #[account(mut, has_one = farm)]
+    pub pending_action: Option<Box<Account<'info, PendingAction>>>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handler(
     ctx: Context<UpdateFarm>,
     config: Option<FarmConfig>,
     manager: Option<Pubkey>,
     max_counts: Option<MaxCounts>,
+    lockup_tiers: Option<Vec<LockupTier>>,
+    early_unstake_penalty: Option<EarlyUnstakePenalty>,
+    per_gem_tenure: Option<bool>,
+    timelock_sec: Option<u64>,
 ) -> Result<()> {
+    let update = FarmUpdate {
+        config,
+        manager,
+        max_counts,
+        lockup_tiers,
+        early_unstake_penalty,
+        per_gem_tenure,
+        timelock_sec,
+    };
+
+    // handing the farm over is never delegated to a role
+    if update.manager.is_some() && ctx.accounts.farm_manager.key() != ctx.accounts.farm.farm_manager
+    {
+        return Err(error!(ErrorCode::MissingRole));
+    }
+
+    consume_pending_action(
+        &ctx.accounts.farm,
+        ctx.accounts.pending_action.as_deref_mut(),
+        &FarmAction::UpdateFarm(update.clone()),
+    )?;
+
     let farm = &mut ctx.accounts.farm;
 
-    if let Some(config) = config {
+    if let Some(config) = update.config {
         farm.config = config;
     }
 
-    if let Some(manager) = manager {
-        farm.farm_manager = manager;
+    // only proposes the handover, the new manager has to accept it (see accept_farm_manager)
+    // proposing Pubkey::default() calls off a pending one
+    if let Some(manager) = update.manager {
+        farm.pending_manager = manager;
     }
 
-    if let Some(max_counts) = max_counts {
+    if let Some(max_counts) = update.max_counts {
         farm.max_counts = max_counts;
     }
 
+    // only affects farmers staking from now on, existing ones keep the tier they signed up for
+    if let Some(lockup_tiers) = update.lockup_tiers {
+        farm.set_lockup_tiers(lockup_tiers)?;
+    }
+
+    // same as lockups, existing farmers keep the penalty that was in place when they staked
+    if let Some(early_unstake_penalty) = update.early_unstake_penalty {
+        farm.set_early_unstake_penalty(early_unstake_penalty)?;
+    }
+
+    // only affects gems staked from now on, existing cohorts are left as they are
+    if let Some(per_gem_tenure) = update.per_gem_tenure {
+        farm.per_gem_tenure = per_gem_tenure;
+    }
+
+    // once on, it can only be turned down/off by queueing an update like any other
+    if let Some(timelock_sec) = update.timelock_sec {
+        farm.timelock_sec = timelock_sec;
+    }
+
     msg!("updated farm");
     Ok(())
 }
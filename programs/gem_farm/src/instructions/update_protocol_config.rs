@@ -0,0 +1,38 @@
+use anchor_lang::prelude::*;
+
+use crate::state::*;
+
+#[derive(Accounts)]
+pub struct UpdateProtocolConfig<'info> {
+    // protocol
+    #[account(mut, has_one = admin, seeds = [b"protocol_config".as_ref()],
+        bump = protocol_config.bump)]
+    pub protocol_config: Box<Account<'info, ProtocolConfig>>,
+    pub admin: Signer<'info>,
+}
+
+pub fn handler(
+    ctx: Context<UpdateProtocolConfig>,
+    admin: Option<Pubkey>,
+    fee_recipient: Option<Pubkey>,
+    fees: Option<FeeSchedule>,
+) -> Result<()> {
+    let config = &mut ctx.accounts.protocol_config;
+
+    // only proposes the handover, the new admin has to accept it (see accept_protocol_admin)
+    // proposing Pubkey::default() calls off a pending one
+    if let Some(admin) = admin {
+        config.pending_admin = admin;
+    }
+
+    if let Some(fee_recipient) = fee_recipient {
+        config.fee_recipient = fee_recipient;
+    }
+
+    if let Some(fees) = fees {
+        config.fees = fees;
+    }
+
+    msg!("updated protocol config");
+    Ok(())
+}
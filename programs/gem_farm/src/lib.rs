@@ -13,14 +13,41 @@ declare_id!("farmL4xeBFVXJqtfxCzU9b28QACM7E2W2ctT6epAjvE");
 pub mod gem_farm {
     use super::*;
 
+    // --------------------------------------- protocol
+
+    pub fn init_protocol_config(
+        ctx: Context<InitProtocolConfig>,
+        fee_recipient: Pubkey,
+        fees: FeeSchedule,
+    ) -> Result<()> {
+        msg!("init protocol config");
+        instructions::init_protocol_config::handler(ctx, fee_recipient, fees)
+    }
+
+    pub fn update_protocol_config(
+        ctx: Context<UpdateProtocolConfig>,
+        admin: Option<Pubkey>,
+        fee_recipient: Option<Pubkey>,
+        fees: Option<FeeSchedule>,
+    ) -> Result<()> {
+        msg!("update protocol config");
+        instructions::update_protocol_config::handler(ctx, admin, fee_recipient, fees)
+    }
+
+    pub fn accept_protocol_admin(ctx: Context<AcceptProtocolAdmin>) -> Result<()> {
+        msg!("accept protocol admin");
+        instructions::accept_protocol_admin::handler(ctx)
+    }
+
     // --------------------------------------- core
 
-    pub fn init_farm(
-        ctx: Context<InitFarm>,
+    #[allow(clippy::too_many_arguments)]
+    pub fn init_farm<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, InitFarm<'info>>,
         bump_auth: u8,
         _bump_treasury: u8,
-        reward_type_a: RewardType,
-        reward_type_b: RewardType,
+        reward_types: Vec<RewardType>,
+        vesting: Vec<VestingConfig>,
         farm_config: FarmConfig,
         max_counts: Option<MaxCounts>,
         farm_treasury: Pubkey,
@@ -29,21 +56,40 @@ pub mod gem_farm {
         instructions::init_farm::handler(
             ctx,
             bump_auth,
-            reward_type_a,
-            reward_type_b,
+            reward_types,
+            vesting,
             farm_config,
             max_counts,
             farm_treasury,
         )
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn update_farm(
         ctx: Context<UpdateFarm>,
         config: Option<FarmConfig>,
         manager: Option<Pubkey>,
         max_counts: Option<MaxCounts>,
+        lockup_tiers: Option<Vec<LockupTier>>,
+        early_unstake_penalty: Option<EarlyUnstakePenalty>,
+        per_gem_tenure: Option<bool>,
+        timelock_sec: Option<u64>,
     ) -> Result<()> {
-        instructions::update_farm::handler(ctx, config, manager, max_counts)
+        instructions::update_farm::handler(
+            ctx,
+            config,
+            manager,
+            max_counts,
+            lockup_tiers,
+            early_unstake_penalty,
+            per_gem_tenure,
+            timelock_sec,
+        )
+    }
+
+    pub fn accept_farm_manager(ctx: Context<AcceptFarmManager>) -> Result<()> {
+        msg!("accept farm manager");
+        instructions::accept_farm_manager::handler(ctx)
     }
 
     pub fn payout_from_treasury(
@@ -56,6 +102,20 @@ pub mod gem_farm {
         instructions::treasury_payout::handler(ctx, bump_treasury, lamports)
     }
 
+    pub fn queue_farm_action(
+        ctx: Context<QueueFarmAction>,
+        action_id: u64,
+        action: FarmAction,
+    ) -> Result<()> {
+        msg!("queue farm action");
+        instructions::queue_farm_action::handler(ctx, action_id, action)
+    }
+
+    pub fn cancel_farm_action(ctx: Context<CancelFarmAction>) -> Result<()> {
+        msg!("cancel farm action");
+        instructions::cancel_farm_action::handler(ctx)
+    }
+
     pub fn add_to_bank_whitelist(
         ctx: Context<AddToBankWhitelist>,
         _bump_auth: u8,
@@ -74,6 +134,30 @@ pub mod gem_farm {
         instructions::remove_from_bank_whitelist::handler(ctx, bump_wl)
     }
 
+    pub fn grant_roles(ctx: Context<GrantRoles>, roles: u32) -> Result<()> {
+        msg!("grant roles");
+        instructions::grant_roles::handler(ctx, roles)
+    }
+
+    pub fn revoke_roles(ctx: Context<RevokeRoles>, _bump: u8) -> Result<()> {
+        msg!("revoke roles");
+        instructions::revoke_roles::handler(ctx)
+    }
+
+    pub fn close_farm<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CloseFarm<'info>>,
+        _bump_auth: u8,
+        bump_treasury: u8,
+    ) -> Result<()> {
+        msg!("close farm");
+        instructions::close_farm::handler(ctx, bump_treasury)
+    }
+
+    pub fn migrate_farm(ctx: Context<MigrateFarm>) -> Result<()> {
+        msg!("migrate farm");
+        instructions::migrate_farm::handler(ctx)
+    }
+
     // --------------------------------------- farmer ops
 
     pub fn init_farmer(ctx: Context<InitFarmer>) -> Result<()> {
@@ -81,9 +165,14 @@ pub mod gem_farm {
         instructions::init_farmer::handler(ctx)
     }
 
-    pub fn stake(ctx: Context<Stake>, _bump_auth: u8, _bump_farmer: u8) -> Result<()> {
+    pub fn stake(
+        ctx: Context<Stake>,
+        _bump_auth: u8,
+        _bump_farmer: u8,
+        lockup_tier: u8,
+    ) -> Result<()> {
         msg!("stake");
-        instructions::stake::handler(ctx)
+        instructions::stake::handler(ctx, lockup_tier)
     }
 
     pub fn unstake(
@@ -97,17 +186,76 @@ pub mod gem_farm {
         instructions::unstake::handler(ctx, skip_rewards)
     }
 
-    pub fn claim(
-        ctx: Context<Claim>,
+    pub fn claim<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, Claim<'info>>,
         _bump_auth: u8,
         _bump_farmer: u8,
-        _bump_pot_a: u8,
-        _bump_pot_b: u8,
     ) -> Result<()> {
         msg!("claim");
         instructions::claim::handler(ctx)
     }
 
+    pub fn partial_unstake(
+        ctx: Context<PartialUnstake>,
+        _bump_farmer: u8,
+        bump_vault_auth: u8,
+        bump_gem_box: u8,
+        bump_gdr: u8,
+        bump_rarity: u8,
+    ) -> Result<()> {
+        msg!("partial unstake");
+        instructions::partial_unstake::handler(
+            ctx,
+            bump_vault_auth,
+            bump_gem_box,
+            bump_gdr,
+            bump_rarity,
+        )
+    }
+
+    pub fn set_reward_recipient(
+        ctx: Context<SetRewardRecipient>,
+        _bump_farmer: u8,
+        reward_recipient: Option<Pubkey>,
+    ) -> Result<()> {
+        msg!("set reward recipient");
+        instructions::set_reward_recipient::handler(ctx, reward_recipient)
+    }
+
+    /// permissionless - pushes the farmer's rewards to their reward_recipient
+    pub fn crank_claim<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CrankClaim<'info>>,
+        _bump_auth: u8,
+    ) -> Result<()> {
+        msg!("crank claim");
+        instructions::crank_claim::handler(ctx)
+    }
+
+    pub fn withdraw_vested<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, WithdrawVested<'info>>,
+        _bump_auth: u8,
+        _bump_farmer: u8,
+    ) -> Result<()> {
+        msg!("withdraw vested");
+        instructions::withdraw_vested::handler(ctx)
+    }
+
+    pub fn compound<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, Compound<'info>>,
+        _bump_auth: u8,
+        _bump_farmer: u8,
+        bump_vault_auth: u8,
+        bump_rarity: u8,
+    ) -> Result<()> {
+        msg!("compound");
+        instructions::compound::handler(ctx, bump_vault_auth, bump_rarity)
+    }
+
+    pub fn close_farmer(ctx: Context<CloseFarmer>, _bump_auth: u8, _bump_farmer: u8) -> Result<()> {
+        msg!("close farmer");
+        instructions::close_farmer::handler(ctx)
+    }
+
     pub fn flash_deposit<'a, 'b, 'c, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, FlashDeposit<'info>>,
         _bump_farmer: u8,
@@ -136,11 +284,21 @@ pub mod gem_farm {
         instructions::refresh_farmer_signed::handler(ctx, reenroll)
     }
 
+    pub fn migrate_farmer(ctx: Context<MigrateFarmer>, _bump: u8) -> Result<()> {
+        msg!("migrate farmer");
+        instructions::migrate_farmer::handler(ctx)
+    }
+
     // --------------------------------------- funder ops
 
-    pub fn authorize_funder(ctx: Context<AuthorizeFunder>) -> Result<()> {
+    pub fn authorize_funder(
+        ctx: Context<AuthorizeFunder>,
+        reward_mint: Option<Pubkey>,
+        max_amount: Option<u64>,
+        expires_ts: Option<u64>,
+    ) -> Result<()> {
         msg!("authorize funder");
-        instructions::authorize_funder::handler(ctx)
+        instructions::authorize_funder::handler(ctx, reward_mint, max_amount, expires_ts)
     }
 
     pub fn deauthorize_funder(ctx: Context<DeauthorizeFunder>, _bump: u8) -> Result<()> {
@@ -148,6 +306,14 @@ pub mod gem_farm {
         instructions::deauthorize_funder::handler(ctx)
     }
 
+    pub fn migrate_authorization_proof(
+        ctx: Context<MigrateAuthorizationProof>,
+        _bump: u8,
+    ) -> Result<()> {
+        msg!("migrate authorization proof");
+        instructions::migrate_authorization_proof::handler(ctx)
+    }
+
     // --------------------------------------- reward ops
 
     pub fn fund_reward(
@@ -161,16 +327,50 @@ pub mod gem_farm {
         instructions::fund_reward::handler(ctx, variable_rate_config, fixed_rate_config)
     }
 
+    pub fn queue_reward_epochs(
+        ctx: Context<QueueRewardEpochs>,
+        _bump_proof: u8,
+        _bump_pot: u8,
+        epochs: Vec<RewardEpoch>,
+    ) -> Result<()> {
+        msg!("queue reward epochs");
+        instructions::queue_reward_epochs::handler(ctx, epochs)
+    }
+
     pub fn cancel_reward(ctx: Context<CancelReward>, _bump_auth: u8, _bump_pot: u8) -> Result<()> {
         msg!("cancel reward");
         instructions::cancel_reward::handler(ctx)
     }
 
+    pub fn cancel_reward_pro_rata<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, CancelRewardProRata<'info>>,
+        _bump_auth: u8,
+        _bump_pot: u8,
+    ) -> Result<()> {
+        msg!("cancel reward pro-rata");
+        instructions::cancel_reward_pro_rata::handler(ctx)
+    }
+
     pub fn lock_reward(ctx: Context<LockReward>) -> Result<()> {
         msg!("lock reward");
         instructions::lock_reward::handler(ctx)
     }
 
+    pub fn add_reward_slot(
+        ctx: Context<AddRewardSlot>,
+        _bump_auth: u8,
+        reward_type: RewardType,
+        vesting: Option<VestingConfig>,
+    ) -> Result<()> {
+        msg!("add reward slot");
+        instructions::add_reward_slot::handler(ctx, reward_type, vesting)
+    }
+
+    pub fn retire_reward_slot(ctx: Context<RetireRewardSlot>) -> Result<()> {
+        msg!("retire reward slot");
+        instructions::retire_reward_slot::handler(ctx)
+    }
+
     // --------------------------------------- rarities
 
     pub fn add_rarities_to_bank<'a, 'b, 'c, 'info>(
@@ -182,6 +382,14 @@ pub mod gem_farm {
         instructions::add_rarities_to_bank::handler(ctx, rarity_configs)
     }
 
+    pub fn remove_rarities_from_bank<'a, 'b, 'c, 'info>(
+        ctx: Context<'a, 'b, 'c, 'info, RemoveRaritiesFromBank<'info>>,
+        _bump_auth: u8,
+    ) -> Result<()> {
+        msg!("remove rarities from bank");
+        instructions::remove_rarities_from_bank::handler(ctx)
+    }
+
     pub fn flash_deposit_pnft<'a, 'b, 'c, 'info>(
         ctx: Context<'a, 'b, 'c, 'info, FlashDepositPnft<'info>>,
         _bump_farmer: u8,
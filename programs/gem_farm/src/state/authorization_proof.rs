@@ -1,8 +1,9 @@
 use anchor_lang::prelude::*;
+use gem_common::{errors::ErrorCode, *};
 
 /// if this PDA exists, this means the funder recorded below has been authorized by the
-/// farm recorded below to fund rewards
-#[proc_macros::assert_size(96)]
+/// farm recorded below to fund rewards, within the limits recorded below
+#[proc_macros::assert_size(128)]
 #[repr(C)]
 #[account]
 pub struct AuthorizationProof {
@@ -10,6 +11,84 @@ pub struct AuthorizationProof {
 
     pub farm: Pubkey,
 
+    /// default Pubkey = can fund any of the farm's rewards
+    /// carved out of the proof's original reserved space, so older proofs can fund any reward
+    pub reward_mint: Pubkey,
+
+    // (!) everything below was appended later on, older proofs have to go through
+    //   migrate_authorization_proof before they can be used again
+    /// cap on amount_funded, only enforced if has_max_amount is set
+    pub max_amount: u64,
+
+    /// counted before any transfer fees, ie what left the funder's wallet
+    pub amount_funded: u64,
+
+    /// 0 = never expires
+    pub expires_ts: u64,
+
+    /// kept separate from max_amount, so that a cap of 0 can be set too
+    pub has_max_amount: bool,
+
     /// reserved for future updates, has to be /8
-    _reserved: [u8; 32],
+    _reserved: [u8; 7],
+}
+
+impl AuthorizationProof {
+    /// size (incl. discriminator) of proofs created before the limits were added
+    pub const LEGACY_LEN: usize = 8 + 96;
+
+    pub fn record_funding(&mut self, now_ts: u64, reward_mint: Pubkey, amount: u64) -> Result<()> {
+        if self.expires_ts > 0 && now_ts >= self.expires_ts {
+            return Err(error!(ErrorCode::FunderAuthorizationExpired));
+        }
+
+        if self.reward_mint != Pubkey::default() && self.reward_mint != reward_mint {
+            return Err(error!(ErrorCode::FunderNotAuthorizedForMint));
+        }
+
+        self.amount_funded.try_add_assign(amount)?;
+
+        if self.has_max_amount && self.amount_funded > self.max_amount {
+            return Err(error!(ErrorCode::FunderAllowanceExceeded));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_funding() {
+        let reward_mint = Pubkey::new_unique();
+        let mut proof = AuthorizationProof {
+            authorized_funder: Pubkey::new_unique(),
+            farm: Pubkey::new_unique(),
+            reward_mint,
+            max_amount: 100,
+            amount_funded: 0,
+            expires_ts: 1000,
+            has_max_amount: true,
+            _reserved: [0; 7],
+        };
+
+        proof.record_funding(10, reward_mint, 60).unwrap();
+        proof.record_funding(20, reward_mint, 40).unwrap();
+        assert_eq!(proof.amount_funded, 100);
+
+        // over the cap, wrong mint, expired
+        assert!(proof.record_funding(30, reward_mint, 1).is_err());
+        proof.has_max_amount = false;
+        assert!(proof.record_funding(30, Pubkey::new_unique(), 1).is_err());
+        assert!(proof.record_funding(1000, reward_mint, 1).is_err());
+        proof.record_funding(999, reward_mint, 1).unwrap();
+
+        // a cap of 0 is still a cap
+        proof.amount_funded = 0;
+        proof.max_amount = 0;
+        proof.has_max_amount = true;
+        assert!(proof.record_funding(10, reward_mint, 1).is_err());
+    }
 }
@@ -0,0 +1,344 @@
+use anchor_lang::prelude::*;
+use gem_common::{errors::ErrorCode, *};
+
+use crate::number128::Number128;
+
+/// max number of points a piecewise-linear curve can have
+pub const MAX_CURVE_POINTS: usize = 4;
+
+/// curve weights / decay are expressed in bps, ie 10_000 = full rate
+pub const CURVE_DENOMINATOR: u64 = 10_000;
+
+#[proc_macros::assert_size(4)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub enum CurveType {
+    /// same rate for the whole duration
+    #[default]
+    Constant,
+
+    /// rate drops by decay_bps every period_sec
+    ExponentialDecay,
+
+    /// rate halves every period_sec
+    Halving,
+
+    /// rate moves linearly between points, stays at the last one's weight after it
+    PiecewiseLinear,
+}
+
+#[proc_macros::assert_size(16)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct CurvePoint {
+    /// counted from when the reward begins
+    pub offset_sec: u64,
+
+    pub weight_bps: u64,
+}
+
+/// shapes the variable rate over the reward's duration - the funded amount is spread so that
+/// the area under the curve adds up to it, and accrual integrates the curve between updates
+#[proc_macros::assert_size(88)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct EmissionCurve {
+    pub curve_type: CurveType,
+
+    /// piecewise-linear only
+    pub points_len: u32,
+
+    /// exponential decay / halving only
+    pub period_sec: u64,
+
+    /// exponential decay only
+    pub decay_bps: u64,
+
+    /// piecewise-linear only, sorted by offset with the 1st one at 0
+    pub points: [CurvePoint; MAX_CURVE_POINTS],
+}
+
+impl EmissionCurve {
+    pub fn assert_valid(&self) -> Result<()> {
+        let valid = match self.curve_type {
+            CurveType::Constant => true,
+            CurveType::ExponentialDecay => {
+                self.period_sec > 0 && self.decay_bps > 0 && self.decay_bps < CURVE_DENOMINATOR
+            }
+            CurveType::Halving => self.period_sec > 0,
+            CurveType::PiecewiseLinear => {
+                let points = self.points();
+                !points.is_empty()
+                    && points.len() <= MAX_CURVE_POINTS
+                    && points[0].offset_sec == 0
+                    && points.windows(2).all(|w| w[0].offset_sec < w[1].offset_sec)
+            }
+        };
+
+        if !valid {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+        Ok(())
+    }
+
+    fn points(&self) -> &[CurvePoint] {
+        let len = std::cmp::min(self.points_len as usize, MAX_CURVE_POINTS);
+        &self.points[..len]
+    }
+
+    /// area under the curve between the 2 offsets (counted from when the reward begins),
+    /// ie how many seconds' worth of full rate were emitted in that window
+    /// (!) taken as the difference of 2 running totals, so adjacent windows always add up to
+    /// the same as the window spanning them, no matter how often the reward is updated
+    pub fn integrate(&self, from_sec: u64, to_sec: u64) -> Result<Number128> {
+        if to_sec <= from_sec {
+            return Ok(Number128::ZERO);
+        }
+
+        self.area_until(to_sec)?.try_sub(self.area_until(from_sec)?)
+    }
+
+    /// area under the curve from when the reward begins, rounded down to Number128's precision
+    fn area_until(&self, offset_sec: u64) -> Result<Number128> {
+        let area = match self.curve_type {
+            CurveType::Constant => return Ok(Number128::from(offset_sec)),
+            CurveType::ExponentialDecay => {
+                self.area_until_decay(CURVE_DENOMINATOR.try_sub(self.decay_bps)?, offset_sec)?
+            }
+            CurveType::Halving => {
+                self.area_until_decay(CURVE_DENOMINATOR.try_div(2)?, offset_sec)?
+            }
+            CurveType::PiecewiseLinear => self.area_until_piecewise(offset_sec)?,
+        };
+
+        area.try_div(full_rate())
+    }
+
+    /// the rate is multiplied by the ratio at the end of every period,
+    /// so the full steps make up a geometric series
+    fn area_until_decay(&self, ratio_bps: u64, offset_sec: u64) -> Result<Number128> {
+        let steps = offset_sec.try_div(self.period_sec)?;
+        let (ratio_pow, series) = geometric_series(Number128::from(ratio_bps), steps)?;
+
+        let full_steps = series.try_mul(Number128::from(self.period_sec))?;
+        let last_step = ratio_pow.try_mul(Number128::from(
+            offset_sec.try_sub(steps.try_mul(self.period_sec)?)?,
+        ))?;
+
+        full_steps.try_add(last_step)
+    }
+
+    /// each segment is a trapezoid
+    fn area_until_piecewise(&self, offset_sec: u64) -> Result<Number128> {
+        let points = self.points();
+        let mut area = Number128::ZERO;
+
+        for (i, point) in points.iter().enumerate() {
+            if offset_sec <= point.offset_sec {
+                break;
+            }
+            let segment_end = points.get(i + 1).map_or(u64::MAX, |p| p.offset_sec);
+            let end = std::cmp::min(offset_sec, segment_end);
+
+            let weights = self
+                .weight_at(i, point.offset_sec)?
+                .try_add(self.weight_at(i, end)?)?;
+            area.try_add_assign(
+                weights
+                    .try_mul(Number128::from(end.try_sub(point.offset_sec)?))?
+                    .try_div(Number128::from(2u64))?,
+            )?;
+        }
+
+        Ok(area)
+    }
+
+    /// linearly interpolated within the segment that begins at the given point
+    fn weight_at(&self, index: usize, offset_sec: u64) -> Result<Number128> {
+        let points = self.points();
+        let from = points[index];
+        let from_weight = Number128::from(from.weight_bps);
+
+        let to = match points.get(index + 1) {
+            Some(to) => to,
+            None => return Ok(from_weight),
+        };
+        let to_weight = Number128::from(to.weight_bps);
+
+        from_weight
+            .try_mul(Number128::from(to.offset_sec.try_sub(offset_sec)?))?
+            .try_add(to_weight.try_mul(Number128::from(offset_sec.try_sub(from.offset_sec)?))?)?
+            .try_div(Number128::from(to.offset_sec.try_sub(from.offset_sec)?))
+    }
+}
+
+/// weights are kept in bps rather than as a fraction of the full rate,
+/// so that Number128's 3 decimals come on top of them
+fn full_rate() -> Number128 {
+    Number128::from(CURVE_DENOMINATOR)
+}
+
+/// returns (ratio^steps, 1 + ratio + .. + ratio^(steps - 1)), both in bps
+/// (!) built up by doubling rather than dividing by (1 - ratio), which would blow up
+/// the rounding for ratios close to 1
+fn geometric_series(ratio: Number128, steps: u64) -> Result<(Number128, Number128)> {
+    let mul = |a: Number128, b: Number128| a.try_mul(b)?.try_div(full_rate());
+    let mut ratio_pow = full_rate();
+    let mut series = Number128::ZERO;
+
+    for bit in (0..u64::BITS - steps.leading_zeros()).rev() {
+        series.try_add_assign(mul(ratio_pow, series)?)?;
+        ratio_pow = mul(ratio_pow, ratio_pow)?;
+
+        if (steps >> bit) & 1 == 1 {
+            series.try_add_assign(ratio_pow)?;
+            ratio_pow = mul(ratio_pow, ratio)?;
+        }
+    }
+
+    Ok((ratio_pow, series))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_constant_curve() {
+        let curve = EmissionCurve::default();
+
+        assert_eq!(Number128::from(50u64), curve.integrate(100, 150).unwrap());
+        assert_eq!(Number128::ZERO, curve.integrate(150, 100).unwrap());
+    }
+
+    #[test]
+    fn test_halving_curve() {
+        let curve = EmissionCurve {
+            curve_type: CurveType::Halving,
+            period_sec: 100,
+            ..Default::default()
+        };
+        assert!(curve.assert_valid().is_ok());
+
+        // 100 + 50 + 25
+        assert_eq!(Number128::from(175u64), curve.integrate(0, 300).unwrap());
+        // 25 + 12.5
+        assert_eq!(
+            Number128::from_decimal(375u64, -1),
+            curve.integrate(150, 250).unwrap()
+        );
+
+        // converges to 2 periods' worth, no matter how long it runs
+        let total = curve.integrate(0, 1_000_000).unwrap();
+        assert!(total <= Number128::from(200u64));
+        assert!(total > Number128::from(199u64));
+    }
+
+    #[test]
+    fn test_exponential_decay_curve() {
+        let curve = EmissionCurve {
+            curve_type: CurveType::ExponentialDecay,
+            period_sec: 10,
+            decay_bps: 1_000,
+            ..Default::default()
+        };
+        assert!(curve.assert_valid().is_ok());
+
+        // 10 + 9 + 8.1
+        assert_eq!(
+            Number128::from_decimal(271u64, -1),
+            curve.integrate(0, 30).unwrap()
+        );
+
+        // weights are kept in bps, so a fine decay isn't lost to Number128's precision: 10 + 9.995
+        let fine_curve = EmissionCurve {
+            decay_bps: 5,
+            ..curve
+        };
+        assert_eq!(
+            Number128::from_decimal(19995u64, -3),
+            fine_curve.integrate(0, 20).unwrap()
+        );
+
+        let bad_curve = EmissionCurve {
+            decay_bps: 10_000,
+            ..curve
+        };
+        assert!(bad_curve.assert_valid().is_err());
+    }
+
+    #[test]
+    fn test_integrate_is_additive() {
+        let decay = EmissionCurve {
+            curve_type: CurveType::ExponentialDecay,
+            period_sec: 7,
+            decay_bps: 333,
+            ..Default::default()
+        };
+        let mut piecewise = EmissionCurve {
+            curve_type: CurveType::PiecewiseLinear,
+            points_len: 3,
+            ..Default::default()
+        };
+        piecewise.points[0] = CurvePoint {
+            offset_sec: 0,
+            weight_bps: 10_000,
+        };
+        piecewise.points[1] = CurvePoint {
+            offset_sec: 33,
+            weight_bps: 1_234,
+        };
+        piecewise.points[2] = CurvePoint {
+            offset_sec: 71,
+            weight_bps: 7_777,
+        };
+
+        // however the window is split up, the pieces add up to the whole
+        for curve in [decay, piecewise] {
+            let whole = curve.integrate(0, 100).unwrap();
+
+            for step in [1, 3, 13] {
+                let mut sum = Number128::ZERO;
+                let mut from = 0;
+                while from < 100 {
+                    let to = std::cmp::min(from + step, 100);
+                    sum.try_add_assign(curve.integrate(from, to).unwrap())
+                        .unwrap();
+                    from = to;
+                }
+                assert_eq!(whole, sum);
+            }
+        }
+    }
+
+    #[test]
+    fn test_piecewise_linear_curve() {
+        let mut curve = EmissionCurve {
+            curve_type: CurveType::PiecewiseLinear,
+            points_len: 2,
+            ..Default::default()
+        };
+        curve.points[0] = CurvePoint {
+            offset_sec: 0,
+            weight_bps: 10_000,
+        };
+        curve.points[1] = CurvePoint {
+            offset_sec: 100,
+            weight_bps: 5_000,
+        };
+        assert!(curve.assert_valid().is_ok());
+
+        // trapezoid (1 + 0.5) / 2 * 100, then flat at 0.5
+        assert_eq!(Number128::from(75u64), curve.integrate(0, 100).unwrap());
+        assert_eq!(Number128::from(100u64), curve.integrate(0, 150).unwrap());
+        // (0.75 + 0.5) / 2 * 50
+        assert_eq!(
+            Number128::from_decimal(3125u64, -2),
+            curve.integrate(50, 100).unwrap()
+        );
+
+        // has to start at 0
+        curve.points[0].offset_sec = 1;
+        assert!(curve.assert_valid().is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+use gem_common::{errors::ErrorCode, *};
+
+/// max number of epochs that can be waiting in the queue, behind the running one
+pub const MAX_REWARD_EPOCHS: usize = 12;
+
+#[proc_macros::assert_size(16)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct RewardEpoch {
+    pub amount: u64,
+
+    pub duration_sec: u64,
+}
+
+impl RewardEpoch {
+    pub fn is_empty(&self) -> bool {
+        self.amount == 0
+    }
+}
+
+/// epochs funded in advance - each one begins as soon as the previous one ends,
+/// at its own rate (amount / duration_sec), so emissions can change without the manager stepping in
+/// kept on the farm (see VariableRateReward::epoch_queue), so that any update can roll over
+/// as many of them as have begun since the last one, each starting exactly when the previous one ended
+/// (!) amounts only count as funded once their epoch begins
+#[proc_macros::assert_size(200)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct EpochQueue {
+    pub epochs: [RewardEpoch; MAX_REWARD_EPOCHS],
+
+    pub len: u64,
+}
+
+impl EpochQueue {
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push(&mut self, epoch: RewardEpoch) -> Result<()> {
+        if epoch.amount == 0 || epoch.duration_sec == 0 {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+
+        let len = self.len as usize;
+        if len >= MAX_REWARD_EPOCHS {
+            return Err(error!(ErrorCode::TooManyRewardEpochs));
+        }
+
+        self.epochs[len] = epoch;
+        self.len.try_add_assign(1)
+    }
+
+    pub fn pop(&mut self) -> Option<RewardEpoch> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let epoch = self.epochs[0];
+        self.epochs.rotate_left(1);
+        self.len -= 1;
+        self.epochs[self.len as usize] = RewardEpoch::default();
+
+        Some(epoch)
+    }
+
+    pub fn clear(&mut self) {
+        self.epochs = [RewardEpoch::default(); MAX_REWARD_EPOCHS];
+        self.len = 0;
+    }
+
+    fn queued(&self) -> &[RewardEpoch] {
+        &self.epochs[..self.len as usize]
+    }
+
+    pub fn total_amount(&self) -> Result<u64> {
+        self.queued()
+            .iter()
+            .try_fold(0u64, |total, e| total.try_add(e.amount))
+    }
+
+    pub fn total_duration(&self) -> Result<u64> {
+        self.queued()
+            .iter()
+            .try_fold(0u64, |total, e| total.try_add(e.duration_sec))
+    }
+}
@@ -1,13 +1,25 @@
 use anchor_lang::prelude::*;
 use gem_common::{errors::ErrorCode, *};
 
-use crate::state::*;
+use crate::{number128::Number128, state::*};
 
-pub const LATEST_FARM_VERSION: u16 = 0;
+pub const LATEST_FARM_VERSION: u16 = 1;
+
+/// max number of reward slots a single farm can run in parallel
+pub const MAX_REWARDS: usize = 4;
+
+/// max number of lockup tiers a farm can offer, on top of the default (no lockup) one
+pub const MAX_LOCKUP_TIERS: usize = 4;
+
+/// lockup multipliers are expressed in bps, ie 10_000 = 1x
+pub const LOCKUP_MULTIPLIER_DENOMINATOR: u64 = 10_000;
+
+/// early unstake penalties are expressed in bps, ie 10_000 = 100%
+pub const PENALTY_DENOMINATOR: u64 = 10_000;
 
 #[proc_macros::assert_size(24)]
 #[repr(C)]
-#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
 pub struct FarmConfig {
     // min time the NFT has to be staked
     pub min_staking_period_sec: u64,
@@ -21,16 +33,128 @@ pub struct FarmConfig {
 /// refers to staked counts
 #[proc_macros::assert_size(12)]
 #[repr(C)]
-#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
 pub struct MaxCounts {
     pub max_farmers: u32,
 
     pub max_gems: u32,
 
+    /// (!) counts effective rarity points, ie after lockup multipliers
     pub max_rarity_points: u32,
 }
 
-#[proc_macros::assert_size(1000)] // + 5 to make it /8
+/// farmers can opt into a tier when staking - they commit to staking for longer
+/// in exchange for their rarity points counting for more
+#[proc_macros::assert_size(16)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct LockupTier {
+    /// farmer can't unstake before this runs out (or min_staking_period_sec, whichever is longer)
+    pub lockup_period_sec: u64,
+
+    /// applied to farmer's rarity points for as long as they're staked
+    pub multiplier_bps: u64,
+}
+
+impl LockupTier {
+    /// what farmers get if they don't opt into a lockup
+    pub fn no_lockup() -> Self {
+        Self {
+            lockup_period_sec: 0,
+            multiplier_bps: LOCKUP_MULTIPLIER_DENOMINATOR,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.lockup_period_sec > 0
+    }
+
+    fn assert_valid(&self) -> Result<()> {
+        // committing for longer should never earn less than not committing at all
+        if !self.is_active() || self.multiplier_bps < LOCKUP_MULTIPLIER_DENOMINATOR {
+            return Err(error!(ErrorCode::InvalidLockupTier));
+        }
+        Ok(())
+    }
+
+    /// (!) in u128, as rarity points times bps would overflow u64 well before the result does
+    pub fn effective_rarity_points(&self, rarity_points: u64) -> Result<u64> {
+        if self.multiplier_bps == LOCKUP_MULTIPLIER_DENOMINATOR {
+            return Ok(rarity_points);
+        }
+
+        (rarity_points as u128)
+            .try_mul(self.multiplier_bps as u128)?
+            .try_div(LOCKUP_MULTIPLIER_DENOMINATOR as u128)?
+            .try_cast()
+    }
+}
+
+#[proc_macros::assert_size(4)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub enum PenaltyDestination {
+    /// goes back into the reward's pending funds, for the funder to re-use or cancel out
+    #[default]
+    ReturnToFunder,
+
+    /// spread over everyone still staked (variable rewards only, fixed ones are returned instead)
+    Redistribute,
+}
+
+/// an alternative to unstaking_fee_lamp - farmers who unstake too early lose a cut
+/// of whatever reward they haven't claimed yet
+#[proc_macros::assert_size(16)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct EarlyUnstakePenalty {
+    /// counted from when the farmer first staked, 0 = no penalty
+    pub period_sec: u64,
+
+    /// cut of the farmer's unclaimed reward that's forfeited
+    pub penalty_bps: u32,
+
+    pub destination: PenaltyDestination,
+}
+
+impl EarlyUnstakePenalty {
+    pub fn is_active(&self) -> bool {
+        self.period_sec > 0 && self.penalty_bps > 0
+    }
+
+    fn assert_valid(&self) -> Result<()> {
+        if self.penalty_bps as u64 > PENALTY_DENOMINATOR {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+        Ok(())
+    }
+
+    /// whether unstaking (or claiming) now is too early, given when the farmer began staking
+    pub fn applies(&self, now_ts: u64, begin_staking_ts: u64) -> Result<bool> {
+        Ok(self.is_active() && now_ts.try_sub(begin_staking_ts)? < self.period_sec)
+    }
+
+    /// how much of the unclaimed reward is forfeited, given when the farmer began staking
+    pub fn forfeited_amount(
+        &self,
+        now_ts: u64,
+        begin_staking_ts: u64,
+        unclaimed_reward: u64,
+    ) -> Result<u64> {
+        if !self.applies(now_ts, begin_staking_ts)? {
+            return Ok(0);
+        }
+
+        (unclaimed_reward as u128)
+            .try_mul(self.penalty_bps as u128)?
+            .try_div(PENALTY_DENOMINATOR as u128)?
+            .try_cast()
+    }
+}
+
+/// (!) a lot larger than the legacy layout (see LegacyFarm), farms created before the
+/// reward slots are grown to the current size by migrate_farm, which reallocs them
+#[proc_macros::assert_size(2824)]
 #[repr(C)]
 #[account]
 #[derive(Debug)]
@@ -38,7 +162,7 @@ pub struct Farm {
     pub version: u16,
 
     /// authorizes funders, whitelists mints/creators, sets farm config params
-    /// can update itself to another Pubkey
+    /// can hand itself over to another Pubkey (see pending_manager)
     pub farm_manager: Pubkey,
 
     /// used for collecting any fees earned by the farm
@@ -68,23 +192,49 @@ pub struct Farm {
     pub gems_staked: u64,
 
     /// currently staked gem count, where each gem is multiplied by its rarity score (1 if absent)
+    /// and by the farmer's lockup multiplier (1x if they didn't opt into a lockup)
     pub rarity_points_staked: u64,
 
     /// how many accounts can create funding schedules
     pub authorized_funder_count: u64,
 
     // ----------------- rewards
-    pub reward_a: FarmReward,
+    /// a slot is in use if it has a reward mint set (see FarmReward::is_active)
+    /// farmer records are matched to these slots by index
+    pub rewards: [FarmReward; MAX_REWARDS],
 
-    pub reward_b: FarmReward,
+    /// incremented each time a reward slot is taken, used to issue reward ids
+    pub last_reward_id: u64,
 
     // ----------------- extra
+    /// tier 0 is always no lockup, so tier N (as passed to stake) lives at index N - 1
+    /// a tier is offered if it has a lockup period set (see LockupTier::is_active)
+    pub lockup_tiers: [LockupTier; MAX_LOCKUP_TIERS],
+
+    pub early_unstake_penalty: EarlyUnstakePenalty,
+
     pub max_counts: MaxCounts,
 
+    /// if set, gems staked on top of existing ones earn fixed-rate tenure and serve
+    /// the min staking period on their own, rather than joining the farmer's (see StakeCohort)
+    pub per_gem_tenure: bool,
+
+    /// if set, sensitive manager actions have to be queued this long in advance (see PendingAction)
+    pub timelock_sec: u64,
+
+    /// incremented each time an action is queued, used to derive pending action PDAs
+    pub last_action_id: u64,
+
+    /// farmers still on the legacy layout (see migrate_farmer) - their records are matched
+    /// to the reward slots by index, so no slot can be retired until they're all migrated
+    pub legacy_farmer_count: u64,
+
+    /// proposed by the current farm manager, only takes over once it accepts
+    /// (default Pubkey = no handover in progress)
+    pub pending_manager: Pubkey,
+
     /// reserved for future updates, has to be /8
     _reserved: [u8; 32],
-    _reserved2: [u8; 16],
-    _reserved3: [u8; 4],
 }
 
 impl Farm {
@@ -125,6 +275,42 @@ impl Farm {
         Ok(())
     }
 
+    pub fn set_lockup_tiers(&mut self, lockup_tiers: Vec<LockupTier>) -> Result<()> {
+        if lockup_tiers.len() > MAX_LOCKUP_TIERS {
+            return Err(error!(ErrorCode::InvalidLockupTier));
+        }
+
+        self.lockup_tiers = [LockupTier::default(); MAX_LOCKUP_TIERS];
+        for (index, tier) in lockup_tiers.into_iter().enumerate() {
+            tier.assert_valid()?;
+            self.lockup_tiers[index] = tier;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_early_unstake_penalty(&mut self, penalty: EarlyUnstakePenalty) -> Result<()> {
+        penalty.assert_valid()?;
+        self.early_unstake_penalty = penalty;
+        Ok(())
+    }
+
+    pub fn is_timelocked(&self) -> bool {
+        self.timelock_sec > 0
+    }
+
+    pub fn lockup_tier(&self, lockup_tier: u8) -> Result<LockupTier> {
+        if lockup_tier == 0 {
+            return Ok(LockupTier::no_lockup());
+        }
+
+        self.lockup_tiers
+            .get(lockup_tier as usize - 1)
+            .filter(|t| t.is_active())
+            .copied()
+            .ok_or_else(|| error!(ErrorCode::InvalidLockupTier))
+    }
+
     pub fn farm_seeds(&self) -> [&[u8]; 2] {
         [
             self.farm_authority_seed.as_ref(),
@@ -133,14 +319,203 @@ impl Farm {
     }
 
     pub fn match_reward_by_mint(&mut self, reward_mint: Pubkey) -> Result<&mut FarmReward> {
-        let reward_a_mint = self.reward_a.reward_mint;
-        let reward_b_mint = self.reward_b.reward_mint;
+        let index = self.reward_index_by_mint(reward_mint)?;
+        Ok(&mut self.rewards[index])
+    }
+
+    pub fn reward_index_by_mint(&self, reward_mint: Pubkey) -> Result<usize> {
+        self.rewards
+            .iter()
+            .position(|r| r.is_active() && r.reward_mint == reward_mint)
+            .ok_or_else(|| error!(ErrorCode::UnknownRewardMint))
+    }
 
-        match reward_mint {
-            _ if reward_mint == reward_a_mint => Ok(&mut self.reward_a),
-            _ if reward_mint == reward_b_mint => Ok(&mut self.reward_b),
-            _ => Err(error!(ErrorCode::UnknownRewardMint)),
+    pub fn active_reward_count(&self) -> usize {
+        self.rewards.iter().filter(|r| r.is_active()).count()
+    }
+
+    /// takes the first free slot, returns its index
+    pub fn add_reward(
+        &mut self,
+        reward_mint: Pubkey,
+        reward_pot: Pubkey,
+        reward_type: RewardType,
+        vesting: VestingConfig,
+    ) -> Result<usize> {
+        vesting.assert_valid()?;
+
+        if self.reward_index_by_mint(reward_mint).is_ok() {
+            return Err(error!(ErrorCode::RewardMintAlreadyUsed));
         }
+
+        let index = self
+            .rewards
+            .iter()
+            .position(|r| !r.is_active())
+            .ok_or_else(|| error!(ErrorCode::TooManyRewards))?;
+
+        self.last_reward_id.try_add_assign(1)?;
+
+        let reward = &mut self.rewards[index];
+        *reward = FarmReward::default();
+        reward.reward_id = self.last_reward_id;
+        reward.reward_mint = reward_mint;
+        reward.reward_pot = reward_pot;
+        reward.reward_type = reward_type;
+        reward.vesting = vesting;
+
+        Ok(index)
+    }
+
+    /// frees up the slot taken by the reward, so that a new one can be added in its place
+    pub fn retire_reward_by_mint(&mut self, now_ts: u64, reward_mint: Pubkey) -> Result<()> {
+        if self.legacy_farmer_count > 0 {
+            return Err(error!(ErrorCode::LegacyFarmersPending));
+        }
+
+        // make sure everything that's due has been accrued
+        self.update_rewards(now_ts, None, false, None)?;
+
+        let reward = self.match_reward_by_mint(reward_mint)?;
+        if !reward.is_retirable(now_ts)? {
+            return Err(error!(ErrorCode::RewardNotRetirable));
+        }
+
+        *reward = FarmReward::default();
+        Ok(())
+    }
+
+    /// a farm can only be closed once everyone has been paid out and has left
+    pub fn assert_settled(&mut self, now_ts: u64) -> Result<()> {
+        // make sure everything that's due has been accrued
+        self.update_rewards(now_ts, None, false, None)?;
+
+        if self.farmer_count > 0 || self.authorized_funder_count > 0 {
+            return Err(error!(ErrorCode::FarmNotSettled));
+        }
+
+        for reward in self.rewards.iter().filter(|r| r.is_active()) {
+            if !reward.is_retirable(now_ts)? {
+                return Err(error!(ErrorCode::FarmNotSettled));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// carries the farm over from the legacy layout, reward a / b taking the first 2 slots
+    /// (!) expects a zeroed out account, so anything the legacy layout didn't have starts off empty
+    pub fn migrate_legacy(
+        &mut self,
+        farm: Pubkey,
+        legacy: &LegacyFarm,
+        fixed_rate_tiers: &mut FixedRateTiers,
+    ) -> Result<()> {
+        self.version = LATEST_FARM_VERSION;
+        self.farm_manager = legacy.farm_manager;
+        self.farm_treasury = legacy.farm_treasury;
+        self.farm_authority = legacy.farm_authority;
+        self.farm_authority_seed = legacy.farm_authority_seed;
+        self.farm_authority_bump_seed = legacy.farm_authority_bump_seed;
+        self.bank = legacy.bank;
+        self.config = legacy.config;
+        self.farmer_count = legacy.farmer_count;
+        self.staked_farmer_count = legacy.staked_farmer_count;
+        self.gems_staked = legacy.gems_staked;
+        self.rarity_points_staked = legacy.rarity_points_staked;
+        self.authorized_funder_count = legacy.authorized_funder_count;
+        self.max_counts = legacy.max_counts;
+
+        for (index, legacy_reward) in legacy.rewards().iter().enumerate() {
+            self.last_reward_id.try_add_assign(1)?;
+            self.rewards[index] = legacy_reward.migrate(self.last_reward_id);
+
+            if legacy_reward.reward_type == RewardType::Fixed {
+                fixed_rate_tiers.set_schedule(
+                    farm,
+                    index,
+                    self.last_reward_id,
+                    &legacy_reward.fixed_rate.schedule.migrate(),
+                )?;
+            }
+        }
+
+        self.legacy_farmer_count = legacy.farmer_count;
+        Ok(())
+    }
+
+    /// carries the farmer's rewards over from the legacy layout, along with the farm's book-keeping:
+    /// - whatever's recorded on the farmer but not yet claimed is now tracked as unclaimed
+    /// - whatever accrued to the pool before the farm was migrated, but wasn't yet recorded
+    ///   on the farmer, is now tracked as unrecorded (anything since has been already)
+    /// (!) expects a zeroed out account, same as migrate_legacy
+    pub fn migrate_legacy_farmer(
+        &mut self,
+        now_ts: u64,
+        legacy: &LegacyFarmer,
+        farmer: &mut Farmer,
+        mut fixed_rate_tiers: Option<&mut FixedRateTiers>,
+    ) -> Result<()> {
+        farmer.migrate_legacy(now_ts, legacy)?;
+
+        for (index, legacy_reward) in legacy.rewards().iter().enumerate() {
+            let reward = &mut self.rewards[index];
+            let farmer_reward = &mut farmer.rewards[index];
+            *farmer_reward = legacy_reward.migrate(reward.reward_id);
+
+            // the farmer keeps the schedule they were promised, which may not be the farm's current one
+            if reward.reward_type == RewardType::Fixed && farmer_reward.fixed_rate.holds_schedule()
+            {
+                farmer_reward.fixed_rate.promised_schedule = fixed_rate_tiers
+                    .as_deref_mut()
+                    .and_then(|t| t.schedules_for(reward.reward_id))
+                    .ok_or_else(|| error!(ErrorCode::FixedRateTiersRequired))?
+                    .enroll_in(legacy_reward.fixed_rate.promised_schedule.migrate())?;
+            }
+
+            reward
+                .unclaimed_reward
+                .try_add_assign(farmer_reward.outstanding_reward()?)?;
+
+            if reward.reward_type == RewardType::Variable && legacy.state == FarmerState::Staked {
+                let unrecorded = Number128::from(legacy.rarity_points_staked).try_mul(
+                    reward
+                        .variable_rate
+                        .legacy_accrued_reward_per_rarity_point
+                        .try_sub(
+                            legacy_reward
+                                .variable_rate
+                                .last_recorded_accrued_reward_per_rarity_point,
+                        )?,
+                )?;
+                reward
+                    .variable_rate
+                    .unrecorded_reward
+                    .try_add_assign(unrecorded)?;
+            }
+        }
+
+        self.legacy_farmer_count.try_sub_assign(1)
+    }
+
+    /// a farmer can only be closed once they've unstaked and claimed everything they're owed
+    pub fn assert_farmer_settled(&self, farmer: &Farmer) -> Result<()> {
+        if farmer.state != FarmerState::Unstaked {
+            return Err(error!(ErrorCode::FarmerNotSettled));
+        }
+
+        for (reward, farmer_reward) in self.rewards.iter().zip(farmer.rewards.iter()) {
+            // records left over from retired rewards don't count
+            if !reward.is_active() || farmer_reward.reward_id != reward.reward_id {
+                continue;
+            }
+
+            if farmer_reward.outstanding_reward()? > 0 || farmer_reward.vesting.is_pending() {
+                return Err(error!(ErrorCode::FarmerNotSettled));
+            }
+        }
+
+        Ok(())
     }
 
     pub fn lock_reward_by_mint(&mut self, reward_mint: Pubkey) -> Result<()> {
@@ -159,47 +534,87 @@ impl Farm {
         reward.fund_reward_by_type(now_ts, variable_rate_config, fixed_rate_config)
     }
 
+    pub fn queue_reward_epochs_by_mint(
+        &mut self,
+        now_ts: u64,
+        reward_mint: Pubkey,
+        epochs: Vec<RewardEpoch>,
+    ) -> Result<()> {
+        let reward = self.match_reward_by_mint(reward_mint)?;
+        reward.queue_epochs(now_ts, epochs)
+    }
+
     pub fn cancel_reward_by_mint(&mut self, now_ts: u64, reward_mint: Pubkey) -> Result<u64> {
         let reward = self.match_reward_by_mint(reward_mint)?;
         reward.cancel_reward_by_type(now_ts)
     }
 
+    /// resets every funder's balance, for when the manager cancels the reward themselves
+    pub fn start_funding_round_by_mint(&mut self, reward_mint: Pubkey) -> Result<()> {
+        self.match_reward_by_mint(reward_mint)?
+            .start_funding_round()
+    }
+
     pub fn update_rewards(
         &mut self,
         now_ts: u64,
-        mut farmer: Option<&mut Account<Farmer>>,
+        farmer: Option<&mut Account<Farmer>>,
         reenroll: bool, //relevant for fixed only
+        mut fixed_rate_tiers: Option<&mut FixedRateTiers>,
     ) -> Result<()> {
-        // reward a
-        let (farmer_points_staked, farmer_reward_a) = match farmer {
-            Some(ref mut farmer) => (
-                Some(farmer.rarity_points_staked),
-                Some(&mut farmer.reward_a),
-            ),
-            None => (None, None),
-        };
-
-        self.reward_a.update_accrued_reward_by_type(
-            now_ts,
-            self.rarity_points_staked,
-            farmer_points_staked,
-            farmer_reward_a,
-            reenroll,
-        )?;
+        let farm_points_staked = self.rarity_points_staked;
+
+        match farmer {
+            Some(farmer) => {
+                self.sync_farmer_rewards(farmer);
+
+                let farmer_points_staked = farmer.rarity_points_staked;
+                let farmer_cohorts = farmer.cohorts().to_vec();
+
+                for (reward, farmer_reward) in
+                    self.rewards.iter_mut().zip(farmer.rewards.iter_mut())
+                {
+                    if !reward.is_active() {
+                        continue;
+                    }
+
+                    reward.update_accrued_reward_by_type(
+                        now_ts,
+                        farm_points_staked,
+                        Some(farmer_points_staked),
+                        Some(&farmer_cohorts),
+                        Some(farmer_reward),
+                        reenroll,
+                        fixed_rate_tiers.as_deref_mut(),
+                    )?;
+                }
+            }
+            None => {
+                for reward in self.rewards.iter_mut().filter(|r| r.is_active()) {
+                    reward.update_accrued_reward_by_type(
+                        now_ts,
+                        farm_points_staked,
+                        None,
+                        None,
+                        None,
+                        reenroll,
+                        None,
+                    )?;
+                }
+            }
+        }
 
-        // reward b
-        let farmer_reward_b = match farmer {
-            Some(ref mut farmer) => Some(&mut farmer.reward_b),
-            None => None,
-        };
+        Ok(())
+    }
 
-        self.reward_b.update_accrued_reward_by_type(
-            now_ts,
-            self.rarity_points_staked,
-            farmer_points_staked,
-            farmer_reward_b,
-            reenroll,
-        )
+    /// farmer records are matched to reward slots by index, so a record left over from
+    /// a reward that has since been retired needs to be wiped before it can be used
+    fn sync_farmer_rewards(&self, farmer: &mut Farmer) {
+        for (reward, farmer_reward) in self.rewards.iter().zip(farmer.rewards.iter_mut()) {
+            if reward.is_active() && farmer_reward.reward_id != reward.reward_id {
+                farmer_reward.reset(reward.reward_id);
+            }
+        }
     }
 
     pub fn begin_staking(
@@ -207,66 +622,96 @@ impl Farm {
         now_ts: u64,
         gems_in_vault: u64,
         rarity_points_in_vault: u64,
-        farmer: &mut Account<Farmer>,
+        lockup_tier: u8,
+        farmer: &mut Farmer,
+        mut fixed_rate_tiers: Option<&mut FixedRateTiers>,
     ) -> Result<()> {
+        self.sync_farmer_rewards(farmer);
+
+        // same as the lockup, the farmer is held to the penalty in place when they first staked
+        if farmer.state != FarmerState::Staked {
+            farmer.early_unstake_penalty = self.early_unstake_penalty;
+        }
+
         // update farmer
         farmer.begin_staking(
             self.config.min_staking_period_sec,
+            self.lockup_tier(lockup_tier)?,
+            self.per_gem_tenure,
             now_ts,
             gems_in_vault,
             rarity_points_in_vault,
         )?;
 
-        // update farm
+        // update farm (with the farmer's effective rarity points, ie after the lockup multiplier)
         self.staked_farmer_count.try_add_assign(1)?;
         self.gems_staked.try_add_assign(gems_in_vault)?;
         self.rarity_points_staked
-            .try_add_assign(rarity_points_in_vault)?;
+            .try_add_assign(farmer.rarity_points_staked)?;
 
         self.assert_valid_max_counts()?;
 
         // fixed-rate only - we need to do some extra book-keeping
-        if self.reward_a.reward_type == RewardType::Fixed {
-            self.reward_a.fixed_rate.enroll_farmer(
-                now_ts,
-                &mut self.reward_a.times,
-                &mut self.reward_a.funds,
-                farmer.rarity_points_staked,
-                &mut farmer.reward_a,
-                None,
-            )?;
-        }
+        let farmer_cohorts = farmer.cohorts().to_vec();
+
+        for (reward, farmer_reward) in self.rewards.iter_mut().zip(farmer.rewards.iter_mut()) {
+            if !reward.is_fixed() {
+                continue;
+            }
 
-        if self.reward_b.reward_type == RewardType::Fixed {
-            self.reward_b.fixed_rate.enroll_farmer(
+            reward.fixed_rate.enroll_farmer(
                 now_ts,
-                &mut self.reward_b.times,
-                &mut self.reward_b.funds,
-                farmer.rarity_points_staked,
-                &mut farmer.reward_b,
-                None,
+                &mut reward.times,
+                &mut reward.funds,
+                &farmer_cohorts,
+                farmer_reward,
+                fixed_rate_tiers
+                    .as_deref_mut()
+                    .and_then(|t| t.schedules_for(reward.reward_id)),
             )?;
         }
 
         Ok(())
     }
 
-    pub fn end_staking(&mut self, now_ts: u64, farmer: &mut Account<Farmer>) -> Result<()> {
+    pub fn end_staking(
+        &mut self,
+        now_ts: u64,
+        farmer: &mut Account<Farmer>,
+        mut fixed_rate_tiers: Option<&mut FixedRateTiers>,
+    ) -> Result<()> {
         match farmer.state {
             FarmerState::Unstaked => Ok(msg!("already unstaked!")),
             FarmerState::Staked => {
-                // fixed-rate only - we need to do some extra book-keeping
-                // (!) MUST COME BEFORE FARMER IS UPDATED - WE NEED CURRENT RARITY POINTS AMOUNT
-                if self.reward_a.reward_type == RewardType::Fixed {
-                    self.reward_a
-                        .fixed_rate
-                        .graduate_farmer(farmer.rarity_points_staked, &mut farmer.reward_a)?;
-                }
+                self.sync_farmer_rewards(farmer);
 
-                if self.reward_b.reward_type == RewardType::Fixed {
-                    self.reward_b
-                        .fixed_rate
-                        .graduate_farmer(farmer.rarity_points_staked, &mut farmer.reward_b)?;
+                // we need to do some extra book-keeping
+                // (!) MUST COME BEFORE FARMER IS UPDATED - WE NEED CURRENT RARITY POINTS AMOUNT
+                let farmer_points_staked = farmer.rarity_points_staked;
+                let farmer_cohorts = farmer.cohorts().to_vec();
+
+                for (reward, farmer_reward) in
+                    self.rewards.iter_mut().zip(farmer.rewards.iter_mut())
+                {
+                    if !reward.is_active() {
+                        continue;
+                    }
+
+                    match reward.reward_type {
+                        // only has an effect if rewards weren't updated before unstaking
+                        RewardType::Variable => reward
+                            .variable_rate
+                            .forfeit_unrecorded_reward(farmer_points_staked, farmer_reward),
+                        RewardType::Fixed => {
+                            reward.fixed_rate.graduate_farmer(
+                                &farmer_cohorts,
+                                farmer_reward,
+                                fixed_rate_tiers
+                                    .as_deref_mut()
+                                    .and_then(|t| t.schedules_for(reward.reward_id)),
+                            )?;
+                        }
+                    }
                 }
 
                 // update farmer
@@ -279,70 +724,264 @@ impl Farm {
                     .try_sub_assign(rarity_points_unstaked)?;
                 self.gems_staked.try_sub_assign(gems_unstaked)?;
 
-                Ok(())
+                // (!) MUST COME AFTER FARM IS UPDATED - ONLY THOSE STILL STAKED SHARE IN IT
+                self.apply_early_unstake_penalty(now_ts, farmer)
             }
             FarmerState::PendingCooldown => farmer.end_cooldown(now_ts),
         }
     }
 
+    fn apply_early_unstake_penalty(&mut self, now_ts: u64, farmer: &mut Farmer) -> Result<()> {
+        let penalty = farmer.early_unstake_penalty;
+        let farm_points_staked = self.rarity_points_staked;
+
+        for (reward, farmer_reward) in self.rewards.iter_mut().zip(farmer.rewards.iter_mut()) {
+            if !reward.is_active() {
+                continue;
+            }
+
+            let forfeited = penalty.forfeited_amount(
+                now_ts,
+                farmer.begin_staking_ts,
+                farmer_reward.outstanding_reward()?,
+            )?;
+
+            reward.forfeit_farmer_reward(
+                forfeited,
+                penalty.destination,
+                farm_points_staked,
+                farmer_reward,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// claiming (or compounding) during the early unstake period forfeits the same cut unstaking would,
+    /// else claiming right before unstaking would be a way around the penalty
+    /// (!) expects the farmer's rewards to be up to date
+    pub fn apply_early_claim_penalty(
+        &mut self,
+        now_ts: u64,
+        index: usize,
+        farmer: &mut Farmer,
+    ) -> Result<()> {
+        if !farmer.in_early_unstake_period(now_ts)? {
+            return Ok(());
+        }
+
+        let penalty = farmer.early_unstake_penalty;
+        // unlike when unstaking, the farmer is still staked, but shouldn't get a share of their own penalty
+        let others_points_staked = self
+            .rarity_points_staked
+            .try_sub(farmer.rarity_points_staked)?;
+
+        let reward = &mut self.rewards[index];
+        let farmer_reward = &mut farmer.rewards[index];
+
+        let forfeited = penalty.forfeited_amount(
+            now_ts,
+            farmer.begin_staking_ts,
+            farmer_reward.outstanding_reward()?,
+        )?;
+
+        reward.forfeit_farmer_reward(
+            forfeited,
+            penalty.destination,
+            others_points_staked,
+            farmer_reward,
+        )?;
+
+        if reward.reward_type == RewardType::Variable {
+            farmer_reward
+                .variable_rate
+                .last_recorded_accrued_reward_per_rarity_point =
+                reward.variable_rate.accrued_reward_per_rarity_point;
+        }
+
+        Ok(())
+    }
+
     pub fn stake_extra_gems(
         &mut self,
         now_ts: u64,
         gems_in_vault: u64,
         rarity_points_in_vault: u64,
         extra_gems: u64,
-        extra_rarity_points: u64,
-        farmer: &mut Account<Farmer>,
+        farmer: &mut Farmer,
+        fixed_rate_tiers: Option<&mut FixedRateTiers>,
     ) -> Result<()> {
-        // update farmer
-        let (_previous_gems, previous_rarity_points) = farmer.begin_staking(
-            self.config.min_staking_period_sec,
+        self.add_extra_gems(
             now_ts,
             gems_in_vault,
             rarity_points_in_vault,
-        )?;
+            extra_gems,
+            true,
+            farmer,
+            fixed_rate_tiers,
+        )
+    }
+
+    /// same as staking extra gems, except the farmer isn't locked in for any longer than they already were
+    pub fn compound_gems(
+        &mut self,
+        now_ts: u64,
+        gems_in_vault: u64,
+        rarity_points_in_vault: u64,
+        extra_gems: u64,
+        farmer: &mut Farmer,
+        fixed_rate_tiers: Option<&mut FixedRateTiers>,
+    ) -> Result<()> {
+        self.add_extra_gems(
+            now_ts,
+            gems_in_vault,
+            rarity_points_in_vault,
+            extra_gems,
+            false,
+            farmer,
+            fixed_rate_tiers,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn add_extra_gems(
+        &mut self,
+        now_ts: u64,
+        gems_in_vault: u64,
+        rarity_points_in_vault: u64,
+        extra_gems: u64,
+        restart_lockup: bool,
+        farmer: &mut Farmer,
+        fixed_rate_tiers: Option<&mut FixedRateTiers>,
+    ) -> Result<()> {
+        self.sync_farmer_rewards(farmer);
+
+        // update farmer
+        let previous_cohorts = farmer.cohorts().to_vec();
+        let (_previous_gems, previous_rarity_points) = match restart_lockup {
+            // (!) staking extra gems restarts the farmer's lockup, same as it does the min staking period
+            //   (unless tenure is tracked per gem, in which case only the extra gems' cohort has to wait)
+            true => farmer.begin_staking(
+                self.config.min_staking_period_sec,
+                farmer.lockup,
+                self.per_gem_tenure,
+                now_ts,
+                gems_in_vault,
+                rarity_points_in_vault,
+            )?,
+            false => farmer.compound_gems(
+                self.per_gem_tenure,
+                now_ts,
+                gems_in_vault,
+                rarity_points_in_vault,
+            )?,
+        };
 
         // update farm
         self.gems_staked.try_add_assign(extra_gems)?;
-        self.rarity_points_staked
-            .try_add_assign(extra_rarity_points)?;
+        self.rarity_points_staked.try_add_assign(
+            farmer
+                .rarity_points_staked
+                .try_sub(previous_rarity_points)?,
+        )?;
 
         self.assert_valid_max_counts()?;
 
-        // fixed-rate only - we need to do some extra book-keeping
-        if self.reward_a.reward_type == RewardType::Fixed {
-            // graduate with PREVIOUS rarity points count
-            let original_begin_staking_ts = self
-                .reward_a
-                .fixed_rate
-                .graduate_farmer(previous_rarity_points, &mut farmer.reward_a)?;
-
-            // re-enroll with NEW rarity points count
-            self.reward_a.fixed_rate.enroll_farmer(
-                now_ts,
-                &mut self.reward_a.times,
-                &mut self.reward_a.funds,
-                farmer.rarity_points_staked,
-                &mut farmer.reward_a,
-                Some(original_begin_staking_ts),
+        self.reenroll_fixed_rate_farmer(now_ts, &previous_cohorts, farmer, fixed_rate_tiers)
+    }
+
+    /// the rest of the farmer's gems stay staked
+    #[allow(clippy::too_many_arguments)]
+    pub fn unstake_gem(
+        &mut self,
+        now_ts: u64,
+        gem_mint: Pubkey,
+        gem_count: u64,
+        vault_rarity_points: u64,
+        deposited_at_ts: u64,
+        farmer: &mut Farmer,
+        mut fixed_rate_tiers: Option<&mut FixedRateTiers>,
+    ) -> Result<()> {
+        // the farmer would be left staked with nothing, the vault locked - unstake ends staking instead
+        if gem_count >= farmer.gems_staked {
+            return Err(error!(ErrorCode::LastGemsUnstaked));
+        }
+
+        self.sync_farmer_rewards(farmer);
+
+        // update farmer
+        let previous_cohorts = farmer.cohorts().to_vec();
+        let previous_rarity_points = farmer.begin_gem_cooldown(
+            now_ts,
+            self.config.cooldown_period_sec,
+            gem_mint,
+            gem_count,
+            vault_rarity_points,
+            deposited_at_ts,
+        )?;
+
+        // update farm
+        self.gems_staked.try_sub_assign(gem_count)?;
+        self.rarity_points_staked
+            .try_sub_assign(previous_rarity_points.try_sub(farmer.rarity_points_staked)?)?;
+
+        // fixed-rate only - no re-enrolling on the way out, the farmer keeps their promise
+        // for the gems that are left, and whatever was reserved for this one is released
+        let farmer_cohorts = farmer.cohorts().to_vec();
+
+        for (reward, farmer_reward) in self.rewards.iter_mut().zip(farmer.rewards.iter_mut()) {
+            if !reward.is_fixed() {
+                continue;
+            }
+
+            reward.fixed_rate.release_unstaked_gems(
+                &previous_cohorts,
+                &farmer_cohorts,
+                farmer_reward,
+                fixed_rate_tiers
+                    .as_deref_mut()
+                    .and_then(|t| t.schedules_for(reward.reward_id))
+                    .as_deref(),
             )?;
         }
 
-        if self.reward_b.reward_type == RewardType::Fixed {
-            // graduate with PREVIOUS rarity points count
-            let original_begin_staking_ts = self
-                .reward_b
-                .fixed_rate
-                .graduate_farmer(previous_rarity_points, &mut farmer.reward_b)?;
+        Ok(())
+    }
+
+    /// fixed-rate only - called when the farmer stakes extra gems w/o unstaking first
+    fn reenroll_fixed_rate_farmer(
+        &mut self,
+        now_ts: u64,
+        previous_cohorts: &[StakeCohort],
+        farmer: &mut Farmer,
+        mut fixed_rate_tiers: Option<&mut FixedRateTiers>,
+    ) -> Result<()> {
+        let farmer_cohorts = farmer.cohorts().to_vec();
 
-            // re-enroll with NEW rarity points count
-            self.reward_b.fixed_rate.enroll_farmer(
+        for (reward, farmer_reward) in self.rewards.iter_mut().zip(farmer.rewards.iter_mut()) {
+            if !reward.is_fixed() {
+                continue;
+            }
+
+            // graduate with PREVIOUS cohorts
+            reward.fixed_rate.graduate_farmer(
+                previous_cohorts,
+                farmer_reward,
+                fixed_rate_tiers
+                    .as_deref_mut()
+                    .and_then(|t| t.schedules_for(reward.reward_id)),
+            )?;
+
+            // re-enroll with NEW cohorts (the ones already there keep their tenure)
+            reward.fixed_rate.enroll_farmer(
                 now_ts,
-                &mut self.reward_b.times,
-                &mut self.reward_b.funds,
-                farmer.rarity_points_staked,
-                &mut farmer.reward_b,
-                Some(original_begin_staking_ts),
+                &mut reward.times,
+                &mut reward.funds,
+                &farmer_cohorts,
+                farmer_reward,
+                fixed_rate_tiers
+                    .as_deref_mut()
+                    .and_then(|t| t.schedules_for(reward.reward_id)),
             )?;
         }
 
@@ -354,27 +993,33 @@ impl Farm {
 
 #[proc_macros::assert_size(4)]
 #[repr(C)]
-#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize, PartialEq)]
 pub enum RewardType {
+    #[default]
     Variable,
     Fixed,
 }
 
 /// these numbers should only ever go up - ie they are cummulative
-#[proc_macros::assert_size(24)]
+#[proc_macros::assert_size(32)]
 #[repr(C)]
-#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize)]
 pub struct FundsTracker {
     pub total_funded: u64,
 
     pub total_refunded: u64,
 
     pub total_accrued_to_stakers: u64,
+
+    /// taken away from early unstakers - whatever's redistributed also counts as accrued again,
+    /// so only what's returned to the funder ends up back in the pending amount
+    pub total_forfeited: u64,
 }
 
 impl FundsTracker {
     pub fn pending_amount(&self) -> Result<u64> {
         self.total_funded
+            .try_add(self.total_forfeited)?
             .try_sub(self.total_refunded)?
             .try_sub(self.total_accrued_to_stakers)
     }
@@ -382,7 +1027,7 @@ impl FundsTracker {
 
 #[proc_macros::assert_size(24)]
 #[repr(C)]
-#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize)]
 pub struct TimeTracker {
     /// total duration for which the reward has been funded
     /// updated with each new funding round
@@ -400,18 +1045,42 @@ impl TimeTracker {
         self.reward_end_ts.try_sub(self.duration_sec)
     }
 
+    /// (!) capped at the full duration, for rewards that haven't begun yet
     pub fn remaining_duration(&self, now_ts: u64) -> Result<u64> {
         if now_ts >= self.reward_end_ts {
             return Ok(0);
         }
 
-        self.reward_end_ts.try_sub(now_ts)
+        Ok(std::cmp::min(
+            self.reward_end_ts.try_sub(now_ts)?,
+            self.duration_sec,
+        ))
     }
 
     pub fn passed_duration(&self, now_ts: u64) -> Result<u64> {
         self.duration_sec.try_sub(self.remaining_duration(now_ts)?)
     }
 
+    /// the reward begins at start_ts if one is passed (has to be in the future), otherwise now
+    /// the window before it is zero-emission, but still covered by lock_reward
+    pub fn schedule_reward(
+        &mut self,
+        now_ts: u64,
+        start_ts: Option<u64>,
+        duration_sec: u64,
+    ) -> Result<()> {
+        let begin_ts = start_ts.unwrap_or(now_ts);
+        if begin_ts < now_ts {
+            return Err(error!(ErrorCode::RewardStartInPast));
+        }
+
+        self.duration_sec = duration_sec;
+        self.reward_end_ts = begin_ts.try_add(duration_sec)?;
+
+        Ok(())
+    }
+
+    /// (!) cancelling a reward that hasn't begun yet leaves it with a duration of 0
     pub fn end_reward(&mut self, now_ts: u64) -> Result<()> {
         self.duration_sec
             .try_sub_assign(self.remaining_duration(now_ts)?)?;
@@ -434,15 +1103,44 @@ impl TimeTracker {
     }
 }
 
-#[proc_macros::assert_size(352)] // +4  to make it /8
+/// if set, claimed rewards aren't paid out right away, but unlock linearly instead
+/// (see FarmerVesting) and have to be withdrawn with withdraw_vested
+#[proc_macros::assert_size(16)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct VestingConfig {
+    /// nothing unlocks until this much time has passed since claiming
+    pub cliff_sec: u64,
+
+    /// everything is unlocked this much time after claiming, 0 = no vesting
+    pub duration_sec: u64,
+}
+
+impl VestingConfig {
+    pub fn is_active(&self) -> bool {
+        self.duration_sec > 0
+    }
+
+    fn assert_valid(&self) -> Result<()> {
+        if self.cliff_sec > self.duration_sec {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+        Ok(())
+    }
+}
+
+#[proc_macros::assert_size(600)] // +4  to make it /8
 #[repr(C)]
-#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize)]
 pub struct FarmReward {
-    /// in v0 the next 3 fields (mint, pot type) are set ONLY once, at farm init
+    /// the next 3 fields (mint, pot type) are set ONLY once, when the slot is taken,
     ///   and can't ever be changed for security reasons
-    ///   potentially in v1++ might find a way around it, but for now just use a new farm
+    /// an unused slot has the default (all zeroes) mint
     pub reward_mint: Pubkey,
 
+    /// unique within the farm, so that farmer records can tell a reused slot apart
+    pub reward_id: u64,
+
     /// where the reward is stored
     pub reward_pot: Pubkey,
 
@@ -457,16 +1155,160 @@ pub struct FarmReward {
 
     pub times: TimeTracker,
 
-    /// reserved for future updates, has to be /8
-    _reserved: [u8; 32],
+    /// recorded on farmer accounts but not yet claimed by them (incl. claimed but still vesting)
+    pub unclaimed_reward: u64,
+
+    /// set ONLY once, when the slot is taken, so farmers know what they're signing up for
+    pub vesting: VestingConfig,
+
+    /// bumped every time the manager cancels the reward - that refund isn't attributed to anyone,
+    /// so funder contributions from an earlier round stop counting towards pro-rata refunds
+    pub funding_round: u64,
+
+    /// funds.total_funded as of when the current funding round started
+    pub round_start_funded: u64,
 }
 
 impl FarmReward {
+    pub fn is_active(&self) -> bool {
+        self.reward_mint != Pubkey::default()
+    }
+
+    fn is_fixed(&self) -> bool {
+        self.is_active() && self.reward_type == RewardType::Fixed
+    }
+
+    /// how much has been funded since the current funding round started
+    pub fn round_funded(&self) -> Result<u64> {
+        self.funds.total_funded.try_sub(self.round_start_funded)
+    }
+
+    fn start_funding_round(&mut self) -> Result<()> {
+        self.funding_round.try_add_assign(1)?;
+        self.round_start_funded = self.funds.total_funded;
+        Ok(())
+    }
+
+    /// a reward can only be retired once it's fully paid out:
+    /// - no more funds waiting to accrue (cancel the reward to sweep any leftovers)
+    /// - nothing recorded on farmer accounts that hasn't been claimed
+    /// - nothing accrued to the pool that hasn't been recorded on farmer accounts
+    ///   (refresh the remaining farmers to record it)
+    fn is_retirable(&self, now_ts: u64) -> Result<bool> {
+        Ok(!self.is_locked(now_ts)
+            && now_ts >= self.times.reward_end_ts
+            && self.funds.pending_amount()? == 0
+            && !self.variable_rate.has_queued_epochs()
+            && self.fixed_rate.reserved_amount == 0
+            && self.unclaimed_reward == 0
+            && self.variable_rate.unrecorded_reward.as_u64(0)? == 0)
+    }
+
+    /// returns the amount to be paid out right away
+    pub fn claim_farmer_reward(
+        &mut self,
+        now_ts: u64,
+        farmer_reward: &mut FarmerReward,
+        pot_balance: u64,
+    ) -> Result<u64> {
+        let to_claim = farmer_reward.claim_reward(pot_balance)?;
+
+        // vesting rewards stay in the pot (and count as unclaimed) until they're withdrawn
+        if self.vesting.is_active() {
+            farmer_reward
+                .vesting
+                .begin_vesting(now_ts, to_claim, &self.vesting)?;
+            return Ok(0);
+        }
+
+        self.unclaimed_reward.try_sub_assign(to_claim)?;
+
+        Ok(to_claim)
+    }
+
+    /// what a permissionless crank is allowed to pay out - the farmer decides when their rewards
+    /// start vesting (or get claimed at a penalty), so for vesting rewards, or during the early
+    /// unstake period, it only pushes out what's already unlocked
+    pub fn crank_farmer_reward(
+        &mut self,
+        now_ts: u64,
+        farmer_reward: &mut FarmerReward,
+        pot_balance: u64,
+        in_early_unstake_period: bool,
+    ) -> Result<u64> {
+        if self.vesting.is_active() || in_early_unstake_period {
+            return self.withdraw_farmer_vested(now_ts, farmer_reward, pot_balance);
+        }
+
+        self.claim_farmer_reward(now_ts, farmer_reward, pot_balance)
+    }
+
+    /// same as claiming, except the amount is staked rather than paid out
+    /// (!) not for vesting rewards, else compounding would be a way around the vesting schedule
+    pub fn compound_farmer_reward(
+        &mut self,
+        farmer_reward: &mut FarmerReward,
+        pot_balance: u64,
+    ) -> Result<u64> {
+        if self.vesting.is_active() {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+
+        let to_compound = farmer_reward.claim_reward(pot_balance)?;
+        self.unclaimed_reward.try_sub_assign(to_compound)?;
+
+        Ok(to_compound)
+    }
+
+    pub fn withdraw_farmer_vested(
+        &mut self,
+        now_ts: u64,
+        farmer_reward: &mut FarmerReward,
+        pot_balance: u64,
+    ) -> Result<u64> {
+        let to_withdraw = farmer_reward.vesting.withdraw(now_ts, pot_balance)?;
+        self.unclaimed_reward.try_sub_assign(to_withdraw)?;
+
+        Ok(to_withdraw)
+    }
+
+    /// takes the amount away from the farmer, and either spreads it over the remaining stakers
+    /// or hands it back to the funder (also if there's no one left to spread it over)
+    fn forfeit_farmer_reward(
+        &mut self,
+        amount: u64,
+        destination: PenaltyDestination,
+        farm_rarity_points_staked: u64,
+        farmer_reward: &mut FarmerReward,
+    ) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        farmer_reward.forfeit_reward(amount)?;
+        self.unclaimed_reward.try_sub_assign(amount)?;
+        self.funds.total_forfeited.try_add_assign(amount)?;
+
+        if destination == PenaltyDestination::Redistribute
+            && self.reward_type == RewardType::Variable
+            && farm_rarity_points_staked > 0
+        {
+            self.funds.total_accrued_to_stakers.try_add_assign(amount)?;
+            self.variable_rate
+                .redistribute_reward(amount, farm_rarity_points_staked)?;
+        }
+
+        Ok(())
+    }
+
     /// (!) THIS OPERATION IS IRREVERSIBLE
     /// locking ensures the committed reward cannot be withdrawn/changed by a malicious farm operator
     /// once locked, any funding / cancellation ixs become non executable until reward_ned_ts is reached
+    /// queued epochs run back to back after the current reward, so they're covered too
     fn lock_reward(&mut self) -> Result<()> {
-        self.times.lock_end_ts = self.times.reward_end_ts;
+        let queued_duration = self.variable_rate.epoch_queue.total_duration()?;
+
+        self.times.lock_end_ts = self.times.reward_end_ts.try_add(queued_duration)?;
 
         // msg!("locked reward up to {}", self.times.reward_end_ts);
         Ok(())
@@ -486,22 +1328,38 @@ impl FarmReward {
             return Err(error!(ErrorCode::RewardLocked));
         }
 
+        let mismatch = || error!(ErrorCode::RewardConfigMismatch);
+
         match self.reward_type {
             RewardType::Variable => self.variable_rate.fund_reward(
                 now_ts,
                 &mut self.times,
                 &mut self.funds,
-                variable_rate_config.unwrap(),
+                variable_rate_config.ok_or_else(mismatch)?,
             ),
             RewardType::Fixed => self.fixed_rate.fund_reward(
                 now_ts,
                 &mut self.times,
                 &mut self.funds,
-                fixed_rate_config.unwrap(),
+                fixed_rate_config.ok_or_else(mismatch)?,
             ),
         }
     }
 
+    fn queue_epochs(&mut self, now_ts: u64, epochs: Vec<RewardEpoch>) -> Result<()> {
+        if self.is_locked(now_ts) {
+            return Err(error!(ErrorCode::RewardLocked));
+        }
+
+        // fixed rate promises are made against a single known schedule, so they can't queue
+        if self.reward_type != RewardType::Variable {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+
+        self.variable_rate
+            .queue_epochs(now_ts, &mut self.times, &mut self.funds, epochs)
+    }
+
     fn cancel_reward_by_type(&mut self, now_ts: u64) -> Result<u64> {
         if self.is_locked(now_ts) {
             return Err(error!(ErrorCode::RewardLocked));
@@ -519,18 +1377,53 @@ impl FarmReward {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn update_accrued_reward_by_type(
         &mut self,
         now_ts: u64,
         farm_rarity_points_staked: u64,
         farmer_rarity_points_staked: Option<u64>,
+        farmer_cohorts: Option<&[StakeCohort]>,
+        mut farmer_reward: Option<&mut FarmerReward>,
+        reenroll: bool,
+        fixed_rate_tiers: Option<&mut FixedRateTiers>,
+    ) -> Result<()> {
+        let accrued_before = farmer_reward.as_ref().map(|r| r.accrued_reward);
+
+        self.update_accrued_reward_by_type_inner(
+            now_ts,
+            farm_rarity_points_staked,
+            farmer_rarity_points_staked,
+            farmer_cohorts,
+            farmer_reward.as_deref_mut(),
+            reenroll,
+            fixed_rate_tiers,
+        )?;
+
+        // keep track of what's been recorded on the farmer but not yet claimed
+        if let (Some(before), Some(farmer_reward)) = (accrued_before, farmer_reward) {
+            self.unclaimed_reward
+                .try_add_assign(farmer_reward.accrued_reward.try_sub(before)?)?;
+        }
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn update_accrued_reward_by_type_inner(
+        &mut self,
+        now_ts: u64,
+        farm_rarity_points_staked: u64,
+        farmer_rarity_points_staked: Option<u64>,
+        farmer_cohorts: Option<&[StakeCohort]>,
         farmer_reward: Option<&mut FarmerReward>,
         reenroll: bool,
+        fixed_rate_tiers: Option<&mut FixedRateTiers>,
     ) -> Result<()> {
         match self.reward_type {
             RewardType::Variable => self.variable_rate.update_accrued_reward(
                 now_ts,
-                &self.times,
+                &mut self.times,
                 &mut self.funds,
                 farm_rarity_points_staked,
                 farmer_rarity_points_staked,
@@ -546,9 +1439,10 @@ impl FarmReward {
                     now_ts,
                     &mut self.times,
                     &mut self.funds,
-                    farmer_rarity_points_staked.unwrap(),
+                    farmer_cohorts.unwrap(),
                     farmer_reward.unwrap(),
                     reenroll,
+                    fixed_rate_tiers.and_then(|t| t.schedules_for(self.reward_id)),
                 )
             }
         }
@@ -559,6 +1453,290 @@ impl FarmReward {
 mod tests {
     use super::*;
 
+    impl Farm {
+        pub fn zeroed() -> Self {
+            let data = vec![0u8; 8 + std::mem::size_of::<Farm>()];
+            Farm::try_deserialize_unchecked(&mut &data[..]).unwrap()
+        }
+    }
+
+    #[test]
+    fn test_fund_reward_wrong_config() {
+        let mut farm = Farm::zeroed();
+        let variable_mint = Pubkey::new_unique();
+        let fixed_mint = Pubkey::new_unique();
+
+        for (mint, reward_type) in [
+            (variable_mint, RewardType::Variable),
+            (fixed_mint, RewardType::Fixed),
+        ] {
+            farm.add_reward(
+                mint,
+                Pubkey::new_unique(),
+                reward_type,
+                VestingConfig::default(),
+            )
+            .unwrap();
+        }
+
+        let variable_config = VariableRateConfig {
+            amount: 1000,
+            duration_sec: 100,
+            start_ts: None,
+            curve: None,
+        };
+        let fixed_config = FixedRateConfig {
+            schedule: FixedRateSchedule::new_base(1, 1),
+            amount: 1000,
+            duration_sec: 100,
+            start_ts: None,
+        };
+
+        // the other type's config (or none at all) is rejected rather than panicking
+        for (mint, variable, fixed) in [
+            (variable_mint, None, Some(fixed_config)),
+            (fixed_mint, Some(variable_config), None),
+            (fixed_mint, None, None),
+        ] {
+            let err = farm
+                .fund_reward_by_mint(100, mint, variable, fixed)
+                .unwrap_err();
+            assert_eq!(err, error!(ErrorCode::RewardConfigMismatch));
+        }
+
+        farm.fund_reward_by_mint(100, variable_mint, Some(variable_config), None)
+            .unwrap();
+        farm.fund_reward_by_mint(100, fixed_mint, None, Some(fixed_config))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_unstake_gem_fixed_rate() {
+        let mut farm = Farm::zeroed();
+        let mut farmer = Farmer::zeroed();
+        let reward_mint = Pubkey::new_unique();
+
+        farm.add_reward(
+            reward_mint,
+            Pubkey::new_unique(),
+            RewardType::Fixed,
+            VestingConfig::default(),
+        )
+        .unwrap();
+        farm.fund_reward_by_mint(
+            100,
+            reward_mint,
+            None,
+            Some(FixedRateConfig {
+                schedule: FixedRateSchedule::new_base(1, 1),
+                amount: 2000,
+                duration_sec: 100,
+                start_ts: None,
+            }),
+        )
+        .unwrap();
+        let mut tiers = FixedRateTiers::default();
+        tiers
+            .set_schedule(
+                Pubkey::new_unique(),
+                0,
+                farm.rewards[0].reward_id,
+                &FixedRateSchedule::new_base(1, 1),
+            )
+            .unwrap();
+
+        // everything that's been funded is promised to the farmer
+        farm.begin_staking(100, 2, 20, 0, &mut farmer, Some(&mut tiers))
+            .unwrap();
+        assert_eq!(2000, farm.rewards[0].fixed_rate.reserved_amount);
+
+        // taking out half the gems releases half of it, the farmer keeps their schedule for the rest
+        farm.unstake_gem(
+            150,
+            Pubkey::new_unique(),
+            1,
+            10,
+            100,
+            &mut farmer,
+            Some(&mut tiers),
+        )
+        .unwrap();
+        assert_eq!(1000, farm.rewards[0].fixed_rate.reserved_amount);
+        assert_eq!(100, farmer.rewards[0].fixed_rate.begin_schedule_ts);
+        assert_eq!(10, farm.rarity_points_staked);
+    }
+
+    #[test]
+    fn test_compound_gems() {
+        let mut farm = Farm::zeroed();
+        let mut farmer = Farmer::zeroed();
+        farm.config.min_staking_period_sec = 100;
+
+        farm.begin_staking(100, 10, 10, 0, &mut farmer, None)
+            .unwrap();
+        farm.compound_gems(150, 15, 15, 5, &mut farmer, None)
+            .unwrap();
+
+        assert_eq!(15, farm.gems_staked);
+        assert_eq!(15, farm.rarity_points_staked);
+        assert_eq!(200, farmer.min_staking_ends_ts);
+
+        // unlike staking extra gems
+        farm.stake_extra_gems(160, 20, 20, 5, &mut farmer, None)
+            .unwrap();
+        assert_eq!(260, farmer.min_staking_ends_ts);
+    }
+
+    #[test]
+    fn test_unstake_gem_not_enrolled() {
+        let mut farm = Farm::zeroed();
+        let mut farmer = Farmer::zeroed();
+
+        // the fixed reward is only added after the farmer staked, so they never enrolled with it
+        farm.begin_staking(100, 2, 20, 0, &mut farmer, None)
+            .unwrap();
+        farm.add_reward(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            RewardType::Fixed,
+            VestingConfig::default(),
+        )
+        .unwrap();
+
+        farm.unstake_gem(150, Pubkey::new_unique(), 1, 10, 100, &mut farmer, None)
+            .unwrap();
+        assert_eq!(1, farm.gems_staked);
+        assert!(!farmer.rewards[0].fixed_rate.is_staked());
+    }
+
+    #[test]
+    fn test_unstake_gem_last_gems() {
+        let mut farm = Farm::zeroed();
+        let mut farmer = Farmer::zeroed();
+
+        farm.begin_staking(100, 2, 20, 0, &mut farmer, None)
+            .unwrap();
+        farm.unstake_gem(150, Pubkey::new_unique(), 1, 10, 100, &mut farmer, None)
+            .unwrap();
+
+        // taking out the last one would leave the farmer staked with nothing
+        assert!(farm
+            .unstake_gem(150, Pubkey::new_unique(), 1, 10, 100, &mut farmer, None)
+            .is_err());
+        assert_eq!(1, farm.gems_staked);
+        assert_eq!(1, farm.staked_farmer_count);
+        assert_eq!(FarmerState::Staked, farmer.state);
+    }
+
+    #[test]
+    fn test_early_claim_penalty() {
+        let mut farm = Farm::zeroed();
+        let mut farmer = Farmer::zeroed();
+        farm.add_reward(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            RewardType::Variable,
+            VestingConfig::default(),
+        )
+        .unwrap();
+        farm.early_unstake_penalty = EarlyUnstakePenalty {
+            period_sec: 100,
+            penalty_bps: 2_500,
+            destination: PenaltyDestination::Redistribute,
+        };
+
+        farm.begin_staking(100, 1, 10, 0, &mut farmer, None)
+            .unwrap();
+        farm.rarity_points_staked += 30; //someone else's
+
+        // raising the penalty later doesn't affect farmers already staked
+        farm.early_unstake_penalty.penalty_bps = 10_000;
+
+        farmer.rewards[0].accrued_reward = 1000;
+        farm.rewards[0].unclaimed_reward = 1000;
+
+        farm.apply_early_claim_penalty(150, 0, &mut farmer).unwrap();
+        assert_eq!(250, farmer.rewards[0].forfeited_reward);
+        assert_eq!(750, farmer.rewards[0].outstanding_reward().unwrap());
+
+        // spread over everyone else, the farmer doesn't get any of it back
+        assert_eq!(
+            farm.rewards[0]
+                .variable_rate
+                .accrued_reward_per_rarity_point,
+            farmer.rewards[0]
+                .variable_rate
+                .last_recorded_accrued_reward_per_rarity_point
+        );
+
+        // nothing once the period is over
+        farm.apply_early_claim_penalty(200, 0, &mut farmer).unwrap();
+        assert_eq!(750, farmer.rewards[0].outstanding_reward().unwrap());
+    }
+
+    #[test]
+    fn test_crank_farmer_reward() {
+        let mut reward = FarmReward {
+            vesting: VestingConfig {
+                cliff_sec: 20,
+                duration_sec: 100,
+            },
+            unclaimed_reward: 2000,
+            ..FarmReward::default()
+        };
+        let mut farmer_reward = FarmerReward {
+            accrued_reward: 1000,
+            ..FarmerReward::default()
+        };
+
+        // cranking doesn't start vesting on the farmer's behalf
+        assert_eq!(
+            0,
+            reward
+                .crank_farmer_reward(100, &mut farmer_reward, 9999, false)
+                .unwrap()
+        );
+        assert!(!farmer_reward.vesting.is_pending());
+        assert_eq!(1000, farmer_reward.outstanding_reward().unwrap());
+
+        // only the farmer claiming does
+        assert_eq!(
+            0,
+            reward
+                .claim_farmer_reward(100, &mut farmer_reward, 9999)
+                .unwrap()
+        );
+
+        // after which cranking pushes out what's unlocked, w/o touching the schedule
+        farmer_reward.accrued_reward = 2000;
+        assert_eq!(
+            500,
+            reward
+                .crank_farmer_reward(150, &mut farmer_reward, 9999, false)
+                .unwrap()
+        );
+        assert_eq!(120, farmer_reward.vesting.cliff_end_ts);
+        assert_eq!(200, farmer_reward.vesting.end_ts);
+        assert_eq!(1000, farmer_reward.outstanding_reward().unwrap());
+        assert_eq!(1500, reward.unclaimed_reward);
+
+        // non-vesting rewards are simply claimed, unless that'd cost the farmer a penalty
+        reward.vesting = VestingConfig::default();
+        assert_eq!(
+            0,
+            reward
+                .crank_farmer_reward(150, &mut farmer_reward, 9999, true)
+                .unwrap()
+        );
+        assert_eq!(
+            1000,
+            reward
+                .crank_farmer_reward(150, &mut farmer_reward, 9999, false)
+                .unwrap()
+        );
+        assert_eq!(500, reward.unclaimed_reward);
+    }
+
     #[test]
     fn test_time_tracker() {
         let times = TimeTracker {
@@ -594,14 +1772,194 @@ mod tests {
         assert_eq!(times.reward_end_ts, 140);
     }
 
+    #[test]
+    fn test_time_tracker_scheduled_start() {
+        let mut times = TimeTracker::default();
+
+        assert!(times.schedule_reward(100, Some(99), 50).is_err());
+
+        times.schedule_reward(100, Some(150), 50).unwrap();
+        assert_eq!(150, times.reward_begin_ts().unwrap());
+        assert_eq!(200, times.reward_end_ts);
+        assert_eq!(50, times.remaining_duration(120).unwrap());
+        assert_eq!(0, times.passed_duration(120).unwrap());
+        assert_eq!(150, times.reward_lower_bound(120).unwrap());
+
+        // cancelled before it began
+        times.end_reward(120).unwrap();
+        assert_eq!(times.duration_sec, 0);
+        assert_eq!(times.reward_end_ts, 120);
+    }
+
     #[test]
     fn test_funds_tracker() {
         let funds = FundsTracker {
             total_funded: 100,
             total_refunded: 50,
             total_accrued_to_stakers: 30,
+            total_forfeited: 0,
         };
 
         assert_eq!(20, funds.pending_amount().unwrap());
     }
+
+    #[test]
+    fn test_lockup_tier() {
+        let tier = LockupTier {
+            lockup_period_sec: 100,
+            multiplier_bps: 15_000,
+        };
+
+        assert!(tier.assert_valid().is_ok());
+        assert_eq!(15, tier.effective_rarity_points(10).unwrap());
+        assert_eq!(
+            10,
+            LockupTier::no_lockup().effective_rarity_points(10).unwrap()
+        );
+
+        // large (eg fungible) amounts don't overflow, unless the result does
+        let large_points = 1 << 62;
+        assert_eq!(
+            large_points,
+            LockupTier::no_lockup()
+                .effective_rarity_points(large_points)
+                .unwrap()
+        );
+        assert_eq!(
+            large_points / 2 * 3,
+            tier.effective_rarity_points(large_points).unwrap()
+        );
+        assert!(tier.effective_rarity_points(u64::MAX).is_err());
+
+        // can't earn less than without a lockup
+        let bad_tier = LockupTier {
+            lockup_period_sec: 100,
+            multiplier_bps: 9_999,
+        };
+        assert!(bad_tier.assert_valid().is_err());
+    }
+
+    #[test]
+    fn test_early_unstake_penalty() {
+        let penalty = EarlyUnstakePenalty {
+            period_sec: 100,
+            penalty_bps: 2_500,
+            destination: PenaltyDestination::Redistribute,
+        };
+
+        assert!(penalty.assert_valid().is_ok());
+        assert_eq!(25, penalty.forfeited_amount(150, 100, 100).unwrap());
+        assert_eq!(0, penalty.forfeited_amount(200, 100, 100).unwrap());
+        assert_eq!(
+            0,
+            EarlyUnstakePenalty::default()
+                .forfeited_amount(150, 100, 100)
+                .unwrap()
+        );
+
+        // can't forfeit more than everything
+        let bad_penalty = EarlyUnstakePenalty {
+            penalty_bps: 10_001,
+            ..penalty
+        };
+        assert!(bad_penalty.assert_valid().is_err());
+
+        // whatever's returned to the funder becomes pending again
+        let funds = FundsTracker {
+            total_funded: 100,
+            total_refunded: 0,
+            total_accrued_to_stakers: 60,
+            total_forfeited: 10,
+        };
+        assert_eq!(50, funds.pending_amount().unwrap());
+    }
+
+    #[test]
+    fn test_migrate_legacy_farmer() {
+        let zeroes = [0u8; 1024];
+        let mut legacy_farm = LegacyFarm::deserialize(&mut &zeroes[..]).unwrap();
+        legacy_farm.farmer_count = 2;
+        legacy_farm.reward_a.reward_mint = Pubkey::new_unique();
+        legacy_farm
+            .reward_a
+            .variable_rate
+            .accrued_reward_per_rarity_point = Number128::from(100u64);
+        legacy_farm.reward_b.reward_mint = Pubkey::new_unique();
+        legacy_farm.reward_b.reward_type = RewardType::Fixed;
+        legacy_farm.reward_b.fixed_rate.schedule.denominator = 1;
+        legacy_farm.reward_b.fixed_rate.schedule.tier1 = Some(TierConfig {
+            reward_rate: 5,
+            required_tenure: 10,
+        });
+
+        let mut farm = Farm::zeroed();
+        let mut tiers = FixedRateTiers::default();
+        let farm_key = Pubkey::new_unique();
+        farm.migrate_legacy(farm_key, &legacy_farm, &mut tiers)
+            .unwrap();
+
+        assert_eq!(LATEST_FARM_VERSION, farm.version);
+        assert_eq!(2, farm.legacy_farmer_count);
+        assert_eq!(2, farm.active_reward_count());
+        assert_eq!(1, farm.rewards[1].fixed_rate.tier_count);
+        let schedules = tiers.schedules_for(farm.rewards[1].reward_id).unwrap();
+        assert_eq!(1, schedules.schedule(schedules.current).unwrap().tier_count);
+
+        let mut legacy_farmer = LegacyFarmer::deserialize(&mut &zeroes[..]).unwrap();
+        legacy_farmer.farm = farm_key;
+        legacy_farmer.state = FarmerState::Staked;
+        legacy_farmer.gems_staked = 10;
+        legacy_farmer.rarity_points_staked = 10;
+        legacy_farmer.reward_a.accrued_reward = 500;
+        legacy_farmer.reward_a.paid_out_reward = 200;
+        legacy_farmer
+            .reward_a
+            .variable_rate
+            .last_recorded_accrued_reward_per_rarity_point = Number128::from(40u64);
+        legacy_farmer.reward_b.fixed_rate.begin_staking_ts = 50;
+        legacy_farmer.reward_b.fixed_rate.begin_schedule_ts = 50;
+        legacy_farmer.reward_b.fixed_rate.promised_duration = 100;
+        legacy_farmer
+            .reward_b
+            .fixed_rate
+            .promised_schedule
+            .base_rate = 2;
+        legacy_farmer
+            .reward_b
+            .fixed_rate
+            .promised_schedule
+            .denominator = 1;
+
+        let mut farmer = Farmer::zeroed();
+        farm.migrate_legacy_farmer(100, &legacy_farmer, &mut farmer, Some(&mut tiers))
+            .unwrap();
+
+        // recorded but unclaimed, and accrued to the pool but unrecorded (10 points * (100 - 40))
+        assert_eq!(300, farm.rewards[0].unclaimed_reward);
+        assert_eq!(
+            600,
+            farm.rewards[0]
+                .variable_rate
+                .unrecorded_reward
+                .as_u64(0)
+                .unwrap()
+        );
+        assert_eq!(1, farm.legacy_farmer_count);
+
+        assert_eq!(farm.rewards[0].reward_id, farmer.rewards[0].reward_id);
+        assert_eq!(50, farmer.begin_staking_ts);
+        assert_eq!(1, farmer.cohort_count);
+        assert_eq!(10, farmer.cohorts[0].rarity_points_staked);
+
+        // the farmer's promise wasn't the farm's current schedule, so it's kept alongside it
+        let schedules = tiers.schedules_for(farm.rewards[1].reward_id).unwrap();
+        let promised = farmer.rewards[1].fixed_rate.promised_schedule;
+        assert_ne!(schedules.current, promised);
+        assert_eq!(2, schedules.schedule(promised).unwrap().base_rate);
+        assert_eq!(1, schedules.schedules[promised as usize].enrolled_farmers);
+
+        // slots can't be retired from under the farmer that's still left
+        let reward_mint = farm.rewards[0].reward_mint;
+        assert!(farm.retire_reward_by_mint(100, reward_mint).is_err());
+    }
 }
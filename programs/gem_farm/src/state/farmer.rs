@@ -1,7 +1,19 @@
 use anchor_lang::prelude::*;
 use gem_common::{errors::ErrorCode, *};
 
-use crate::{number128::Number128, state::FixedRateSchedule};
+use crate::{
+    number128::Number128,
+    state::{
+        EarlyUnstakePenalty, FixedRateSchedule, LegacyFarmer, LockupTier, VestingConfig,
+        MAX_REWARDS,
+    },
+};
+
+/// max number of separately tracked batches of gems a farmer can have staked at once
+pub const MAX_STAKE_COHORTS: usize = 8;
+
+/// max number of individually unstaked gems a farmer can have cooling down at once
+pub const MAX_PENDING_WITHDRAWALS: usize = 4;
 
 #[proc_macros::assert_size(4)]
 #[repr(C)]
@@ -12,7 +24,42 @@ pub enum FarmerState {
     PendingCooldown,
 }
 
-#[proc_macros::assert_size(600)] // +4 to make it /8
+/// gems that started staking at the same time - fixed-rate tenure and the min staking period
+/// are counted from when the cohort began, rather than from when the farmer first staked
+#[proc_macros::assert_size(40)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct StakeCohort {
+    pub begin_staking_ts: u64,
+
+    pub min_staking_ends_ts: u64,
+
+    pub gems_staked: u64,
+
+    /// as counted by the vault, ie before the lockup multiplier
+    pub vault_rarity_points: u64,
+
+    /// after the lockup multiplier, these add up to the farmer's rarity_points_staked
+    pub rarity_points_staked: u64,
+}
+
+/// a gem unstaked on its own - it sits in the (still locked) vault until its cooldown passes
+#[proc_macros::assert_size(56)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct PendingWithdrawal {
+    pub gem_mint: Pubkey,
+
+    /// slot is free if 0
+    pub gem_count: u64,
+
+    /// as counted by the vault, ie before the lockup multiplier
+    pub vault_rarity_points: u64,
+
+    pub cooldown_ends_ts: u64,
+}
+
+#[proc_macros::assert_size(1408)] // +4 to make it /8
 #[repr(C)]
 #[account]
 #[derive(Debug)]
@@ -30,7 +77,7 @@ pub struct Farmer {
     /// total number of gems at the time when the vault is locked
     pub gems_staked: u64,
 
-    /// total number of gems * rarity of each gem (1 if un-appraised)
+    /// total number of gems * rarity of each gem (1 if un-appraised) * lockup multiplier
     pub rarity_points_staked: u64,
 
     /// this will be updated when they decide to unstake taking into acc. config set at farm level
@@ -40,34 +87,405 @@ pub struct Farmer {
     pub cooldown_ends_ts: u64,
 
     // ----------------- rewards
-    pub reward_a: FarmerReward,
+    /// aligned by index with the reward slots on the farm
+    pub rewards: [FarmerReward; MAX_REWARDS],
+
+    /// copied over from the farm when staking, so later changes to the tiers don't affect it
+    pub lockup: LockupTier,
+
+    /// copied over from the farm when the farmer first stakes, same as the lockup
+    pub early_unstake_penalty: EarlyUnstakePenalty,
+
+    /// set when the farmer first stakes, NOT reset when they stake extra gems
+    pub begin_staking_ts: u64,
 
-    pub reward_b: FarmerReward,
+    /// oldest first, only the first cohort_count are in use
+    /// unless the farm tracks tenure per gem, extra gems join the latest cohort
+    pub cohorts: [StakeCohort; MAX_STAKE_COHORTS],
+
+    pub cohort_count: u64,
+
+    /// no longer staked, but still in the vault (see partial_unstake)
+    pub pending_withdrawals: [PendingWithdrawal; MAX_PENDING_WITHDRAWALS],
+
+    /// where crank_claim sends rewards to - the identity itself if not set
+    pub reward_recipient: Pubkey,
 
     /// reserved for future updates, has to be /8
-    _reserved: [u8; 32],
+    _reserved: [u8; 8],
 }
 
 impl Farmer {
+    pub fn reward_recipient(&self) -> Pubkey {
+        match self.reward_recipient == Pubkey::default() {
+            true => self.identity,
+            false => self.reward_recipient,
+        }
+    }
+
+    /// claiming now would forfeit part of the reward (see Farm::apply_early_claim_penalty)
+    pub fn in_early_unstake_period(&self, now_ts: u64) -> Result<bool> {
+        Ok(self.state == FarmerState::Staked
+            && self
+                .early_unstake_penalty
+                .applies(now_ts, self.begin_staking_ts)?)
+    }
+
+    /// carries the farmer over from the legacy layout
+    /// the rewards are carried over by the farm, which has to keep track (see Farm::migrate_legacy_farmer)
+    /// (!) expects a zeroed out account, so anything the legacy layout didn't have starts off empty
+    pub fn migrate_legacy(&mut self, now_ts: u64, legacy: &LegacyFarmer) -> Result<()> {
+        self.farm = legacy.farm;
+        self.identity = legacy.identity;
+        self.vault = legacy.vault;
+        self.state = legacy.state;
+        self.gems_staked = legacy.gems_staked;
+        self.rarity_points_staked = legacy.rarity_points_staked;
+        self.min_staking_ends_ts = legacy.min_staking_ends_ts;
+        self.cooldown_ends_ts = legacy.cooldown_ends_ts;
+
+        if self.state != FarmerState::Staked {
+            return Ok(());
+        }
+
+        // fixed rate tenure used to be counted from when the farmer enrolled,
+        // otherwise there's no record of when they first staked
+        self.begin_staking_ts = legacy
+            .rewards()
+            .iter()
+            .map(|r| r.fixed_rate.begin_staking_ts)
+            .filter(|ts| *ts > 0)
+            .min()
+            .unwrap_or(now_ts);
+        self.lockup = LockupTier::no_lockup();
+
+        // everything staked goes into a single cohort
+        self.cohorts[0] = StakeCohort {
+            begin_staking_ts: self.begin_staking_ts,
+            min_staking_ends_ts: self.min_staking_ends_ts,
+            gems_staked: self.gems_staked,
+            vault_rarity_points: self.rarity_points_staked,
+            rarity_points_staked: self.rarity_points_staked,
+        };
+        self.cohort_count = 1;
+
+        Ok(())
+    }
+
     pub fn begin_staking(
         &mut self,
         min_staking_period_sec: u64,
+        lockup: LockupTier,
+        per_gem_tenure: bool,
         now_ts: u64,
         gems_in_vault: u64,
         rarity_points_in_vault: u64,
     ) -> Result<(u64, u64)> {
+        if self.state != FarmerState::Staked {
+            self.begin_staking_ts = now_ts;
+            self.clear_cohorts();
+            self.clear_pending_withdrawals();
+        }
         self.state = FarmerState::Staked;
 
+        self.min_staking_ends_ts = now_ts.try_add(std::cmp::max(
+            min_staking_period_sec,
+            lockup.lockup_period_sec,
+        ))?;
+        self.lockup = lockup;
+        self.cooldown_ends_ts = 0; //zero it out in case it was set before
+
+        self.stake_new_gems(
+            per_gem_tenure,
+            now_ts,
+            gems_in_vault,
+            rarity_points_in_vault,
+        )
+    }
+
+    /// (!) unlike staking extra gems, neither the min staking period nor the lockup are restarted,
+    /// the new gems are staked under whatever's left of them (see compound)
+    pub fn compound_gems(
+        &mut self,
+        per_gem_tenure: bool,
+        now_ts: u64,
+        gems_in_vault: u64,
+        rarity_points_in_vault: u64,
+    ) -> Result<(u64, u64)> {
+        if self.state != FarmerState::Staked {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+
+        self.stake_new_gems(
+            per_gem_tenure,
+            now_ts,
+            gems_in_vault,
+            rarity_points_in_vault,
+        )
+    }
+
+    /// returns previous gems and rarity points staked
+    fn stake_new_gems(
+        &mut self,
+        per_gem_tenure: bool,
+        now_ts: u64,
+        gems_in_vault: u64,
+        rarity_points_in_vault: u64,
+    ) -> Result<(u64, u64)> {
         let previous_gems_staked = self.gems_staked;
         let previous_rarity_points_staked = self.rarity_points_staked;
-        self.gems_staked = gems_in_vault;
-        self.rarity_points_staked = rarity_points_in_vault;
-        self.min_staking_ends_ts = now_ts.try_add(min_staking_period_sec)?;
-        self.cooldown_ends_ts = 0; //zero it out in case it was set before
+
+        // whatever the cohorts (or pending withdrawals) don't cover yet has just been staked
+        let (cohort_gems, cohort_rarity_points) = self.cohort_totals()?;
+        let (pending_gems, pending_rarity_points) = self.pending_withdrawal_totals()?;
+        self.add_to_cohorts(
+            per_gem_tenure,
+            now_ts,
+            gems_in_vault.try_sub(cohort_gems)?.try_sub(pending_gems)?,
+            rarity_points_in_vault
+                .try_sub(cohort_rarity_points)?
+                .try_sub(pending_rarity_points)?,
+        )?;
+
+        self.gems_staked = gems_in_vault.try_sub(pending_gems)?;
+        self.rarity_points_staked = self.cohort_totals_staked()?;
 
         Ok((previous_gems_staked, previous_rarity_points_staked))
     }
 
+    pub fn cohorts(&self) -> &[StakeCohort] {
+        let count = std::cmp::min(self.cohort_count as usize, MAX_STAKE_COHORTS);
+        &self.cohorts[..count]
+    }
+
+    fn clear_cohorts(&mut self) {
+        self.cohorts = [StakeCohort::default(); MAX_STAKE_COHORTS];
+        self.cohort_count = 0;
+    }
+
+    /// gems and rarity points (as counted by the vault) across all cohorts
+    fn cohort_totals(&self) -> Result<(u64, u64)> {
+        self.cohorts()
+            .iter()
+            .try_fold((0_u64, 0_u64), |(gems, points), c| {
+                Ok((
+                    gems.try_add(c.gems_staked)?,
+                    points.try_add(c.vault_rarity_points)?,
+                ))
+            })
+    }
+
+    fn cohort_totals_staked(&self) -> Result<u64> {
+        self.cohorts()
+            .iter()
+            .try_fold(0_u64, |points, c| points.try_add(c.rarity_points_staked))
+    }
+
+    /// per gem tenure: newly staked gems start a cohort of their own (when there's no room left,
+    ///   the 2 oldest are merged, keeping the older start)
+    /// otherwise: they join the latest cohort, which takes on the farmer's min staking period
+    ///   (restarted, unless compounding) but keeps its tenure
+    fn add_to_cohorts(
+        &mut self,
+        per_gem_tenure: bool,
+        now_ts: u64,
+        gems: u64,
+        vault_rarity_points: u64,
+    ) -> Result<()> {
+        let lockup = self.lockup;
+        let min_staking_ends_ts = self.min_staking_ends_ts;
+
+        if self.cohort_count == 0 || (per_gem_tenure && gems > 0) {
+            if self.cohort_count as usize == MAX_STAKE_COHORTS {
+                self.merge_oldest_cohorts()?;
+            }
+
+            let cohort = &mut self.cohorts[self.cohort_count as usize];
+            *cohort = StakeCohort {
+                begin_staking_ts: now_ts,
+                ..Default::default()
+            };
+            self.cohort_count.try_add_assign(1)?;
+        }
+
+        let last = self.cohort_count.try_sub(1)? as usize;
+        let cohort = &mut self.cohorts[last];
+        cohort.min_staking_ends_ts = min_staking_ends_ts;
+        cohort.gems_staked.try_add_assign(gems)?;
+        cohort
+            .vault_rarity_points
+            .try_add_assign(vault_rarity_points)?;
+        cohort.rarity_points_staked = lockup.effective_rarity_points(cohort.vault_rarity_points)?;
+
+        Ok(())
+    }
+
+    /// takes a single gem (or rather, all of the gems in its GDR) out of the stake
+    /// it's removed from the cohort it was staked with - that cohort's min staking period has to have passed
+    /// returns previous rarity points staked
+    pub fn begin_gem_cooldown(
+        &mut self,
+        now_ts: u64,
+        cooldown_period_sec: u64,
+        gem_mint: Pubkey,
+        gem_count: u64,
+        vault_rarity_points: u64,
+        deposited_at_ts: u64,
+    ) -> Result<u64> {
+        if self.state != FarmerState::Staked || gem_count == 0 {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+
+        // gems deposited before the farmer staked only started staking with them
+        let staked_at_ts = std::cmp::max(deposited_at_ts, self.begin_staking_ts);
+        let index = self
+            .cohorts()
+            .iter()
+            .rposition(|c| c.begin_staking_ts <= staked_at_ts)
+            .ok_or_else(|| error!(ErrorCode::InvalidParameter))?;
+
+        if now_ts < self.cohorts[index].min_staking_ends_ts {
+            return Err(error!(ErrorCode::MinStakingNotPassed));
+        }
+
+        self.add_pending_withdrawal(PendingWithdrawal {
+            gem_mint,
+            gem_count,
+            vault_rarity_points,
+            cooldown_ends_ts: now_ts.try_add(cooldown_period_sec)?,
+        })?;
+
+        // if the cohort doesn't hold all of them (eg a top-up of fungible gems), the rest come from older ones
+        let lockup = self.lockup;
+        let rarity_per_gem = vault_rarity_points.try_div(gem_count)?;
+        let mut gems_left = gem_count;
+        let mut rarity_points_left = vault_rarity_points;
+        for cohort in self.cohorts[..=index].iter_mut().rev() {
+            let gems = std::cmp::min(gems_left, cohort.gems_staked);
+            let rarity_points = match gems == gems_left {
+                true => rarity_points_left,
+                false => rarity_per_gem.try_mul(gems)?,
+            };
+            let rarity_points = std::cmp::min(rarity_points, cohort.vault_rarity_points);
+
+            cohort.gems_staked.try_sub_assign(gems)?;
+            cohort.vault_rarity_points.try_sub_assign(rarity_points)?;
+            cohort.rarity_points_staked =
+                lockup.effective_rarity_points(cohort.vault_rarity_points)?;
+
+            gems_left.try_sub_assign(gems)?;
+            rarity_points_left = rarity_points_left.saturating_sub(rarity_points);
+            if gems_left == 0 {
+                break;
+            }
+        }
+        if gems_left > 0 {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+
+        self.remove_empty_cohorts();
+
+        let previous_rarity_points_staked = self.rarity_points_staked;
+        self.gems_staked.try_sub_assign(gem_count)?;
+        self.rarity_points_staked = self.cohort_totals_staked()?;
+        if let Some(min_staking_ends_ts) =
+            self.cohorts().iter().map(|c| c.min_staking_ends_ts).max()
+        {
+            self.min_staking_ends_ts = min_staking_ends_ts;
+        }
+
+        Ok(previous_rarity_points_staked)
+    }
+
+    /// returns the number of gems that can now be withdrawn
+    pub fn end_gem_cooldown(&mut self, now_ts: u64, gem_mint: Pubkey) -> Result<u64> {
+        let pending = self
+            .pending_withdrawals
+            .iter_mut()
+            .find(|w| w.gem_count > 0 && w.gem_mint == gem_mint)
+            .ok_or_else(|| error!(ErrorCode::InvalidParameter))?;
+
+        if now_ts < pending.cooldown_ends_ts {
+            return Err(error!(ErrorCode::CooldownNotPassed));
+        }
+
+        let gem_count = pending.gem_count;
+        *pending = PendingWithdrawal::default();
+
+        Ok(gem_count)
+    }
+
+    pub fn has_pending_withdrawal(&self, gem_mint: Pubkey) -> bool {
+        self.pending_withdrawals
+            .iter()
+            .any(|w| w.gem_count > 0 && w.gem_mint == gem_mint)
+    }
+
+    fn add_pending_withdrawal(&mut self, withdrawal: PendingWithdrawal) -> Result<()> {
+        if self.has_pending_withdrawal(withdrawal.gem_mint) {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+
+        let slot = self
+            .pending_withdrawals
+            .iter_mut()
+            .find(|w| w.gem_count == 0)
+            .ok_or_else(|| error!(ErrorCode::TooManyPendingWithdrawals))?;
+        *slot = withdrawal;
+
+        Ok(())
+    }
+
+    fn clear_pending_withdrawals(&mut self) {
+        self.pending_withdrawals = [PendingWithdrawal::default(); MAX_PENDING_WITHDRAWALS];
+    }
+
+    /// gems and rarity points (as counted by the vault) still in the vault, but no longer staked
+    fn pending_withdrawal_totals(&self) -> Result<(u64, u64)> {
+        self.pending_withdrawals
+            .iter()
+            .try_fold((0_u64, 0_u64), |(gems, points), w| {
+                Ok((
+                    gems.try_add(w.gem_count)?,
+                    points.try_add(w.vault_rarity_points)?,
+                ))
+            })
+    }
+
+    fn remove_empty_cohorts(&mut self) {
+        let mut kept = 0;
+        for i in 0..self.cohorts().len() {
+            if self.cohorts[i].gems_staked > 0 {
+                self.cohorts[kept] = self.cohorts[i];
+                kept += 1;
+            }
+        }
+        for cohort in self.cohorts[kept..].iter_mut() {
+            *cohort = StakeCohort::default();
+        }
+        self.cohort_count = kept as u64;
+    }
+
+    fn merge_oldest_cohorts(&mut self) -> Result<()> {
+        let second = self.cohorts[1];
+        let first = &mut self.cohorts[0];
+        first.min_staking_ends_ts =
+            std::cmp::max(first.min_staking_ends_ts, second.min_staking_ends_ts);
+        first.gems_staked.try_add_assign(second.gems_staked)?;
+        first
+            .vault_rarity_points
+            .try_add_assign(second.vault_rarity_points)?;
+        first.rarity_points_staked = self
+            .lockup
+            .effective_rarity_points(first.vault_rarity_points)?;
+
+        self.cohorts.copy_within(2.., 1);
+        self.cohort_count.try_sub_assign(1)?;
+        self.cohorts[self.cohort_count as usize] = StakeCohort::default();
+
+        Ok(())
+    }
+
     pub fn end_staking_begin_cooldown(
         &mut self,
         now_ts: u64,
@@ -83,6 +501,7 @@ impl Farmer {
         let rarity_points_unstaked = self.rarity_points_staked;
         self.gems_staked = 0; //no rewards will accrue during cooldown period
         self.rarity_points_staked = 0;
+        self.clear_cohorts();
         self.cooldown_ends_ts = now_ts.try_add(cooldown_period_sec)?;
 
         // msg!(
@@ -105,6 +524,9 @@ impl Farmer {
         self.rarity_points_staked = 0;
         self.min_staking_ends_ts = 0;
         self.cooldown_ends_ts = 0;
+        self.lockup = LockupTier::default();
+        self.begin_staking_ts = 0;
+        self.clear_pending_withdrawals(); //the vault unlocks, so they can be withdrawn normally
 
         // msg!(
         //     "gems now unstaked and available for withdrawal for {}",
@@ -124,28 +546,50 @@ impl Farmer {
 
 // --------------------------------------- farmer reward
 
-#[proc_macros::assert_size(216)]
+#[proc_macros::assert_size(160)]
 #[repr(C)]
-#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize)]
 pub struct FarmerReward {
+    /// id of the farm reward this record belongs to (see FarmReward::reward_id)
+    pub reward_id: u64,
+
     /// total, not per rarity point. Never goes down (ie is cumulative)
     pub paid_out_reward: u64,
 
     /// total, not per rarity point. Never goes down (ie is cumulative)
     pub accrued_reward: u64,
 
+    /// total, not per rarity point, lost to early unstake penalties. Never goes down (ie is cumulative)
+    pub forfeited_reward: u64,
+
     /// only one of these two (fixed and variable) will actually be used, per reward
     pub variable_rate: FarmerVariableRateReward,
 
     pub fixed_rate: FarmerFixedRateReward,
 
-    /// reserved for future updates, has to be /8
-    _reserved: [u8; 32],
+    /// only used if the reward vests, claimed amounts end up here rather than in farmer's wallet
+    pub vesting: FarmerVesting,
 }
 
 impl FarmerReward {
+    /// wipes a record left over from a retired reward, so that the slot can be reused
+    pub fn reset(&mut self, reward_id: u64) {
+        *self = Self::default();
+        self.reward_id = reward_id;
+    }
+
     pub fn outstanding_reward(&self) -> Result<u64> {
-        self.accrued_reward.try_sub(self.paid_out_reward)
+        self.accrued_reward
+            .try_sub(self.paid_out_reward)?
+            .try_sub(self.forfeited_reward)
+    }
+
+    pub fn forfeit_reward(&mut self, amount: u64) -> Result<()> {
+        if amount > self.outstanding_reward()? {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+
+        self.forfeited_reward.try_add_assign(amount)
     }
 
     pub fn claim_reward(&mut self, pot_balance: u64) -> Result<u64> {
@@ -179,11 +623,111 @@ impl FarmerReward {
     }
 }
 
+// --------------------------------------- vesting
+
+#[proc_macros::assert_size(40)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct FarmerVesting {
+    /// unlocks linearly between last_unlock_ts and end_ts
+    pub locked_amount: u64,
+
+    /// ready to be withdrawn
+    pub unlocked_amount: u64,
+
+    /// only moves forward once the cliff has passed
+    pub last_unlock_ts: u64,
+
+    pub cliff_end_ts: u64,
+
+    pub end_ts: u64,
+}
+
+impl FarmerVesting {
+    pub fn is_pending(&self) -> bool {
+        self.locked_amount > 0 || self.unlocked_amount > 0
+    }
+
+    /// moves whatever has vested since the last unlock from locked to unlocked
+    /// what's left keeps unlocking at the same rate, since it's spread over the remaining time
+    pub fn unlock(&mut self, now_ts: u64) -> Result<()> {
+        if self.locked_amount == 0 || now_ts < self.cliff_end_ts {
+            return Ok(());
+        }
+
+        let newly_unlocked = if now_ts >= self.end_ts {
+            self.locked_amount
+        } else {
+            (self.locked_amount as u128)
+                .try_mul(now_ts.try_sub(self.last_unlock_ts)? as u128)?
+                .try_div(self.end_ts.try_sub(self.last_unlock_ts)? as u128)?
+                .try_cast()?
+        };
+
+        self.locked_amount.try_sub_assign(newly_unlocked)?;
+        self.unlocked_amount.try_add_assign(newly_unlocked)?;
+        self.last_unlock_ts = now_ts;
+
+        Ok(())
+    }
+
+    /// a top up is blended into what's still locked, weighted by amount,
+    /// so the cliff and the end only move as much as the new amount justifies
+    /// (a cliff that's already passed stays passed)
+    pub fn begin_vesting(
+        &mut self,
+        now_ts: u64,
+        amount: u64,
+        config: &VestingConfig,
+    ) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+
+        self.unlock(now_ts)?;
+
+        let cliff_end_ts = now_ts.try_add(config.cliff_sec)?;
+        let end_ts = now_ts.try_add(config.duration_sec)?;
+
+        if self.locked_amount == 0 {
+            self.last_unlock_ts = now_ts;
+            self.cliff_end_ts = cliff_end_ts;
+            self.end_ts = end_ts;
+        } else {
+            if now_ts < self.cliff_end_ts {
+                self.cliff_end_ts =
+                    blend_ts(self.cliff_end_ts, self.locked_amount, cliff_end_ts, amount)?;
+            }
+            self.end_ts = blend_ts(self.end_ts, self.locked_amount, end_ts, amount)?;
+        }
+
+        self.locked_amount.try_add_assign(amount)
+    }
+
+    pub fn withdraw(&mut self, now_ts: u64, pot_balance: u64) -> Result<u64> {
+        self.unlock(now_ts)?;
+
+        let to_withdraw = std::cmp::min(self.unlocked_amount, pot_balance);
+        self.unlocked_amount.try_sub_assign(to_withdraw)?;
+
+        Ok(to_withdraw)
+    }
+}
+
+/// amount-weighted average of two timestamps
+fn blend_ts(ts_a: u64, amount_a: u64, ts_b: u64, amount_b: u64) -> Result<u64> {
+    (ts_a as u128)
+        .try_mul(amount_a as u128)?
+        .try_add((ts_b as u128).try_mul(amount_b as u128)?)?
+        .try_div((amount_a as u128).try_add(amount_b as u128)?)?
+        .try_cast()
+}
+
 // --------------------------------------- variable rate reward
 
 #[proc_macros::assert_size(32)]
 #[repr(C)]
-#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize)]
 pub struct FarmerVariableRateReward {
     /// used to keep track of how much of the variable reward has been updated for this farmer
     /// (read more in variable rate config)
@@ -195,12 +739,13 @@ pub struct FarmerVariableRateReward {
 
 // --------------------------------------- fixed rate reward
 
-#[proc_macros::assert_size(136)]
+#[proc_macros::assert_size(56)]
 #[repr(C)]
 #[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize)]
 pub struct FarmerFixedRateReward {
     /// this is the time the farmer staked
     /// can be WAY BACK in the past, if we've rolled them multiple times
+    /// (!) only marks them as enrolled - tenure is counted per cohort (see StakeCohort)
     pub begin_staking_ts: u64,
 
     /// this is the time the latest reward schedule they subscribed to begins
@@ -215,7 +760,9 @@ pub struct FarmerFixedRateReward {
     /// that then "reserves" a certain amount of funds so that they can't be promised to other farmers
     /// only if the farmer unstakes, will the reserve be void, and the funds become available again
     /// for either funding other farmers or withdrawing (when the reward is cancelled)
-    pub promised_schedule: FixedRateSchedule,
+    /// (!) only the index is kept here, the schedule itself is in the FixedRateTiers account
+    /// (see RewardSchedules), and only while there's anything left to pay out on it
+    pub promised_schedule: u64,
 
     pub promised_duration: u64,
 
@@ -225,8 +772,8 @@ pub struct FarmerFixedRateReward {
 
 impl FarmerFixedRateReward {
     /// accrued to rolled stakers, whose begin_staking_ts < begin_schedule_ts
-    pub fn loyal_staker_bonus_time(&self) -> Result<u64> {
-        self.begin_schedule_ts.try_sub(self.begin_staking_ts)
+    pub fn loyal_staker_bonus_time(&self, begin_staking_ts: u64) -> Result<u64> {
+        self.begin_schedule_ts.try_sub(begin_staking_ts)
     }
 
     pub fn end_schedule_ts(&self) -> Result<u64> {
@@ -238,38 +785,69 @@ impl FarmerFixedRateReward {
         self.begin_staking_ts > 0 && self.begin_schedule_ts > 0
     }
 
+    /// enrolled w/ nothing to promise (eg before the reward was funded) doesn't take up a schedule
+    pub fn holds_schedule(&self) -> bool {
+        self.is_staked() && self.promised_duration > 0
+    }
+
     pub fn is_time_to_graduate(&self, now_ts: u64) -> Result<bool> {
         Ok(now_ts >= self.end_schedule_ts()?)
     }
 
+    /// never before the schedule begins, which can be in the future for scheduled rewards
     pub fn reward_upper_bound(&self, now_ts: u64) -> Result<u64> {
-        Ok(std::cmp::min(now_ts, self.end_schedule_ts()?))
+        Ok(std::cmp::max(
+            self.begin_schedule_ts,
+            std::cmp::min(now_ts, self.end_schedule_ts()?),
+        ))
     }
 
-    pub fn time_from_staking_to_update(&self) -> Result<u64> {
-        self.last_updated_ts.try_sub(self.begin_staking_ts)
+    pub fn time_from_staking_to_update(&self, begin_staking_ts: u64) -> Result<u64> {
+        self.last_updated_ts.try_sub(begin_staking_ts)
     }
 
     /// (!) intentionally uses begin_staking_ts for both start_from and end_at
     /// in doing so we increase both start_from and end_at by exactly loyal_staker_bonus_time
-    pub fn voided_reward(&self, rarity_points: u64) -> Result<u64> {
-        let start_from = self.time_from_staking_to_update()?;
-        let end_at = self.end_schedule_ts()?.try_sub(self.begin_staking_ts)?;
-
-        self.promised_schedule
-            .reward_amount(start_from, end_at, rarity_points)
+    pub fn voided_reward(
+        &self,
+        cohorts: &[StakeCohort],
+        schedule: &FixedRateSchedule,
+    ) -> Result<u64> {
+        let end_ts = self.end_schedule_ts()?;
+        self.reward_for_cohorts(cohorts, end_ts, schedule)
     }
 
     /// (!) intentionally uses begin_staking_ts for both start_from and end_at
     /// in doing so we increase both start_from and end_at by exactly loyal_staker_bonus_time
-    pub fn newly_accrued_reward(&self, now_ts: u64, rarity_points: u64) -> Result<u64> {
-        let start_from = self.time_from_staking_to_update()?;
-        let end_at = self
-            .reward_upper_bound(now_ts)?
-            .try_sub(self.begin_staking_ts)?;
+    pub fn newly_accrued_reward(
+        &self,
+        now_ts: u64,
+        cohorts: &[StakeCohort],
+        schedule: &FixedRateSchedule,
+    ) -> Result<u64> {
+        let end_ts = self.reward_upper_bound(now_ts)?;
+        self.reward_for_cohorts(cohorts, end_ts, schedule)
+    }
+
+    /// from the last update up to end_ts, each cohort at its own tenure
+    /// (!) nothing is due to a farmer who isn't enrolled, eg one who staked before the reward
+    ///   was added, or who opted out of re-enrolling
+    fn reward_for_cohorts(
+        &self,
+        cohorts: &[StakeCohort],
+        end_ts: u64,
+        schedule: &FixedRateSchedule,
+    ) -> Result<u64> {
+        if cohorts.is_empty() || !self.is_staked() {
+            return Ok(0);
+        }
 
-        self.promised_schedule
-            .reward_amount(start_from, end_at, rarity_points)
+        cohorts.iter().try_fold(0_u64, |total, c| {
+            let start_from = self.time_from_staking_to_update(c.begin_staking_ts)?;
+            let end_at = end_ts.try_sub(c.begin_staking_ts)?;
+
+            total.try_add(schedule.reward_amount(start_from, end_at, c.rarity_points_staked)?)
+        })
     }
 }
 
@@ -278,45 +856,60 @@ mod tests {
     use super::*;
     use crate::state::TierConfig;
 
+    fn promised_schedule() -> FixedRateSchedule {
+        FixedRateSchedule::from_tiers(
+            3,
+            &[
+                TierConfig {
+                    reward_rate: 5,
+                    required_tenure: 55,
+                },
+                TierConfig {
+                    reward_rate: 7,
+                    required_tenure: 65,
+                },
+                TierConfig {
+                    reward_rate: 11,
+                    required_tenure: 75,
+                },
+            ],
+            1,
+        )
+    }
+
     impl FarmerFixedRateReward {
         pub fn new() -> Self {
             Self {
                 begin_staking_ts: 100,
                 begin_schedule_ts: 150,
                 last_updated_ts: 155,
-                promised_schedule: FixedRateSchedule {
-                    base_rate: 3,
-                    tier1: Some(TierConfig {
-                        reward_rate: 5,
-                        required_tenure: 55,
-                    }),
-                    tier2: Some(TierConfig {
-                        reward_rate: 7,
-                        required_tenure: 65,
-                    }),
-                    tier3: Some(TierConfig {
-                        reward_rate: 11,
-                        required_tenure: 75,
-                    }),
-                    denominator: 1,
-                },
+                promised_schedule: 0,
                 promised_duration: 60,
                 _reserved: [0; 16],
             }
         }
     }
 
+    impl Farmer {
+        pub fn zeroed() -> Self {
+            let data = vec![0u8; 8 + std::mem::size_of::<Farmer>()];
+            Farmer::try_deserialize_unchecked(&mut &data[..]).unwrap()
+        }
+    }
+
     impl FarmerReward {
         pub fn new() -> Self {
             Self {
+                reward_id: 1,
                 paid_out_reward: 0,
                 accrued_reward: 123,
+                forfeited_reward: 0,
                 variable_rate: FarmerVariableRateReward {
                     last_recorded_accrued_reward_per_rarity_point: Number128::from(10u64),
                     _reserved: [0; 16],
                 },
                 fixed_rate: FarmerFixedRateReward::new(),
-                _reserved: [0; 32],
+                vesting: FarmerVesting::default(),
             }
         }
     }
@@ -325,26 +918,172 @@ mod tests {
     fn test_farmer_fixed_rate_reward() {
         let r = FarmerFixedRateReward::new();
 
-        assert_eq!(50, r.loyal_staker_bonus_time().unwrap());
+        let cohorts = [StakeCohort {
+            begin_staking_ts: 100,
+            rarity_points_staked: 10,
+            ..Default::default()
+        }];
+
+        assert_eq!(50, r.loyal_staker_bonus_time(100).unwrap());
         assert_eq!(210, r.end_schedule_ts().unwrap());
         assert_eq!(true, r.is_time_to_graduate(210).unwrap());
         assert_eq!(210, r.reward_upper_bound(250).unwrap());
-        assert_eq!(55, r.time_from_staking_to_update().unwrap());
+        assert_eq!(55, r.time_from_staking_to_update(100).unwrap());
 
         // last update - staking = 55
         // ub - staking = 110
         // reward accrues for a total of 55s, with 50s bonus and 5s coming from current staking period
-        assert_eq!((50 + 70 + 11 * 35) * 10, r.voided_reward(10).unwrap());
+        let schedule = promised_schedule();
+        assert_eq!(
+            (50 + 70 + 11 * 35) * 10,
+            r.voided_reward(&cohorts, &schedule).unwrap()
+        );
 
         // last update - staking = 55
         // now - staking = 85
         // reward accrues for a total of 30s, with 50s bonus and 5s coming from current staking period
         assert_eq!(
             (50 + 70 + 110) * 10,
-            r.newly_accrued_reward(185, 10).unwrap()
+            r.newly_accrued_reward(185, &cohorts, &schedule).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_farmer_fixed_rate_reward_per_cohort() {
+        let r = FarmerFixedRateReward::new();
+
+        // same points, but the 2nd cohort only joined at the last update, so it's still on the base rate
+        let cohorts = [
+            StakeCohort {
+                begin_staking_ts: 100,
+                rarity_points_staked: 10,
+                ..Default::default()
+            },
+            StakeCohort {
+                begin_staking_ts: 155,
+                rarity_points_staked: 10,
+                ..Default::default()
+            },
+        ];
+
+        assert_eq!(
+            (50 + 70 + 110) * 10 + 3 * 30 * 10,
+            r.newly_accrued_reward(185, &cohorts, &promised_schedule())
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_farmer_fixed_rate_reward_not_enrolled() {
+        let r = FarmerFixedRateReward::default();
+
+        let cohorts = [StakeCohort {
+            begin_staking_ts: 100,
+            rarity_points_staked: 10,
+            ..Default::default()
+        }];
+
+        let schedule = promised_schedule();
+        assert_eq!(0, r.newly_accrued_reward(185, &cohorts, &schedule).unwrap());
+        assert_eq!(0, r.voided_reward(&cohorts, &schedule).unwrap());
+        assert!(!r.holds_schedule());
+    }
+
+    #[test]
+    fn test_stake_cohorts() {
+        let mut farmer = Farmer::zeroed();
+        let lockup = LockupTier::no_lockup();
+
+        farmer.begin_staking(10, lockup, true, 100, 2, 20).unwrap();
+        farmer.begin_staking(10, lockup, true, 150, 3, 35).unwrap();
+        assert_eq!(2, farmer.cohorts().len());
+        assert_eq!(150, farmer.cohorts()[1].begin_staking_ts);
+        assert_eq!(15, farmer.cohorts()[1].vault_rarity_points);
+        assert_eq!(35, farmer.rarity_points_staked);
+
+        // w/o per gem tenure, extra gems join the latest cohort
+        farmer.begin_staking(10, lockup, false, 200, 4, 40).unwrap();
+        assert_eq!(2, farmer.cohorts().len());
+        assert_eq!(150, farmer.cohorts()[1].begin_staking_ts);
+        assert_eq!(210, farmer.cohorts()[1].min_staking_ends_ts);
+
+        // once full, the oldest 2 get merged
+        for i in 0..MAX_STAKE_COHORTS as u64 {
+            farmer
+                .begin_staking(10, lockup, true, 300 + i, 5 + i, 45 + i)
+                .unwrap();
+        }
+        assert_eq!(MAX_STAKE_COHORTS, farmer.cohorts().len());
+        assert_eq!(100, farmer.cohorts()[0].begin_staking_ts);
+        assert_eq!(
+            (
+                5 + MAX_STAKE_COHORTS as u64 - 1,
+                45 + MAX_STAKE_COHORTS as u64 - 1
+            ),
+            farmer.cohort_totals().unwrap()
         );
     }
 
+    #[test]
+    fn test_compound_gems() {
+        let mut farmer = Farmer::zeroed();
+        let lockup = LockupTier {
+            lockup_period_sec: 100,
+            multiplier_bps: 20_000,
+        };
+
+        // only staked farmers can compound
+        assert!(farmer.compound_gems(false, 100, 10, 10).is_err());
+
+        farmer
+            .begin_staking(10, lockup, false, 100, 10, 10)
+            .unwrap();
+        assert_eq!((10, 20), farmer.compound_gems(false, 150, 15, 15).unwrap());
+
+        // staked with the same multiplier, w/o restarting the lockup
+        assert_eq!(15, farmer.gems_staked);
+        assert_eq!(30, farmer.rarity_points_staked);
+        assert_eq!(200, farmer.min_staking_ends_ts);
+        assert_eq!(1, farmer.cohorts().len());
+        assert_eq!(200, farmer.cohorts()[0].min_staking_ends_ts);
+
+        // w/ per gem tenure they get a cohort of their own, but can leave with the rest
+        farmer.compound_gems(true, 160, 20, 20).unwrap();
+        assert_eq!(2, farmer.cohorts().len());
+        assert_eq!(160, farmer.cohorts()[1].begin_staking_ts);
+        assert_eq!(200, farmer.cohorts()[1].min_staking_ends_ts);
+        assert_eq!(200, farmer.min_staking_ends_ts);
+    }
+
+    #[test]
+    fn test_gem_cooldown() {
+        let mut farmer = Farmer::zeroed();
+        let lockup = LockupTier::no_lockup();
+        let mint = Pubkey::new_unique();
+
+        farmer.begin_staking(10, lockup, true, 100, 2, 20).unwrap();
+        farmer.begin_staking(10, lockup, true, 150, 3, 35).unwrap();
+
+        // the gem deposited at 150 is in the 2nd cohort, which is still in its min staking period
+        assert!(farmer.begin_gem_cooldown(155, 5, mint, 1, 15, 150).is_err());
+
+        assert_eq!(
+            35,
+            farmer.begin_gem_cooldown(160, 5, mint, 1, 15, 150).unwrap()
+        );
+        assert_eq!(1, farmer.cohorts().len());
+        assert_eq!(2, farmer.gems_staked);
+        assert_eq!(20, farmer.rarity_points_staked);
+
+        // still in the vault, so doesn't count as newly staked
+        farmer.begin_staking(10, lockup, true, 170, 3, 35).unwrap();
+        assert_eq!(1, farmer.cohorts().len());
+
+        assert!(farmer.end_gem_cooldown(164, mint).is_err());
+        assert_eq!(1, farmer.end_gem_cooldown(165, mint).unwrap());
+        assert!(!farmer.has_pending_withdrawal(mint));
+    }
+
     #[test]
     fn test_farmer_reward_update_variable() {
         let mut r = FarmerReward::new();
@@ -378,4 +1117,73 @@ mod tests {
         r.claim_reward(100).unwrap();
         assert_eq!(23, r.outstanding_reward().unwrap());
     }
+
+    #[test]
+    fn test_farmer_reward_reset() {
+        let mut r = FarmerReward::new();
+
+        r.reset(2);
+        assert_eq!(2, r.reward_id);
+        assert_eq!(0, r.outstanding_reward().unwrap());
+        assert_eq!(
+            Number128::ZERO,
+            r.variable_rate
+                .last_recorded_accrued_reward_per_rarity_point
+        );
+        assert!(!r.fixed_rate.is_staked());
+    }
+
+    #[test]
+    fn test_farmer_vesting() {
+        let config = VestingConfig {
+            cliff_sec: 20,
+            duration_sec: 100,
+        };
+        let mut v = FarmerVesting::default();
+        v.begin_vesting(100, 1000, &config).unwrap();
+
+        // nothing before the cliff
+        assert_eq!(0, v.withdraw(119, 9999).unwrap());
+
+        // at the cliff, everything since the start unlocks at once
+        assert_eq!(200, v.withdraw(120, 9999).unwrap());
+
+        // then linearly, capped at the pot balance
+        assert_eq!(100, v.withdraw(150, 100).unwrap());
+        assert_eq!(200, v.unlocked_amount);
+        assert_eq!(500, v.locked_amount);
+
+        // everything after the end
+        assert_eq!(700, v.withdraw(9999, 9999).unwrap());
+        assert!(!v.is_pending());
+    }
+
+    #[test]
+    fn test_farmer_vesting_top_up() {
+        let config = VestingConfig {
+            cliff_sec: 20,
+            duration_sec: 100,
+        };
+        let mut v = FarmerVesting::default();
+        v.begin_vesting(100, 1000, &config).unwrap();
+
+        // topping up before the cliff pushes it out, but only by the weighted amount
+        v.begin_vesting(110, 1000, &config).unwrap();
+        assert_eq!(125, v.cliff_end_ts);
+        assert_eq!(205, v.end_ts);
+
+        let mut v = FarmerVesting::default();
+        v.begin_vesting(100, 1000, &config).unwrap();
+        assert_eq!(500, v.withdraw(150, 9999).unwrap());
+
+        // topping up after the cliff doesn't bring it back
+        v.begin_vesting(150, 1000, &config).unwrap();
+        assert_eq!(120, v.cliff_end_ts);
+        assert_eq!(233, v.end_ts); //(500 * 200 + 1000 * 250) / 1500
+        assert_eq!(18, v.withdraw(151, 9999).unwrap());
+
+        // everything after the later end
+        assert_eq!(1482, v.withdraw(233, 9999).unwrap());
+        assert!(!v.is_pending());
+    }
 }
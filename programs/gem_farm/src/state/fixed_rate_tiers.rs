@@ -0,0 +1,201 @@
+use anchor_lang::prelude::*;
+use gem_common::{errors::ErrorCode, *};
+
+use crate::state::*;
+
+/// max number of different schedules farmers of a single reward can be enrolled in at once
+/// (a new one is only taken up when the reward is funded w/ different rates while farmers are enrolled)
+pub const MAX_FIXED_RATE_SCHEDULES: usize = 4;
+
+/// a schedule farmers have been promised, along w/ how many of them are still holding on to it
+#[proc_macros::assert_size(160)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct PromisedSchedule {
+    pub schedule: FixedRateSchedule,
+
+    /// free to be reused once back at 0
+    pub enrolled_farmers: u64,
+}
+
+/// the schedules of a single fixed rate reward, matched to it by reward_id
+#[proc_macros::assert_size(656)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct RewardSchedules {
+    pub reward_id: u64,
+
+    /// the one farmers enrolling now are promised, moved whenever the reward is funded
+    pub current: u64,
+
+    pub schedules: [PromisedSchedule; MAX_FIXED_RATE_SCHEDULES],
+}
+
+impl RewardSchedules {
+    /// an entry w/ the same rates if there is one, else a free one
+    /// (!) the current one is only up for grabs if it's being replaced
+    fn position_for(&self, schedule: &FixedRateSchedule, replace_current: bool) -> Result<usize> {
+        let is_free = |i: &usize| {
+            self.schedules[*i].enrolled_farmers == 0
+                && (replace_current || *i as u64 != self.current)
+        };
+
+        (0..MAX_FIXED_RATE_SCHEDULES)
+            .find(|i| self.schedules[*i].schedule == *schedule)
+            .or_else(|| Some(self.current as usize).filter(is_free))
+            .or_else(|| (0..MAX_FIXED_RATE_SCHEDULES).find(is_free))
+            .ok_or_else(|| error!(ErrorCode::TooManyFixedRateSchedules))
+    }
+
+    fn set_current(&mut self, schedule: FixedRateSchedule) -> Result<()> {
+        let index = self.position_for(&schedule, true)?;
+        self.schedules[index].schedule = schedule;
+        self.current = index as u64;
+        Ok(())
+    }
+
+    pub fn schedule(&self, index: u64) -> Result<&FixedRateSchedule> {
+        self.schedules
+            .get(index as usize)
+            .map(|s| &s.schedule)
+            .ok_or_else(|| error!(ErrorCode::InvalidParameter))
+    }
+
+    /// returns where the farmer's promise is kept, along w/ the promise itself
+    pub fn enroll(&mut self) -> Result<(u64, FixedRateSchedule)> {
+        let entry = &mut self.schedules[self.current as usize];
+        entry.enrolled_farmers.try_add_assign(1)?;
+        Ok((self.current, entry.schedule))
+    }
+
+    /// for farmers carried over w/ a promise of their own, rather than the current schedule
+    pub fn enroll_in(&mut self, schedule: FixedRateSchedule) -> Result<u64> {
+        let schedule = normalized(&schedule);
+        let index = self.position_for(&schedule, false)?;
+        let entry = &mut self.schedules[index];
+        entry.schedule = schedule;
+        entry.enrolled_farmers.try_add_assign(1)?;
+        Ok(index as u64)
+    }
+
+    pub fn graduate(&mut self, index: u64) -> Result<()> {
+        self.schedules
+            .get_mut(index as usize)
+            .ok_or_else(|| error!(ErrorCode::InvalidParameter))?
+            .enrolled_farmers
+            .try_sub_assign(1)
+    }
+}
+
+/// anything past tier_count is left out, so that the same rates always compare equal
+fn normalized(schedule: &FixedRateSchedule) -> FixedRateSchedule {
+    FixedRateSchedule::from_tiers(schedule.base_rate, schedule.tiers(), schedule.denominator)
+}
+
+/// schedules of the farm's fixed rate rewards, one entry per reward slot
+/// lives at [b"fixed_rate_tiers", farm], created the first time a fixed rate reward is funded
+/// (!) farmers only keep track of which schedule they were promised (see FarmerFixedRateReward),
+/// so this is needed by any ix that updates a farmer enrolled in a fixed rate reward
+#[proc_macros::assert_size(2688)]
+#[repr(C)]
+#[account]
+#[derive(Debug, Default)]
+pub struct FixedRateTiers {
+    pub farm: Pubkey,
+
+    pub rewards: [RewardSchedules; MAX_REWARDS],
+
+    /// reserved for future updates, has to be /8
+    _reserved: [u8; 32],
+}
+
+impl FixedRateTiers {
+    /// (!) expects a valid schedule (see FixedRateSchedule::verify_schedule_invariants)
+    pub fn set_schedule(
+        &mut self,
+        farm: Pubkey,
+        index: usize,
+        reward_id: u64,
+        schedule: &FixedRateSchedule,
+    ) -> Result<()> {
+        self.farm = farm;
+
+        // left over from a retired reward
+        let entry = &mut self.rewards[index];
+        if entry.reward_id != reward_id {
+            *entry = RewardSchedules {
+                reward_id,
+                ..Default::default()
+            };
+        }
+
+        entry.set_current(normalized(schedule))
+    }
+
+    pub fn schedules_for(&mut self, reward_id: u64) -> Option<&mut RewardSchedules> {
+        self.rewards
+            .iter_mut()
+            .find(|r| r.reward_id != 0 && r.reward_id == reward_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedules_reused_once_free() {
+        let mut tiers = FixedRateTiers::default();
+        let farm = Pubkey::new_unique();
+        let schedule = |base_rate| FixedRateSchedule::new_base(base_rate, 1);
+
+        tiers.set_schedule(farm, 0, 1, &schedule(1)).unwrap();
+        let schedules = tiers.schedules_for(1).unwrap();
+        assert_eq!(schedules.enroll().unwrap(), (0, schedule(1)));
+
+        // the farmer keeps their promise, new rates go elsewhere
+        tiers.set_schedule(farm, 0, 1, &schedule(2)).unwrap();
+        let schedules = tiers.schedules_for(1).unwrap();
+        assert_eq!(schedules.current, 1);
+        assert_eq!(*schedules.schedule(0).unwrap(), schedule(1));
+
+        // nobody's enrolled in the 2nd yet, so it's simply replaced
+        tiers.set_schedule(farm, 0, 1, &schedule(3)).unwrap();
+        let schedules = tiers.schedules_for(1).unwrap();
+        assert_eq!(schedules.current, 1);
+        assert_eq!(*schedules.schedule(1).unwrap(), schedule(3));
+
+        // going back to the 1st rates picks up the entry farmers are already on
+        tiers.set_schedule(farm, 0, 1, &schedule(1)).unwrap();
+        let schedules = tiers.schedules_for(1).unwrap();
+        assert_eq!(schedules.current, 0);
+
+        // a promise of their own doesn't take the current entry
+        schedules.graduate(0).unwrap();
+        assert_eq!(schedules.enroll_in(schedule(4)).unwrap(), 1);
+        assert!(schedules.graduate(2).is_err());
+    }
+
+    #[test]
+    fn test_too_many_schedules() {
+        let mut tiers = FixedRateTiers::default();
+        let farm = Pubkey::new_unique();
+
+        for base_rate in 1..=MAX_FIXED_RATE_SCHEDULES as u64 {
+            tiers
+                .set_schedule(farm, 0, 1, &FixedRateSchedule::new_base(base_rate, 1))
+                .unwrap();
+            tiers.schedules_for(1).unwrap().enroll().unwrap();
+        }
+
+        assert!(tiers
+            .set_schedule(farm, 0, 1, &FixedRateSchedule::new_base(9, 1))
+            .is_err());
+
+        // a retired reward's schedules don't count
+        tiers
+            .set_schedule(farm, 0, 2, &FixedRateSchedule::new_base(9, 1))
+            .unwrap();
+        assert!(tiers.schedules_for(1).is_none());
+    }
+}
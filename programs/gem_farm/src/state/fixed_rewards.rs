@@ -3,19 +3,12 @@ use gem_common::{errors::ErrorCode, *};
 
 use crate::state::*;
 
-#[proc_macros::assert_size(4)]
-#[repr(C)]
-#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
-pub enum FixedRateRewardTier {
-    Base,
-    Tier1,
-    Tier2,
-    Tier3,
-}
+/// max number of tiers a fixed rate schedule can have on top of the base rate
+pub const MAX_FIXED_RATE_TIERS: usize = 8;
 
 #[proc_macros::assert_size(16)]
 #[repr(C)]
-#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize, PartialEq)]
 pub struct TierConfig {
     /// tokens/denominator/rarity point / sec
     pub reward_rate: u64,
@@ -24,18 +17,17 @@ pub struct TierConfig {
     pub required_tenure: u64,
 }
 
-#[proc_macros::assert_size(88)]
+#[proc_macros::assert_size(152)]
 #[repr(C)]
-#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
 pub struct FixedRateSchedule {
     /// tokens/denominator / sec
     pub base_rate: u64,
 
-    pub tier1: Option<TierConfig>, //16 + 8 overhead
-
-    pub tier2: Option<TierConfig>,
+    /// only the first tier_count are used, sorted by required_tenure
+    pub tiers: [TierConfig; MAX_FIXED_RATE_TIERS],
 
-    pub tier3: Option<TierConfig>,
+    pub tier_count: u64,
 
     /// needed to slow down the payout schedule (else min would be 1 token/rarity point/s or 86k/rarity point/day
     /// only used in fixed rate - in variable overall duration serves as sufficient speed regulator  
@@ -47,15 +39,14 @@ impl Default for FixedRateSchedule {
     fn default() -> Self {
         Self {
             base_rate: 0,
-            tier1: None,
-            tier2: None,
-            tier3: None,
+            tiers: [TierConfig::default(); MAX_FIXED_RATE_TIERS],
+            tier_count: 0,
             denominator: 1,
         }
     }
 }
 
-#[proc_macros::assert_size(104)]
+#[proc_macros::assert_size(184)]
 #[repr(C)]
 #[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
 pub struct FixedRateConfig {
@@ -69,6 +60,10 @@ pub struct FixedRateConfig {
     /// set this carefully!
     /// every farmer enrolled will be "reserved" an amount to cover the schedule for this duration
     pub duration_sec: u64,
+
+    /// when it becomes active, if not right away (see TimeTracker::schedule_reward)
+    /// farmers who enroll before then are promised the full duration, beginning at start_ts
+    pub start_ts: Option<u64>,
 }
 
 /// a tenure which we can definitely apply the reward rate to
@@ -112,118 +107,72 @@ impl HeldTenure {
 }
 
 impl FixedRateSchedule {
-    /// rates themselves can be anything, no invariant
-    pub fn verify_schedule_invariants(&self) {
-        if let Some(t3) = self.tier3 {
-            // later tiers require earlier tiers to be present (no gaps)
-            assert!(self.tier2.is_some() && self.tier1.is_some());
-
-            // later tenures must be further into the future than earlier tenures
-            let t2_tenure = self.tier2.unwrap().required_tenure;
-            assert!(t3.required_tenure >= t2_tenure);
-
-            let t1_tenure = self.tier1.unwrap().required_tenure;
-            assert!(t2_tenure >= t1_tenure);
+    /// (!) expects at most MAX_FIXED_RATE_TIERS tiers
+    pub fn from_tiers(base_rate: u64, tiers: &[TierConfig], denominator: u64) -> Self {
+        let mut schedule = Self {
+            base_rate,
+            tier_count: tiers.len() as u64,
+            denominator,
+            ..Default::default()
         };
+        schedule.tiers[..tiers.len()].copy_from_slice(tiers);
+        schedule
+    }
 
-        if let Some(t2) = self.tier2 {
-            // later tiers require earlier tiers to be present (no gaps)
-            assert!(self.tier1.is_some());
+    /// rates themselves can be anything, no invariant
+    pub fn verify_schedule_invariants(&self) -> Result<()> {
+        if self.tier_count > MAX_FIXED_RATE_TIERS as u64 {
+            return Err(error!(ErrorCode::InvalidFixedRateSchedule));
+        }
 
-            // later tenures must be further into the future than earlier tenures
-            let t1_tenure = self.tier1.unwrap().required_tenure;
-            assert!(t2.required_tenure >= t1_tenure);
-        };
+        // later tenures must be further into the future than earlier tenures
+        if !self
+            .tiers()
+            .windows(2)
+            .all(|w| w[1].required_tenure >= w[0].required_tenure)
+        {
+            return Err(error!(ErrorCode::InvalidFixedRateSchedule));
+        }
 
         // denominator can't be 0
-        assert_ne!(self.denominator, 0);
-    }
-
-    pub fn extract_tenure_and_rate(&self, tier: &str) -> Option<(u64, u64)> {
-        match tier {
-            "t1" => {
-                if let Some(t) = self.tier1 {
-                    Some((t.required_tenure, t.reward_rate))
-                } else {
-                    None
-                }
-            }
-            "t2" => {
-                if let Some(t) = self.tier2 {
-                    Some((t.required_tenure, t.reward_rate))
-                } else {
-                    None
-                }
-            }
-            "t3" => {
-                if let Some(t) = self.tier3 {
-                    Some((t.required_tenure, t.reward_rate))
-                } else {
-                    None
-                }
-            }
-            _ => panic!("undefined tier"),
+        if self.denominator == 0 {
+            return Err(error!(ErrorCode::InvalidFixedRateSchedule));
         }
-    }
 
-    pub fn get_base_reward(&self, start: u64, end: u64) -> Result<u64> {
-        let duration = end.try_sub(start)?;
-        self.base_rate.try_mul(duration)
+        Ok(())
     }
 
-    /// extracts held tenure from a combination of
-    ///   1) actual start & end times, and
-    ///   2) appropriate lower/upper bounds
-    /// lower bound: required_tenure extracted from appropriate TierConfig
-    /// upper bound: for first iteration (last-most TierConfig) simply U64::MAX,
-    ///   later recursively updated with previous TierConfig's required_tenure
-    fn extract_held_tenure(
-        &self,
-        tier: &str,
-        start_from: u64,
-        end_at: u64,
-        max_end: &mut u64,
-    ) -> Option<HeldTenure> {
-        match self.extract_tenure_and_rate(tier) {
-            // "required tenure" serves as lower bound
-            // previous iteration's
-            Some((begin, rate)) => {
-                let ht = HeldTenure::new(rate, start_from, end_at, begin, *max_end);
-                *max_end = begin;
-                ht
-            }
-            _ => None,
-        }
+    pub fn tiers(&self) -> &[TierConfig] {
+        let count = std::cmp::min(self.tier_count as usize, MAX_FIXED_RATE_TIERS);
+        &self.tiers[..count]
     }
 
     /// calculates reward per rarity point, by
-    ///   1) recursively extracting tenures,
-    ///   2) calling get_reward() on each which isn't None
-    ///   3) calculating base rate
-    ///   4) folding base rate and non-None tenure rewards
+    ///   1) splitting the tenure into segments - base rate up to the 1st tier,
+    ///      then each tier up to the next one (the last one has no upper bound)
+    ///   2) calling get_reward() on each segment that was actually held
+    ///   3) adding them up
     fn reward_per_rarity_point(&self, start_from: u64, end_at: u64) -> Result<u64> {
-        let mut cap = u64::MAX;
-
-        // collect definitively held tenures for 3 periods - still missing base
-        let t3 = self.extract_held_tenure("t3", start_from, end_at, &mut cap);
-        let t2 = self.extract_held_tenure("t2", start_from, end_at, &mut cap);
-        let t1 = self.extract_held_tenure("t1", start_from, end_at, &mut cap);
-
-        // flatten the options and call get_reward() on those that are Some()
-        let mut iter = vec![t1, t2, t3]
-            .into_iter()
-            .flatten()
-            .map(|t| t.get_reward());
-
-        // Base case is either base_reward or first applicable tenure, depending
-        // on the start position relative to the first applicable tenure
-        let init = match start_from < cap {
-            // If iter is empty, cap is MAX.  So this can only panic if start_from is also MAX.
-            false => iter.next().unwrap(),
-            true => self.get_base_reward(start_from, std::cmp::min(cap, end_at)),
-        };
+        let tiers = self.tiers();
+        let mut reward = 0_u64;
 
-        iter.fold(init, |last, this| last?.try_add(this?))
+        let mut lower_bound = 0;
+        let mut rate = self.base_rate;
+
+        for i in 0..=tiers.len() {
+            let upper_bound = tiers.get(i).map_or(u64::MAX, |t| t.required_tenure);
+
+            if let Some(ht) = HeldTenure::new(rate, start_from, end_at, lower_bound, upper_bound) {
+                reward.try_add_assign(ht.get_reward()?)?;
+            }
+
+            if let Some(t) = tiers.get(i) {
+                lower_bound = t.required_tenure;
+                rate = t.reward_rate;
+            }
+        }
+
+        Ok(reward)
     }
 
     pub fn reward_amount(&self, start_from: u64, end_at: u64, rarity_points: u64) -> Result<u64> {
@@ -241,12 +190,17 @@ impl FixedRateSchedule {
     }
 }
 
-#[proc_macros::assert_size(128)]
+#[proc_macros::assert_size(64)]
 #[repr(C)]
 #[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
 pub struct FixedRateReward {
-    /// configured on funding
-    pub schedule: FixedRateSchedule,
+    /// configured on funding, same as FixedRateSchedule
+    /// (!) the schedule itself is kept in its own account (see FixedRateTiers)
+    pub base_rate: u64,
+
+    pub tier_count: u64,
+
+    pub denominator: u64,
 
     /// amount that has been promised to existing stakers and hence can't be withdrawn
     pub reserved_amount: u64,
@@ -255,7 +209,36 @@ pub struct FixedRateReward {
     _reserved: [u8; 32],
 }
 
+/// custom impl for the same reason as FixedRateSchedule
+impl Default for FixedRateReward {
+    fn default() -> Self {
+        Self {
+            base_rate: 0,
+            tier_count: 0,
+            denominator: 1,
+            reserved_amount: 0,
+            _reserved: [0; 32],
+        }
+    }
+}
+
 impl FixedRateReward {
+    /// the schedule the farmer was promised, if they're holding on to one
+    fn promised_schedule(
+        farmer_reward: &FarmerReward,
+        schedules: Option<&RewardSchedules>,
+    ) -> Result<Option<FixedRateSchedule>> {
+        if !farmer_reward.fixed_rate.holds_schedule() {
+            return Ok(None);
+        }
+
+        let schedules = schedules.ok_or_else(|| error!(ErrorCode::FixedRateTiersRequired))?;
+        Ok(Some(
+            *schedules.schedule(farmer_reward.fixed_rate.promised_schedule)?,
+        ))
+    }
+
+    /// (!) the schedule is stored separately, by the ix
     pub fn fund_reward(
         &mut self,
         now_ts: u64,
@@ -267,16 +250,18 @@ impl FixedRateReward {
             schedule,
             amount,
             duration_sec,
+            start_ts,
         } = new_config;
 
-        schedule.verify_schedule_invariants();
+        schedule.verify_schedule_invariants()?;
 
-        times.duration_sec = duration_sec;
-        times.reward_end_ts = now_ts.try_add(duration_sec)?;
+        times.schedule_reward(now_ts, start_ts, duration_sec)?;
 
         funds.total_funded.try_add_assign(amount)?;
 
-        self.schedule = schedule;
+        self.base_rate = schedule.base_rate;
+        self.tier_count = schedule.tier_count;
+        self.denominator = schedule.denominator;
 
         // msg!("recorded new funding of {}", amount);
         Ok(())
@@ -297,18 +282,26 @@ impl FixedRateReward {
         Ok(refund_amount)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn update_accrued_reward(
         &mut self,
         now_ts: u64,
         times: &mut TimeTracker,
         funds: &mut FundsTracker,
-        farmer_rarity_points_staked: u64,
+        farmer_cohorts: &[StakeCohort],
         farmer_reward: &mut FarmerReward,
         reenroll: bool,
+        mut schedules: Option<&mut RewardSchedules>,
     ) -> Result<()> {
-        let newly_accrued_reward = farmer_reward
-            .fixed_rate
-            .newly_accrued_reward(now_ts, farmer_rarity_points_staked)?;
+        let newly_accrued_reward =
+            match Self::promised_schedule(farmer_reward, schedules.as_deref())? {
+                Some(schedule) => farmer_reward.fixed_rate.newly_accrued_reward(
+                    now_ts,
+                    farmer_cohorts,
+                    &schedule,
+                )?,
+                None => 0,
+            };
 
         // update farm (move amount from reserved to accrued)
         funds
@@ -322,10 +315,9 @@ impl FixedRateReward {
         if farmer_reward.fixed_rate.is_staked()
             && farmer_reward.fixed_rate.is_time_to_graduate(now_ts)?
         {
-            let original_staking_start =
-                self.graduate_farmer(farmer_rarity_points_staked, farmer_reward)?;
+            self.graduate_farmer(farmer_cohorts, farmer_reward, schedules.as_deref_mut())?;
 
-            // if desired, we roll them forward with original staking time
+            // if desired, we roll them forward - cohorts keep their original staking time
             // why would it not be desired?
             //   one scenario is where there isn't sufficient funding to enroll them,
             //   which causes update ix to fail. So instead we can let them opt out of reenrolling
@@ -334,9 +326,9 @@ impl FixedRateReward {
                     now_ts,
                     times,
                     funds,
-                    farmer_rarity_points_staked,
+                    farmer_cohorts,
                     farmer_reward,
-                    Some(original_staking_start),
+                    schedules,
                 )?;
             }
         }
@@ -350,34 +342,51 @@ impl FixedRateReward {
         now_ts: u64,
         times: &mut TimeTracker,
         funds: &mut FundsTracker,
-        farmer_rarity_points_staked: u64,
+        farmer_cohorts: &[StakeCohort],
         farmer_reward: &mut FarmerReward,
-        original_staking_start: Option<u64>, //used when we roll a farmer forward, w/o them unstaking
+        schedules: Option<&mut RewardSchedules>,
     ) -> Result<()> {
         // calc time left
         // do NOT throw an error if 0 - A might hav ended but B not
-        // do NOT return OK(()) - this prevents us from passing down original_staking_start when next reward not ready
         let remaining_duration = times.remaining_duration(now_ts)?;
 
-        // calc any bonus due to previous staking
-        farmer_reward.fixed_rate.begin_staking_ts = original_staking_start.unwrap_or(now_ts);
-        farmer_reward.fixed_rate.begin_schedule_ts = now_ts;
-        let bonus_time = farmer_reward.fixed_rate.loyal_staker_bonus_time()?;
-
-        // calc how much we'd have to reserve for them
-        let reserve_amount = self.schedule.reward_amount(
-            bonus_time,
-            remaining_duration.try_add(bonus_time)?,
-            farmer_rarity_points_staked,
-        )?;
+        // (!) if the reward hasn't begun yet, their schedule only begins with it
+        farmer_reward.fixed_rate.begin_staking_ts = farmer_cohorts
+            .first()
+            .map_or(now_ts, |c| c.begin_staking_ts);
+        farmer_reward.fixed_rate.begin_schedule_ts = times.reward_lower_bound(now_ts)?;
+        farmer_reward.fixed_rate.last_updated_ts = farmer_reward.fixed_rate.begin_schedule_ts;
+        farmer_reward.fixed_rate.promised_duration = remaining_duration;
+
+        // nothing to promise (eg the reward hasn't been funded yet) - they're enrolled all the same,
+        // so that they're rolled into the reward once it is
+        if remaining_duration == 0 {
+            return Ok(());
+        }
+
+        let schedules = schedules.ok_or_else(|| error!(ErrorCode::FixedRateTiersRequired))?;
+        let schedule = *schedules.schedule(schedules.current)?;
+
+        // calc how much we'd have to reserve for them, incl. any bonus due to previous staking
+        let mut reserve_amount = 0;
+        for cohort in farmer_cohorts {
+            let bonus_time = farmer_reward
+                .fixed_rate
+                .loyal_staker_bonus_time(cohort.begin_staking_ts)?;
+
+            reserve_amount.try_add_assign(schedule.reward_amount(
+                bonus_time,
+                remaining_duration.try_add(bonus_time)?,
+                cohort.rarity_points_staked,
+            )?)?;
+        }
         if reserve_amount > funds.pending_amount()? {
             return Err(error!(ErrorCode::RewardUnderfunded));
         }
 
         // update farmer
-        farmer_reward.fixed_rate.last_updated_ts = now_ts;
-        farmer_reward.fixed_rate.promised_schedule = self.schedule;
-        farmer_reward.fixed_rate.promised_duration = remaining_duration;
+        let (index, _) = schedules.enroll()?;
+        farmer_reward.fixed_rate.promised_schedule = index;
 
         // update farm
         self.reserved_amount.try_add_assign(reserve_amount)?;
@@ -386,30 +395,61 @@ impl FixedRateReward {
         Ok(())
     }
 
+    /// called when some of the farmer's gems are unstaked, but not all of them (see partial_unstake)
+    /// the farmer stays enrolled, only what was reserved for the unstaked gems is released
+    pub fn release_unstaked_gems(
+        &mut self,
+        previous_cohorts: &[StakeCohort],
+        farmer_cohorts: &[StakeCohort],
+        farmer_reward: &mut FarmerReward,
+        schedules: Option<&RewardSchedules>,
+    ) -> Result<()> {
+        let schedule = match Self::promised_schedule(farmer_reward, schedules)? {
+            Some(schedule) => schedule,
+            None => return Ok(()),
+        };
+
+        let fixed_rate = &farmer_reward.fixed_rate;
+        let released_reward = fixed_rate
+            .voided_reward(previous_cohorts, &schedule)?
+            .try_sub(fixed_rate.voided_reward(farmer_cohorts, &schedule)?)?;
+
+        self.reserved_amount.try_sub_assign(released_reward)
+    }
+
     /// called in these sits:
     /// 1) by the staker themselves, when they unstake
     /// 2) by the staker themselves, when they stake extra gems
     /// 3) by the farm if is_time_to_graduate
-    /// returns original staking time
     pub fn graduate_farmer(
         &mut self,
-        farmer_rarity_points_staked: u64,
+        farmer_cohorts: &[StakeCohort],
         farmer_reward: &mut FarmerReward,
-    ) -> Result<u64> {
-        let original_begin_staking_ts = farmer_reward.fixed_rate.begin_staking_ts;
+        schedules: Option<&mut RewardSchedules>,
+    ) -> Result<()> {
+        // nothing was reserved for them if they never enrolled
+        if !farmer_reward.fixed_rate.is_staked() {
+            return Ok(());
+        }
 
-        // reduce reserved amount
-        let voided_reward = farmer_reward
-            .fixed_rate
-            .voided_reward(farmer_rarity_points_staked)?;
+        // reduce reserved amount, and let go of the schedule
+        if farmer_reward.fixed_rate.holds_schedule() {
+            let schedules = schedules.ok_or_else(|| error!(ErrorCode::FixedRateTiersRequired))?;
+            let index = farmer_reward.fixed_rate.promised_schedule;
 
-        self.reserved_amount.try_sub_assign(voided_reward)?;
+            let voided_reward = farmer_reward
+                .fixed_rate
+                .voided_reward(farmer_cohorts, schedules.schedule(index)?)?;
+
+            self.reserved_amount.try_sub_assign(voided_reward)?;
+            schedules.graduate(index)?;
+        }
 
         // zero out the data on the farmer
         farmer_reward.fixed_rate = FarmerFixedRateReward::default();
 
         // msg!("graduated farmer on {}", now_ts);
-        Ok(original_begin_staking_ts)
+        Ok(())
     }
 }
 
@@ -419,42 +459,35 @@ mod tests {
 
     impl FixedRateSchedule {
         pub fn new_base(base_rate: u64, denominator: u64) -> Self {
-            Self {
-                base_rate,
-                tier1: None,
-                tier2: None,
-                tier3: None,
-                denominator,
-            }
+            Self::from_tiers(base_rate, &[], denominator)
         }
         pub fn new_t1(reward_rate: u64, required_tenure: u64) -> Self {
             //30 + ...
-            Self {
-                base_rate: 3,
-                tier1: Some(TierConfig {
+            Self::from_tiers(
+                3,
+                &[TierConfig {
                     reward_rate,
                     required_tenure,
-                }),
-                tier2: None,
-                tier3: None,
-                denominator: 1,
-            }
+                }],
+                1,
+            )
         }
         pub fn new_t2(reward_rate: u64, required_tenure: u64) -> Self {
             //30 + 50 + ...
-            Self {
-                base_rate: 3,
-                tier1: Some(TierConfig {
-                    reward_rate: 5,
-                    required_tenure: 10,
-                }),
-                tier2: Some(TierConfig {
-                    reward_rate,
-                    required_tenure,
-                }),
-                tier3: None,
-                denominator: 1,
-            }
+            Self::from_tiers(
+                3,
+                &[
+                    TierConfig {
+                        reward_rate: 5,
+                        required_tenure: 10,
+                    },
+                    TierConfig {
+                        reward_rate,
+                        required_tenure,
+                    },
+                ],
+                1,
+            )
         }
         pub fn new_t3(
             reward_rate2: u64,
@@ -463,138 +496,212 @@ mod tests {
             required_tenure3: u64,
         ) -> Self {
             //30 + 50 + ... + ...
-            Self {
-                base_rate: 3,
-                tier1: Some(TierConfig {
-                    reward_rate: 5,
-                    required_tenure: 10,
-                }),
-                tier2: Some(TierConfig {
-                    reward_rate: reward_rate2,
-                    required_tenure: required_tenure2,
-                }),
-                tier3: Some(TierConfig {
-                    reward_rate: reward_rate3,
-                    required_tenure: required_tenure3,
-                }),
-                denominator: 1,
-            }
-        }
-        pub fn bad_t2() -> Self {
-            Self {
-                base_rate: 3,
-                tier1: None,
-                tier2: Some(TierConfig {
-                    reward_rate: 7,
-                    required_tenure: 20,
-                }),
-                tier3: None,
-                denominator: 1,
-            }
-        }
-        pub fn bad_t3_gap_t1() -> Self {
-            Self {
-                base_rate: 3,
-                tier1: None,
-                tier2: Some(TierConfig {
-                    reward_rate: 7,
-                    required_tenure: 20,
-                }),
-                tier3: Some(TierConfig {
-                    reward_rate: 11,
-                    required_tenure: 30,
-                }),
-                denominator: 1,
-            }
-        }
-        pub fn bad_t3_gap_t2() -> Self {
-            Self {
-                base_rate: 3,
-                tier1: Some(TierConfig {
-                    reward_rate: 5,
-                    required_tenure: 10,
-                }),
-                tier2: None,
-                tier3: Some(TierConfig {
-                    reward_rate: 11,
-                    required_tenure: 30,
-                }),
-                denominator: 1,
-            }
+            Self::from_tiers(
+                3,
+                &[
+                    TierConfig {
+                        reward_rate: 5,
+                        required_tenure: 10,
+                    },
+                    TierConfig {
+                        reward_rate: reward_rate2,
+                        required_tenure: required_tenure2,
+                    },
+                    TierConfig {
+                        reward_rate: reward_rate3,
+                        required_tenure: required_tenure3,
+                    },
+                ],
+                1,
+            )
         }
     }
 
     #[test]
     fn test_good_schedule_invariants() {
         let base = FixedRateSchedule::new_base(3, 1);
-        base.verify_schedule_invariants();
+        assert!(base.verify_schedule_invariants().is_ok());
 
         let t1 = FixedRateSchedule::new_t1(5, 10);
-        t1.verify_schedule_invariants();
+        assert!(t1.verify_schedule_invariants().is_ok());
 
         let t1_min = FixedRateSchedule::new_t1(5, 0);
-        t1_min.verify_schedule_invariants();
+        assert!(t1_min.verify_schedule_invariants().is_ok());
 
         let t2 = FixedRateSchedule::new_t2(7, 20);
-        t2.verify_schedule_invariants();
+        assert!(t2.verify_schedule_invariants().is_ok());
 
         let t2_min = FixedRateSchedule::new_t2(7, 10);
-        t2_min.verify_schedule_invariants();
+        assert!(t2_min.verify_schedule_invariants().is_ok());
 
         let t3 = FixedRateSchedule::new_t3(7, 20, 11, 30);
-        t3.verify_schedule_invariants();
+        assert!(t3.verify_schedule_invariants().is_ok());
 
         let t3_min = FixedRateSchedule::new_t3(7, 20, 11, 20);
-        t3_min.verify_schedule_invariants();
+        assert!(t3_min.verify_schedule_invariants().is_ok());
+
+        let max_tiers: Vec<TierConfig> = (0..MAX_FIXED_RATE_TIERS as u64)
+            .map(|i| TierConfig {
+                reward_rate: i + 5,
+                required_tenure: (i + 1) * 10,
+            })
+            .collect();
+        let t_max = FixedRateSchedule::from_tiers(3, &max_tiers, 1);
+        assert!(t_max.verify_schedule_invariants().is_ok());
     }
 
     #[test]
-    #[should_panic]
     fn test_t2_bad_tenure() {
         let t2 = FixedRateSchedule::new_t2(7, 9);
-        t2.verify_schedule_invariants();
+        assert!(t2.verify_schedule_invariants().is_err());
     }
 
     #[test]
-    #[should_panic]
     fn test_t3_bad_tenure_t2() {
         let t3 = FixedRateSchedule::new_t3(7, 20, 11, 19);
-        t3.verify_schedule_invariants();
+        assert!(t3.verify_schedule_invariants().is_err());
     }
 
     #[test]
-    #[should_panic]
     fn test_t3_bad_tenure_t3() {
         let t3 = FixedRateSchedule::new_t3(7, 9, 11, 30);
-        t3.verify_schedule_invariants();
+        assert!(t3.verify_schedule_invariants().is_err());
     }
 
     #[test]
-    #[should_panic]
-    fn test_t2_bad_gap() {
-        let t2 = FixedRateSchedule::bad_t2();
-        t2.verify_schedule_invariants();
+    fn test_too_many_tiers() {
+        let mut t3 = FixedRateSchedule::new_t3(7, 20, 11, 30);
+        t3.tier_count = MAX_FIXED_RATE_TIERS as u64 + 1;
+        assert!(t3.verify_schedule_invariants().is_err());
     }
 
     #[test]
-    #[should_panic]
-    fn test_t3_bad_gap_t1() {
-        let t3 = FixedRateSchedule::bad_t3_gap_t1();
-        t3.verify_schedule_invariants();
+    fn test_farmer_not_enrolled() {
+        // eg staked before the reward slot was added, so never enrolled with it
+        let mut reward = FixedRateReward::default();
+        let mut times = TimeTracker::default();
+        let mut funds = FundsTracker::default();
+        let mut farmer_reward = FarmerReward::default();
+
+        let cohorts = [StakeCohort {
+            begin_staking_ts: 100,
+            rarity_points_staked: 10,
+            ..Default::default()
+        }];
+
+        reward
+            .update_accrued_reward(
+                200,
+                &mut times,
+                &mut funds,
+                &cohorts,
+                &mut farmer_reward,
+                false,
+                None,
+            )
+            .unwrap();
+        assert_eq!(0, farmer_reward.accrued_reward);
+
+        // eg when they unstake
+        reward
+            .graduate_farmer(&cohorts, &mut farmer_reward, None)
+            .unwrap();
+        assert_eq!(0, reward.reserved_amount);
     }
 
     #[test]
-    #[should_panic]
-    fn test_t3_bad_gap_t2() {
-        let t3 = FixedRateSchedule::bad_t3_gap_t2();
-        t3.verify_schedule_invariants();
+    fn test_enroll_needs_schedules() {
+        let t2 = FixedRateSchedule::new_t2(7, 20);
+        let mut reward = FixedRateReward::default();
+        let mut times = TimeTracker::default();
+        let mut funds = FundsTracker::default();
+        let mut farmer_reward = FarmerReward::default();
+
+        let cohorts = [StakeCohort {
+            begin_staking_ts: 100,
+            rarity_points_staked: 1,
+            ..Default::default()
+        }];
+
+        // nothing to promise before the reward is funded, so nothing to look up
+        reward
+            .enroll_farmer(
+                100,
+                &mut times,
+                &mut funds,
+                &cohorts,
+                &mut farmer_reward,
+                None,
+            )
+            .unwrap();
+        assert!(farmer_reward.fixed_rate.is_staked());
+        assert!(!farmer_reward.fixed_rate.holds_schedule());
+
+        reward
+            .fund_reward(
+                100,
+                &mut times,
+                &mut funds,
+                FixedRateConfig {
+                    schedule: t2,
+                    amount: 1000,
+                    duration_sec: 30,
+                    start_ts: None,
+                },
+            )
+            .unwrap();
+        let mut tiers = FixedRateTiers::default();
+        tiers.set_schedule(Pubkey::new_unique(), 0, 1, &t2).unwrap();
+
+        assert!(reward
+            .enroll_farmer(
+                100,
+                &mut times,
+                &mut funds,
+                &cohorts,
+                &mut farmer_reward,
+                None
+            )
+            .is_err());
+
+        reward
+            .enroll_farmer(
+                100,
+                &mut times,
+                &mut funds,
+                &cohorts,
+                &mut farmer_reward,
+                tiers.schedules_for(1),
+            )
+            .unwrap();
+        assert!(farmer_reward.fixed_rate.holds_schedule());
+        assert_eq!(reward.reserved_amount, t2.reward_amount(0, 30, 1).unwrap());
+        assert_eq!(tiers.rewards[0].schedules[0].enrolled_farmers, 1);
+
+        // accruing and graduating need them just the same
+        assert!(reward
+            .update_accrued_reward(
+                110,
+                &mut times,
+                &mut funds,
+                &cohorts,
+                &mut farmer_reward,
+                false,
+                None,
+            )
+            .is_err());
+
+        reward
+            .graduate_farmer(&cohorts, &mut farmer_reward, tiers.schedules_for(1))
+            .unwrap();
+        assert_eq!(reward.reserved_amount, 0);
+        assert_eq!(tiers.rewards[0].schedules[0].enrolled_farmers, 0);
     }
 
     #[test]
-    #[should_panic]
     fn test_base_bad_denominator() {
         let base = FixedRateSchedule::new_base(1, 0);
-        base.verify_schedule_invariants();
+        assert!(base.verify_schedule_invariants().is_err());
     }
 
     #[test]
@@ -0,0 +1,167 @@
+use anchor_lang::prelude::*;
+use gem_common::{errors::ErrorCode, *};
+
+/// lives at [b"contribution", farm, reward_mint, funder] and keeps track of what a single
+/// funder has put into (and gotten back from) a single reward
+#[proc_macros::assert_size(152)]
+#[repr(C)]
+#[account]
+pub struct FunderContribution {
+    pub farm: Pubkey,
+
+    pub funder: Pubkey,
+
+    pub reward_mint: Pubkey,
+
+    /// matches FarmReward::reward_id, so that a reused slot isn't mixed up with an old one
+    pub reward_id: u64,
+
+    /// net of any transfer fees, same as what's recorded on the reward
+    pub total_funded: u64,
+
+    /// only counts pro-rata refunds, the manager's own cancellations start a new funding round instead
+    pub total_refunded: u64,
+
+    /// matches FarmReward::funding_round, contributions from an earlier round are worth nothing
+    /// carved out of the contribution's reserved space
+    pub funding_round: u64,
+
+    /// reserved for future updates, has to be /8
+    _reserved: [u8; 24],
+}
+
+impl FunderContribution {
+    pub fn record_funding(
+        &mut self,
+        farm: Pubkey,
+        funder: Pubkey,
+        reward_mint: Pubkey,
+        reward_id: u64,
+        funding_round: u64,
+        amount: u64,
+    ) -> Result<()> {
+        self.farm = farm;
+        self.funder = funder;
+        self.reward_mint = reward_mint;
+
+        // left over from a retired reward that used the same mint, or from before the manager
+        // cancelled the reward
+        if !self.is_current(reward_id, funding_round) {
+            self.reward_id = reward_id;
+            self.funding_round = funding_round;
+            self.total_funded = 0;
+            self.total_refunded = 0;
+        }
+
+        self.total_funded.try_add_assign(amount)
+    }
+
+    pub fn is_current(&self, reward_id: u64, funding_round: u64) -> bool {
+        self.reward_id == reward_id && self.funding_round == funding_round
+    }
+
+    /// what the funder still has in the reward, pro-rata refunds are split by this
+    pub fn balance(&self) -> Result<u64> {
+        self.total_funded.try_sub(self.total_refunded)
+    }
+}
+
+/// splits a refund across funders in proportion to their current balances (see
+/// FunderContribution::balance), the largest one gets whatever's left over from rounding down
+pub fn pro_rata_shares(refund_amount: u64, balances: &[u64]) -> Result<Vec<u64>> {
+    // nobody to refund to
+    if balances.is_empty() {
+        require!(refund_amount == 0, ErrorCode::IncompleteFunderList);
+        return Ok(vec![]);
+    }
+
+    let total_balance = balances
+        .iter()
+        .try_fold(0u64, |total, b| total.try_add(*b))?;
+
+    // nothing to split by
+    if total_balance == 0 {
+        require!(refund_amount == 0, ErrorCode::NoFunderBalance);
+        return Ok(vec![0; balances.len()]);
+    }
+
+    let mut shares = balances
+        .iter()
+        .map(|balance| {
+            (refund_amount as u128)
+                .try_mul(*balance as u128)?
+                .try_div(total_balance as u128)?
+                .try_cast()
+        })
+        .collect::<Result<Vec<u64>>>()?;
+
+    let shared = shares.iter().try_fold(0u64, |total, s| total.try_add(*s))?;
+    // (!) max_by_key picks the last of equals, so reversed it's the first
+    let largest = balances
+        .iter()
+        .enumerate()
+        .rev()
+        .max_by_key(|(_, balance)| **balance)
+        .map_or(0, |(i, _)| i);
+    shares[largest].try_add_assign(refund_amount.try_sub(shared)?)?;
+
+    Ok(shares)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pro_rata_shares() {
+        assert_eq!(
+            pro_rata_shares(100, &[50, 30, 20]).unwrap(),
+            vec![50, 30, 20]
+        );
+        assert_eq!(pro_rata_shares(10, &[1, 1, 1]).unwrap(), vec![4, 3, 3]);
+        assert_eq!(pro_rata_shares(10, &[1, 3, 2]).unwrap(), vec![1, 6, 3]);
+        assert_eq!(pro_rata_shares(0, &[5, 5]).unwrap(), vec![0, 0]);
+        assert_eq!(pro_rata_shares(7, &[3]).unwrap(), vec![7]);
+        assert!(pro_rata_shares(0, &[]).unwrap().is_empty());
+        assert!(pro_rata_shares(1, &[]).is_err());
+        assert_eq!(pro_rata_shares(0, &[0, 0]).unwrap(), vec![0, 0]);
+        assert!(pro_rata_shares(1, &[0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_pro_rata_shares_zero_balance_last() {
+        // a funder who's already been refunded in full gets nothing, not the rounding remainder
+        assert_eq!(pro_rata_shares(10, &[1, 2, 0]).unwrap(), vec![3, 7, 0]);
+        assert_eq!(pro_rata_shares(5, &[5, 0]).unwrap(), vec![5, 0]);
+    }
+
+    #[test]
+    fn test_record_funding_resets_on_new_round() {
+        let (farm, funder, mint) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut c = FunderContribution {
+            farm,
+            funder,
+            reward_mint: mint,
+            reward_id: 1,
+            total_funded: 0,
+            total_refunded: 0,
+            funding_round: 0,
+            _reserved: [0; 24],
+        };
+
+        c.record_funding(farm, funder, mint, 1, 0, 100).unwrap();
+        c.total_refunded = 40;
+        assert_eq!(c.balance().unwrap(), 60);
+
+        c.record_funding(farm, funder, mint, 1, 0, 10).unwrap();
+        assert_eq!(c.balance().unwrap(), 70);
+
+        // the manager cancelled the reward in between
+        c.record_funding(farm, funder, mint, 1, 1, 10).unwrap();
+        assert_eq!((c.total_funded, c.total_refunded), (10, 0));
+    }
+}
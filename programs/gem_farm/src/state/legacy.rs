@@ -0,0 +1,258 @@
+use anchor_lang::prelude::*;
+
+use crate::{number128::Number128, state::*};
+
+// farm & farmer accounts as they were laid out before the reward slots (reward_a / reward_b)
+// only ever read, by the migration ixs (see migrate_farm, migrate_farmer)
+
+#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyFixedRateSchedule {
+    pub base_rate: u64,
+
+    pub tier1: Option<TierConfig>,
+
+    pub tier2: Option<TierConfig>,
+
+    pub tier3: Option<TierConfig>,
+
+    pub denominator: u64,
+}
+
+impl LegacyFixedRateSchedule {
+    pub fn tiers(&self) -> Vec<TierConfig> {
+        [self.tier1, self.tier2, self.tier3]
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    pub fn migrate(&self) -> FixedRateSchedule {
+        FixedRateSchedule::from_tiers(self.base_rate, &self.tiers(), self.denominator)
+    }
+}
+
+#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyFixedRateReward {
+    pub schedule: LegacyFixedRateSchedule,
+
+    pub reserved_amount: u64,
+
+    _reserved: [u8; 32],
+}
+
+#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyVariableRateReward {
+    pub reward_rate: Number128,
+
+    pub reward_last_updated_ts: u64,
+
+    pub accrued_reward_per_rarity_point: Number128,
+
+    _reserved: [u8; 32],
+}
+
+#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyFundsTracker {
+    pub total_funded: u64,
+
+    pub total_refunded: u64,
+
+    pub total_accrued_to_stakers: u64,
+}
+
+#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyFarmReward {
+    pub reward_mint: Pubkey,
+
+    pub reward_pot: Pubkey,
+
+    pub reward_type: RewardType,
+
+    pub fixed_rate: LegacyFixedRateReward,
+
+    pub variable_rate: LegacyVariableRateReward,
+
+    pub funds: LegacyFundsTracker,
+
+    pub times: TimeTracker,
+
+    _reserved: [u8; 32],
+}
+
+impl LegacyFarmReward {
+    /// (!) the fixed rate tiers are stored separately, by the caller
+    pub fn migrate(&self, reward_id: u64) -> FarmReward {
+        let mut reward = FarmReward {
+            reward_mint: self.reward_mint,
+            reward_id,
+            reward_pot: self.reward_pot,
+            reward_type: self.reward_type,
+            funds: FundsTracker {
+                total_funded: self.funds.total_funded,
+                total_refunded: self.funds.total_refunded,
+                total_accrued_to_stakers: self.funds.total_accrued_to_stakers,
+                total_forfeited: 0,
+            },
+            times: self.times,
+            ..Default::default()
+        };
+
+        let schedule = &self.fixed_rate.schedule;
+        let fixed_rate = &mut reward.fixed_rate;
+        fixed_rate.base_rate = schedule.base_rate;
+        fixed_rate.tier_count = schedule.tiers().len() as u64;
+        fixed_rate.denominator = schedule.denominator;
+        fixed_rate.reserved_amount = self.fixed_rate.reserved_amount;
+
+        let variable_rate = &mut reward.variable_rate;
+        variable_rate.reward_rate = self.variable_rate.reward_rate;
+        variable_rate.reward_last_updated_ts = self.variable_rate.reward_last_updated_ts;
+        variable_rate.accrued_reward_per_rarity_point =
+            self.variable_rate.accrued_reward_per_rarity_point;
+        variable_rate.legacy_accrued_reward_per_rarity_point =
+            self.variable_rate.accrued_reward_per_rarity_point;
+
+        reward
+    }
+}
+
+#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyFarm {
+    pub version: u16,
+
+    pub farm_manager: Pubkey,
+
+    pub farm_treasury: Pubkey,
+
+    pub farm_authority: Pubkey,
+
+    pub farm_authority_seed: Pubkey,
+
+    pub farm_authority_bump_seed: [u8; 1],
+
+    pub bank: Pubkey,
+
+    pub config: FarmConfig,
+
+    pub farmer_count: u64,
+
+    pub staked_farmer_count: u64,
+
+    pub gems_staked: u64,
+
+    pub rarity_points_staked: u64,
+
+    pub authorized_funder_count: u64,
+
+    pub reward_a: LegacyFarmReward,
+
+    pub reward_b: LegacyFarmReward,
+
+    pub max_counts: MaxCounts,
+
+    _reserved: [u8; 32],
+    _reserved2: [u8; 16],
+    _reserved3: [u8; 4],
+}
+
+impl LegacyFarm {
+    /// aligned by index with the reward slots they're migrated into
+    pub fn rewards(&self) -> [LegacyFarmReward; 2] {
+        [self.reward_a, self.reward_b]
+    }
+}
+
+// --------------------------------------- farmer
+
+#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyFarmerVariableRateReward {
+    pub last_recorded_accrued_reward_per_rarity_point: Number128,
+
+    _reserved: [u8; 16],
+}
+
+#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyFarmerFixedRateReward {
+    pub begin_staking_ts: u64,
+
+    pub begin_schedule_ts: u64,
+
+    pub last_updated_ts: u64,
+
+    pub promised_schedule: LegacyFixedRateSchedule,
+
+    pub promised_duration: u64,
+
+    _reserved: [u8; 16],
+}
+
+#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyFarmerReward {
+    pub paid_out_reward: u64,
+
+    pub accrued_reward: u64,
+
+    pub variable_rate: LegacyFarmerVariableRateReward,
+
+    pub fixed_rate: LegacyFarmerFixedRateReward,
+
+    _reserved: [u8; 32],
+}
+
+impl LegacyFarmerReward {
+    /// (!) the promised schedule is stored separately, by the caller
+    pub fn migrate(&self, reward_id: u64) -> FarmerReward {
+        let mut reward = FarmerReward {
+            reward_id,
+            paid_out_reward: self.paid_out_reward,
+            accrued_reward: self.accrued_reward,
+            ..Default::default()
+        };
+
+        reward
+            .variable_rate
+            .last_recorded_accrued_reward_per_rarity_point = self
+            .variable_rate
+            .last_recorded_accrued_reward_per_rarity_point;
+
+        let fixed_rate = &mut reward.fixed_rate;
+        fixed_rate.begin_staking_ts = self.fixed_rate.begin_staking_ts;
+        fixed_rate.begin_schedule_ts = self.fixed_rate.begin_schedule_ts;
+        fixed_rate.last_updated_ts = self.fixed_rate.last_updated_ts;
+        fixed_rate.promised_duration = self.fixed_rate.promised_duration;
+
+        reward
+    }
+}
+
+#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct LegacyFarmer {
+    pub farm: Pubkey,
+
+    pub identity: Pubkey,
+
+    pub vault: Pubkey,
+
+    pub state: FarmerState,
+
+    pub gems_staked: u64,
+
+    pub rarity_points_staked: u64,
+
+    pub min_staking_ends_ts: u64,
+
+    pub cooldown_ends_ts: u64,
+
+    pub reward_a: LegacyFarmerReward,
+
+    pub reward_b: LegacyFarmerReward,
+
+    _reserved: [u8; 32],
+}
+
+impl LegacyFarmer {
+    /// aligned by index with the farm's legacy rewards (see LegacyFarm::rewards)
+    pub fn rewards(&self) -> [LegacyFarmerReward; 2] {
+        [self.reward_a, self.reward_b]
+    }
+}
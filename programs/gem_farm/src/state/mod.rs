@@ -1,11 +1,27 @@
 pub mod authorization_proof;
+pub mod emission_curve;
+pub mod epoch_queue;
 pub mod farm;
 pub mod farmer;
+pub mod fixed_rate_tiers;
 pub mod fixed_rewards;
+pub mod funder_contribution;
+pub mod legacy;
+pub mod pending_action;
+pub mod protocol_config;
+pub mod role_proof;
 pub mod variable_rewards;
 
 pub use authorization_proof::*;
+pub use emission_curve::*;
+pub use epoch_queue::*;
 pub use farm::*;
 pub use farmer::*;
+pub use fixed_rate_tiers::*;
 pub use fixed_rewards::*;
+pub use funder_contribution::*;
+pub use legacy::*;
+pub use pending_action::*;
+pub use protocol_config::*;
+pub use role_proof::*;
 pub use variable_rewards::*;
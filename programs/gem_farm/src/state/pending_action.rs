@@ -0,0 +1,243 @@
+use anchor_lang::prelude::*;
+use gem_common::{errors::ErrorCode, *};
+
+use crate::state::*;
+
+/// generous upper bound on a borsh-serialized FarmAction (UpdateFarm being the largest)
+pub const MAX_FARM_ACTION_LEN: usize = 256;
+
+/// how many rarities a single queued RemoveRaritiesFromBank can cover, so that it fits the above
+pub const MAX_QUEUED_RARITY_REMOVALS: usize = 7;
+
+/// same args as update_farm, None = leave as is
+#[derive(Debug, Clone, Default, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub struct FarmUpdate {
+    pub config: Option<FarmConfig>,
+
+    /// proposes handing the farm over to a new manager
+    pub manager: Option<Pubkey>,
+
+    pub max_counts: Option<MaxCounts>,
+
+    pub lockup_tiers: Option<Vec<LockupTier>>,
+
+    pub early_unstake_penalty: Option<EarlyUnstakePenalty>,
+
+    pub per_gem_tenure: Option<bool>,
+
+    /// 0 turns timelock mode off
+    pub timelock_sec: Option<u64>,
+}
+
+/// manager actions that can hurt stakers, and so have to be queued when the farm is timelocked
+#[derive(Debug, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
+pub enum FarmAction {
+    UpdateFarm(FarmUpdate),
+
+    CancelReward {
+        reward_mint: Pubkey,
+        receiver: Pubkey,
+    },
+
+    CancelRewardProRata {
+        reward_mint: Pubkey,
+    },
+
+    PayoutFromTreasury {
+        destination: Pubkey,
+        lamports: u64,
+    },
+
+    RemoveFromBankWhitelist {
+        address_to_remove: Pubkey,
+    },
+
+    AddRewardSlot {
+        reward_mint: Pubkey,
+        reward_type: RewardType,
+        vesting: Option<VestingConfig>,
+    },
+
+    RetireRewardSlot {
+        reward_mint: Pubkey,
+    },
+
+    /// same order as the remaining accounts passed to remove_rarities_from_bank
+    RemoveRaritiesFromBank {
+        gem_mints: Vec<Pubkey>,
+    },
+
+    /// same args as authorize_funder
+    AuthorizeFunder {
+        funder: Pubkey,
+        reward_mint: Option<Pubkey>,
+        max_amount: Option<u64>,
+        expires_ts: Option<u64>,
+    },
+
+    DeauthorizeFunder {
+        funder: Pubkey,
+    },
+}
+
+impl FarmAction {
+    /// only checks what's needed for the action to fit into its account,
+    /// the rest is validated as usual when it's executed
+    pub fn assert_valid(&self) -> Result<()> {
+        match self {
+            FarmAction::UpdateFarm(FarmUpdate {
+                lockup_tiers: Some(lockup_tiers),
+                ..
+            }) if lockup_tiers.len() > MAX_LOCKUP_TIERS => {
+                Err(error!(ErrorCode::InvalidLockupTier))
+            }
+            FarmAction::RemoveRaritiesFromBank { gem_mints }
+                if gem_mints.len() > MAX_QUEUED_RARITY_REMOVALS =>
+            {
+                Err(error!(ErrorCode::InvalidParameter))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// a manager action that's been announced on-chain, lives at
+/// [b"pending_action", farm, action_id] until it's either executed or cancelled
+#[account]
+#[derive(Debug)]
+pub struct PendingAction {
+    pub farm: Pubkey,
+
+    pub action_id: u64,
+
+    pub queued_ts: u64,
+
+    /// as of queueing - the farm's current timelock applies too, if it's since been raised
+    pub executable_ts: u64,
+
+    /// 0 until executed, after which the action can only be cleaned up (see cancel_farm_action)
+    pub executed_ts: u64,
+
+    pub action: FarmAction,
+}
+
+impl PendingAction {
+    pub const SPACE: usize = 8 + 32 + 8 + 8 + 8 + 8 + MAX_FARM_ACTION_LEN;
+
+    pub fn assert_executable(
+        &self,
+        now_ts: u64,
+        farm: Pubkey,
+        timelock_sec: u64,
+        action: &FarmAction,
+    ) -> Result<()> {
+        if self.farm != farm || self.action != *action {
+            return Err(error!(ErrorCode::PendingActionMismatch));
+        }
+        if self.executed_ts > 0 {
+            return Err(error!(ErrorCode::PendingActionExecuted));
+        }
+
+        let executable_ts =
+            std::cmp::max(self.executable_ts, self.queued_ts.try_add(timelock_sec)?);
+        if now_ts < executable_ts {
+            return Err(error!(ErrorCode::TimelockNotPassed));
+        }
+        Ok(())
+    }
+
+    pub fn execute(
+        &mut self,
+        now_ts: u64,
+        farm: Pubkey,
+        timelock_sec: u64,
+        action: &FarmAction,
+    ) -> Result<()> {
+        self.assert_executable(now_ts, farm, timelock_sec, action)?;
+        self.executed_ts = now_ts;
+        Ok(())
+    }
+}
+
+/// executing a sensitive action either uses up a matching pending action,
+/// or is only allowed if the farm isn't timelocked
+/// (!) the pending action is only marked as executed, the manager closes it to get the rent back
+pub fn consume_pending_action(
+    farm: &Account<Farm>,
+    pending_action: Option<&mut Account<PendingAction>>,
+    action: &FarmAction,
+) -> Result<()> {
+    match pending_action {
+        Some(pending_action) => {
+            pending_action.execute(now_ts()?, farm.key(), farm.timelock_sec, action)
+        }
+        None if farm.is_timelocked() => Err(error!(ErrorCode::TimelockRequired)),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_largest_action_fits() {
+        let action = FarmAction::UpdateFarm(FarmUpdate {
+            config: Some(FarmConfig {
+                min_staking_period_sec: 1,
+                cooldown_period_sec: 1,
+                unstaking_fee_lamp: 1,
+            }),
+            manager: Some(Pubkey::new_unique()),
+            max_counts: Some(MaxCounts {
+                max_farmers: 1,
+                max_gems: 1,
+                max_rarity_points: 1,
+            }),
+            lockup_tiers: Some(vec![LockupTier::default(); MAX_LOCKUP_TIERS]),
+            early_unstake_penalty: Some(EarlyUnstakePenalty::default()),
+            per_gem_tenure: Some(true),
+            timelock_sec: Some(1),
+        });
+
+        assert!(action.assert_valid().is_ok());
+        assert!(action.try_to_vec().unwrap().len() <= MAX_FARM_ACTION_LEN);
+
+        let action = FarmAction::RemoveRaritiesFromBank {
+            gem_mints: vec![Pubkey::new_unique(); MAX_QUEUED_RARITY_REMOVALS],
+        };
+
+        assert!(action.assert_valid().is_ok());
+        assert!(action.try_to_vec().unwrap().len() <= MAX_FARM_ACTION_LEN);
+    }
+
+    #[test]
+    fn test_execute_pending_action() {
+        let farm = Pubkey::new_unique();
+        let action = FarmAction::RetireRewardSlot {
+            reward_mint: Pubkey::new_unique(),
+        };
+        let mut pending_action = PendingAction {
+            farm,
+            action_id: 1,
+            queued_ts: 100,
+            executable_ts: 200,
+            executed_ts: 0,
+            action: action.clone(),
+        };
+
+        // the timelock has since been raised to 150s
+        assert!(pending_action.execute(200, farm, 150, &action).is_err());
+
+        let other = FarmAction::RetireRewardSlot {
+            reward_mint: Pubkey::new_unique(),
+        };
+        assert!(pending_action.execute(250, farm, 150, &other).is_err());
+
+        pending_action.execute(250, farm, 150, &action).unwrap();
+        assert_eq!(250, pending_action.executed_ts);
+
+        // can't be executed twice
+        assert!(pending_action.execute(300, farm, 150, &action).is_err());
+    }
+}
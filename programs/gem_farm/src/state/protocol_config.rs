@@ -0,0 +1,43 @@
+use anchor_lang::prelude::*;
+
+/// all fees are in lamports and can be set to 0, in which case no fee is collected
+#[proc_macros::assert_size(40)]
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize)]
+pub struct FeeSchedule {
+    pub init_farm_fee_lamp: u64,
+
+    pub init_farmer_fee_lamp: u64,
+
+    /// also charged when a flash deposit is what starts the staking
+    pub stake_fee_lamp: u64,
+
+    /// charged on each unstake call (takes 2 to get out, if the farm has a cooldown)
+    pub unstake_fee_lamp: u64,
+
+    /// flash deposits into an already staked vault
+    pub flash_deposit_fee_lamp: u64,
+}
+
+/// program-wide, there's only ever one of these - at [b"protocol_config"]
+#[proc_macros::assert_size(176)]
+#[repr(C)]
+#[account]
+#[derive(Debug)]
+pub struct ProtocolConfig {
+    /// can update the config, and hand itself over to another Pubkey (see pending_admin)
+    pub admin: Pubkey,
+
+    /// receives all protocol fees
+    pub fee_recipient: Pubkey,
+
+    pub fees: FeeSchedule,
+
+    pub bump: u8,
+
+    /// proposed by the admin, takes over once it accepts (see accept_protocol_admin)
+    pub pending_admin: Pubkey,
+
+    /// reserved for future updates, has to be /8
+    _reserved: [u8; 32],
+}
@@ -0,0 +1,106 @@
+use anchor_lang::prelude::*;
+use gem_common::errors::ErrorCode;
+
+use crate::state::*;
+
+bitflags::bitflags! {
+    pub struct FarmRoles: u32 {
+        /// add_to_bank_whitelist, remove_from_bank_whitelist
+        const WHITELIST_ADMIN = 1 << 0;
+        /// add_rarities_to_bank, remove_rarities_from_bank
+        const RARITY_ADMIN = 1 << 1;
+        /// payout_from_treasury
+        const TREASURY = 1 << 2;
+        /// update_farm (except for handing the farm over)
+        const CONFIG = 1 << 3;
+        /// authorize_funder, deauthorize_funder
+        const FUNDER_ADMIN = 1 << 4;
+    }
+}
+
+/// if this PDA exists, the holder recorded below can act on behalf of the farm manager
+/// recorded below, but only for the roles it's been granted
+#[proc_macros::assert_size(128)]
+#[repr(C)]
+#[account]
+pub struct RoleProof {
+    pub holder: Pubkey,
+
+    pub farm: Pubkey,
+
+    /// the farm manager that granted the roles - once the farm changes hands, the proof is void
+    pub granted_by: Pubkey,
+
+    pub roles: u32,
+
+    /// reserved for future updates, has to be /8
+    _reserved: [u8; 28],
+}
+
+impl RoleProof {
+    pub fn read_roles(roles: u32) -> Result<FarmRoles> {
+        FarmRoles::from_bits(roles).ok_or(error!(ErrorCode::InvalidParameter))
+    }
+
+    pub fn grants(
+        &self,
+        farm: Pubkey,
+        farm_manager: Pubkey,
+        signer: Pubkey,
+        role: FarmRoles,
+    ) -> bool {
+        self.farm == farm
+            && self.granted_by == farm_manager
+            && self.holder == signer
+            && FarmRoles::from_bits_truncate(self.roles).contains(role)
+    }
+}
+
+/// the farm manager can do everything, anyone else needs a role proof with the role in it
+pub fn has_farm_role(
+    farm: &Account<Farm>,
+    signer: Pubkey,
+    role_proof: Option<&Account<RoleProof>>,
+    role: FarmRoles,
+) -> bool {
+    if signer == farm.farm_manager {
+        return true;
+    }
+
+    role_proof.map_or(false, |proof| {
+        proof.grants(farm.key(), farm.farm_manager, signer, role)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_role_proof_grants() {
+        let farm = Pubkey::new_unique();
+        let farm_manager = Pubkey::new_unique();
+        let holder = Pubkey::new_unique();
+
+        let proof = RoleProof {
+            holder,
+            farm,
+            granted_by: farm_manager,
+            roles: (FarmRoles::TREASURY | FarmRoles::CONFIG).bits(),
+            _reserved: [0; 28],
+        };
+
+        assert!(proof.grants(farm, farm_manager, holder, FarmRoles::TREASURY));
+        assert!(!proof.grants(farm, farm_manager, holder, FarmRoles::FUNDER_ADMIN));
+        assert!(!proof.grants(farm, farm_manager, farm_manager, FarmRoles::TREASURY));
+        assert!(!proof.grants(
+            Pubkey::new_unique(),
+            farm_manager,
+            holder,
+            FarmRoles::TREASURY
+        ));
+
+        // the farm has since been handed over
+        assert!(!proof.grants(farm, Pubkey::new_unique(), holder, FarmRoles::TREASURY));
+    }
+}
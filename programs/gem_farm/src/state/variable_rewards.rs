@@ -1,9 +1,9 @@
 use anchor_lang::prelude::*;
-use gem_common::*;
+use gem_common::{errors::ErrorCode, *};
 
 use crate::{number128::Number128, state::*};
 
-#[proc_macros::assert_size(16)]
+#[proc_macros::assert_size(120)]
 #[repr(C)]
 #[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize, PartialEq)]
 pub struct VariableRateConfig {
@@ -12,13 +12,20 @@ pub struct VariableRateConfig {
 
     /// over which period it's active
     pub duration_sec: u64,
+
+    /// when it becomes active, if not right away (see TimeTracker::schedule_reward)
+    pub start_ts: Option<u64>,
+
+    /// how the rate changes over the duration, constant if not passed
+    pub curve: Option<EmissionCurve>,
 }
 
-#[proc_macros::assert_size(72)]
+#[proc_macros::assert_size(360)]
 #[repr(C)]
-#[derive(Debug, Copy, Clone, AnchorSerialize, AnchorDeserialize)]
+#[derive(Debug, Copy, Clone, Default, AnchorSerialize, AnchorDeserialize)]
 pub struct VariableRateReward {
-    /// in tokens/s, = calculated as total reward pot at initialization / reward duration
+    /// in tokens/s at full weight, = calculated as total reward pot at initialization / area under
+    /// the curve over the reward duration (which for a constant curve is just the duration)
     pub reward_rate: Number128,
 
     /// set to upper bound, not just now_ts (except funding, when there is no upper bound)
@@ -32,8 +39,20 @@ pub struct VariableRateReward {
     /// 3) update their record of flag position, so that next time we don't count this distance again
     pub accrued_reward_per_rarity_point: Number128,
 
-    /// reserved for future updates, has to be /8
-    _reserved: [u8; 32],
+    /// total (not per rarity point) that has accrued to the pool but hasn't yet been recorded
+    /// on individual farmer accounts. Once below 1, no farmer has anything left to pick up
+    pub unrecorded_reward: Number128,
+
+    /// up next, in order - the first begins as soon as the current funding runs out
+    pub epoch_queue: EpochQueue,
+
+    /// set with each funding, starts over from when the reward begins
+    pub curve: EmissionCurve,
+
+    /// where the pool stood when a legacy farm was migrated - legacy farmers haven't recorded
+    /// anything past it yet (see Farm::migrate_legacy_farmer). Zero on farms created since
+    /// carved out of the reserved space
+    pub legacy_accrued_reward_per_rarity_point: Number128,
 }
 
 impl VariableRateReward {
@@ -47,20 +66,32 @@ impl VariableRateReward {
         let VariableRateConfig {
             amount,
             duration_sec,
+            start_ts,
+            curve,
         } = new_config;
 
+        let curve = curve.unwrap_or_default();
+        curve.assert_valid()?;
+
+        let full_rate_duration = curve.integrate(0, duration_sec)?;
+        if full_rate_duration == Number128::ZERO {
+            return Err(error!(ErrorCode::InvalidParameter));
+        }
+
         // if previous reward has been exhausted
         if now_ts > times.reward_end_ts {
-            self.reward_rate = Number128::from(amount).try_div(Number128::from(duration_sec))?;
+            self.reward_rate = Number128::from(amount).try_div(full_rate_duration)?;
         // else if previous reward is still active (merge the two)
+        // (!) if the new one starts later, nothing is emitted in between
         } else {
             self.reward_rate = Number128::from(amount)
                 .try_add(Number128::from(funds.pending_amount()?))?
-                .try_div(Number128::from(duration_sec))?;
+                .try_div(full_rate_duration)?;
         }
 
-        times.duration_sec = duration_sec;
-        times.reward_end_ts = now_ts.try_add(duration_sec)?;
+        self.curve = curve;
+
+        times.schedule_reward(now_ts, start_ts, duration_sec)?;
 
         funds.total_funded.try_add_assign(amount)?;
 
@@ -70,12 +101,70 @@ impl VariableRateReward {
         Ok(())
     }
 
+    pub fn has_queued_epochs(&self) -> bool {
+        !self.epoch_queue.is_empty()
+    }
+
+    /// (!) funding a new reward later on doesn't affect the queue, it simply starts once that one ends
+    /// (!) expects the reward to have been updated up to now_ts
+    pub fn queue_epochs(
+        &mut self,
+        now_ts: u64,
+        times: &mut TimeTracker,
+        funds: &mut FundsTracker,
+        epochs: Vec<RewardEpoch>,
+    ) -> Result<()> {
+        for epoch in epochs {
+            self.epoch_queue.push(epoch)?;
+        }
+
+        // nothing running for the queue to follow - begin right away
+        if now_ts >= times.reward_end_ts {
+            self.begin_next_epoch(now_ts, times, funds)?;
+            self.reward_last_updated_ts = now_ts;
+        }
+
+        Ok(())
+    }
+
+    fn begin_next_epoch(
+        &mut self,
+        begin_ts: u64,
+        times: &mut TimeTracker,
+        funds: &mut FundsTracker,
+    ) -> Result<()> {
+        let RewardEpoch {
+            amount,
+            duration_sec,
+        } = self
+            .epoch_queue
+            .pop()
+            .ok_or_else(|| error!(ErrorCode::InvalidParameter))?;
+
+        self.reward_rate = Number128::from(amount).try_div(Number128::from(duration_sec))?;
+        self.curve = EmissionCurve::default();
+
+        times.duration_sec = duration_sec;
+        times.reward_end_ts = begin_ts.try_add(duration_sec)?;
+
+        funds.total_funded.try_add_assign(amount)?;
+
+        // msg!("began new epoch of {}", amount);
+        Ok(())
+    }
+
     pub fn cancel_reward(
         &mut self,
         now_ts: u64,
         times: &mut TimeTracker,
         funds: &mut FundsTracker,
     ) -> Result<u64> {
+        // queued epochs are refunded too
+        funds
+            .total_funded
+            .try_add_assign(self.epoch_queue.total_amount()?)?;
+        self.epoch_queue.clear();
+
         let refund_amount = funds.pending_amount()?;
         funds.total_refunded.try_add_assign(refund_amount)?;
 
@@ -91,27 +180,21 @@ impl VariableRateReward {
     pub fn update_accrued_reward(
         &mut self,
         now_ts: u64,
-        times: &TimeTracker,
+        times: &mut TimeTracker,
         funds: &mut FundsTracker,
         farm_rarity_points_staked: u64,
         farmer_rarity_points_staked: Option<u64>,
         farmer_reward: Option<&mut FarmerReward>,
     ) -> Result<()> {
-        let reward_upper_bound = times.reward_upper_bound(now_ts);
-
-        // calc & update reward per rarity point
-        let newly_accrued_reward_per_rarity_point = self
-            .newly_accrued_reward_per_rarity_point(farm_rarity_points_staked, reward_upper_bound)?;
+        // roll over into every epoch that's begun since the last update, each one starting
+        // when the previous one ended and accruing at its own rate up to its own end
+        while now_ts > times.reward_end_ts && self.has_queued_epochs() {
+            let epoch_end_ts = times.reward_end_ts;
+            self.accrue_to_pool(epoch_end_ts, times, funds, farm_rarity_points_staked)?;
+            self.begin_next_epoch(epoch_end_ts, times, funds)?;
+        }
 
-        self.accrued_reward_per_rarity_point
-            .try_add_assign(newly_accrued_reward_per_rarity_point)?;
-
-        // update overall reward
-        funds.total_accrued_to_stakers.try_add_assign(
-            newly_accrued_reward_per_rarity_point
-                .try_mul(Number128::from(farm_rarity_points_staked))?
-                .as_u64_ceil(0)?, //overestimate at farm level
-        )?;
+        self.accrue_to_pool(now_ts, times, funds, farm_rarity_points_staked)?;
 
         // update farmer, if one was passed
         if let Some(farmer_reward) = farmer_reward {
@@ -128,17 +211,110 @@ impl VariableRateReward {
                 newly_accrued_to_farmer.as_u64(0)?, //underestimate at farmer level
                 self.accrued_reward_per_rarity_point,
             )?;
+
+            self.record_reward(newly_accrued_to_farmer);
         }
 
+        // msg!("updated reward as of {}", self.reward_last_updated_ts);
+        Ok(())
+    }
+
+    fn accrue_to_pool(
+        &mut self,
+        now_ts: u64,
+        times: &TimeTracker,
+        funds: &mut FundsTracker,
+        farm_rarity_points_staked: u64,
+    ) -> Result<()> {
+        let reward_upper_bound = times.reward_upper_bound(now_ts);
+
+        // nothing accrues before a scheduled reward begins
+        let reward_lower_bound = times.reward_lower_bound(self.reward_last_updated_ts)?;
+
+        // calc & update reward per rarity point
+        let newly_accrued_reward_per_rarity_point = self.newly_accrued_reward_per_rarity_point(
+            farm_rarity_points_staked,
+            times.reward_begin_ts()?,
+            reward_lower_bound,
+            reward_upper_bound,
+        )?;
+
+        self.accrued_reward_per_rarity_point
+            .try_add_assign(newly_accrued_reward_per_rarity_point)?;
+
+        // update overall reward
+        let newly_accrued_reward = newly_accrued_reward_per_rarity_point
+            .try_mul(Number128::from(farm_rarity_points_staked))?;
+
+        funds
+            .total_accrued_to_stakers
+            .try_add_assign(newly_accrued_reward.as_u64_ceil(0)?)?; //overestimate at farm level
+
+        self.unrecorded_reward
+            .try_add_assign(newly_accrued_reward)?;
+
         self.reward_last_updated_ts = reward_upper_bound;
 
-        // msg!("updated reward as of {}", self.reward_last_updated_ts);
         Ok(())
     }
 
+    /// called when a farmer's rarity points are about to change without their reward
+    /// having been updated first (eg unstaking with skip_rewards) - whatever hasn't been
+    /// recorded on their account yet is forfeited
+    /// (!) never fails, as skip_rewards is how farmers get out when their rewards overflow
+    pub fn forfeit_unrecorded_reward(
+        &mut self,
+        farmer_rarity_points_staked: u64,
+        farmer_reward: &mut FarmerReward,
+    ) {
+        let forfeited = self
+            .accrued_reward_per_rarity_point
+            .try_sub(
+                farmer_reward
+                    .variable_rate
+                    .last_recorded_accrued_reward_per_rarity_point,
+            )
+            .and_then(|n| Number128::from(farmer_rarity_points_staked).try_mul(n));
+
+        farmer_reward
+            .variable_rate
+            .last_recorded_accrued_reward_per_rarity_point = self.accrued_reward_per_rarity_point;
+
+        if let Ok(forfeited) = forfeited {
+            self.record_reward(forfeited);
+        }
+    }
+
+    /// moves the flag forward for everyone still staked, as if the amount had just accrued to them
+    pub fn redistribute_reward(
+        &mut self,
+        amount: u64,
+        farm_rarity_points_staked: u64,
+    ) -> Result<()> {
+        let amount = Number128::from(amount);
+
+        self.accrued_reward_per_rarity_point
+            .try_add_assign(amount.try_div(Number128::from(farm_rarity_points_staked))?)?;
+        self.unrecorded_reward.try_add_assign(amount)?;
+
+        Ok(())
+    }
+
+    /// farm-level and farmer-level amounts are computed from the same per rarity point numbers
+    /// so this never goes below zero, but as it's only used for book-keeping we don't want
+    /// to ever block a payout on it either
+    fn record_reward(&mut self, amount: Number128) {
+        self.unrecorded_reward = self
+            .unrecorded_reward
+            .try_sub(amount)
+            .unwrap_or(Number128::ZERO);
+    }
+
     fn newly_accrued_reward_per_rarity_point(
         &self,
         farm_rarity_points_staked: u64,
+        reward_begin_ts: u64,
+        reward_lower_bound: u64,
         reward_upper_bound: u64,
     ) -> Result<Number128> {
         if farm_rarity_points_staked == 0 {
@@ -146,9 +322,17 @@ impl VariableRateReward {
             return Ok(Number128::ZERO);
         }
 
-        let time_since_last_calc = reward_upper_bound.try_sub(self.reward_last_updated_ts)?;
+        if reward_upper_bound <= reward_lower_bound {
+            return Ok(Number128::ZERO);
+        }
 
-        Number128::from(time_since_last_calc)
+        // for a constant curve this is simply the time since the last calc
+        let full_rate_time_since_last_calc = self.curve.integrate(
+            reward_lower_bound.try_sub(reward_begin_ts)?,
+            reward_upper_bound.try_sub(reward_begin_ts)?,
+        )?;
+
+        full_rate_time_since_last_calc
             .try_mul(self.reward_rate)?
             .try_div(Number128::from(farm_rarity_points_staked))
     }
@@ -164,14 +348,24 @@ mod tests {
             reward_rate: Number128::from(10u64),
             reward_last_updated_ts: 200,
             accrued_reward_per_rarity_point: Number128::from(1234u64),
-            _reserved: [0; 32],
+            unrecorded_reward: Number128::ZERO,
+            epoch_queue: EpochQueue::default(),
+            curve: EmissionCurve::default(),
+            legacy_accrued_reward_per_rarity_point: Number128::ZERO,
         };
 
         let farm_points_staked = 25;
+        let reward_begin_ts = 100;
+        let reward_lower_bound = 200;
         let reward_upper_bound = 205;
 
         let newly_accrued = var_reward
-            .newly_accrued_reward_per_rarity_point(farm_points_staked, reward_upper_bound)
+            .newly_accrued_reward_per_rarity_point(
+                farm_points_staked,
+                reward_begin_ts,
+                reward_lower_bound,
+                reward_upper_bound,
+            )
             .unwrap();
 
         assert_eq!(newly_accrued, Number128::from(2u64));
@@ -188,10 +382,13 @@ mod tests {
             total_funded: 100,
             total_refunded: 0,
             total_accrued_to_stakers: 0,
+            total_forfeited: 0,
         };
         let new_config = VariableRateConfig {
             amount: 10,
             duration_sec: 80,
+            start_ts: None,
+            curve: None,
         };
 
         let now_ts = 201; //just after the previous reward ends at 200s
@@ -200,7 +397,10 @@ mod tests {
             reward_rate: Number128::from(10u64),
             reward_last_updated_ts: 0,
             accrued_reward_per_rarity_point: Number128::from(1234u64),
-            _reserved: [0; 32],
+            unrecorded_reward: Number128::ZERO,
+            epoch_queue: EpochQueue::default(),
+            curve: EmissionCurve::default(),
+            legacy_accrued_reward_per_rarity_point: Number128::ZERO,
         };
 
         var_reward
@@ -234,10 +434,13 @@ mod tests {
             total_funded: 100,
             total_refunded: 0,
             total_accrued_to_stakers: 0,
+            total_forfeited: 0,
         };
         let new_config = VariableRateConfig {
             amount: 100,
             duration_sec: 400,
+            start_ts: None,
+            curve: None,
         };
 
         let now_ts = 199; //just before the previous reward, which triggers a merge
@@ -246,7 +449,10 @@ mod tests {
             reward_rate: Number128::from(10u64),
             reward_last_updated_ts: 0,
             accrued_reward_per_rarity_point: Number128::from(1234u64),
-            _reserved: [0; 32],
+            unrecorded_reward: Number128::ZERO,
+            epoch_queue: EpochQueue::default(),
+            curve: EmissionCurve::default(),
+            legacy_accrued_reward_per_rarity_point: Number128::ZERO,
         };
 
         var_reward
@@ -278,10 +484,13 @@ mod tests {
             total_funded: 100,
             total_refunded: 20,
             total_accrued_to_stakers: 30,
+            total_forfeited: 0,
         };
         let new_config = VariableRateConfig {
             amount: 100,
             duration_sec: 400,
+            start_ts: None,
+            curve: None,
         };
 
         let now_ts = 199; //just before the previous reward, which triggers a merge
@@ -290,7 +499,10 @@ mod tests {
             reward_rate: Number128::from(10u64),
             reward_last_updated_ts: 0,
             accrued_reward_per_rarity_point: Number128::from(1234u64),
-            _reserved: [0; 32],
+            unrecorded_reward: Number128::ZERO,
+            epoch_queue: EpochQueue::default(),
+            curve: EmissionCurve::default(),
+            legacy_accrued_reward_per_rarity_point: Number128::ZERO,
         };
 
         var_reward
@@ -312,4 +524,193 @@ mod tests {
         assert_eq!(times.duration_sec, 400);
         assert_eq!(times.reward_end_ts, 599);
     }
+
+    #[test]
+    fn test_unrecorded_reward() {
+        let mut times = TimeTracker {
+            duration_sec: 100,
+            reward_end_ts: 300,
+            lock_end_ts: 0,
+        };
+        let mut funds = FundsTracker {
+            total_funded: 1000,
+            total_refunded: 0,
+            total_accrued_to_stakers: 0,
+            total_forfeited: 0,
+        };
+
+        let mut var_reward = VariableRateReward {
+            reward_rate: Number128::from(10u64),
+            reward_last_updated_ts: 200,
+            accrued_reward_per_rarity_point: Number128::ZERO,
+            unrecorded_reward: Number128::ZERO,
+            epoch_queue: EpochQueue::default(),
+            curve: EmissionCurve::default(),
+            legacy_accrued_reward_per_rarity_point: Number128::ZERO,
+        };
+
+        // 50 accrues to the farm, 10 of which gets recorded on the farmer
+        let mut farmer_reward = FarmerReward::default();
+        var_reward
+            .update_accrued_reward(
+                205,
+                &mut times,
+                &mut funds,
+                25,
+                Some(5),
+                Some(&mut farmer_reward),
+            )
+            .unwrap();
+
+        assert_eq!(funds.total_accrued_to_stakers, 50);
+        assert_eq!(farmer_reward.accrued_reward, 10);
+        assert_eq!(var_reward.unrecorded_reward, Number128::from(40u64));
+
+        // the other farmer unstakes w/o updating their reward
+        let mut other_farmer_reward = FarmerReward::default();
+        var_reward.forfeit_unrecorded_reward(20, &mut other_farmer_reward);
+
+        assert_eq!(other_farmer_reward.accrued_reward, 0);
+        assert_eq!(var_reward.unrecorded_reward, Number128::ZERO);
+    }
+
+    #[test]
+    fn test_scheduled_reward() {
+        let mut times = TimeTracker::default();
+        let mut funds = FundsTracker::default();
+        let new_config = VariableRateConfig {
+            amount: 1000,
+            duration_sec: 100,
+            start_ts: Some(300),
+            curve: None,
+        };
+
+        let mut var_reward = VariableRateReward::default();
+        var_reward
+            .fund_reward(200, &mut times, &mut funds, new_config)
+            .unwrap();
+
+        assert_eq!(var_reward.reward_rate, Number128::from(10u64));
+        assert_eq!(times.reward_end_ts, 400);
+
+        // nothing accrues before the start
+        var_reward
+            .update_accrued_reward(250, &mut times, &mut funds, 10, None, None)
+            .unwrap();
+        assert_eq!(funds.total_accrued_to_stakers, 0);
+
+        // only the time since the start counts
+        var_reward
+            .update_accrued_reward(310, &mut times, &mut funds, 10, None, None)
+            .unwrap();
+        assert_eq!(funds.total_accrued_to_stakers, 100);
+        assert_eq!(var_reward.reward_last_updated_ts, 310);
+    }
+
+    #[test]
+    fn test_epoch_queue() {
+        let mut times = TimeTracker::default();
+        let mut funds = FundsTracker::default();
+        let mut var_reward = VariableRateReward::default();
+
+        let epoch = |amount| RewardEpoch {
+            amount,
+            duration_sec: 100,
+        };
+
+        // 1000 over 100s, then 500 over 100s
+        var_reward
+            .queue_epochs(100, &mut times, &mut funds, vec![epoch(1000), epoch(500)])
+            .unwrap();
+
+        // 1st one begins right away, the rest aren't funded yet
+        assert_eq!(var_reward.reward_rate, Number128::from(10u64));
+        assert_eq!(times.reward_end_ts, 200);
+        assert_eq!(funds.total_funded, 1000);
+        assert_eq!(var_reward.epoch_queue.len, 1);
+
+        // crossing into the 2nd epoch accrues each at its own rate: 100 * 10 + 50 * 5
+        var_reward
+            .update_accrued_reward(250, &mut times, &mut funds, 10, None, None)
+            .unwrap();
+
+        assert_eq!(funds.total_accrued_to_stakers, 1250);
+        assert_eq!(funds.total_funded, 1500);
+        assert_eq!(times.reward_end_ts, 300);
+        assert!(!var_reward.has_queued_epochs());
+
+        // queued once the reward has run dry, the next one begins right away
+        var_reward
+            .update_accrued_reward(350, &mut times, &mut funds, 10, None, None)
+            .unwrap();
+        assert_eq!(funds.total_accrued_to_stakers, 1500);
+
+        var_reward
+            .queue_epochs(350, &mut times, &mut funds, vec![epoch(200)])
+            .unwrap();
+        assert_eq!(times.reward_end_ts, 450);
+        assert_eq!(funds.total_funded, 1700);
+        assert!(!var_reward.has_queued_epochs());
+
+        // can only queue up so many
+        let epochs = vec![epoch(1); MAX_REWARD_EPOCHS];
+        var_reward
+            .queue_epochs(350, &mut times, &mut funds, epochs)
+            .unwrap();
+        assert!(var_reward
+            .queue_epochs(350, &mut times, &mut funds, vec![epoch(1)])
+            .is_err());
+
+        // cancelling refunds whatever's pending + queued
+        let refund = var_reward
+            .cancel_reward(350, &mut times, &mut funds)
+            .unwrap();
+        assert_eq!(refund, 200 + MAX_REWARD_EPOCHS as u64);
+        assert!(!var_reward.has_queued_epochs());
+    }
+
+    #[test]
+    fn test_epoch_queue_multiple_rollovers() {
+        let mut times = TimeTracker::default();
+        let mut funds = FundsTracker::default();
+        let mut var_reward = VariableRateReward::default();
+
+        let epoch = |amount| RewardEpoch {
+            amount,
+            duration_sec: 100,
+        };
+
+        // 1000, then 500, then 200, then 100, each over 100s
+        var_reward
+            .queue_epochs(
+                100,
+                &mut times,
+                &mut funds,
+                vec![epoch(1000), epoch(500), epoch(200), epoch(100)],
+            )
+            .unwrap();
+
+        // nothing updates until well into the 3rd epoch - the 2nd and 3rd still start
+        // exactly when the previous one ended: 100 * 10 + 100 * 5 + 50 * 2
+        var_reward
+            .update_accrued_reward(350, &mut times, &mut funds, 10, None, None)
+            .unwrap();
+
+        assert_eq!(funds.total_accrued_to_stakers, 1600);
+        assert_eq!(funds.total_funded, 1700);
+        assert_eq!(times.reward_end_ts, 400);
+        assert_eq!(var_reward.reward_rate, Number128::from(2u64));
+        assert_eq!(var_reward.epoch_queue.len, 1);
+
+        // jumping past the end of the queue accrues everything, and nothing more
+        var_reward
+            .update_accrued_reward(1000, &mut times, &mut funds, 10, None, None)
+            .unwrap();
+
+        assert_eq!(funds.total_accrued_to_stakers, 1800);
+        assert_eq!(funds.total_funded, 1800);
+        assert_eq!(times.reward_end_ts, 500);
+        assert_eq!(var_reward.reward_last_updated_ts, 500);
+        assert!(!var_reward.has_queued_epochs());
+    }
 }
@@ -14,6 +14,9 @@ import {
 import { AccountUtils, isKp } from '../gem-common';
 import { GemBank } from '../types/gem_bank';
 import {
+  findCnftReceiptPDA,
+  findCoreReceiptPDA,
+  findFrozenReceiptPDA,
   findGdrPDA,
   findGemBoxPDA,
   findRarityPDA,
@@ -35,6 +38,44 @@ import {
   getTotalComputeIxs,
 } from '../gem-common/pnft';
 
+export const MPL_CORE_PROG_ID = new PublicKey(
+  'CoREENxT6tW1HoK8ypY1SxRMZTcVPm7R94rH4PZNhX7d'
+);
+export const BUBBLEGUM_PROG_ID = new PublicKey(
+  'BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY'
+);
+export const ACCOUNT_COMPRESSION_PROG_ID = new PublicKey(
+  'cmtDvXumGCrqC1Age74AVPhSRVXJMd8PJS91L8KbNCK'
+);
+export const NOOP_PROG_ID = new PublicKey(
+  'noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV'
+);
+
+// everything needed to prove a leaf, apart from the root + proof path
+export interface CnftLeaf {
+  dataHash: number[];
+  creatorHash: number[];
+  nonce: BN;
+  index: number;
+}
+
+export interface CnftCreator {
+  address: PublicKey;
+  verified: boolean;
+  share: number;
+}
+
+export const findCnftAssetId = async (merkleTree: PublicKey, nonce: BN) => {
+  return PublicKey.findProgramAddress(
+    [
+      Buffer.from('asset'),
+      merkleTree.toBytes(),
+      nonce.toArrayLike(Buffer, 'le', 8),
+    ],
+    BUBBLEGUM_PROG_ID
+  );
+};
+
 export enum BankFlags {
   FreezeVaults = 1 << 0,
 }
@@ -42,6 +83,7 @@ export enum BankFlags {
 export enum WhitelistType {
   Creator = 1 << 0,
   Mint = 1 << 1,
+  Collection = 1 << 2,
 }
 
 export class GemBankClient extends AccountUtils {
@@ -220,7 +262,7 @@ export class GemBankClient extends AccountUtils {
     const signers = [];
     if (isKp(bankManager)) signers.push(<Keypair>bankManager);
 
-    console.log('updating bank manager to', newManager.toBase58());
+    console.log('proposing bank manager', newManager.toBase58());
     const txSig = await this.bankProgram.methods
       .updateBankManager(newManager)
       .accounts({
@@ -235,6 +277,25 @@ export class GemBankClient extends AccountUtils {
     return { txSig };
   }
 
+  async acceptBankManager(bank: PublicKey, newManager: PublicKey | Keypair) {
+    const signers = [];
+    if (isKp(newManager)) signers.push(<Keypair>newManager);
+
+    console.log('accepting bank manager handover');
+    const txSig = await this.bankProgram.methods
+      .acceptBankManager()
+      .accounts({
+        bank,
+        newManager: isKp(newManager)
+          ? (<Keypair>newManager).publicKey
+          : newManager,
+      })
+      .signers(signers)
+      .rpc();
+
+    return { txSig };
+  }
+
   async initVault(
     bank: PublicKey,
     creator: PublicKey | Keypair,
@@ -278,7 +339,7 @@ export class GemBankClient extends AccountUtils {
     const signers = [];
     if (isKp(existingOwner)) signers.push(<Keypair>existingOwner);
 
-    console.log('updating vault owner to', newOwner.toBase58());
+    console.log('proposing vault owner', newOwner.toBase58());
     const txSig = await this.bankProgram.methods
       .updateVaultOwner(newOwner)
       .accounts({
@@ -294,6 +355,28 @@ export class GemBankClient extends AccountUtils {
     return { txSig };
   }
 
+  async acceptVaultOwner(
+    bank: PublicKey,
+    vault: PublicKey,
+    newOwner: Keypair | PublicKey
+  ) {
+    const signers = [];
+    if (isKp(newOwner)) signers.push(<Keypair>newOwner);
+
+    console.log('accepting vault owner handover');
+    const txSig = await this.bankProgram.methods
+      .acceptVaultOwner()
+      .accounts({
+        bank,
+        vault,
+        newOwner: isKp(newOwner) ? (<Keypair>newOwner).publicKey : newOwner,
+      })
+      .signers(signers)
+      .rpc();
+
+    return { txSig };
+  }
+
   async setVaultLock(
     bank: PublicKey,
     vault: PublicKey,
@@ -887,6 +970,437 @@ export class GemBankClient extends AccountUtils {
     };
   }
 
+  // --------------------------------------- non-custodial (frozen in the owner's wallet)
+
+  // gemAccount = the owner's token account holding the (p)NFT
+  async freezeGem(
+    bank: PublicKey,
+    vault: PublicKey,
+    vaultOwner: PublicKey | Keypair,
+    gemMint: PublicKey,
+    gemAccount: PublicKey,
+    mintProof?: PublicKey,
+    creatorProof?: PublicKey,
+    collectionProof?: PublicKey
+  ) {
+    const [vaultAuth, vaultAuthBump] = await findVaultAuthorityPDA(vault);
+    const [frozenReceipt, frozenReceiptBump] = await findFrozenReceiptPDA(
+      vault,
+      gemMint
+    );
+    const [gemRarity, gemRarityBump] = await findRarityPDA(bank, gemMint);
+
+    //the gem never moves, so source and destination are the same account
+    const { meta, ownerTokenRecordPda, ruleSet, nftEditionPda } =
+      await this.prepPnftAccounts({
+        nftMint: gemMint,
+        sourceAta: gemAccount,
+        destAta: gemAccount,
+      });
+
+    // the mint proof always goes first, even if there's only a creator / collection proof
+    const remainingAccounts = [];
+    if (!!ruleSet)
+      remainingAccounts.push({
+        pubkey: ruleSet,
+        isWritable: false,
+        isSigner: false,
+      });
+    if (mintProof || creatorProof || collectionProof)
+      remainingAccounts.push({
+        pubkey: mintProof ?? (await findWhitelistProofPDA(bank, gemMint))[0],
+        isWritable: false,
+        isSigner: false,
+      });
+    for (const proof of [creatorProof, collectionProof])
+      if (proof)
+        remainingAccounts.push({
+          pubkey: proof,
+          isWritable: false,
+          isSigner: false,
+        });
+
+    const signers = [];
+    if (isKp(vaultOwner)) signers.push(<Keypair>vaultOwner);
+
+    console.log(
+      `freezing gem ${gemMint.toBase58()} in ${gemAccount.toBase58()}`
+    );
+    const builder = this.bankProgram.methods
+      .freezeGem(vaultAuthBump, gemRarityBump, null, !!ruleSet)
+      .accounts({
+        bank,
+        vault,
+        owner: isKp(vaultOwner) ? (<Keypair>vaultOwner).publicKey : vaultOwner,
+        authority: vaultAuth,
+        gemAccount,
+        frozenGemReceipt: frozenReceipt,
+        gemMint,
+        gemRarity,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        gemMetadata: meta,
+        gemEdition: nftEditionPda,
+        gemTokenRecord: ownerTokenRecordPda,
+        pnftShared: {
+          authorizationRulesProgram: AUTH_PROG_ID,
+          tokenMetadataProgram: TMETA_PROG_ID,
+          instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        },
+      })
+      .remainingAccounts(remainingAccounts)
+      .signers(signers);
+
+    const [modifyComputeUnits, addPriorityFee] = getTotalComputeIxs(
+      400000,
+      1
+    );
+    const txSig = await buildAndSendTx({
+      provider: this.provider as AnchorProvider,
+      ixs: [modifyComputeUnits, addPriorityFee, await builder.instruction()],
+    });
+
+    return {
+      vaultAuth,
+      vaultAuthBump,
+      frozenReceipt,
+      frozenReceiptBump,
+      gemRarity,
+      gemRarityBump,
+      txSig,
+    };
+  }
+
+  async thawGem(
+    bank: PublicKey,
+    vault: PublicKey,
+    vaultOwner: PublicKey | Keypair,
+    gemMint: PublicKey,
+    gemAccount: PublicKey
+  ) {
+    const [vaultAuth, vaultAuthBump] = await findVaultAuthorityPDA(vault);
+    const [frozenReceipt, frozenReceiptBump] = await findFrozenReceiptPDA(
+      vault,
+      gemMint
+    );
+    const [gemRarity, gemRarityBump] = await findRarityPDA(bank, gemMint);
+
+    const { meta, ownerTokenRecordPda, ruleSet, nftEditionPda } =
+      await this.prepPnftAccounts({
+        nftMint: gemMint,
+        sourceAta: gemAccount,
+        destAta: gemAccount,
+      });
+
+    const remainingAccounts = [];
+    if (!!ruleSet)
+      remainingAccounts.push({
+        pubkey: ruleSet,
+        isWritable: false,
+        isSigner: false,
+      });
+
+    const signers = [];
+    if (isKp(vaultOwner)) signers.push(<Keypair>vaultOwner);
+
+    console.log(
+      `thawing gem ${gemMint.toBase58()} in ${gemAccount.toBase58()}`
+    );
+    const builder = this.bankProgram.methods
+      .thawGem(
+        vaultAuthBump,
+        frozenReceiptBump,
+        gemRarityBump,
+        null,
+        !!ruleSet
+      )
+      .accounts({
+        bank,
+        vault,
+        owner: isKp(vaultOwner) ? (<Keypair>vaultOwner).publicKey : vaultOwner,
+        authority: vaultAuth,
+        gemAccount,
+        frozenGemReceipt: frozenReceipt,
+        gemMint,
+        gemRarity,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        gemMetadata: meta,
+        gemEdition: nftEditionPda,
+        gemTokenRecord: ownerTokenRecordPda,
+        pnftShared: {
+          authorizationRulesProgram: AUTH_PROG_ID,
+          tokenMetadataProgram: TMETA_PROG_ID,
+          instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        },
+      })
+      .remainingAccounts(remainingAccounts)
+      .signers(signers);
+
+    const [modifyComputeUnits, addPriorityFee] = getTotalComputeIxs(
+      400000,
+      1
+    );
+    const txSig = await buildAndSendTx({
+      provider: this.provider as AnchorProvider,
+      ixs: [modifyComputeUnits, addPriorityFee, await builder.instruction()],
+    });
+
+    return {
+      vaultAuth,
+      vaultAuthBump,
+      frozenReceipt,
+      frozenReceiptBump,
+      gemRarity,
+      gemRarityBump,
+      txSig,
+    };
+  }
+
+  // --------------------------------------- compressed nfts
+
+  // proof is the path from the leaf up, minus whatever's in the tree's canopy
+  async depositCnft(
+    bank: PublicKey,
+    vault: PublicKey,
+    vaultOwner: PublicKey | Keypair,
+    treeAuthority: PublicKey,
+    merkleTree: PublicKey,
+    root: number[],
+    leaf: CnftLeaf,
+    proof: PublicKey[],
+    leafDelegate?: PublicKey,
+    creators: CnftCreator[] = [],
+    mintProof?: PublicKey,
+    creatorProof?: PublicKey
+  ) {
+    const [assetId] = await findCnftAssetId(merkleTree, leaf.nonce);
+    const [vaultAuth, vaultAuthBump] = await findVaultAuthorityPDA(vault);
+    const [cnftReceipt, cnftReceiptBump] = await findCnftReceiptPDA(
+      vault,
+      assetId
+    );
+    const [gemRarity, gemRarityBump] = await findRarityPDA(bank, assetId);
+    const owner = isKp(vaultOwner)
+      ? (<Keypair>vaultOwner).publicKey
+      : <PublicKey>vaultOwner;
+
+    const signers = [];
+    if (isKp(vaultOwner)) signers.push(<Keypair>vaultOwner);
+
+    console.log(`depositing cnft ${assetId.toBase58()}`);
+    const txSig = await this.bankProgram.methods
+      .depositCnft(vaultAuthBump, gemRarityBump, root, leaf, creators)
+      .accounts({
+        bank,
+        vault,
+        owner,
+        authority: vaultAuth,
+        leafDelegate: leafDelegate ?? owner,
+        treeAuthority,
+        merkleTree,
+        cnftDepositReceipt: cnftReceipt,
+        gemRarity,
+        mintWhitelistProof: mintProof ?? null,
+        creatorWhitelistProof: creatorProof ?? null,
+        cnftShared: {
+          bubblegumProgram: BUBBLEGUM_PROG_ID,
+          compressionProgram: ACCOUNT_COMPRESSION_PROG_ID,
+          logWrapper: NOOP_PROG_ID,
+        },
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(
+        proof.map((pubkey) => ({ pubkey, isWritable: false, isSigner: false }))
+      )
+      .signers(signers)
+      .rpc();
+
+    return {
+      assetId,
+      vaultAuth,
+      vaultAuthBump,
+      cnftReceipt,
+      cnftReceiptBump,
+      gemRarity,
+      gemRarityBump,
+      txSig,
+    };
+  }
+
+  async withdrawCnft(
+    bank: PublicKey,
+    vault: PublicKey,
+    vaultOwner: PublicKey | Keypair,
+    treeAuthority: PublicKey,
+    merkleTree: PublicKey,
+    assetId: PublicKey,
+    root: number[],
+    proof: PublicKey[],
+    receiver: PublicKey
+  ) {
+    const [vaultAuth, vaultAuthBump] = await findVaultAuthorityPDA(vault);
+    const [cnftReceipt, cnftReceiptBump] = await findCnftReceiptPDA(
+      vault,
+      assetId
+    );
+    const [gemRarity, gemRarityBump] = await findRarityPDA(bank, assetId);
+
+    const signers = [];
+    if (isKp(vaultOwner)) signers.push(<Keypair>vaultOwner);
+
+    console.log(`withdrawing cnft ${assetId.toBase58()}`);
+    const txSig = await this.bankProgram.methods
+      .withdrawCnft(vaultAuthBump, cnftReceiptBump, gemRarityBump, root)
+      .accounts({
+        bank,
+        vault,
+        owner: isKp(vaultOwner) ? (<Keypair>vaultOwner).publicKey : vaultOwner,
+        authority: vaultAuth,
+        treeAuthority,
+        merkleTree,
+        cnftDepositReceipt: cnftReceipt,
+        gemRarity,
+        receiver,
+        cnftShared: {
+          bubblegumProgram: BUBBLEGUM_PROG_ID,
+          compressionProgram: ACCOUNT_COMPRESSION_PROG_ID,
+          logWrapper: NOOP_PROG_ID,
+        },
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(
+        proof.map((pubkey) => ({ pubkey, isWritable: false, isSigner: false }))
+      )
+      .signers(signers)
+      .rpc();
+
+    return {
+      vaultAuth,
+      vaultAuthBump,
+      cnftReceipt,
+      cnftReceiptBump,
+      gemRarity,
+      gemRarityBump,
+      txSig,
+    };
+  }
+
+  // --------------------------------------- metaplex core
+
+  // collection has to be passed if the asset is part of one
+  async depositCore(
+    bank: PublicKey,
+    vault: PublicKey,
+    vaultOwner: PublicKey | Keypair,
+    asset: PublicKey,
+    collection?: PublicKey,
+    mintProof?: PublicKey,
+    collectionProof?: PublicKey
+  ) {
+    const [vaultAuth, vaultAuthBump] = await findVaultAuthorityPDA(vault);
+    const [coreReceipt, coreReceiptBump] = await findCoreReceiptPDA(
+      vault,
+      asset
+    );
+    const [gemRarity, gemRarityBump] = await findRarityPDA(bank, asset);
+
+    // the asset proof always goes first, even if there's only a collection proof
+    const remainingAccounts = [];
+    if (mintProof || collectionProof)
+      remainingAccounts.push({
+        pubkey: mintProof ?? (await findWhitelistProofPDA(bank, asset))[0],
+        isWritable: false,
+        isSigner: false,
+      });
+    if (collectionProof)
+      remainingAccounts.push({
+        pubkey: collectionProof,
+        isWritable: false,
+        isSigner: false,
+      });
+
+    const signers = [];
+    if (isKp(vaultOwner)) signers.push(<Keypair>vaultOwner);
+
+    console.log(`depositing core asset ${asset.toBase58()}`);
+    const txSig = await this.bankProgram.methods
+      .depositCore(vaultAuthBump, gemRarityBump)
+      .accounts({
+        bank,
+        vault,
+        owner: isKp(vaultOwner) ? (<Keypair>vaultOwner).publicKey : vaultOwner,
+        authority: vaultAuth,
+        asset,
+        collection: collection ?? null,
+        coreDepositReceipt: coreReceipt,
+        gemRarity,
+        mplCoreProgram: MPL_CORE_PROG_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts(remainingAccounts)
+      .signers(signers)
+      .rpc();
+
+    return {
+      vaultAuth,
+      vaultAuthBump,
+      coreReceipt,
+      coreReceiptBump,
+      gemRarity,
+      gemRarityBump,
+      txSig,
+    };
+  }
+
+  async withdrawCore(
+    bank: PublicKey,
+    vault: PublicKey,
+    vaultOwner: PublicKey | Keypair,
+    asset: PublicKey,
+    receiver: PublicKey,
+    collection?: PublicKey
+  ) {
+    const [vaultAuth, vaultAuthBump] = await findVaultAuthorityPDA(vault);
+    const [coreReceipt, coreReceiptBump] = await findCoreReceiptPDA(
+      vault,
+      asset
+    );
+    const [gemRarity, gemRarityBump] = await findRarityPDA(bank, asset);
+
+    const signers = [];
+    if (isKp(vaultOwner)) signers.push(<Keypair>vaultOwner);
+
+    console.log(`withdrawing core asset ${asset.toBase58()}`);
+    const txSig = await this.bankProgram.methods
+      .withdrawCore(vaultAuthBump, coreReceiptBump, gemRarityBump)
+      .accounts({
+        bank,
+        vault,
+        owner: isKp(vaultOwner) ? (<Keypair>vaultOwner).publicKey : vaultOwner,
+        authority: vaultAuth,
+        asset,
+        collection: collection ?? null,
+        coreDepositReceipt: coreReceipt,
+        gemRarity,
+        receiver,
+        mplCoreProgram: MPL_CORE_PROG_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(signers)
+      .rpc();
+
+    return {
+      vaultAuth,
+      vaultAuthBump,
+      coreReceipt,
+      coreReceiptBump,
+      gemRarity,
+      gemRarityBump,
+      txSig,
+    };
+  }
+
   async addToWhitelist(
     bank: PublicKey,
     bankManager: PublicKey | Keypair,
@@ -42,3 +42,30 @@ export const findRarityPDA = async (bank: PublicKey, mint: PublicKey) => {
     GEM_BANK_PROG_ID
   );
 };
+
+export const findCoreReceiptPDA = async (vault: PublicKey, asset: PublicKey) => {
+  return PublicKey.findProgramAddress(
+    [Buffer.from('core_deposit_receipt'), vault.toBytes(), asset.toBytes()],
+    GEM_BANK_PROG_ID
+  );
+};
+
+export const findCnftReceiptPDA = async (
+  vault: PublicKey,
+  assetId: PublicKey
+) => {
+  return PublicKey.findProgramAddress(
+    [Buffer.from('cnft_deposit_receipt'), vault.toBytes(), assetId.toBytes()],
+    GEM_BANK_PROG_ID
+  );
+};
+
+export const findFrozenReceiptPDA = async (
+  vault: PublicKey,
+  mint: PublicKey
+) => {
+  return PublicKey.findProgramAddress(
+    [Buffer.from('frozen_gem_receipt'), vault.toBytes(), mint.toBytes()],
+    GEM_BANK_PROG_ID
+  );
+};
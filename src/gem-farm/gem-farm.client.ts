@@ -27,16 +27,21 @@ import {
 } from '@solana/spl-token';
 import {
   findAuthorizationProofPDA,
+  findFixedRateTiersPDA,
   findFarmAuthorityPDA,
   findFarmerPDA,
   findFarmTreasuryPDA,
+  findFunderContributionPDA,
+  findPendingActionPDA,
+  findProtocolConfigPDA,
   findRewardsPotPDA,
+  findRoleProofPDA,
 } from './gem-farm.pda';
 import { PROGRAM_ID as AUTH_PROG_ID } from '@metaplex-foundation/mpl-token-auth-rules/dist/src/generated';
 import { PROGRAM_ID as TMETA_PROG_ID } from '@metaplex-foundation/mpl-token-metadata/dist/src/generated';
 
-export const feeAccount = new PublicKey(
-  '2xhBxVVuXkdq2MRKerE9mr2s1szfHSedy21MVqf8gPoM'
+const BPF_LOADER_UPGRADEABLE_PROG_ID = new PublicKey(
+  'BPFLoaderUpgradeab1e11111111111111111111111'
 );
 
 //acts as an enum
@@ -45,6 +50,39 @@ export const RewardType = {
   Fixed: { fixed: {} },
 };
 
+//acts as a bitmask
+export const FarmRoles = {
+  WhitelistAdmin: 1 << 0,
+  RarityAdmin: 1 << 1,
+  Treasury: 1 << 2,
+  Config: 1 << 3,
+  FunderAdmin: 1 << 4,
+};
+
+// all optional - no mint = any reward, no max amount = no cap, no expiry = never expires
+// all in lamports, 0 = no fee
+export interface FeeSchedule {
+  initFarmFeeLamp: BN;
+  initFarmerFeeLamp: BN;
+  stakeFeeLamp: BN;
+  unstakeFeeLamp: BN;
+  flashDepositFeeLamp: BN;
+}
+
+// all optional - only what's passed gets updated
+export interface ProtocolConfigUpdate {
+  // only proposes the handover, the new admin has to accept it (see acceptProtocolAdmin)
+  admin?: PublicKey;
+  feeRecipient?: PublicKey;
+  fees?: FeeSchedule;
+}
+
+export interface FunderAllowance {
+  rewardMint?: PublicKey;
+  maxAmount?: BN;
+  expiresTs?: BN;
+}
+
 export interface FarmConfig {
   minStakingPeriodSec: BN;
   cooldownPeriodSec: BN;
@@ -57,28 +95,105 @@ export interface MaxCounts {
   maxRarityPoints: number;
 }
 
+export interface LockupTier {
+  lockupPeriodSec: BN;
+  multiplierBps: BN;
+}
+
+//acts as an enum
+export const PenaltyDestination = {
+  ReturnToFunder: { returnToFunder: {} },
+  Redistribute: { redistribute: {} },
+};
+
+export interface EarlyUnstakePenalty {
+  periodSec: BN;
+  penaltyBps: number;
+  destination: any; //PenaltyDestination
+}
+
 export interface TierConfig {
   rewardRate: BN;
   requiredTenure: BN;
 }
 
+export const MAX_FIXED_RATE_TIERS = 8;
+
 export interface FixedRateSchedule {
   baseRate: BN;
-  tier1: TierConfig | null;
-  tier2: TierConfig | null;
-  tier3: TierConfig | null;
+  tiers: TierConfig[]; //always MAX_FIXED_RATE_TIERS long, see buildFixedRateSchedule()
+  tierCount: BN;
   denominator: BN;
 }
 
+// pads the tiers out to the fixed size the program expects
+export function buildFixedRateSchedule(
+  baseRate: BN,
+  tiers: TierConfig[],
+  denominator: BN
+): FixedRateSchedule {
+  const padding = Array.from(
+    { length: MAX_FIXED_RATE_TIERS - tiers.length },
+    () => ({ rewardRate: new BN(0), requiredTenure: new BN(0) })
+  );
+  return {
+    baseRate,
+    tiers: [...tiers, ...padding],
+    tierCount: new BN(tiers.length),
+    denominator,
+  };
+}
+
 export interface FixedRateConfig {
   schedule: FixedRateSchedule;
   amount: BN;
   durationSec: BN;
+  startTs?: BN;
+}
+
+//acts as an enum
+export const CurveType = {
+  Constant: { constant: {} },
+  ExponentialDecay: { exponentialDecay: {} },
+  Halving: { halving: {} },
+  PiecewiseLinear: { piecewiseLinear: {} },
+};
+
+export interface CurvePoint {
+  offsetSec: BN;
+  weightBps: BN;
+}
+
+export interface EmissionCurve {
+  curveType: any; //CurveType
+  pointsLen: number;
+  periodSec: BN;
+  decayBps: BN;
+  points: CurvePoint[]; //always 4, unused ones zeroed out
 }
 
 export interface VariableRateConfig {
   amount: BN;
   durationSec: BN;
+  startTs?: BN;
+  curve?: EmissionCurve;
+}
+
+export interface RewardEpoch {
+  amount: BN;
+  durationSec: BN;
+}
+
+// claimed rewards unlock linearly after the cliff, durationSec 0 = no vesting
+export interface VestingConfig {
+  cliffSec: BN;
+  durationSec: BN;
+}
+
+export interface FarmRewardInit {
+  rewardMint: PublicKey;
+  rewardType: any; //RewardType instance
+  vesting?: VestingConfig;
 }
 
 export interface RarityConfig {
@@ -132,6 +247,31 @@ export class GemFarmClient extends GemBankClient {
     );
   }
 
+  async fetchFixedRateTiersAcc(farm: PublicKey) {
+    const [fixedRateTiers] = await findFixedRateTiersPDA(farm);
+    return (this.farmProgram.account as any).fixedRateTiers.fetch(
+      fixedRateTiers
+    );
+  }
+
+  async fetchProtocolConfigAcc() {
+    const [protocolConfig] = await findProtocolConfigPDA();
+    return (this.farmProgram.account as any).protocolConfig.fetch(
+      protocolConfig
+    );
+  }
+
+  // every fee-charging ix takes the config along with its current fee recipient
+  async findProtocolFeeAccounts() {
+    const [protocolConfig] = await findProtocolConfigPDA();
+    const { feeRecipient } = await this.fetchProtocolConfigAcc();
+    return { protocolConfig, feeAcc: feeRecipient };
+  }
+
+  async fetchPendingActionAcc(pendingAction: PublicKey) {
+    return this.farmProgram.account.pendingAction.fetch(pendingAction);
+  }
+
   async fetchTokenAcc(rewardMint: PublicKey, rewardAcc: PublicKey) {
     return this.deserializeTokenAccount(rewardMint, rewardAcc);
   }
@@ -206,17 +346,116 @@ export class GemFarmClient extends GemBankClient {
     return pdas;
   }
 
+  async fetchAllContributionPDAs(farm?: PublicKey, rewardMint?: PublicKey) {
+    const filter: any = [];
+    if (farm) {
+      filter.push({
+        memcmp: {
+          offset: 8, //need to prepend 8 bytes for anchor's disc
+          bytes: farm.toBase58(),
+        },
+      });
+    }
+    if (rewardMint) {
+      filter.push({
+        memcmp: {
+          offset: 72, //need to prepend 8 bytes for anchor's disc
+          bytes: rewardMint.toBase58(),
+        },
+      });
+    }
+    const pdas = await this.farmProgram.account.funderContribution.all(filter);
+    console.log(`found a total of ${pdas.length} funder contributions`);
+    return pdas;
+  }
+
+  // --------------------------------------- protocol ixs
+
+  // can only be called once, by the program's upgrade authority
+  async initProtocolConfig(
+    admin: PublicKey | Keypair,
+    feeRecipient: PublicKey,
+    fees: FeeSchedule
+  ) {
+    const [protocolConfig, protocolConfigBump] = await findProtocolConfigPDA();
+    const [programData] = await PublicKey.findProgramAddress(
+      [this.farmProgram.programId.toBytes()],
+      BPF_LOADER_UPGRADEABLE_PROG_ID
+    );
+
+    const signers = [];
+    if (isKp(admin)) signers.push(<Keypair>admin);
+
+    console.log('initializing protocol config');
+    const txSig = await this.farmProgram.methods
+      .initProtocolConfig(feeRecipient, fees)
+      .accounts({
+        protocolConfig,
+        admin: isKp(admin) ? (<Keypair>admin).publicKey : admin,
+        program: this.farmProgram.programId,
+        programData,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(signers)
+      .rpc();
+
+    return { protocolConfig, protocolConfigBump, txSig };
+  }
+
+  async updateProtocolConfig(
+    admin: PublicKey | Keypair,
+    update: ProtocolConfigUpdate
+  ) {
+    const [protocolConfig] = await findProtocolConfigPDA();
+
+    const signers = [];
+    if (isKp(admin)) signers.push(<Keypair>admin);
+
+    console.log('updating protocol config');
+    const txSig = await this.farmProgram.methods
+      .updateProtocolConfig(
+        update.admin ?? null,
+        update.feeRecipient ?? null,
+        update.fees ?? null
+      )
+      .accounts({
+        protocolConfig,
+        admin: isKp(admin) ? (<Keypair>admin).publicKey : admin,
+      })
+      .signers(signers)
+      .rpc();
+
+    return { protocolConfig, txSig };
+  }
+
+  async acceptProtocolAdmin(newAdmin: PublicKey | Keypair) {
+    const [protocolConfig] = await findProtocolConfigPDA();
+
+    const signers = [];
+    if (isKp(newAdmin)) signers.push(<Keypair>newAdmin);
+
+    console.log('accepting protocol admin handover');
+    const txSig = await this.farmProgram.methods
+      .acceptProtocolAdmin()
+      .accounts({
+        protocolConfig,
+        newAdmin: isKp(newAdmin) ? (<Keypair>newAdmin).publicKey : newAdmin,
+      })
+      .signers(signers)
+      .rpc();
+
+    return { protocolConfig, txSig };
+  }
+
   // --------------------------------------- core ixs
 
+  // one entry per reward slot taken up front, more can be added later (see addRewardSlot)
   async initFarm(
     farm: Keypair,
     farmManager: PublicKey | Keypair,
     payer: PublicKey | Keypair,
     bank: Keypair,
-    rewardAMint: PublicKey,
-    rewardAType: any, //RewardType instance
-    rewardBMint: PublicKey,
-    rewardBType: any, //RewardType instance
+    rewards: FarmRewardInit[],
     farmConfig: FarmConfig,
     maxCounts?: MaxCounts
   ) {
@@ -224,17 +463,28 @@ export class GemFarmClient extends GemBankClient {
     const [farmTreasury, farmTreasuryBump] = await findFarmTreasuryPDA(
       farm.publicKey
     );
-    const [rewardAPot, rewardAPotBump] = await findRewardsPotPDA(
-      farm.publicKey,
-      rewardAMint
-    );
-    const [rewardBPot, rewardBPotBump] = await findRewardsPotPDA(
-      farm.publicKey,
-      rewardBMint
+
+    const rewardPots = [];
+    const remainingAccounts = [];
+    for (const { rewardMint } of rewards) {
+      const [pot] = await findRewardsPotPDA(farm.publicKey, rewardMint);
+      rewardPots.push(pot);
+      remainingAccounts.push(
+        { pubkey: rewardMint, isWritable: false, isSigner: false },
+        { pubkey: pot, isWritable: true, isSigner: false }
+      );
+    }
+
+    //rewards without a vesting config don't vest
+    const vesting = rewards.map(
+      (r) =>
+        r.vesting ??
+        <VestingConfig>{ cliffSec: new BN(0), durationSec: new BN(0) }
     );
 
     const signers = [farm, bank];
     if (isKp(farmManager)) signers.push(<Keypair>farmManager);
+    if (isKp(payer)) signers.push(<Keypair>payer);
 
     console.log('starting farm at', farm.publicKey.toBase58());
 
@@ -242,8 +492,8 @@ export class GemFarmClient extends GemBankClient {
       .initFarm(
         farmAuthBump,
         farmTreasuryBump,
-        rewardAType,
-        rewardBType,
+        rewards.map((r) => r.rewardType),
+        vesting,
         farmConfig,
         maxCounts ?? null,
         farmTreasury
@@ -254,18 +504,16 @@ export class GemFarmClient extends GemBankClient {
           ? (<Keypair>farmManager).publicKey
           : farmManager,
         farmAuthority: farmAuth,
-        payer: isKp(payer) ? (<Keypair>payer).publicKey : farmManager,
-        feeAcc: feeAccount,
-        rewardAPot,
-        rewardAMint,
-        rewardBPot,
-        rewardBMint,
+        payer: isKp(payer) ? (<Keypair>payer).publicKey : payer,
+        ...(await this.findProtocolFeeAccounts()),
         bank: bank.publicKey,
         gemBank: this.bankProgram.programId,
         tokenProgram: TOKEN_PROGRAM_ID,
+        token2022Program: null,
         systemProgram: SystemProgram.programId,
         rent: anchor.web3.SYSVAR_RENT_PUBKEY,
       })
+      .remainingAccounts(remainingAccounts)
       .signers(signers)
       .rpc();
 
@@ -274,10 +522,7 @@ export class GemFarmClient extends GemBankClient {
       farmAuthBump,
       farmTreasury,
       farmTreasuryBump,
-      rewardAPot,
-      rewardAPotBump,
-      rewardBPot,
-      rewardBPotBump,
+      rewardPots,
       txSig,
     };
   }
@@ -287,19 +532,53 @@ export class GemFarmClient extends GemBankClient {
     farmManager: PublicKey | Keypair,
     config: FarmConfig | null = null,
     newManager: PublicKey | null = null,
-    maxCounts?: MaxCounts
+    maxCounts?: MaxCounts,
+    lockupTiers?: LockupTier[],
+    earlyUnstakePenalty?: EarlyUnstakePenalty,
+    perGemTenure?: boolean,
+    timelockSec?: BN,
+    pendingAction?: PublicKey
   ) {
     const signers = [];
     if (isKp(farmManager)) signers.push(<Keypair>farmManager);
 
     console.log('updating farm');
     const txSig = await this.farmProgram.methods
-      .updateFarm(config, newManager, maxCounts ?? null)
+      .updateFarm(
+        config,
+        newManager,
+        maxCounts ?? null,
+        lockupTiers ?? null,
+        earlyUnstakePenalty ?? null,
+        perGemTenure ?? null,
+        timelockSec ?? null
+      )
       .accounts({
         farm,
         farmManager: isKp(farmManager)
           ? (<Keypair>farmManager).publicKey
           : farmManager,
+        roleProof: await this.findRoleProofIfNeeded(farm, farmManager),
+        pendingAction: pendingAction ?? null,
+      })
+      .signers(signers)
+      .rpc();
+
+    return { txSig };
+  }
+
+  async acceptFarmManager(farm: PublicKey, newManager: PublicKey | Keypair) {
+    const signers = [];
+    if (isKp(newManager)) signers.push(<Keypair>newManager);
+
+    console.log('accepting farm manager handover');
+    const txSig = await this.farmProgram.methods
+      .acceptFarmManager()
+      .accounts({
+        farm,
+        newManager: isKp(newManager)
+          ? (<Keypair>newManager).publicKey
+          : newManager,
       })
       .signers(signers)
       .rpc();
@@ -311,7 +590,8 @@ export class GemFarmClient extends GemBankClient {
     farm: PublicKey,
     farmManager: PublicKey | Keypair,
     destination: PublicKey,
-    lamports: BN
+    lamports: BN,
+    pendingAction?: PublicKey
   ) {
     const [farmAuth, farmAuthBump] = await findFarmAuthorityPDA(farm);
     const [farmTreasury, farmTreasuryBump] = await findFarmTreasuryPDA(farm);
@@ -327,9 +607,11 @@ export class GemFarmClient extends GemBankClient {
         farmManager: isKp(farmManager)
           ? (<Keypair>farmManager).publicKey
           : farmManager,
+        roleProof: await this.findRoleProofIfNeeded(farm, farmManager),
         farmAuthority: farmAuth,
         farmTreasury,
         destination,
+        pendingAction: pendingAction ?? null,
         systemProgram: SystemProgram.programId,
       })
       .signers(signers)
@@ -369,6 +651,7 @@ export class GemFarmClient extends GemBankClient {
         farmManager: isKp(farmManager)
           ? (<Keypair>farmManager).publicKey
           : farmManager,
+        roleProof: await this.findRoleProofIfNeeded(farm, farmManager),
         farmAuthority: farmAuth,
         bank: farmAcc.bank,
         addressToWhitelist,
@@ -391,7 +674,8 @@ export class GemFarmClient extends GemBankClient {
   async removeFromBankWhitelist(
     farm: PublicKey,
     farmManager: PublicKey | Keypair,
-    addressToRemove: PublicKey
+    addressToRemove: PublicKey,
+    pendingAction?: PublicKey
   ) {
     const farmAcc = await this.fetchFarmAcc(farm);
 
@@ -412,11 +696,13 @@ export class GemFarmClient extends GemBankClient {
         farmManager: isKp(farmManager)
           ? (<Keypair>farmManager).publicKey
           : farmManager,
+        roleProof: await this.findRoleProofIfNeeded(farm, farmManager),
         farmAuthority: farmAuth,
         bank: farmAcc.bank,
         addressToRemove,
         whitelistProof,
         gemBank: this.bankProgram.programId,
+        pendingAction: pendingAction ?? null,
       })
       .signers(signers)
       .rpc();
@@ -430,6 +716,157 @@ export class GemFarmClient extends GemBankClient {
     };
   }
 
+  // in timelock mode, the above have to be queued first and executed with the pending action
+  // action is eg { payoutFromTreasury: { destination, lamports } }
+  async queueFarmAction(
+    farm: PublicKey,
+    farmManager: PublicKey | Keypair,
+    action: any
+  ) {
+    const farmAcc = await this.fetchFarmAcc(farm);
+    const actionId = farmAcc.lastActionId.add(new BN(1));
+    const [pendingAction, pendingActionBump] = await findPendingActionPDA(
+      farm,
+      actionId
+    );
+
+    const signers = [];
+    if (isKp(farmManager)) signers.push(<Keypair>farmManager);
+
+    console.log('queueing farm action', actionId.toString());
+    const txSig = await this.farmProgram.methods
+      .queueFarmAction(actionId, action)
+      .accounts({
+        farm,
+        farmManager: isKp(farmManager)
+          ? (<Keypair>farmManager).publicKey
+          : farmManager,
+        pendingAction,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(signers)
+      .rpc();
+
+    return { actionId, pendingAction, pendingActionBump, txSig };
+  }
+
+  async cancelFarmAction(
+    farm: PublicKey,
+    farmManager: PublicKey | Keypair,
+    pendingAction: PublicKey
+  ) {
+    const signers = [];
+    if (isKp(farmManager)) signers.push(<Keypair>farmManager);
+
+    console.log('cancelling farm action', pendingAction.toBase58());
+    const txSig = await this.farmProgram.methods
+      .cancelFarmAction()
+      .accounts({
+        farm,
+        farmManager: isKp(farmManager)
+          ? (<Keypair>farmManager).publicKey
+          : farmManager,
+        pendingAction,
+      })
+      .signers(signers)
+      .rpc();
+
+    return { txSig };
+  }
+
+  // roles are set as a bitmask, see FarmRoles
+  async grantRoles(
+    farm: PublicKey,
+    farmManager: PublicKey | Keypair,
+    holder: PublicKey,
+    roles: number
+  ) {
+    const [roleProof, roleProofBump] = await findRoleProofPDA(farm, holder);
+
+    const signers = [];
+    if (isKp(farmManager)) signers.push(<Keypair>farmManager);
+
+    console.log('granting roles to', holder.toBase58());
+    const txSig = await this.farmProgram.methods
+      .grantRoles(roles)
+      .accounts({
+        farm,
+        farmManager: isKp(farmManager)
+          ? (<Keypair>farmManager).publicKey
+          : farmManager,
+        holder,
+        roleProof,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(signers)
+      .rpc();
+
+    return { roleProof, roleProofBump, txSig };
+  }
+
+  async revokeRoles(
+    farm: PublicKey,
+    farmManager: PublicKey | Keypair,
+    holder: PublicKey
+  ) {
+    const [roleProof, roleProofBump] = await findRoleProofPDA(farm, holder);
+
+    const signers = [];
+    if (isKp(farmManager)) signers.push(<Keypair>farmManager);
+
+    console.log('revoking roles from', holder.toBase58());
+    const txSig = await this.farmProgram.methods
+      .revokeRoles(roleProofBump)
+      .accounts({
+        farm,
+        farmManager: isKp(farmManager)
+          ? (<Keypair>farmManager).publicKey
+          : farmManager,
+        holder,
+        roleProof,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(signers)
+      .rpc();
+
+    return { roleProof, roleProofBump, txSig };
+  }
+
+  // the manager doesn't need a role proof, anyone else acting on its behalf does
+  async findRoleProofIfNeeded(farm: PublicKey, signer: PublicKey | Keypair) {
+    const signerPk = isKp(signer)
+      ? (<Keypair>signer).publicKey
+      : <PublicKey>signer;
+    const farmAcc = await this.fetchFarmAcc(farm);
+    if (farmAcc.farmManager.equals(signerPk)) return null;
+
+    const [roleProof] = await findRoleProofPDA(farm, signerPk);
+    return roleProof;
+  }
+
+  // farms created before the reward slots have to be migrated before use,
+  // followed by each of their farmers (see migrateFarmer)
+  async migrateFarm(farm: PublicKey, payer: PublicKey | Keypair) {
+    const [fixedRateTiers] = await findFixedRateTiersPDA(farm);
+
+    const signers = [];
+    if (isKp(payer)) signers.push(<Keypair>payer);
+
+    console.log('migrating farm', farm.toBase58());
+    const txSig = await this.farmProgram.methods
+      .migrateFarm()
+      .accounts({
+        farm,
+        fixedRateTiers,
+        payer: isKp(payer) ? (<Keypair>payer).publicKey : payer,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(signers)
+      .rpc();
+
+    return { fixedRateTiers, txSig };
+  }
+
   // --------------------------------------- farmer ops ixs
 
   async initFarmer(
@@ -487,7 +924,7 @@ export class GemFarmClient extends GemBankClient {
         farmer,
         identity: identityPk,
         payer: isKp(payer) ? (<Keypair>payer).publicKey : payer,
-        feeAcc: feeAccount,
+        ...(await this.findProtocolFeeAccounts()),
         bank: farmAcc.bank,
         vault,
         gemBank: this.bankProgram.programId,
@@ -510,7 +947,8 @@ export class GemFarmClient extends GemBankClient {
     farm: PublicKey,
     farmerIdentity: PublicKey | Keypair,
     unstake = false,
-    skipRewards = false
+    skipRewards = false,
+    lockupTier = 0
   ) {
     const {
       farmer,
@@ -522,7 +960,13 @@ export class GemFarmClient extends GemBankClient {
       farmTreasury,
       farmTreasuryBump,
       builder,
-    } = await this.buildStakeCommon(farm, farmerIdentity, unstake, skipRewards);
+    } = await this.buildStakeCommon(
+      farm,
+      farmerIdentity,
+      unstake,
+      skipRewards,
+      lockupTier
+    );
 
     const txSig = await builder.rpc();
 
@@ -543,7 +987,8 @@ export class GemFarmClient extends GemBankClient {
     farm: PublicKey,
     farmerIdentity: PublicKey | Keypair,
     unstake = false,
-    skipRewards = false
+    skipRewards = false,
+    lockupTier = 0
   ) {
     const identityPk = isKp(farmerIdentity)
       ? (<Keypair>farmerIdentity).publicKey
@@ -564,6 +1009,7 @@ export class GemFarmClient extends GemBankClient {
           .unstake(farmAuthBump, farmTreasuryBump, farmerBump, skipRewards)
           .accounts({
             farm,
+            fixedRateTiers: await this.findFixedRateTiersIfNeeded(farm),
             farmer,
             farmTreasury,
             identity: identityPk,
@@ -572,20 +1018,21 @@ export class GemFarmClient extends GemBankClient {
             farmAuthority: farmAuth,
             gemBank: this.bankProgram.programId,
             systemProgram: SystemProgram.programId,
-            feeAcc: feeAccount,
+            ...(await this.findProtocolFeeAccounts()),
           })
           .signers(signers)
       : await this.farmProgram.methods
-          .stake(farmAuthBump, farmerBump)
+          .stake(farmAuthBump, farmerBump, lockupTier)
           .accounts({
             farm,
+            fixedRateTiers: await this.findFixedRateTiersIfNeeded(farm),
             farmer,
             identity: identityPk,
             bank: farmAcc.bank,
             vault,
             farmAuthority: farmAuth,
             gemBank: this.bankProgram.programId,
-            feeAcc: feeAccount,
+            ...(await this.findProtocolFeeAccounts()),
             systemProgram: SystemProgram.programId,
           })
           .signers(signers);
@@ -603,23 +1050,291 @@ export class GemFarmClient extends GemBankClient {
     };
   }
 
-  async stake(farm: PublicKey, farmerIdentity: PublicKey | Keypair) {
-    return this.stakeCommon(farm, farmerIdentity, false);
+  // lockup tier 0 = no lockup
+  async stake(
+    farm: PublicKey,
+    farmerIdentity: PublicKey | Keypair,
+    lockupTier = 0
+  ) {
+    return this.stakeCommon(farm, farmerIdentity, false, false, lockupTier);
+  }
+
+  async unstake(
+    farm: PublicKey,
+    farmerIdentity: PublicKey | Keypair,
+    skipRewards = false
+  ) {
+    return this.stakeCommon(farm, farmerIdentity, true, skipRewards);
+  }
+
+  // only for farms where the reward mint is also the gem mint
+  async compound(
+    farm: PublicKey,
+    farmerIdentity: PublicKey | Keypair,
+    rewardMint: PublicKey,
+    mintProof?: PublicKey
+  ) {
+    const identityPk = isKp(farmerIdentity)
+      ? (<Keypair>farmerIdentity).publicKey
+      : <PublicKey>farmerIdentity;
+
+    const farmAcc = await this.fetchFarmAcc(farm);
+
+    const [farmAuth, farmAuthBump] = await findFarmAuthorityPDA(farm);
+    const [farmer, farmerBump] = await findFarmerPDA(farm, identityPk);
+    const [vault] = await findVaultPDA(farmAcc.bank, identityPk);
+    const [pot] = await findRewardsPotPDA(farm, rewardMint);
+
+    const [gemBox] = await findGemBoxPDA(vault, rewardMint);
+    const [GDR] = await findGdrPDA(vault, rewardMint);
+    const [vaultAuth, vaultAuthBump] = await findVaultAuthorityPDA(vault);
+    const [gemRarity, gemRarityBump] = await findRarityPDA(
+      farmAcc.bank,
+      rewardMint
+    );
+    const gemSource = await this.findATA(rewardMint, identityPk);
+
+    const remainingAccounts = [];
+    if (mintProof)
+      remainingAccounts.push({
+        pubkey: mintProof,
+        isWritable: false,
+        isSigner: false,
+      });
+
+    const signers = [];
+    if (isKp(farmerIdentity)) signers.push(<Keypair>farmerIdentity);
+
+    const txSig = await this.farmProgram.methods
+      .compound(farmAuthBump, farmerBump, vaultAuthBump, gemRarityBump)
+      .accounts({
+        farm,
+        fixedRateTiers: await this.findFixedRateTiersIfNeeded(farm),
+        farmAuthority: farmAuth,
+        farmer,
+        identity: identityPk,
+        rewardPot: pot,
+        gemSource,
+        gemMint: rewardMint,
+        bank: farmAcc.bank,
+        vault,
+        vaultAuthority: vaultAuth,
+        gemBox,
+        gemDepositReceipt: GDR,
+        gemRarity,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        gemBank: this.bankProgram.programId,
+      })
+      .remainingAccounts(remainingAccounts)
+      .signers(signers)
+      .rpc();
+
+    return { farmer, vault, GDR, txSig };
+  }
+
+  // call once to start the gem's cooldown, then again once it's passed to withdraw it
+  async partialUnstake(
+    farm: PublicKey,
+    farmerIdentity: PublicKey | Keypair,
+    gemMint: PublicKey
+  ) {
+    const identityPk = isKp(farmerIdentity)
+      ? (<Keypair>farmerIdentity).publicKey
+      : <PublicKey>farmerIdentity;
+
+    const farmAcc = await this.fetchFarmAcc(farm);
+
+    const [farmer, farmerBump] = await findFarmerPDA(farm, identityPk);
+    const [vault] = await findVaultPDA(farmAcc.bank, identityPk);
+    const [farmAuth] = await findFarmAuthorityPDA(farm);
+    const [farmTreasury] = await findFarmTreasuryPDA(farm);
+
+    const [gemBox, gemBoxBump] = await findGemBoxPDA(vault, gemMint);
+    const [GDR, GDRBump] = await findGdrPDA(vault, gemMint);
+    const [vaultAuth, vaultAuthBump] = await findVaultAuthorityPDA(vault);
+    const [gemRarity, gemRarityBump] = await findRarityPDA(
+      farmAcc.bank,
+      gemMint
+    );
+    const gemDestination = await this.findATA(gemMint, identityPk);
+
+    const signers = [];
+    if (isKp(farmerIdentity)) signers.push(<Keypair>farmerIdentity);
+
+    const txSig = await this.farmProgram.methods
+      .partialUnstake(
+        farmerBump,
+        vaultAuthBump,
+        gemBoxBump,
+        GDRBump,
+        gemRarityBump
+      )
+      .accounts({
+        farm,
+        farmAuthority: farmAuth,
+        farmTreasury,
+        fixedRateTiers: await this.findFixedRateTiersIfNeeded(farm),
+        farmer,
+        identity: identityPk,
+        bank: farmAcc.bank,
+        vault,
+        vaultAuthority: vaultAuth,
+        gemBox,
+        gemDepositReceipt: GDR,
+        gemDestination,
+        gemMint,
+        gemRarity,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        gemBank: this.bankProgram.programId,
+        ...(await this.findProtocolFeeAccounts()),
+      })
+      .signers(signers)
+      .rpc();
+
+    return { farmer, vault, GDR, gemDestination, txSig };
+  }
+
+  // claims each of the rewards passed in, any subset of the farm's rewards
+  async claim(
+    farm: PublicKey,
+    farmerIdentity: PublicKey | Keypair,
+    rewardMints: PublicKey[]
+  ) {
+    const identityPk = isKp(farmerIdentity)
+      ? (<Keypair>farmerIdentity).publicKey
+      : <PublicKey>farmerIdentity;
+
+    const [farmAuth, farmAuthBump] = await findFarmAuthorityPDA(farm);
+    const [farmer, farmerBump] = await findFarmerPDA(farm, identityPk);
+
+    const rewardDestinations = [];
+    const remainingAccounts = [];
+    for (const rewardMint of rewardMints) {
+      const [pot] = await findRewardsPotPDA(farm, rewardMint);
+      const destination = await this.findATA(rewardMint, identityPk);
+      rewardDestinations.push(destination);
+      remainingAccounts.push(
+        { pubkey: rewardMint, isWritable: false, isSigner: false },
+        { pubkey: pot, isWritable: true, isSigner: false },
+        { pubkey: destination, isWritable: true, isSigner: false }
+      );
+    }
+
+    const signers = [];
+    if (isKp(farmerIdentity)) signers.push(<Keypair>farmerIdentity);
+
+    const txSig = await this.farmProgram.methods
+      .claim(farmAuthBump, farmerBump)
+      .accounts({
+        farm,
+        farmAuthority: farmAuth,
+        fixedRateTiers: await this.findFixedRateTiersIfNeeded(farm),
+        farmer,
+        identity: identityPk,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        token2022Program: null,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .remainingAccounts(remainingAccounts)
+      .signers(signers)
+      .rpc();
+
+    return {
+      farmAuth,
+      farmAuthBump,
+      farmer,
+      farmerBump,
+      rewardDestinations,
+      txSig,
+    };
+  }
+
+  // pass null to send rewards back to the identity
+  async setRewardRecipient(
+    farm: PublicKey,
+    farmerIdentity: PublicKey | Keypair,
+    rewardRecipient: PublicKey | null
+  ) {
+    const identityPk = isKp(farmerIdentity)
+      ? (<Keypair>farmerIdentity).publicKey
+      : <PublicKey>farmerIdentity;
+
+    const [farmer, farmerBump] = await findFarmerPDA(farm, identityPk);
+
+    const signers = [];
+    if (isKp(farmerIdentity)) signers.push(<Keypair>farmerIdentity);
+
+    const txSig = await this.farmProgram.methods
+      .setRewardRecipient(farmerBump, rewardRecipient)
+      .accounts({
+        farm,
+        farmer,
+        identity: identityPk,
+      })
+      .signers(signers)
+      .rpc();
+
+    return { farmer, txSig };
   }
 
-  async unstake(
+  // permissionless - anyone can push a farmer's rewards to their reward recipient
+  // (vesting rewards: only what has already unlocked)
+  async crankClaim(
     farm: PublicKey,
-    farmerIdentity: PublicKey | Keypair,
-    skipRewards = false
+    farmer: PublicKey,
+    rewardMints: PublicKey[]
   ) {
-    return this.stakeCommon(farm, farmerIdentity, true, skipRewards);
+    const [farmAuth, farmAuthBump] = await findFarmAuthorityPDA(farm);
+
+    const farmerAcc = await this.fetchFarmerAcc(farmer);
+    const rewardRecipient = farmerAcc.rewardRecipient.equals(PublicKey.default)
+      ? farmerAcc.identity
+      : farmerAcc.rewardRecipient;
+
+    const remainingAccounts = [];
+    for (const rewardMint of rewardMints) {
+      const [pot] = await findRewardsPotPDA(farm, rewardMint);
+      const destination = await this.findATA(rewardMint, rewardRecipient);
+      remainingAccounts.push(
+        { pubkey: rewardMint, isWritable: false, isSigner: false },
+        { pubkey: pot, isWritable: true, isSigner: false },
+        { pubkey: destination, isWritable: true, isSigner: false }
+      );
+    }
+
+    const txSig = await this.farmProgram.methods
+      .crankClaim(farmAuthBump)
+      .accounts({
+        farm,
+        fixedRateTiers: await this.findFixedRateTiersIfNeeded(farm),
+        farmAuthority: farmAuth,
+        farmer,
+        rewardRecipient,
+        payer: this.wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .remainingAccounts(remainingAccounts)
+      .rpc();
+
+    return { farmAuth, rewardRecipient, txSig };
   }
 
-  async claim(
+  // withdraws whatever has unlocked so far, for each of the vesting rewards passed in
+  async withdrawVested(
     farm: PublicKey,
     farmerIdentity: PublicKey | Keypair,
-    rewardAMint: PublicKey,
-    rewardBMint: PublicKey
+    rewardMints: PublicKey[]
   ) {
     const identityPk = isKp(farmerIdentity)
       ? (<Keypair>farmerIdentity).publicKey
@@ -628,49 +1343,38 @@ export class GemFarmClient extends GemBankClient {
     const [farmAuth, farmAuthBump] = await findFarmAuthorityPDA(farm);
     const [farmer, farmerBump] = await findFarmerPDA(farm, identityPk);
 
-    const [potA, potABump] = await findRewardsPotPDA(farm, rewardAMint);
-    const [potB, potBBump] = await findRewardsPotPDA(farm, rewardBMint);
-
-    const rewardADestination = await this.findATA(rewardAMint, identityPk);
-    const rewardBDestination = await this.findATA(rewardBMint, identityPk);
+    const remainingAccounts = [];
+    for (const rewardMint of rewardMints) {
+      const [pot] = await findRewardsPotPDA(farm, rewardMint);
+      const destination = await this.findATA(rewardMint, identityPk);
+      remainingAccounts.push(
+        { pubkey: rewardMint, isWritable: false, isSigner: false },
+        { pubkey: pot, isWritable: true, isSigner: false },
+        { pubkey: destination, isWritable: true, isSigner: false }
+      );
+    }
 
     const signers = [];
     if (isKp(farmerIdentity)) signers.push(<Keypair>farmerIdentity);
 
     const txSig = await this.farmProgram.methods
-      .claim(farmAuthBump, farmerBump, potABump, potBBump)
+      .withdrawVested(farmAuthBump, farmerBump)
       .accounts({
         farm,
         farmAuthority: farmAuth,
         farmer,
         identity: identityPk,
-        rewardAPot: potA,
-        rewardAMint,
-        rewardADestination,
-        rewardBPot: potB,
-        rewardBMint,
-        rewardBDestination,
         tokenProgram: TOKEN_PROGRAM_ID,
+        token2022Program: null,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
         rent: anchor.web3.SYSVAR_RENT_PUBKEY,
       })
+      .remainingAccounts(remainingAccounts)
       .signers(signers)
       .rpc();
 
-    return {
-      farmAuth,
-      farmAuthBump,
-      farmer,
-      farmerBump,
-      potA,
-      potABump,
-      potB,
-      potBBump,
-      rewardADestination,
-      rewardBDestination,
-      txSig,
-    };
+    return { farmAuth, farmer, txSig };
   }
 
   async flashDeposit(
@@ -734,6 +1438,7 @@ export class GemFarmClient extends GemBankClient {
         accounts: {
           farm,
           farmAuthority: farmAuth,
+          fixedRateTiers: await this.findFixedRateTiersIfNeeded(farm),
           farmer,
           identity: identityPk,
           bank: farmAcc.bank,
@@ -748,7 +1453,7 @@ export class GemFarmClient extends GemBankClient {
           systemProgram: SystemProgram.programId,
           rent: anchor.web3.SYSVAR_RENT_PUBKEY,
           gemBank: this.bankProgram.programId,
-          feeAcc: feeAccount,
+          ...(await this.findProtocolFeeAccounts()),
         },
         remainingAccounts,
       }
@@ -865,6 +1570,7 @@ export class GemFarmClient extends GemBankClient {
         accounts: {
           farm,
           farmAuthority: farmAuth,
+          fixedRateTiers: await this.findFixedRateTiersIfNeeded(farm),
           farmer,
           identity: identityPk,
           bank: farmAcc.bank,
@@ -879,7 +1585,7 @@ export class GemFarmClient extends GemBankClient {
           systemProgram: SystemProgram.programId,
           rent: anchor.web3.SYSVAR_RENT_PUBKEY,
           gemBank: this.bankProgram.programId,
-          feeAcc: feeAccount,
+          ...(await this.findProtocolFeeAccounts()),
           gemMetadata: meta,
           gemEdition: nftEditionPda,
           ownerTokenRecord: ownerTokenRecordPda,
@@ -952,6 +1658,7 @@ export class GemFarmClient extends GemBankClient {
         .refreshFarmerSigned(farmerBump, reenroll)
         .accounts({
           farm,
+          fixedRateTiers: await this.findFixedRateTiersIfNeeded(farm),
           farmer,
           identity: identityPk,
         })
@@ -963,6 +1670,7 @@ export class GemFarmClient extends GemBankClient {
         .refreshFarmer(farmerBump)
         .accounts({
           farm,
+          fixedRateTiers: await this.findFixedRateTiersIfNeeded(farm),
           farmer,
           identity: identityPk,
         })
@@ -976,13 +1684,43 @@ export class GemFarmClient extends GemBankClient {
     };
   }
 
+  // the farm has to have been migrated first (see migrateFarm)
+  async migrateFarmer(
+    farm: PublicKey,
+    farmerIdentity: PublicKey,
+    payer: PublicKey | Keypair
+  ) {
+    const [farmer, farmerBump] = await findFarmerPDA(farm, farmerIdentity);
+
+    const signers = [];
+    if (isKp(payer)) signers.push(<Keypair>payer);
+
+    console.log('migrating farmer', farmerIdentity.toBase58());
+    const txSig = await this.farmProgram.methods
+      .migrateFarmer(farmerBump)
+      .accounts({
+        farm,
+        identity: farmerIdentity,
+        farmer,
+        fixedRateTiers: await this.findFixedRateTiersIfNeeded(farm),
+        payer: isKp(payer) ? (<Keypair>payer).publicKey : payer,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(signers)
+      .rpc();
+
+    return { farmer, farmerBump, txSig };
+  }
+
   // --------------------------------------- funder ops ixs
 
   async authorizeCommon(
     farm: PublicKey,
     farmManager: PublicKey | Keypair,
     funder: PublicKey,
-    deauthorize = false
+    deauthorize = false,
+    allowance: FunderAllowance = {},
+    pendingAction?: PublicKey
   ) {
     const [authorizationProof, authorizationProofBump] =
       await findAuthorizationProofPDA(farm, funder);
@@ -1000,8 +1738,10 @@ export class GemFarmClient extends GemBankClient {
           farmManager: isKp(farmManager)
             ? (<Keypair>farmManager).publicKey
             : farmManager,
+          roleProof: await this.findRoleProofIfNeeded(farm, farmManager),
           funderToDeauthorize: funder,
           authorizationProof,
+          pendingAction: pendingAction ?? null,
           systemProgram: SystemProgram.programId,
         })
         .signers(signers)
@@ -1009,14 +1749,20 @@ export class GemFarmClient extends GemBankClient {
     } else {
       console.log('authorizing funder', funder.toBase58());
       txSig = await this.farmProgram.methods
-        .authorizeFunder()
+        .authorizeFunder(
+          allowance.rewardMint ?? null,
+          allowance.maxAmount ?? null,
+          allowance.expiresTs ?? null
+        )
         .accounts({
           farm,
           farmManager: isKp(farmManager)
             ? (<Keypair>farmManager).publicKey
             : farmManager,
+          roleProof: await this.findRoleProofIfNeeded(farm, farmManager),
           funderToAuthorize: funder,
           authorizationProof,
+          pendingAction: pendingAction ?? null,
           systemProgram: SystemProgram.programId,
         })
         .signers(signers)
@@ -1029,17 +1775,61 @@ export class GemFarmClient extends GemBankClient {
   async authorizeFunder(
     farm: PublicKey,
     farmManager: PublicKey | Keypair,
-    funderToAuthorize: PublicKey
+    funderToAuthorize: PublicKey,
+    allowance?: FunderAllowance,
+    pendingAction?: PublicKey
   ) {
-    return this.authorizeCommon(farm, farmManager, funderToAuthorize, false);
+    return this.authorizeCommon(
+      farm,
+      farmManager,
+      funderToAuthorize,
+      false,
+      allowance,
+      pendingAction
+    );
   }
 
   async deauthorizeFunder(
     farm: PublicKey,
     farmManager: PublicKey | Keypair,
-    funderToDeauthorize: PublicKey
+    funderToDeauthorize: PublicKey,
+    pendingAction?: PublicKey
+  ) {
+    return this.authorizeCommon(
+      farm,
+      farmManager,
+      funderToDeauthorize,
+      true,
+      {},
+      pendingAction
+    );
+  }
+
+  async migrateAuthorizationProof(
+    farm: PublicKey,
+    authorizedFunder: PublicKey,
+    payer: PublicKey | Keypair
   ) {
-    return this.authorizeCommon(farm, farmManager, funderToDeauthorize, true);
+    const [authorizationProof, authorizationProofBump] =
+      await findAuthorizationProofPDA(farm, authorizedFunder);
+
+    const signers = [];
+    if (isKp(payer)) signers.push(<Keypair>payer);
+
+    console.log('migrating authorization proof', authorizationProof.toBase58());
+    const txSig = await this.farmProgram.methods
+      .migrateAuthorizationProof(authorizationProofBump)
+      .accounts({
+        farm,
+        authorizedFunder,
+        authorizationProof,
+        payer: isKp(payer) ? (<Keypair>payer).publicKey : payer,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(signers)
+      .rpc();
+
+    return { authorizationProof, authorizationProofBump, txSig };
   }
 
   // --------------------------------------- reward ops ixs
@@ -1060,6 +1850,12 @@ export class GemFarmClient extends GemBankClient {
     const [authorizationProof, authorizationProofBump] =
       await findAuthorizationProofPDA(farm, funderPk);
     const [pot, potBump] = await findRewardsPotPDA(farm, rewardMint);
+    const [contribution] = await findFunderContributionPDA(
+      farm,
+      rewardMint,
+      funderPk
+    );
+    const [fixedRateTiers] = await findFixedRateTiersPDA(farm);
 
     const signers = [];
     if (isKp(funder)) signers.push(<Keypair>funder);
@@ -1076,6 +1872,8 @@ export class GemFarmClient extends GemBankClient {
         farm,
         authorizationProof,
         authorizedFunder: funderPk,
+        contribution,
+        fixedRateTiers,
         rewardPot: pot,
         rewardSource,
         rewardMint,
@@ -1096,11 +1894,75 @@ export class GemFarmClient extends GemBankClient {
     };
   }
 
+  async queueRewardEpochs(
+    farm: PublicKey,
+    rewardMint: PublicKey,
+    funder: PublicKey | Keypair,
+    rewardSource: PublicKey,
+    epochs: RewardEpoch[]
+  ) {
+    const funderPk = isKp(funder)
+      ? (<Keypair>funder).publicKey
+      : <PublicKey>funder;
+
+    const [authorizationProof, authorizationProofBump] =
+      await findAuthorizationProofPDA(farm, funderPk);
+    const [pot, potBump] = await findRewardsPotPDA(farm, rewardMint);
+    const [contribution] = await findFunderContributionPDA(
+      farm,
+      rewardMint,
+      funderPk
+    );
+    const signers = [];
+    if (isKp(funder)) signers.push(<Keypair>funder);
+
+    console.log('queueing reward epochs in pot', pot.toBase58());
+    const txSig = await this.farmProgram.methods
+      .queueRewardEpochs(authorizationProofBump, potBump, epochs)
+      .accounts({
+        farm,
+        authorizationProof,
+        authorizedFunder: funderPk,
+        contribution,
+        rewardPot: pot,
+        rewardSource,
+        rewardMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(signers)
+      .rpc();
+
+    return {
+      authorizationProof,
+      authorizationProofBump,
+      pot,
+      potBump,
+      txSig,
+    };
+  }
+
+  // farmers only keep track of which fixed rate schedule they were promised,
+  // so the account has to be passed whenever the farm has a fixed rate reward
+  // (it's only created once one has been funded)
+  async findFixedRateTiersIfNeeded(farm: PublicKey) {
+    const farmAcc = await this.fetchFarmAcc(farm);
+    const hasFixedRate = (farmAcc.rewards as any[]).some(
+      (r) => !!r.rewardType.fixed
+    );
+    if (!hasFixedRate) return null;
+
+    const [fixedRateTiers] = await findFixedRateTiersPDA(farm);
+    const tiersAcc = await this.conn.getAccountInfo(fixedRateTiers);
+    return tiersAcc ? fixedRateTiers : null;
+  }
+
   async cancelReward(
     farm: PublicKey,
     farmManager: PublicKey | Keypair,
     rewardMint: PublicKey,
-    receiver: PublicKey
+    receiver: PublicKey,
+    pendingAction?: PublicKey
   ) {
     const [farmAuth, farmAuthBump] = await findFarmAuthorityPDA(farm);
     const [pot, potBump] = await findRewardsPotPDA(farm, rewardMint);
@@ -1121,6 +1983,7 @@ export class GemFarmClient extends GemBankClient {
         rewardDestination,
         rewardMint,
         receiver,
+        pendingAction: pendingAction ?? null,
         tokenProgram: TOKEN_PROGRAM_ID,
         associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
@@ -1139,6 +2002,64 @@ export class GemFarmClient extends GemBankClient {
     };
   }
 
+  // every funder of the reward has to be passed in, see fetchAllContributionPDAs
+  async cancelRewardProRata(
+    farm: PublicKey,
+    farmManager: PublicKey | Keypair,
+    rewardMint: PublicKey,
+    funders: PublicKey[],
+    pendingAction?: PublicKey
+  ) {
+    const [farmAuth, farmAuthBump] = await findFarmAuthorityPDA(farm);
+    const [pot, potBump] = await findRewardsPotPDA(farm, rewardMint);
+
+    const remainingAccounts = [];
+    for (const funder of funders) {
+      const [contribution] = await findFunderContributionPDA(
+        farm,
+        rewardMint,
+        funder
+      );
+      const funderDestination = await this.findATA(rewardMint, funder);
+      remainingAccounts.push(
+        { pubkey: contribution, isWritable: true, isSigner: false },
+        { pubkey: funder, isWritable: false, isSigner: false },
+        { pubkey: funderDestination, isWritable: true, isSigner: false }
+      );
+    }
+
+    const signers = [];
+    if (isKp(farmManager)) signers.push(<Keypair>farmManager);
+
+    const txSig = await this.farmProgram.methods
+      .cancelRewardProRata(farmAuthBump, potBump)
+      .accounts({
+        farm,
+        farmManager: isKp(farmManager)
+          ? (<Keypair>farmManager).publicKey
+          : farmManager,
+        farmAuthority: farmAuth,
+        rewardPot: pot,
+        rewardMint,
+        pendingAction: pendingAction ?? null,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .remainingAccounts(remainingAccounts)
+      .signers(signers)
+      .rpc();
+
+    return {
+      farmAuth,
+      farmAuthBump,
+      pot,
+      potBump,
+      txSig,
+    };
+  }
+
   async lockReward(
     farm: PublicKey,
     farmManager: PublicKey | Keypair,
@@ -1162,6 +2083,67 @@ export class GemFarmClient extends GemBankClient {
     return { txSig };
   }
 
+  // vesting can only be set when the slot is taken
+  async addRewardSlot(
+    farm: PublicKey,
+    farmManager: PublicKey | Keypair,
+    rewardMint: PublicKey,
+    rewardType: any, //RewardType instance
+    vesting?: VestingConfig,
+    pendingAction?: PublicKey
+  ) {
+    const [farmAuth, farmAuthBump] = await findFarmAuthorityPDA(farm);
+    const [rewardPot] = await findRewardsPotPDA(farm, rewardMint);
+
+    const signers = [];
+    if (isKp(farmManager)) signers.push(<Keypair>farmManager);
+
+    const txSig = await this.farmProgram.methods
+      .addRewardSlot(farmAuthBump, rewardType, vesting ?? null)
+      .accounts({
+        farm,
+        farmManager: isKp(farmManager)
+          ? (<Keypair>farmManager).publicKey
+          : farmManager,
+        farmAuthority: farmAuth,
+        rewardPot,
+        rewardMint,
+        pendingAction: pendingAction ?? null,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers(signers)
+      .rpc();
+
+    return { farmAuth, rewardPot, txSig };
+  }
+
+  async retireRewardSlot(
+    farm: PublicKey,
+    farmManager: PublicKey | Keypair,
+    rewardMint: PublicKey,
+    pendingAction?: PublicKey
+  ) {
+    const signers = [];
+    if (isKp(farmManager)) signers.push(<Keypair>farmManager);
+
+    const txSig = await this.farmProgram.methods
+      .retireRewardSlot()
+      .accounts({
+        farm,
+        farmManager: isKp(farmManager)
+          ? (<Keypair>farmManager).publicKey
+          : farmManager,
+        rewardMint,
+        pendingAction: pendingAction ?? null,
+      })
+      .signers(signers)
+      .rpc();
+
+    return { txSig };
+  }
+
   // --------------------------------------- rarity
 
   async addRaritiesToBank(
@@ -1205,6 +2187,7 @@ export class GemFarmClient extends GemBankClient {
         farmManager: isKp(farmManager)
           ? (<Keypair>farmManager).publicKey
           : farmManager,
+        roleProof: await this.findRoleProofIfNeeded(farm, farmManager),
         farmAuthority: farmAuth,
         bank,
         gemBank: this.bankProgram.programId,
@@ -1223,6 +2206,50 @@ export class GemFarmClient extends GemBankClient {
     };
   }
 
+  async removeRaritiesFromBank(
+    farm: PublicKey,
+    farmManager: PublicKey | Keypair,
+    gemMints: PublicKey[],
+    pendingAction?: PublicKey
+  ) {
+    const farmAcc = await this.fetchFarmAcc(farm);
+    const bank = farmAcc.bank;
+
+    const [farmAuth, farmAuthBump] = await findFarmAuthorityPDA(farm);
+
+    const remainingAccounts = [];
+    for (const mint of gemMints) {
+      const [gemRarity] = await findRarityPDA(bank, mint);
+      remainingAccounts.push(
+        { pubkey: mint, isWritable: false, isSigner: false },
+        { pubkey: gemRarity, isWritable: true, isSigner: false }
+      );
+    }
+
+    const signers = [];
+    if (isKp(farmManager)) signers.push(<Keypair>farmManager);
+
+    console.log("removing rarities from farm's bank");
+    const txSig = await this.farmProgram.methods
+      .removeRaritiesFromBank(farmAuthBump)
+      .accounts({
+        farm,
+        farmManager: isKp(farmManager)
+          ? (<Keypair>farmManager).publicKey
+          : farmManager,
+        roleProof: await this.findRoleProofIfNeeded(farm, farmManager),
+        farmAuthority: farmAuth,
+        bank,
+        gemBank: this.bankProgram.programId,
+        pendingAction: pendingAction ?? null,
+      })
+      .remainingAccounts(remainingAccounts)
+      .signers(signers)
+      .rpc();
+
+    return { bank, farmAuth, farmAuthBump, txSig };
+  }
+
   // --------------------------------------- helpers
 
   //returns "variable" or "fixed"
@@ -1,3 +1,4 @@
+import { BN } from '@project-serum/anchor';
 import { PublicKey } from '@solana/web3.js';
 import { GEM_FARM_PROG_ID } from '../index';
 
@@ -29,9 +30,57 @@ export const findAuthorizationProofPDA = (
   );
 };
 
+export const findPendingActionPDA = (farm: PublicKey, actionId: BN) => {
+  return PublicKey.findProgramAddress(
+    [
+      Buffer.from('pending_action'),
+      farm.toBytes(),
+      actionId.toArrayLike(Buffer, 'le', 8),
+    ],
+    GEM_FARM_PROG_ID
+  );
+};
+
+export const findFunderContributionPDA = (
+  farm: PublicKey,
+  rewardMint: PublicKey,
+  funder: PublicKey
+) => {
+  return PublicKey.findProgramAddress(
+    [
+      Buffer.from('contribution'),
+      farm.toBytes(),
+      rewardMint.toBytes(),
+      funder.toBytes(),
+    ],
+    GEM_FARM_PROG_ID
+  );
+};
+
+export const findRoleProofPDA = (farm: PublicKey, holder: PublicKey) => {
+  return PublicKey.findProgramAddress(
+    [Buffer.from('role'), farm.toBytes(), holder.toBytes()],
+    GEM_FARM_PROG_ID
+  );
+};
+
 export const findRewardsPotPDA = (farm: PublicKey, rewardMint: PublicKey) => {
   return PublicKey.findProgramAddress(
     [Buffer.from('reward_pot'), farm.toBytes(), rewardMint.toBytes()],
     GEM_FARM_PROG_ID
   );
 };
+
+export const findFixedRateTiersPDA = (farm: PublicKey) => {
+  return PublicKey.findProgramAddress(
+    [Buffer.from('fixed_rate_tiers'), farm.toBytes()],
+    GEM_FARM_PROG_ID
+  );
+};
+
+export const findProtocolConfigPDA = () => {
+  return PublicKey.findProgramAddress(
+    [Buffer.from('protocol_config')],
+    GEM_FARM_PROG_ID
+  );
+};
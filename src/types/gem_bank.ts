@@ -260,7 +260,7 @@ export type GemBank = {
         },
         {
           "name": "gemMint",
-          "isMut": false,
+          "isMut": true,
           "isSigner": false
         },
         {
@@ -1442,7 +1442,7 @@ export const IDL: GemBank = {
         },
         {
           "name": "gemMint",
-          "isMut": false,
+          "isMut": true,
           "isSigner": false
         },
         {
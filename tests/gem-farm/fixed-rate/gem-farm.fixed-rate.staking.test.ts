@@ -7,6 +7,7 @@ import {
 } from '../gem-farm.tester';
 import { BN } from '@project-serum/anchor';
 import {
+  buildFixedRateSchedule,
   FixedRateConfig,
   pause,
   RewardType,
@@ -19,13 +20,7 @@ import { createMetadata } from '../../metaplex';
 chai.use(chaiAsPromised);
 
 const shortFixedConfig = <FixedRateConfig>{
-  schedule: {
-    baseRate: toBN(3),
-    tier1: null,
-    tier2: null,
-    tier3: null,
-    denominator: toBN(1),
-  },
+  schedule: buildFixedRateSchedule(toBN(3), [], toBN(1)),
   amount: new BN(30000),
   durationSec: new BN(5), //5s only
 };
@@ -45,6 +40,20 @@ describe('staking (fixed rate)', () => {
     await gf.callFundReward(undefined, defaultFixedConfig);
   });
 
+  it('keeps the schedule in its own account', async () => {
+    const farmAcc = (await gf.fetchFarm()) as any;
+    const reward = farmAcc.rewards[0];
+    assert(reward.fixedRate.tierCount.eq(toBN(3)));
+
+    const tiersAcc = await gf.fetchFixedRateTiersAcc(gf.farm.publicKey);
+    const entry = tiersAcc.rewards.find((r: any) =>
+      r.rewardId.eq(reward.rewardId)
+    );
+    const { schedule } = entry.schedules[entry.current.toNumber()];
+    assert(schedule.tierCount.eq(toBN(3)));
+    assert(schedule.tiers[1].rewardRate.eq(toBN(7)));
+  });
+
   it('stakes -> accrues -> claims (multi farmer)', async () => {
     // ----------------- stake + accrue
     await gf.stakeAndVerify(gf.farmer1Identity);
@@ -224,11 +233,7 @@ describe('staking (fixed rate)', () => {
       )
     );
     const oldEndTs = farmerAcc.minStakingEndsTs;
-    const originalBeginStakingTs =
-      farmerAcc[gf.reward].fixedRate.beginStakingTs;
-    const originalBeginScheduleTs =
-      farmerAcc[gf.reward].fixedRate.beginScheduleTs;
-    const originalDuration = farmerAcc[gf.reward].fixedRate.promisedDuration;
+    const original = farmerAcc.rewards[gf.rewardIndex].fixedRate;
 
     //wait for 1 sec so that flash deposit staking time is recorded as different
     await pause(1000);
@@ -262,12 +267,11 @@ describe('staking (fixed rate)', () => {
     assert(farmerAcc.minStakingEndsTs.gt(oldEndTs));
 
     //check to make sure schedule renewed, but original staking TS preserved
-    const newBeginStakingTs = farmerAcc[gf.reward].fixedRate.beginStakingTs;
-    const newBeginScheduleTs = farmerAcc[gf.reward].fixedRate.beginScheduleTs;
-    const newDuration = farmerAcc[gf.reward].fixedRate.promisedDuration;
-    assert(originalBeginStakingTs.eq(newBeginStakingTs));
-    assert(originalBeginScheduleTs.lt(newBeginScheduleTs));
-    assert(originalDuration.gt(newDuration)); //since less time left on schedule
+    const renewed = farmerAcc.rewards[gf.rewardIndex].fixedRate;
+    assert(original.beginStakingTs.eq(renewed.beginStakingTs));
+    assert(original.beginScheduleTs.lt(renewed.beginScheduleTs));
+    //since less time left on schedule
+    assert(original.promisedDuration.gt(renewed.promisedDuration));
   });
 
   it('flash deposit works when farmer NOT staked beforehand', async () => {
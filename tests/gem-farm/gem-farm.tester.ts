@@ -1,6 +1,8 @@
 import { Keypair, LAMPORTS_PER_SOL, PublicKey } from '@solana/web3.js';
 import {
+  buildFixedRateSchedule,
   FarmConfig,
+  FeeSchedule,
   findFarmerPDA,
   findVaultPDA,
   FixedRateConfig,
@@ -37,28 +39,29 @@ export const defaultVariableConfig = <VariableRateConfig>{
 };
 
 export const defaultFixedConfig = <FixedRateConfig>{
-  schedule: {
-    //total 30 per gem
-    baseRate: toBN(3),
-    tier1: {
-      rewardRate: toBN(5),
-      requiredTenure: toBN(2),
-    },
-    tier2: {
-      rewardRate: toBN(7),
-      requiredTenure: toBN(4),
-    },
-    //leaving this one at 0 so that it's easy to test how much accrued over first 6s
-    tier3: {
-      rewardRate: toBN(0),
-      requiredTenure: toBN(6),
-    },
-    denominator: toBN(1),
-  },
+  //total 30 per gem
+  schedule: buildFixedRateSchedule(
+    toBN(3),
+    [
+      { rewardRate: toBN(5), requiredTenure: toBN(2) },
+      { rewardRate: toBN(7), requiredTenure: toBN(4) },
+      //leaving this one at 0 so that it's easy to test how much accrued over first 6s
+      { rewardRate: toBN(0), requiredTenure: toBN(6) },
+    ],
+    toBN(1)
+  ),
   amount: new BN(30000), //fund 1000 gems
   durationSec: new BN(100),
 };
 
+export const defaultFees = <FeeSchedule>{
+  initFarmFeeLamp: new BN(2.5 * LAMPORTS_PER_SOL),
+  initFarmerFeeLamp: new BN(0.01 * LAMPORTS_PER_SOL),
+  stakeFeeLamp: new BN(0.002 * LAMPORTS_PER_SOL),
+  unstakeFeeLamp: new BN(0.001 * LAMPORTS_PER_SOL),
+  flashDepositFeeLamp: new BN(0.002 * LAMPORTS_PER_SOL),
+};
+
 // --------------------------------------- tester class
 
 export class GemFarmTester extends GemFarmClient {
@@ -77,7 +80,8 @@ export class GemFarmTester extends GemFarmClient {
   farmer2Vault!: PublicKey;
 
   //rewards + funder
-  reward = 'rewardA';
+  //slot the reward under test (rewardMint) sits in - the second mint takes the other one
+  rewardIndex = 0;
   rewardMint!: Token;
   rewardSource!: PublicKey;
   rewardSecondMint!: Token;
@@ -103,14 +107,31 @@ export class GemFarmTester extends GemFarmClient {
     this.funder = this.nw.wallet.payer;
   }
 
+  // the config is program-wide - whichever test file runs first creates it
+  async ensureProtocolConfig() {
+    try {
+      return await this.fetchProtocolConfigAcc();
+    } catch (e) {
+      //the provider wallet deploys the program, so it's the upgrade authority
+      await this.initProtocolConfig(
+        this.funder,
+        Keypair.generate().publicKey,
+        defaultFees
+      );
+      return this.fetchProtocolConfigAcc();
+    }
+  }
+
   async prepAccounts(
     initialFundingAmount: Numerical,
     gem1PerGemRarity: number = 1,
     gem2PerGemRarity: number = 1,
-    reward?: string
+    rewardIndex?: number
   ) {
-    reward = Math.random() < 0.5 ? 'rewardA' : 'rewardB';
-    console.log('running tests for', reward);
+    await this.ensureProtocolConfig();
+
+    this.rewardIndex = rewardIndex ?? (Math.random() < 0.5 ? 0 : 1);
+    console.log('running tests for reward slot', this.rewardIndex);
 
     this.bank = Keypair.generate();
     this.farm = Keypair.generate();
@@ -131,7 +152,6 @@ export class GemFarmTester extends GemFarmClient {
       this.farmer2Identity.publicKey
     );
 
-    if (reward) this.reward = reward;
     this.rewardMint = await this.nw.createMint(0);
     this.rewardSource = await this.nw.createAndFundATA(
       this.rewardMint,
@@ -186,6 +206,12 @@ export class GemFarmTester extends GemFarmClient {
     return this.fetchTreasuryBalance(this.farm.publicKey);
   }
 
+  // aligned with the farm's reward slots
+  rewardMintsBySlot() {
+    const mints = [this.rewardMint.publicKey, this.rewardSecondMint.publicKey];
+    return this.rewardIndex === 0 ? mints : mints.reverse();
+  }
+
   // --------------------------------------- callers
   // ----------------- core
 
@@ -194,17 +220,15 @@ export class GemFarmTester extends GemFarmClient {
     rewardType?: any,
     maxCounts?: MaxCounts
   ) {
-    const isRewardA = this.reward === 'rewardA';
-
     return this.initFarm(
       this.farm,
       this.farmManager,
       this.farmManager,
       this.bank,
-      isRewardA ? this.rewardMint.publicKey : this.rewardSecondMint.publicKey,
-      rewardType ?? RewardType.Variable,
-      isRewardA ? this.rewardSecondMint.publicKey : this.rewardMint.publicKey,
-      rewardType ?? RewardType.Variable,
+      this.rewardMintsBySlot().map((rewardMint) => ({
+        rewardMint,
+        rewardType: rewardType ?? RewardType.Variable,
+      })),
       farmConfig,
       maxCounts
     );
@@ -298,14 +322,7 @@ export class GemFarmTester extends GemFarmClient {
   }
 
   async callClaimRewards(identity: Keypair) {
-    const isRewardA = this.reward === 'rewardA';
-
-    return this.claim(
-      this.farm.publicKey,
-      identity,
-      isRewardA ? this.rewardMint.publicKey : this.rewardSecondMint.publicKey,
-      isRewardA ? this.rewardSecondMint.publicKey : this.rewardMint.publicKey
-    );
+    return this.claim(this.farm.publicKey, identity, this.rewardMintsBySlot());
   }
 
   async callFlashDeposit(
@@ -441,7 +458,7 @@ export class GemFarmTester extends GemFarmClient {
     accrued?: Numerical
   ) {
     let farmAcc = (await this.fetchFarm()) as any;
-    let funds = farmAcc[this.reward].funds;
+    let funds = farmAcc.rewards[this.rewardIndex].funds;
 
     if (funded || funded === 0) {
       assert(funds.totalFunded.eq(toBN(funded)));
@@ -462,7 +479,7 @@ export class GemFarmTester extends GemFarmClient {
     lockEnd?: Numerical
   ) {
     let farmAcc = (await this.fetchFarm()) as any;
-    let times = farmAcc[this.reward].times;
+    let times = farmAcc.rewards[this.rewardIndex].times;
 
     if (duration || duration === 0) {
       assert(times.durationSec.eq(toBN(duration)));
@@ -483,7 +500,7 @@ export class GemFarmTester extends GemFarmClient {
     accruedRewardPerRarityPoint?: Numerical
   ) {
     let farmAcc = (await this.fetchFarm()) as any;
-    let reward = farmAcc[this.reward].variableRate;
+    let reward = farmAcc.rewards[this.rewardIndex].variableRate;
 
     if (rewardRate || rewardRate === 0) {
       assert(reward.rewardRate.n.div(toBN(PRECISION)).eq(toBN(rewardRate)));
@@ -504,7 +521,7 @@ export class GemFarmTester extends GemFarmClient {
 
   async verifyFixedReward(reservedAmount?: Numerical) {
     let farmAcc = (await this.fetchFarm()) as any;
-    let reward = farmAcc[this.reward].fixedRate;
+    let reward = farmAcc.rewards[this.rewardIndex].fixedRate;
 
     // console.log('reserved is', reward.reservedAmount.toNumber());
     // console.log('expected is', toBN(reservedAmount).toNumber());
@@ -607,7 +624,7 @@ export class GemFarmTester extends GemFarmClient {
       identity.publicKey
     );
     const farmerAcc = (await this.fetchFarmerAcc(farmer)) as any;
-    const reward = farmerAcc[this.reward];
+    const reward = farmerAcc.rewards[this.rewardIndex];
 
     if (paidOutReward || paidOutReward === 0) {
       assert(reward.paidOutReward.eq(toBN(paidOutReward)));
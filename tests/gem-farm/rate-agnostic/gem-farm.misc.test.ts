@@ -6,10 +6,9 @@ import {
   GemFarmTester,
 } from '../gem-farm.tester';
 import { BN } from '@project-serum/anchor';
-import { LAMPORTS_PER_SOL, PublicKey } from '@solana/web3.js';
+import { Keypair, LAMPORTS_PER_SOL, PublicKey } from '@solana/web3.js';
 import {
   FarmConfig,
-  feeAccount,
   RewardType,
   WhitelistType,
 } from '../../../src';
@@ -50,14 +49,38 @@ describe('misc', () => {
     const farmAcc = (await gf.fetchFarm()) as any;
     assert.equal(farmAcc.bank.toBase58(), gf.bank.publicKey.toBase58());
     assert.equal(
-      farmAcc[gf.reward].rewardMint.toBase58(),
+      farmAcc.rewards[gf.rewardIndex].rewardMint.toBase58(),
       gf.rewardMint.publicKey.toBase58()
     );
 
-    let bal = await gf.getBalance(feeAccount);
+    const { feeRecipient } = await gf.fetchProtocolConfigAcc();
+    let bal = await gf.getBalance(feeRecipient);
     assert(bal > 0); //can't check exact amount coz depends on order of tests
   });
 
+  it('hands the protocol admin over (and back)', async () => {
+    const newAdmin = Keypair.generate();
+    await gf.updateProtocolConfig(gf.funder, { admin: newAdmin.publicKey });
+
+    // only proposed until accepted
+    let configAcc = await gf.fetchProtocolConfigAcc();
+    assert(configAcc.admin.equals(gf.funder.publicKey));
+    assert(configAcc.pendingAdmin.equals(newAdmin.publicKey));
+
+    await expect(gf.acceptProtocolAdmin(Keypair.generate())).to.be.rejectedWith(
+      'NotPendingAuthority'
+    );
+
+    await gf.acceptProtocolAdmin(newAdmin);
+    configAcc = await gf.fetchProtocolConfigAcc();
+    assert(configAcc.admin.equals(newAdmin.publicKey));
+    assert(configAcc.pendingAdmin.equals(PublicKey.default));
+
+    // the config is shared w/ the other test files
+    await gf.updateProtocolConfig(newAdmin, { admin: gf.funder.publicKey });
+    await gf.acceptProtocolAdmin(gf.funder);
+  });
+
   it('updates the farm', async () => {
     await gf.callUpdateFarm(updatedFarmConfig);
 
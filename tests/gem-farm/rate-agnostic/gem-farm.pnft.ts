@@ -9,7 +9,6 @@ import { AnchorProvider, BN } from '@project-serum/anchor';
 import { Keypair, LAMPORTS_PER_SOL, PublicKey } from '@solana/web3.js';
 import {
   FarmConfig,
-  feeAccount,
   NodeWallet,
   pause,
   RewardType,